@@ -0,0 +1,293 @@
+//! Typed accessors of [`Node`]s.
+//!
+//! The `is_*` methods check the variant of a node, the `as_*` methods borrow
+//! its value and the `try_into_*` methods consume the node and return an
+//! [`Error`] describing the actual node if the variant doesn't match.
+//!
+//! Accessors don't look through tags, i.e. `!Ref web` is neither a string
+//! nor returned by [`Node::as_str`]. Use [`Node::untagged`] to skip them.
+//!
+//! ```
+//! use yaml_ast::{Error, Mapping, Node};
+//!
+//! let node = Node::from(Mapping::from([("replicas".into(), 3.into())]));
+//! let replicas = node.as_mapping().and_then(|m| m.get("replicas"));
+//! assert_eq!(replicas.and_then(Node::as_i64), Some(3));
+//!
+//! assert_eq!(
+//!     node.try_into_sequence(),
+//!     Err(Error::UnexpectedNode {
+//!         expected: "sequence".into(),
+//!         found: "mapping".into(),
+//!     })
+//! );
+//! ```
+use std::mem;
+
+use snafu::Snafu;
+
+use crate::{float::Float, integer::BigInteger, tag::Tagged, timestamp::Timestamp, Mapping, Node};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("expected {expected}, found {found}"))]
+    UnexpectedNode { expected: String, found: String },
+
+    #[snafu(display(
+        "the document at index {duplicate} duplicates the document at index {first}"
+    ))]
+    DuplicateDocument { first: usize, duplicate: usize },
+}
+
+/// Implements a consuming conversion which returns the value of a variant.
+/// Values are taken out of the node because [`Node`] implements [`Drop`].
+macro_rules! try_into {
+    ($name:ident, $expected:literal, $ty:ty, $pattern:pat => $value:expr) => {
+        pub fn $name(mut self) -> Result<$ty, Error> {
+            match &mut self {
+                $pattern => Ok($value),
+                node => UnexpectedNodeSnafu {
+                    expected: $expected,
+                    found: node.description(),
+                }
+                .fail(),
+            }
+        }
+    };
+}
+
+impl Node {
+    /// Returns a short description of the node used in error messages, e.g.
+    /// `string` or `tagged node !Ref`.
+    pub fn description(&self) -> String {
+        match self {
+            Node::Mapping(_) => "mapping".into(),
+            Node::Sequence(_) => "sequence".into(),
+            Node::String(_) => "string".into(),
+            Node::Null => "null".into(),
+            Node::Boolean(_) => "boolean".into(),
+            Node::Integer(_) | Node::BigInteger(_) => "integer".into(),
+            Node::FloatingPoint(_) => "float".into(),
+            Node::Binary(_) => "binary".into(),
+            Node::Timestamp(_) => "timestamp".into(),
+            Node::Tagged(tagged) => format!("tagged node {}", tagged.tag),
+        }
+    }
+
+    /// Returns the node without its tags.
+    pub fn untagged(&self) -> &Node {
+        match self {
+            Node::Tagged(tagged) => tagged.node.untagged(),
+            node => node,
+        }
+    }
+
+    pub fn is_mapping(&self) -> bool {
+        matches!(self, Node::Mapping(_))
+    }
+
+    pub fn is_sequence(&self) -> bool {
+        matches!(self, Node::Sequence(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, Node::String(_))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Node::Null)
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Node::Boolean(_))
+    }
+
+    /// Returns `true` for integers of any size.
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Node::Integer(_) | Node::BigInteger(_))
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Node::FloatingPoint(_))
+    }
+
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Node::Binary(_))
+    }
+
+    pub fn is_timestamp(&self) -> bool {
+        matches!(self, Node::Timestamp(_))
+    }
+
+    pub fn is_tagged(&self) -> bool {
+        matches!(self, Node::Tagged(_))
+    }
+
+    pub fn as_mapping(&self) -> Option<&Mapping> {
+        match self {
+            Node::Mapping(mapping) => Some(mapping),
+            _ => None,
+        }
+    }
+
+    pub fn as_mapping_mut(&mut self) -> Option<&mut Mapping> {
+        match self {
+            Node::Mapping(mapping) => Some(mapping),
+            _ => None,
+        }
+    }
+
+    pub fn as_sequence(&self) -> Option<&[Node]> {
+        match self {
+            Node::Sequence(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_sequence_mut(&mut self) -> Option<&mut Vec<Node>> {
+        match self {
+            Node::Sequence(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Node::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Node::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if it fits into an [`i64`].
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Node::Integer(i) => Some(*i),
+            Node::BigInteger(i) => i64::try_from(i).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if it fits into a [`u64`].
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Node::Integer(i) => u64::try_from(*i).ok(),
+            Node::BigInteger(i) => u64::try_from(i).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a float. Integers are not converted.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Node::FloatingPoint(f) => Some(f.value()),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<&Float> {
+        match self {
+            Node::FloatingPoint(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn as_binary(&self) -> Option<&[u8]> {
+        match self {
+            Node::Binary(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_timestamp(&self) -> Option<&Timestamp> {
+        match self {
+            Node::Timestamp(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    pub fn as_tagged(&self) -> Option<&Tagged> {
+        match self {
+            Node::Tagged(tagged) => Some(tagged),
+            _ => None,
+        }
+    }
+
+    try_into!(try_into_mapping, "mapping", Mapping, Node::Mapping(mapping) => mem::take(mapping));
+    try_into!(try_into_sequence, "sequence", Vec<Node>, Node::Sequence(items) => mem::take(items));
+    try_into!(try_into_string, "string", String, Node::String(s) => mem::take(s));
+    try_into!(try_into_bool, "boolean", bool, Node::Boolean(b) => *b);
+    try_into!(try_into_f64, "float", f64, Node::FloatingPoint(f) => f.value());
+    try_into!(try_into_float, "float", Float, Node::FloatingPoint(f) => f.clone());
+    try_into!(try_into_binary, "binary", Vec<u8>, Node::Binary(b) => mem::take(b));
+    try_into!(try_into_timestamp, "timestamp", Timestamp, Node::Timestamp(t) => *t);
+    try_into!(try_into_tagged, "tagged node", Tagged, Node::Tagged(tagged) => Tagged::new(
+        mem::take(&mut tagged.tag),
+        mem::take(&mut tagged.node),
+    ));
+
+    /// Returns integers of any size as a [`BigInteger`].
+    pub fn try_into_big_integer(self) -> Result<BigInteger, Error> {
+        match &self {
+            Node::Integer(i) => Ok(BigInteger::from(*i)),
+            Node::BigInteger(i) => Ok(i.clone()),
+            node => UnexpectedNodeSnafu {
+                expected: "integer",
+                found: node.description(),
+            }
+            .fail(),
+        }
+    }
+
+    /// Returns the integer if it fits into an [`i64`].
+    pub fn try_into_i64(self) -> Result<i64, Error> {
+        match self.as_i64() {
+            Some(i) => Ok(i),
+            None => UnexpectedNodeSnafu {
+                expected: "64-bit integer",
+                found: self.description(),
+            }
+            .fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn accessors() {
+        let big = Node::from(u64::MAX);
+        assert!(big.is_integer());
+        assert_eq!(big.as_i64(), None);
+        assert_eq!(big.as_u64(), Some(u64::MAX));
+        assert_eq!(Node::from(-1).as_u64(), None);
+        assert_eq!(Node::from(1.5).as_f64(), Some(1.5));
+        assert_eq!(Node::from(1).as_f64(), None);
+
+        let tagged = Node::from(Tagged::new("!Ref", "web".into()));
+        assert_eq!(tagged.as_str(), None);
+        assert_eq!(tagged.untagged().as_str(), Some("web"));
+
+        let mut node = Node::from(vec![1, 2]);
+        node.as_sequence_mut().unwrap().push(3.into());
+        assert_eq!(node.as_sequence().map(<[Node]>::len), Some(3));
+
+        assert_eq!(Node::from("a").try_into_string(), Ok("a".to_string()));
+        assert_eq!(
+            big.try_into_i64().unwrap_err().to_string(),
+            "expected 64-bit integer, found integer"
+        );
+        assert_eq!(
+            tagged.try_into_bool().unwrap_err().to_string(),
+            "expected boolean, found tagged node !Ref"
+        );
+    }
+}
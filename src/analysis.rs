@@ -0,0 +1,344 @@
+//! Statistics about the nodes and scalars of a [`Stream`].
+//!
+//! [`Stream::stats`] counts the nodes of a stream by type and measures its
+//! depth and the size of its scalars, e.g. to limit the complexity of
+//! configurations in CI. [`Cst::stats`] additionally counts the anchors and
+//! aliases of the source text.
+//!
+//! [`scalar_profile`] records, per key path, the types of the scalars found
+//! in each document. [`ScalarProfile::drift`] then reports the paths whose
+//! type differs between documents, e.g. a `replicas` value which is an
+//! integer in one manifest and a string in another.
+//!
+//! ```
+//! use yaml_ast::{analysis::scalar_profile, Stream};
+//!
+//! let stream: Stream = "replicas: 3\n---\nreplicas: \"3\"\n".parse().unwrap();
+//! let drift = scalar_profile(&stream).drift();
+//!
+//! assert_eq!(drift.len(), 1);
+//! assert_eq!(drift[0].to_string(), "replicas: integer in documents 0; string in documents 1");
+//! ```
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::{
+    cst::{self, Cst, CstKind, CstNode},
+    emitter::key_segment,
+    events::{format_path, PathSegment},
+    Node, Stream,
+};
+
+/// Statistics about the nodes of a stream, see [`Stream::stats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub documents: usize,
+
+    /// The number of nodes of each type, named as by [`Node::description`],
+    /// including mapping keys. Tagged nodes are counted by the type of the
+    /// tagged node.
+    pub nodes: BTreeMap<String, usize>,
+
+    /// The maximum number of nested nodes from a root node to a scalar,
+    /// e.g. 2 for `{a: 1}`. Zero if the stream has no nodes.
+    pub max_depth: usize,
+
+    /// The total size of all scalars in bytes, written in plain style, i.e.
+    /// without quotes and escapes. Binary scalars are counted in base64.
+    pub scalar_bytes: usize,
+
+    /// The number of anchors, only counted by [`Cst::stats`].
+    pub anchors: usize,
+
+    /// The number of aliases, only counted by [`Cst::stats`].
+    pub aliases: usize,
+}
+
+impl Stats {
+    /// Returns the total number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.values().sum()
+    }
+
+    fn record(&mut self, node: &Node, depth: usize) {
+        let node = node.untagged();
+        *self.nodes.entry(node.description()).or_default() += 1;
+        self.max_depth = self.max_depth.max(depth);
+
+        match node {
+            Node::Mapping(mapping) => {
+                for pair in mapping.pairs() {
+                    self.record(&pair.key, depth + 1);
+                    self.record(&pair.value, depth + 1);
+                }
+            }
+            Node::Sequence(items) => {
+                for item in items {
+                    self.record(item, depth + 1);
+                }
+            }
+            Node::String(value) => self.scalar_bytes += value.len(),
+            scalar => {
+                let PathSegment::Key(text) = key_segment(scalar) else {
+                    unreachable!("scalars are formatted as key segments")
+                };
+                self.scalar_bytes += text.len();
+            }
+        }
+    }
+
+    fn record_properties(&mut self, node: &CstNode) {
+        if node.anchor.is_some() {
+            self.anchors += 1;
+        }
+
+        match &node.kind {
+            CstKind::Alias(_) => self.aliases += 1,
+            CstKind::Scalar { .. } => {}
+            CstKind::Sequence { items, .. } => {
+                for item in items {
+                    self.record_properties(&item.node);
+                }
+            }
+            CstKind::Mapping { pairs, .. } => {
+                for pair in pairs {
+                    self.record_properties(&pair.key);
+                    self.record_properties(&pair.value);
+                }
+            }
+        }
+    }
+}
+
+impl Stream {
+    /// Returns statistics about the documents and nodes of the stream.
+    ///
+    /// ```
+    /// use yaml_ast::Stream;
+    ///
+    /// let stream: Stream = "name: web\nports: [80, 443]\n".parse().unwrap();
+    /// let stats = stream.stats();
+    ///
+    /// assert_eq!(stats.documents, 1);
+    /// assert_eq!(stats.nodes["integer"], 2);
+    /// assert_eq!(stats.node_count(), 7);
+    /// assert_eq!(stats.max_depth, 3);
+    /// assert_eq!(stats.scalar_bytes, 17);
+    /// ```
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            documents: self.len(),
+            ..Default::default()
+        };
+
+        for document in self.iter() {
+            for node in &document.nodes {
+                stats.record(node, 1);
+            }
+        }
+
+        stats
+    }
+}
+
+impl Cst {
+    /// Returns the [`Stream::stats`] of the stream resolved with the
+    /// default [`ResolveOptions`](cst::ResolveOptions), together with the
+    /// number of anchors and aliases in the source text. The nodes are
+    /// counted after resolving, i.e. aliased nodes are counted at every
+    /// alias.
+    pub fn stats(&self) -> Result<Stats, cst::Error> {
+        let mut stats = self.to_stream()?.stats();
+
+        for document in self.documents() {
+            if let Some(root) = document.root() {
+                stats.record_properties(root);
+            }
+        }
+
+        Ok(stats)
+    }
+}
+
+/// The types of the scalars found at the key paths of a stream.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScalarProfile {
+    /// The profiles by path. Keys are separated by dots, sequence items are
+    /// represented by `[*]`, e.g. `spec.containers[*].image`. Scalar root
+    /// nodes have the path `.`.
+    pub paths: BTreeMap<String, PathProfile>,
+}
+
+/// The types of the scalars found at a single key path.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PathProfile {
+    /// The occurrences of each type, named as by [`Node::description`].
+    pub types: BTreeMap<String, TypeOccurrences>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TypeOccurrences {
+    /// The number of scalars of this type.
+    pub count: usize,
+
+    /// The indices of the documents containing scalars of this type.
+    pub documents: BTreeSet<usize>,
+}
+
+/// A path whose scalar type differs between documents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Drift {
+    pub path: String,
+
+    /// The documents containing each type found at the path.
+    pub types: BTreeMap<String, BTreeSet<usize>>,
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+
+        for (index, (name, documents)) in self.types.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+
+            let documents: Vec<_> = documents.iter().map(ToString::to_string).collect();
+            write!(f, "{name} in documents {}", documents.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the types of all scalars of the `stream` by their key path.
+pub fn scalar_profile(stream: &Stream) -> ScalarProfile {
+    let mut profile = ScalarProfile::default();
+
+    for (index, document) in stream.iter().enumerate() {
+        for node in &document.nodes {
+            profile.record(node, index, &mut Vec::new());
+        }
+    }
+
+    profile
+}
+
+impl ScalarProfile {
+    /// Returns the paths which have a different set of types in some of the
+    /// documents they occur in. Nulls are ignored, as they usually mark
+    /// omitted values. Mixed types within a single document, like the items
+    /// of `args: [1, "a"]`, are no drift if all documents agree.
+    pub fn drift(&self) -> Vec<Drift> {
+        let mut drift = Vec::new();
+
+        for (path, profile) in &self.paths {
+            let types: BTreeMap<String, BTreeSet<usize>> = profile
+                .types
+                .iter()
+                .filter(|(name, _)| *name != "null")
+                .map(|(name, occurrences)| (name.clone(), occurrences.documents.clone()))
+                .collect();
+
+            // All types occur in the same documents if their document sets
+            // are equal
+            let mut documents = types.values();
+            let first = documents.next();
+
+            if documents.any(|documents| Some(documents) != first) {
+                drift.push(Drift {
+                    path: path.clone(),
+                    types,
+                });
+            }
+        }
+
+        drift
+    }
+
+    /// Records the scalars of `node`, where all sequence items share the
+    /// index 0 of the `path`, which is formatted as `[*]`.
+    fn record(&mut self, node: &Node, document: usize, path: &mut Vec<PathSegment>) {
+        match node.untagged() {
+            Node::Mapping(mapping) => {
+                for pair in mapping.pairs() {
+                    path.push(key_segment(&pair.key));
+                    self.record(&pair.value, document, path);
+                    path.pop();
+                }
+            }
+            Node::Sequence(items) => {
+                for item in items {
+                    path.push(PathSegment::Index(0));
+                    self.record(item, document, path);
+                    path.pop();
+                }
+            }
+            _ => {
+                let occurrences = self
+                    .paths
+                    .entry(format_path(path, |_| "*".into()))
+                    .or_default()
+                    .types
+                    .entry(node.description())
+                    .or_default();
+
+                occurrences.count += 1;
+                occurrences.documents.insert(document);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn profile() {
+        let stream: Stream =
+            "replicas: 3\nargs: [1, a]\nports:\n  - port: 80\n---\nreplicas: ~\nargs: [2, b]\nports:\n  - port: http\n"
+                .parse()
+                .unwrap();
+        let profile = scalar_profile(&stream);
+
+        assert_eq!(
+            profile.paths.keys().collect::<Vec<_>>(),
+            ["args[*]", "ports[*].port", "replicas"]
+        );
+        assert_eq!(profile.paths["args[*]"].types["integer"].count, 2);
+
+        let drift = profile.drift();
+        assert_eq!(drift.len(), 1);
+        assert_eq!(
+            drift[0].to_string(),
+            "ports[*].port: integer in documents 0; string in documents 1"
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let cst = Cst::parse("base: &base {a: !x 1.5, b: \"\\n\"}\nother: *base\n---\n---\n[~]\n")
+            .unwrap();
+        let stats = cst.stats().unwrap();
+
+        assert_eq!(
+            stats,
+            Stats {
+                documents: 3,
+                nodes: BTreeMap::from([
+                    ("mapping".into(), 3),
+                    ("null".into(), 1),
+                    ("sequence".into(), 1),
+                    ("string".into(), 10),
+                ]),
+                max_depth: 3,
+                scalar_bytes: 25,
+                anchors: 1,
+                aliases: 1,
+            }
+        );
+    }
+}
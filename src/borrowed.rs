@@ -0,0 +1,235 @@
+//! Node trees which borrow their strings.
+//!
+//! A [`BorrowedNode`] stores strings, keys and other text as [`Cow`]s,
+//! which either borrow from a longer-living buffer or own their content.
+//! This avoids one allocation per scalar when a tree is built from data
+//! which is already in memory, e.g. the input buffer of a parser or the
+//! strings of another [`Node`].
+//!
+//! ```
+//! use std::borrow::Cow;
+//!
+//! use yaml_ast::{borrowed::BorrowedNode, yaml};
+//!
+//! let input = "name=web;zone=a";
+//! let mut mapping = BorrowedNode::Mapping(Vec::new());
+//!
+//! for pair in input.split(';') {
+//!     let (key, value) = pair.split_once('=').unwrap();
+//!     mapping.push_pair(BorrowedNode::from(key), BorrowedNode::from(value));
+//! }
+//!
+//! // The keys and values borrow from the input
+//! let BorrowedNode::Mapping(pairs) = &mapping else { unreachable!() };
+//! assert_eq!(pairs[0].0, BorrowedNode::String(Cow::Borrowed("name")));
+//!
+//! assert_eq!(mapping.into_owned(), yaml!({name: "web", zone: "a"}));
+//! ```
+use std::borrow::Cow;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use crate::{
+    events::{Event, IntoEvents},
+    float::Float,
+    integer::BigInteger,
+    tag::Tagged,
+    timestamp::Timestamp,
+    Mapping, Node,
+};
+
+/// A YAML node whose text is borrowed or owned. The variants mirror the
+/// ones of [`Node`], big integers and floats are stored as their text.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BorrowedNode<'a> {
+    Mapping(Vec<(BorrowedNode<'a>, BorrowedNode<'a>)>),
+    Sequence(Vec<BorrowedNode<'a>>),
+    String(Cow<'a, str>),
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    BigInteger(Cow<'a, str>),
+    FloatingPoint(Cow<'a, str>),
+    Binary(Cow<'a, [u8]>),
+    Timestamp(Timestamp),
+    Tagged(Cow<'a, str>, Box<BorrowedNode<'a>>),
+}
+
+impl<'a> From<&'a str> for BorrowedNode<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::String(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for BorrowedNode<'_> {
+    fn from(value: String) -> Self {
+        Self::String(Cow::Owned(value))
+    }
+}
+
+impl<'a> From<&'a Node> for BorrowedNode<'a> {
+    /// Borrows the strings, tags and binary data of the `node`. Numbers
+    /// which are stored as text are formatted.
+    fn from(node: &'a Node) -> Self {
+        match node {
+            Node::Mapping(mapping) => Self::Mapping(
+                mapping
+                    .pairs()
+                    .map(|pair| (Self::from(&pair.key), Self::from(&pair.value)))
+                    .collect(),
+            ),
+            Node::Sequence(sequence) => Self::Sequence(sequence.iter().map(Self::from).collect()),
+            Node::String(s) => Self::String(Cow::Borrowed(s)),
+            Node::Null => Self::Null,
+            Node::Boolean(b) => Self::Boolean(*b),
+            Node::Integer(i) => Self::Integer(*i),
+            Node::BigInteger(i) => Self::BigInteger(Cow::Owned(i.to_string())),
+            Node::FloatingPoint(f) => Self::FloatingPoint(Cow::Owned(f.to_string())),
+            Node::Binary(b) => Self::Binary(Cow::Borrowed(b)),
+            Node::Timestamp(t) => Self::Timestamp(*t),
+            Node::Tagged(tagged) => Self::Tagged(
+                Cow::Borrowed(&tagged.tag),
+                Box::new(Self::from(&tagged.node)),
+            ),
+        }
+    }
+}
+
+impl BorrowedNode<'_> {
+    /// Appends a pair at the end of the mapping. Does nothing if this node
+    /// is not a mapping.
+    pub fn push_pair(&mut self, key: Self, value: Self) -> &mut Self {
+        if let Self::Mapping(pairs) = self {
+            pairs.push((key, value));
+        }
+
+        self
+    }
+
+    /// Appends an item at the end of the sequence. Does nothing if this node
+    /// is not a sequence.
+    pub fn push_item(&mut self, item: Self) -> &mut Self {
+        if let Self::Sequence(items) = self {
+            items.push(item);
+        }
+
+        self
+    }
+
+    /// Converts this node into an owned [`Node`]. Owned text is moved,
+    /// borrowed text is copied.
+    pub fn into_owned(self) -> Node {
+        match self {
+            Self::Mapping(pairs) => Node::Mapping(
+                pairs
+                    .into_iter()
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect::<Mapping>(),
+            ),
+            Self::Sequence(items) => {
+                Node::Sequence(items.into_iter().map(Self::into_owned).collect())
+            }
+            Self::String(s) => Node::String(s.into_owned()),
+            Self::Null => Node::Null,
+            Self::Boolean(b) => Node::Boolean(b),
+            Self::Integer(i) => Node::Integer(i),
+            Self::BigInteger(i) => i
+                .parse::<BigInteger>()
+                .map_or_else(|_| Node::String(i.into_owned()), Node::from),
+            Self::FloatingPoint(f) => f
+                .parse()
+                .map_or_else(|_| Node::String(f.into_owned()), Node::FloatingPoint),
+            Self::Binary(b) => Node::Binary(b.into_owned()),
+            Self::Timestamp(t) => Node::Timestamp(t),
+            Self::Tagged(tag, node) => {
+                Node::Tagged(Box::new(Tagged::new(tag.into_owned(), node.into_owned())))
+            }
+        }
+    }
+
+    fn push_events(self, events: &mut Vec<Event>) {
+        let tag = |name: &str| Some(format!("tag:yaml.org,2002:{name}"));
+
+        match self {
+            Self::Mapping(pairs) => {
+                events.push(Event::mapping_start());
+                for (key, value) in pairs {
+                    key.push_events(events);
+                    value.push_events(events);
+                }
+                events.push(Event::MappingEnd);
+            }
+            Self::Sequence(items) => {
+                events.push(Event::sequence_start());
+                for item in items {
+                    item.push_events(events);
+                }
+                events.push(Event::SequenceEnd);
+            }
+            Self::String(s) => events.push(Event::scalar(s.into_owned(), tag("str"))),
+            Self::Null => events.push(Event::scalar("null", tag("null"))),
+            Self::Boolean(b) => events.push(Event::scalar(b.to_string(), tag("bool"))),
+            Self::Integer(i) => events.push(Event::scalar(i.to_string(), tag("int"))),
+            Self::BigInteger(i) => events.push(Event::scalar(i.into_owned(), tag("int"))),
+            Self::FloatingPoint(f) => events.push(Event::scalar(
+                f.parse::<Float>()
+                    .map_or_else(|_| f.into_owned(), |f| f.to_string()),
+                tag("float"),
+            )),
+            Self::Binary(b) => events.push(Event::scalar(BASE64.encode(b), tag("binary"))),
+            Self::Timestamp(t) => events.push(Event::scalar(t.to_string(), tag("timestamp"))),
+            Self::Tagged(tag, node) => {
+                let start = events.len();
+                node.push_events(events);
+
+                // The tag is a property of the first event of the node, the
+                // outermost tag of nested tagged nodes applies
+                if let Event::Scalar { tag: node_tag, .. }
+                | Event::SequenceStart { tag: node_tag, .. }
+                | Event::MappingStart { tag: node_tag, .. } = &mut events[start]
+                {
+                    *node_tag = Some(tag.into_owned());
+                }
+            }
+        }
+    }
+}
+
+impl IntoEvents for BorrowedNode<'_> {
+    /// Returns the events of the node. Events own their values, which is
+    /// why borrowed strings are copied.
+    fn into_events(self) -> Vec<Event> {
+        let mut events = Vec::new();
+        self.push_events(&mut events);
+        events
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{tag::Tagged, yaml};
+
+    #[test]
+    fn round_trip() {
+        let mut node = yaml!({name: "web", ports: [80, null], ratio: 0.5, data: {}});
+        if let Node::Mapping(mapping) = &mut node {
+            mapping.push_pair(
+                "ref".into(),
+                Node::Tagged(Box::new(Tagged::new("!Ref", "web".into()))),
+            );
+        }
+
+        let borrowed = BorrowedNode::from(&node);
+        let BorrowedNode::Mapping(pairs) = &borrowed else {
+            panic!("expected a mapping");
+        };
+        assert!(matches!(
+            &pairs[0].1,
+            BorrowedNode::String(Cow::Borrowed("web"))
+        ));
+
+        assert_eq!(borrowed.clone().into_events(), node.clone().into_events());
+        assert_eq!(borrowed.into_owned(), node);
+    }
+}
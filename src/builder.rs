@@ -0,0 +1,159 @@
+//! Fluent builders for [`Document`]s and [`Mapping`]s.
+//!
+//! The builders attach comments and blank lines to the pairs they belong to,
+//! which keeps the ordering right without juggling [`MappingPair`] fields.
+//!
+//! ```
+//! use yaml_ast::{
+//!     emitter::{Emitter, EmitterOptions},
+//!     events::IntoEvents,
+//!     Document, Mapping, Stream,
+//! };
+//!
+//! let document = Document::builder()
+//!     .comment("Generated, do not edit")
+//!     .root(
+//!         Mapping::builder()
+//!             .key("name").value("web")
+//!             .key("replicas").value(3).comment("scaled for prod")
+//!             .blank_lines(1)
+//!             .leading_comment("Network")
+//!             .key("ports").value(vec![80, 443])
+//!             .build(),
+//!     )
+//!     .build();
+//!
+//! let mut output = String::new();
+//! let events = Stream::from_documents(vec![document]).into_events();
+//! Emitter::new(events, EmitterOptions::default()).emit(&mut output).unwrap();
+//!
+//! assert_eq!(
+//!     output,
+//!     "# Generated, do not edit\n---\nname: web\nreplicas: 3 # scaled for prod\n\n# Network\n\
+//!      ports:\n  - 80\n  - 443\n...\n"
+//! );
+//! ```
+use crate::{directive::Directive, Document, Mapping, MappingPair, Node};
+
+impl Document {
+    pub fn builder() -> DocumentBuilder {
+        DocumentBuilder::default()
+    }
+}
+
+impl Mapping {
+    pub fn builder() -> MappingBuilder {
+        MappingBuilder::default()
+    }
+}
+
+/// Builds a [`Document`] with a single root node.
+#[derive(Debug, Default)]
+pub struct DocumentBuilder {
+    document: Document,
+}
+
+impl DocumentBuilder {
+    /// Adds a directive, which is emitted before the document start marker.
+    pub fn directive(mut self, directive: Directive) -> Self {
+        self.document.directives.push(directive);
+        self
+    }
+
+    /// Adds a comment emitted before the document start marker.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.document.comments.push(comment.into());
+        self
+    }
+
+    /// Sets the number of blank lines emitted before the document.
+    pub fn blank_lines(mut self, blank_lines: usize) -> Self {
+        self.document.blank_lines = blank_lines;
+        self
+    }
+
+    /// Sets the root node, replacing a previously set root node.
+    pub fn root(mut self, root: impl Into<Node>) -> Self {
+        self.document.nodes = vec![root.into()];
+        self
+    }
+
+    pub fn build(self) -> Document {
+        self.document
+    }
+}
+
+/// Builds a [`Mapping`] pair by pair. Each pair is started with
+/// [`MappingBuilder::key`] and completed with [`MappingKeyBuilder::value`].
+#[derive(Debug, Default)]
+pub struct MappingBuilder {
+    mapping: Mapping,
+
+    /// The leading comments of the next pair.
+    comments: Vec<String>,
+
+    /// The blank lines before the next pair.
+    blank_lines: usize,
+}
+
+impl MappingBuilder {
+    /// Starts a new pair with `key`.
+    pub fn key(self, key: impl Into<Node>) -> MappingKeyBuilder {
+        MappingKeyBuilder {
+            builder: self,
+            key: key.into(),
+        }
+    }
+
+    /// Adds a complete pair.
+    pub fn pair(self, key: impl Into<Node>, value: impl Into<Node>) -> Self {
+        self.key(key).value(value)
+    }
+
+    /// Sets the comment emitted at the end of the line of the last pair. If
+    /// no pair was added yet, the comment is emitted before the first pair.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        match self.mapping.last_mut() {
+            Some(pair) => pair.comment = Some(comment.into()),
+            None => self.comments.push(comment.into()),
+        }
+        self
+    }
+
+    /// Adds a comment emitted on its own line before the next pair.
+    pub fn leading_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comments.push(comment.into());
+        self
+    }
+
+    /// Sets the number of blank lines emitted before the next pair.
+    pub fn blank_lines(mut self, blank_lines: usize) -> Self {
+        self.blank_lines = blank_lines;
+        self
+    }
+
+    pub fn build(self) -> Mapping {
+        self.mapping
+    }
+}
+
+/// A [`MappingBuilder`] with the key of a pair, which is waiting for the
+/// value.
+#[derive(Debug)]
+#[must_use]
+pub struct MappingKeyBuilder {
+    builder: MappingBuilder,
+    key: Node,
+}
+
+impl MappingKeyBuilder {
+    /// Completes the pair with `value`.
+    pub fn value(mut self, value: impl Into<Node>) -> MappingBuilder {
+        let mut pair = MappingPair::new(self.key, value.into())
+            .with_blank_lines(std::mem::take(&mut self.builder.blank_lines));
+        pair.comments = std::mem::take(&mut self.builder.comments);
+
+        self.builder.mapping.push(pair);
+        self.builder
+    }
+}
@@ -0,0 +1,240 @@
+//! Bump allocated node trees.
+//!
+//! This module is only available when the `bump` feature is enabled. A
+//! [`BumpNode`] stores its children and scalar contents inside a
+//! [`Bump`] arena instead of the global allocator. This makes short-lived
+//! generate-and-emit cycles cheap: the whole tree is freed at once by
+//! resetting or dropping the arena.
+//!
+//! ```
+//! use bumpalo::Bump;
+//! use yaml_ast::{bump::BumpNode, events::IntoEvents};
+//!
+//! let bump = Bump::new();
+//! let mut mapping = BumpNode::mapping(&bump);
+//! mapping.push_pair(BumpNode::string(&bump, "replicas"), BumpNode::Integer(3));
+//!
+//! let events = mapping.into_events();
+//! assert_eq!(events.len(), 4);
+//! ```
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bumpalo::{collections::Vec as BumpVec, Bump};
+
+use crate::{
+    events::{Event, IntoEvents},
+    float::Float,
+    integer::BigInteger,
+    tag::Tagged,
+    timestamp::Timestamp,
+    Mapping, Node,
+};
+
+/// A YAML node which is allocated in a [`Bump`] arena. The variants mirror
+/// the ones of [`Node`].
+#[derive(Debug)]
+pub enum BumpNode<'bump> {
+    Mapping(BumpVec<'bump, (BumpNode<'bump>, BumpNode<'bump>)>),
+    Sequence(BumpVec<'bump, BumpNode<'bump>>),
+    String(&'bump str),
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    BigInteger(&'bump str),
+    FloatingPoint(&'bump str),
+    Binary(&'bump [u8]),
+    Timestamp(Timestamp),
+    Tagged(&'bump str, &'bump BumpNode<'bump>),
+}
+
+impl<'bump> BumpNode<'bump> {
+    /// Creates a new (empty) mapping allocated in `bump`.
+    pub fn mapping(bump: &'bump Bump) -> Self {
+        Self::Mapping(BumpVec::new_in(bump))
+    }
+
+    /// Creates a new (empty) sequence allocated in `bump`.
+    pub fn sequence(bump: &'bump Bump) -> Self {
+        Self::Sequence(BumpVec::new_in(bump))
+    }
+
+    /// Creates a string node by copying `value` into `bump`.
+    pub fn string(bump: &'bump Bump, value: &str) -> Self {
+        Self::String(bump.alloc_str(value))
+    }
+
+    /// Creates a floating point node by copying `value` into `bump`.
+    pub fn float(bump: &'bump Bump, value: &str) -> Self {
+        Self::FloatingPoint(bump.alloc_str(value))
+    }
+
+    /// Appends a pair at the end of the mapping. Does nothing if this node
+    /// is not a mapping.
+    pub fn push_pair(&mut self, key: Self, value: Self) -> &mut Self {
+        if let Self::Mapping(pairs) = self {
+            pairs.push((key, value));
+        }
+
+        self
+    }
+
+    /// Appends an item at the end of the sequence. Does nothing if this node
+    /// is not a sequence.
+    pub fn push_item(&mut self, item: Self) -> &mut Self {
+        if let Self::Sequence(items) = self {
+            items.push(item);
+        }
+
+        self
+    }
+
+    /// Copies the (heap allocated) `node` into `bump`.
+    pub fn from_node(bump: &'bump Bump, node: &Node) -> Self {
+        match node {
+            Node::Mapping(mapping) => {
+                let mut pairs = BumpVec::with_capacity_in(mapping.len(), bump);
+                pairs.extend(mapping.pairs().map(|pair| {
+                    (
+                        Self::from_node(bump, &pair.key),
+                        Self::from_node(bump, &pair.value),
+                    )
+                }));
+                Self::Mapping(pairs)
+            }
+            Node::Sequence(sequence) => {
+                let mut items = BumpVec::with_capacity_in(sequence.len(), bump);
+                items.extend(sequence.iter().map(|item| Self::from_node(bump, item)));
+                Self::Sequence(items)
+            }
+            Node::String(s) => Self::string(bump, s),
+            Node::Null => Self::Null,
+            Node::Boolean(b) => Self::Boolean(*b),
+            Node::Integer(i) => Self::Integer(*i),
+            Node::BigInteger(i) => Self::BigInteger(bump.alloc_str(&i.to_string())),
+            Node::FloatingPoint(f) => Self::float(bump, &f.to_string()),
+            Node::Binary(b) => Self::Binary(bump.alloc_slice_copy(b)),
+            Node::Timestamp(t) => Self::Timestamp(*t),
+            Node::Tagged(tagged) => Self::Tagged(
+                bump.alloc_str(&tagged.tag),
+                bump.alloc(Self::from_node(bump, &tagged.node)),
+            ),
+        }
+    }
+
+    /// Copies this node into a (heap allocated) [`Node`], which can outlive
+    /// the arena.
+    pub fn to_node(&self) -> Node {
+        match self {
+            Self::Mapping(pairs) => Node::Mapping(
+                pairs
+                    .iter()
+                    .map(|(key, value)| (key.to_node(), value.to_node()))
+                    .collect::<Mapping>(),
+            ),
+            Self::Sequence(items) => Node::Sequence(items.iter().map(Self::to_node).collect()),
+            Self::String(s) => Node::String(s.to_string()),
+            Self::Null => Node::Null,
+            Self::Boolean(b) => Node::Boolean(*b),
+            Self::Integer(i) => Node::Integer(*i),
+            Self::BigInteger(i) => i
+                .parse::<BigInteger>()
+                .map_or_else(|_| Node::String(i.to_string()), Node::from),
+            Self::FloatingPoint(f) => f
+                .parse()
+                .map_or_else(|_| Node::String(f.to_string()), Node::FloatingPoint),
+            Self::Binary(b) => Node::Binary(b.to_vec()),
+            Self::Timestamp(t) => Node::Timestamp(*t),
+            Self::Tagged(tag, node) => Node::Tagged(Box::new(Tagged::new(*tag, node.to_node()))),
+        }
+    }
+
+    fn collect_events(&self, events: &mut Vec<Event>) {
+        let tag = |name: &str| Some(format!("tag:yaml.org,2002:{name}"));
+
+        match self {
+            Self::Mapping(pairs) => {
+                events.push(Event::mapping_start());
+                for (key, value) in pairs {
+                    key.collect_events(events);
+                    value.collect_events(events);
+                }
+                events.push(Event::MappingEnd);
+            }
+            Self::Sequence(items) => {
+                events.push(Event::sequence_start());
+                for item in items {
+                    item.collect_events(events);
+                }
+                events.push(Event::SequenceEnd);
+            }
+            Self::String(s) => events.push(Event::scalar(s.to_string(), tag("str"))),
+            Self::Null => events.push(Event::scalar("null", tag("null"))),
+            Self::Boolean(b) => events.push(Event::scalar(b.to_string(), tag("bool"))),
+            Self::Integer(i) => events.push(Event::scalar(i.to_string(), tag("int"))),
+            Self::BigInteger(i) => events.push(Event::scalar(i.to_string(), tag("int"))),
+            Self::FloatingPoint(f) => events.push(Event::scalar(
+                f.parse::<Float>()
+                    .map_or_else(|_| f.to_string(), |f| f.to_string()),
+                tag("float"),
+            )),
+            Self::Binary(b) => events.push(Event::scalar(BASE64.encode(b), tag("binary"))),
+            Self::Timestamp(t) => events.push(Event::scalar(t.to_string(), tag("timestamp"))),
+            Self::Tagged(tag, node) => {
+                let start = events.len();
+                node.collect_events(events);
+
+                // The tag is a property of the first event of the node, the
+                // outermost tag of nested tagged nodes applies
+                if let Event::Scalar { tag: node_tag, .. }
+                | Event::SequenceStart { tag: node_tag, .. }
+                | Event::MappingStart { tag: node_tag, .. } = &mut events[start]
+                {
+                    *node_tag = Some(tag.to_string());
+                }
+            }
+        }
+    }
+}
+
+impl IntoEvents for BumpNode<'_> {
+    fn into_events(self) -> Vec<Event> {
+        let mut events = Vec::new();
+        self.collect_events(&mut events);
+        events
+    }
+}
+
+impl IntoEvents for &BumpNode<'_> {
+    fn into_events(self) -> Vec<Event> {
+        let mut events = Vec::new();
+        self.collect_events(&mut events);
+        events
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Sequence;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let node = Node::Mapping(Mapping::from([
+            (Node::String("name".into()), Node::String("web".into())),
+            (
+                Node::String("ports".into()),
+                Node::Sequence(Sequence::from([Node::Integer(80), Node::Null])),
+            ),
+            (
+                Node::String("ratio".into()),
+                Node::FloatingPoint("0.5".parse().unwrap()),
+            ),
+        ]));
+
+        let bump = Bump::new();
+        let bump_node = BumpNode::from_node(&bump, &node);
+
+        assert_eq!(bump_node.to_node(), node);
+        assert_eq!((&bump_node).into_events(), node.clone().into_events());
+    }
+}
@@ -0,0 +1,158 @@
+//! Equality, ordering and hashing of [`Node`]s.
+//!
+//! YAML allows any node to be used as a mapping key, including floats, nulls
+//! and collections. To make these keys usable in Rust (sorting, duplicate
+//! detection, hashing), nodes implement [`Eq`], [`Ord`] and [`Hash`] with the
+//! following semantics:
+//!
+//! - Two nodes are only equal if they have the same tag. `1` (an integer) and
+//!   `1.0` (a float) are different keys.
+//! - Floats are compared by their numeric value. `.nan` is equal to itself
+//!   (as its canonical form is the same) and `-0.0` is equal to `0.0`.
+//! - Nodes of different kinds are ordered by tag: null, boolean, integer,
+//!   float, string, sequence and mapping. Within a kind, values are ordered
+//!   naturally. Floats are totally ordered with `.nan` sorted last.
+//!
+//! See <https://yaml.org/spec/1.2.2/#node-comparison>
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use crate::{integer, Node};
+
+impl Node {
+    /// The rank of the node kind, used to order nodes of different kinds.
+    fn rank(&self) -> u8 {
+        match self {
+            Node::Null => 0,
+            Node::Boolean(_) => 1,
+            Node::Integer(_) | Node::BigInteger(_) => 2,
+            Node::FloatingPoint(_) => 3,
+            Node::String(_) => 4,
+            Node::Sequence(_) => 5,
+            Node::Mapping(_) => 6,
+            Node::Tagged(_) => 7,
+            Node::Binary(_) => 8,
+            Node::Timestamp(_) => 9,
+        }
+    }
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Node::Null, Node::Null) => Ordering::Equal,
+            (Node::Boolean(a), Node::Boolean(b)) => a.cmp(b),
+            (Node::Integer(a), Node::Integer(b)) => a.cmp(b),
+            (Node::Integer(a), Node::BigInteger(b)) => integer::cmp_i64(*a, b),
+            (Node::BigInteger(a), Node::Integer(b)) => integer::cmp_i64(*b, a).reverse(),
+            (Node::BigInteger(a), Node::BigInteger(b)) => a.cmp(b),
+            (Node::FloatingPoint(a), Node::FloatingPoint(b)) => a.cmp(b),
+            (Node::String(a), Node::String(b)) => a.cmp(b),
+            (Node::Sequence(a), Node::Sequence(b)) => a.cmp(b),
+            (Node::Mapping(a), Node::Mapping(b)) => a.cmp(b),
+            (Node::Tagged(a), Node::Tagged(b)) => (&a.tag, &a.node).cmp(&(&b.tag, &b.node)),
+            (Node::Binary(a), Node::Binary(b)) => a.cmp(b),
+            (Node::Timestamp(a), Node::Timestamp(b)) => a.cmp(b),
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+}
+
+impl Hash for Node {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+
+        match self {
+            Node::Null => {}
+            Node::Boolean(b) => b.hash(state),
+            Node::Integer(i) => i.hash(state),
+            // Big integers which fit into an i64 are equal to the integer
+            Node::BigInteger(i) => match i64::try_from(i) {
+                Ok(i) => i.hash(state),
+                Err(_) => i.hash(state),
+            },
+            Node::FloatingPoint(f) => f.hash(state),
+            Node::String(s) => s.hash(state),
+            Node::Binary(b) => b.hash(state),
+            Node::Timestamp(t) => t.hash(state),
+            Node::Sequence(s) => s.hash(state),
+            Node::Mapping(m) => m.hash(state),
+            Node::Tagged(t) => {
+                t.tag.hash(state);
+                t.node.hash(state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn float(text: &str) -> Node {
+        Node::FloatingPoint(text.parse().unwrap())
+    }
+
+    #[test]
+    fn float_keys() {
+        let nan = float(".nan");
+        assert_eq!(nan, float(".NaN"));
+        assert_eq!(float("-0.0"), float("0"));
+        assert_ne!(float("1.0"), Node::Integer(1));
+
+        let keys = HashSet::from([
+            nan.clone(),
+            float(".NAN"),
+            float("1e3"),
+            float("1000.0"),
+            Node::Null,
+            Node::Null,
+        ]);
+        assert_eq!(keys.len(), 3);
+    }
+
+    #[test]
+    fn ordering() {
+        let mut keys = vec![
+            Node::String("a".into()),
+            float(".nan"),
+            float("-.inf"),
+            Node::Integer(2),
+            Node::Null,
+            float("1.5"),
+            Node::Boolean(false),
+        ];
+        keys.sort();
+
+        assert_eq!(
+            keys,
+            vec![
+                Node::Null,
+                Node::Boolean(false),
+                Node::Integer(2),
+                float("-.inf"),
+                float("1.5"),
+                float(".nan"),
+                Node::String("a".into()),
+            ]
+        );
+    }
+}
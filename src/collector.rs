@@ -0,0 +1,164 @@
+//! Collecting documents rendered in parallel into a single [`Stream`].
+//!
+//! A [`StreamCollector`] hands out [`StreamProducer`]s, which can be moved to
+//! worker threads. Each document is pushed together with an ordering key.
+//! Once all producers are dropped, [`StreamCollector::finish`] returns the
+//! documents sorted by their keys, independent of the order in which the
+//! workers finished.
+//!
+//! ```
+//! use std::thread;
+//!
+//! use yaml_ast::{collector::StreamCollector, Document, Mapping};
+//!
+//! let collector = StreamCollector::new();
+//!
+//! thread::scope(|scope| {
+//!     for index in 0..4 {
+//!         let producer = collector.producer();
+//!         scope.spawn(move || {
+//!             let mapping = Mapping::builder().key("index").value(index).build();
+//!             producer.push(index, Document::from_mapping(mapping)).unwrap();
+//!         });
+//!     }
+//! });
+//!
+//! let stream = collector.finish();
+//! assert_eq!(stream.len(), 4);
+//! assert_eq!(
+//!     stream.get(3).unwrap().nodes[0].get_path("index").and_then(|n| n.as_i64()),
+//!     Some(3)
+//! );
+//! ```
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use snafu::Snafu;
+
+use crate::{Document, Stream};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("the stream collector was dropped"))]
+    CollectorDropped,
+}
+
+/// Collects documents from multiple producers. See the [module](self)
+/// documentation for details.
+#[derive(Debug)]
+pub struct StreamCollector<K> {
+    sender: Sender<(K, Document)>,
+    receiver: Receiver<(K, Document)>,
+}
+
+impl<K> Default for StreamCollector<K>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> StreamCollector<K>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+
+    /// Returns a new producer, which can be sent to another thread.
+    pub fn producer(&self) -> StreamProducer<K> {
+        StreamProducer {
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// Waits until all producers are dropped and returns the collected
+    /// documents ordered by their keys.
+    ///
+    /// Documents with equal keys are kept in the order they were pushed,
+    /// which is only deterministic if they were pushed by the same producer.
+    /// Calling this while a producer is still alive on the current thread
+    /// blocks forever.
+    pub fn finish(self) -> Stream {
+        let Self { sender, receiver } = self;
+        drop(sender);
+
+        let mut documents: Vec<_> = receiver.into_iter().collect();
+        documents.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        documents
+            .into_iter()
+            .map(|(_, document)| document)
+            .collect()
+    }
+}
+
+/// Pushes documents into a [`StreamCollector`].
+#[derive(Debug)]
+pub struct StreamProducer<K> {
+    sender: Sender<(K, Document)>,
+}
+
+impl<K> Clone for StreamProducer<K> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<K> StreamProducer<K> {
+    /// Pushes a `document`, which is ordered by `key` in the final stream.
+    /// Returns an error if the collector was dropped without being finished.
+    pub fn push(&self, key: K, document: Document) -> Result<(), Error> {
+        self.sender
+            .send((key, document))
+            .map_err(|_| Error::CollectorDropped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use crate::{Mapping, Node};
+
+    use super::*;
+
+    #[test]
+    fn deterministic_order() {
+        let collector = StreamCollector::new();
+
+        thread::scope(|scope| {
+            for worker in 0..4 {
+                let producer = collector.producer();
+                scope.spawn(move || {
+                    for index in (worker..32).step_by(4).rev() {
+                        let mapping = Mapping::builder().key("index").value(index).build();
+                        producer
+                            .push(("resource", index), Document::from_mapping(mapping))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let indices: Vec<_> = collector
+            .finish()
+            .iter()
+            .map(|document| document.nodes[0].get_path("index").and_then(Node::as_i64))
+            .collect();
+        assert_eq!(indices, (0..32).map(Some).collect::<Vec<_>>());
+
+        let collector = StreamCollector::new();
+        let producer = collector.producer();
+        drop(collector);
+        assert_eq!(
+            producer.push(0, Document::new()),
+            Err(Error::CollectorDropped)
+        );
+    }
+}
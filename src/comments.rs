@@ -0,0 +1,185 @@
+//! Placement of the comments of sequence items in the event stream.
+//!
+//! Sequences are plain lists of nodes, which is why the comments of their
+//! items are stored in [`Document::item_comments`](crate::Document::item_comments)
+//! by path. When the document is turned into events, leading comments are
+//! inserted before the first event of the item and the inline comment
+//! directly after it, which places it on the line of the entry indicator.
+//!
+//! ```
+//! use yaml_ast::Stream;
+//!
+//! let input = "args:\n  # Verbose output\n  - -v\n  - --port=80 # default\n";
+//! let stream: Stream = input.parse().unwrap();
+//! assert_eq!(stream.to_string(), format!("---\n{input}...\n"));
+//! ```
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    emitter::key_segment,
+    events::{Event, PathSegment},
+    ItemComments, Node,
+};
+
+/// Returns the comments of each sequence item of `node` in the order the
+/// items start in the events produced by
+/// [`IntoEvents`](crate::events::IntoEvents), or [`None`] if the item has
+/// none.
+pub(crate) fn item_comments(
+    node: &Node,
+    comments: &HashMap<Vec<PathSegment>, ItemComments>,
+) -> Vec<Option<ItemComments>> {
+    let mut items = Vec::new();
+
+    if !comments.is_empty() {
+        collect_comments(node, Some(comments), &mut Vec::new(), &mut items);
+    }
+
+    items
+}
+
+/// Inserts the `comments` returned by [`item_comments`] into the `events`
+/// of the node.
+pub(crate) fn apply_item_comments<I: Iterator<Item = Event>>(
+    events: I,
+    comments: Vec<Option<ItemComments>>,
+) -> ItemCommentEvents<I> {
+    ItemCommentEvents {
+        events,
+        comments: comments.into_iter(),
+        output: VecDeque::new(),
+        sequences: Vec::new(),
+        inline_comment: None,
+    }
+}
+
+/// The events of a node with the comments of its sequence items, see
+/// [`apply_item_comments`].
+pub(crate) struct ItemCommentEvents<I> {
+    events: I,
+    comments: std::vec::IntoIter<Option<ItemComments>>,
+    output: VecDeque<Event>,
+
+    /// Whether each open collection is a sequence
+    sequences: Vec<bool>,
+    inline_comment: Option<String>,
+}
+
+impl<I: Iterator<Item = Event>> Iterator for ItemCommentEvents<I> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.output.pop_front() {
+            return Some(event);
+        }
+
+        let event = self.events.next()?;
+
+        // No comments are left to insert
+        if self.comments.as_slice().is_empty() && self.inline_comment.is_none() {
+            return Some(event);
+        }
+
+        let starts_node = event.starts_node();
+
+        if starts_node && self.sequences.last() == Some(&true) {
+            if let Some(Some(item)) = self.comments.next() {
+                self.output
+                    .extend(item.comments.into_iter().map(Event::Comment));
+                self.inline_comment = item.comment;
+            }
+        }
+
+        match &event {
+            Event::SequenceStart { .. } => self.sequences.push(true),
+            Event::MappingStart { .. } => self.sequences.push(false),
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.sequences.pop();
+            }
+            _ => {}
+        }
+
+        self.output.push_back(event);
+
+        if starts_node {
+            if let Some(text) = self.inline_comment.take() {
+                self.output.push_back(Event::InlineComment(text));
+            }
+        }
+
+        self.output.pop_front()
+    }
+}
+
+fn collect_comments(
+    node: &Node,
+    comments: Option<&HashMap<Vec<PathSegment>, ItemComments>>,
+    path: &mut Vec<PathSegment>,
+    items: &mut Vec<Option<ItemComments>>,
+) {
+    match node {
+        Node::Mapping(mapping) => {
+            for pair in mapping.pairs() {
+                collect_comments(&pair.key, None, path, items);
+
+                path.push(key_segment(&pair.key));
+                collect_comments(&pair.value, comments, path, items);
+                path.pop();
+            }
+        }
+        Node::Sequence(sequence) => {
+            for (index, item) in sequence.iter().enumerate() {
+                path.push(PathSegment::Index(index));
+                items.push(comments.and_then(|comments| comments.get(path.as_slice()).cloned()));
+                collect_comments(item, comments, path, items);
+                path.pop();
+            }
+        }
+        Node::Tagged(tagged) => collect_comments(&tagged.node, comments, path, items),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{events::PathSegment, yaml, Document, ItemComments, Stream};
+
+    #[test]
+    fn round_trip() {
+        let input = "\
+hosts:
+  # Primary
+  - name: a
+  - # Replica
+    name: b
+  - !Host # tagged
+    name: c
+matrix:
+  - # first row
+    - 1
+  - - 3 # nested
+";
+        let stream: Stream = input.parse().unwrap();
+        assert_eq!(stream.to_string(), format!("---\n{input}...\n"));
+    }
+
+    #[test]
+    fn document() {
+        let mut document = Document {
+            nodes: vec![yaml!({args: ["-v", 80]})],
+            ..Default::default()
+        };
+        document.item_comments.insert(
+            vec![PathSegment::Key("args".into()), PathSegment::Index(1)],
+            ItemComments {
+                comments: vec!["Port".into()],
+                comment: Some("default".into()),
+            },
+        );
+
+        assert_eq!(
+            document.to_string(),
+            "---\nargs:\n  - -v\n  # Port\n  - 80 # default\n...\n"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Output compatibility tests for downstream crates.
+//!
+//! The exact formatting of the emitter can change between releases of this
+//! crate. Downstream crates which need stable output, e.g. because generated
+//! files are committed, can pin it with a directory of fixtures and a test:
+//!
+//! ```no_run
+//! #[test]
+//! fn output_is_stable() {
+//!     yaml_ast::compat::assert_output_stable("tests/fixtures");
+//! }
+//! ```
+//!
+//! Each fixture consists of an input file `<name>.input.yaml` and the
+//! expected output `<name>.output.yaml`. The input is parsed and emitted
+//! again, which must reproduce the expected output byte for byte. Missing
+//! output files are created. Set the `YAML_AST_BLESS` environment variable
+//! to overwrite all output files with the current output instead, e.g.
+//! after reviewing an intentional change.
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::{
+    cst::{self, Cst},
+    emitter::{self, Emitter, EmitterOptions},
+    events::IntoEvents,
+};
+
+/// The environment variable which overwrites all expected outputs.
+pub const BLESS_VAR: &str = "YAML_AST_BLESS";
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("failed to read the fixtures directory {path:?}"))]
+    ReadDir {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to read the fixture {path:?}"))]
+    ReadFixture {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to write the fixture {path:?}"))]
+    WriteFixture {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to parse the fixture {path:?}"))]
+    ParseFixture { source: cst::Error, path: PathBuf },
+
+    #[snafu(display("failed to emit the fixture {path:?}"))]
+    EmitFixture {
+        source: emitter::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display(
+        "the output of {} fixture(s) changed:\n{}",
+        changes.len(),
+        changes.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n")
+    ))]
+    OutputChanged { changes: Vec<Change> },
+}
+
+/// The first line of a fixture whose output changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub path: PathBuf,
+
+    /// The number of the first changed line, starting at 1.
+    pub line: usize,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: expected {:?}, found {:?}",
+            self.path.display(),
+            self.line,
+            self.expected.as_deref().unwrap_or("<end of file>"),
+            self.actual.as_deref().unwrap_or("<end of file>")
+        )
+    }
+}
+
+/// Checks all fixtures in `dir` using the default [`EmitterOptions`] and
+/// panics if the output of any fixture changed. See the [module](self)
+/// documentation for details.
+#[track_caller]
+pub fn assert_output_stable(dir: impl AsRef<Path>) {
+    if let Err(error) = check_output_stable(dir, EmitterOptions::default) {
+        panic!("{error}");
+    }
+}
+
+/// Checks all fixtures in `dir`, emitting them with the options returned by
+/// `options`. Returns [`Error::OutputChanged`] with all changed fixtures.
+pub fn check_output_stable<F>(dir: impl AsRef<Path>, options: F) -> Result<(), Error>
+where
+    F: Fn() -> EmitterOptions,
+{
+    let dir = dir.as_ref();
+    let bless = std::env::var_os(BLESS_VAR).is_some();
+
+    let mut inputs: Vec<PathBuf> = fs::read_dir(dir)
+        .context(ReadDirSnafu { path: dir })?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.to_string_lossy().ends_with(".input.yaml"))
+        .collect();
+    inputs.sort();
+
+    let mut changes = Vec::new();
+
+    for input_path in inputs {
+        let input =
+            fs::read_to_string(&input_path).context(ReadFixtureSnafu { path: &input_path })?;
+
+        let stream = Cst::parse(input)
+            .context(cst::ParseSnafu)
+            .and_then(|cst| cst.to_stream())
+            .context(ParseFixtureSnafu { path: &input_path })?;
+
+        let mut actual = String::new();
+        Emitter::new(stream.into_events(), options())
+            .emit(&mut actual)
+            .context(EmitFixtureSnafu { path: &input_path })?;
+
+        let name = input_path
+            .to_string_lossy()
+            .replace(".input.yaml", ".output.yaml");
+        let output_path = PathBuf::from(name);
+
+        if bless || !output_path.exists() {
+            fs::write(&output_path, &actual).context(WriteFixtureSnafu { path: &output_path })?;
+            continue;
+        }
+
+        let expected =
+            fs::read_to_string(&output_path).context(ReadFixtureSnafu { path: &output_path })?;
+
+        if let Some(change) = first_change(&output_path, &expected, &actual) {
+            changes.push(change);
+        }
+    }
+
+    ensure!(changes.is_empty(), OutputChangedSnafu { changes });
+    Ok(())
+}
+
+/// Returns the first line which differs between `expected` and `actual`.
+fn first_change(path: &Path, expected: &str, actual: &str) -> Option<Change> {
+    if expected == actual {
+        return None;
+    }
+
+    let mut expected_lines = expected.split_inclusive('\n');
+    let mut actual_lines = actual.split_inclusive('\n');
+    let mut line = 1;
+
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (expected, actual) => {
+                return Some(Change {
+                    path: path.to_path_buf(),
+                    line,
+                    expected: expected.map(Into::into),
+                    actual: actual.map(Into::into),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detects_changes() {
+        let dir = std::env::temp_dir().join(format!("yaml-ast-compat-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.input.yaml"), "a:   1\n\nb: [2]\n").unwrap();
+
+        // The first run creates the missing output
+        check_output_stable(&dir, EmitterOptions::default).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("a.output.yaml")).unwrap(),
+            "---\na: 1\n\nb:\n  - 2\n...\n"
+        );
+        assert_output_stable(&dir);
+
+        let options = || {
+            EmitterOptions::builder()
+                .max_blank_lines(Some(0))
+                .build()
+                .unwrap()
+        };
+        let result = check_output_stable(&dir, options);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let Err(Error::OutputChanged { changes }) = result else {
+            panic!("expected changed output");
+        };
+        assert_eq!(changes[0].line, 3);
+        assert_eq!(changes[0].actual.as_deref(), Some("b:\n"));
+    }
+}
@@ -0,0 +1,456 @@
+//! A harness which runs the cases of the yaml-test-suite.
+//!
+//! The cases are loaded from a checkout of the `data` branch of the suite,
+//! where each case is a directory containing the input (`in.yaml`), the
+//! expected events (`test.event`), optionally the expected output
+//! (`out.yaml`) and an `error` file if parsing the input must fail. Cases
+//! with multiple variants contain numbered subdirectories instead.
+//!
+//! Each case runs the following checks, which are reported individually:
+//!
+//! - [`Check::Parse`]: the input parses and resolves, or fails to if an
+//!   error is expected.
+//! - [`Check::Events`]: the events of the parsed input, formatted by
+//!   [`Cst::to_test_suite_string`], match the expected events.
+//! - [`Check::RoundTrip`]: emitting the AST and parsing the output results
+//!   in the same AST.
+//! - [`Check::Output`]: the expected output results in the same AST as
+//!   the input.
+//!
+//! The same harness can run custom cases, e.g. to test application-specific
+//! tags with a custom schema set in the [`ResolveOptions`].
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use yaml_ast::{conformance, cst::ResolveOptions};
+//!
+//! let cases = conformance::load_cases(Path::new("yaml-test-suite")).unwrap();
+//! let report = conformance::run(&cases, &ResolveOptions::default());
+//!
+//! println!("{report}");
+//! ```
+//!
+//! This module requires the `conformance` feature.
+//!
+//! #### Reference
+//!
+//! - <https://github.com/yaml/yaml-test-suite>
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    cst::{Cst, ResolveOptions},
+    emitter::{Emitter, EmitterOptions},
+    events::{diff_events, IntoEvents},
+    Stream,
+};
+
+/// The maximum number of AST differences reported per check.
+const DIFFERENCE_LIMIT: usize = 5;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("failed to read {path:?}"))]
+    Read { path: PathBuf, source: io::Error },
+
+    #[snafu(display("the case directory {path:?} contains no in.yaml"))]
+    MissingInput { path: PathBuf },
+}
+
+/// A single case of the test suite.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Case {
+    /// The name of the case directory, followed by the variant for cases
+    /// with multiple variants, e.g. `2JQS` or `6XDY/01`.
+    pub id: String,
+
+    /// The description of the case, read from the `===` file.
+    pub name: String,
+    pub input: String,
+
+    /// The expected events in the notation of the test suite.
+    pub events: Option<String>,
+
+    /// The expected output of an emitter. Its formatting is not compared,
+    /// only the resulting AST.
+    pub output: Option<String>,
+
+    /// Whether parsing the input must fail.
+    pub error: bool,
+}
+
+/// A check run for each [`Case`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    Parse,
+    Events,
+    RoundTrip,
+    Output,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Check::Parse => "parse",
+            Check::Events => "events",
+            Check::RoundTrip => "round trip",
+            Check::Output => "output",
+        })
+    }
+}
+
+/// The outcome of a [`Check`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+
+    /// The check failed, described by a message or diff.
+    Failed(String),
+
+    /// The check doesn't apply to the case, e.g. because the case has no
+    /// expected output or the input failed to parse.
+    Skipped,
+}
+
+/// The outcomes of all checks of a [`Case`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaseReport {
+    pub id: String,
+    pub name: String,
+    pub outcomes: Vec<(Check, Outcome)>,
+}
+
+impl CaseReport {
+    /// Returns `true` if no check failed.
+    pub fn passed(&self) -> bool {
+        self.outcomes
+            .iter()
+            .all(|(_, outcome)| !matches!(outcome, Outcome::Failed(_)))
+    }
+}
+
+/// The reports of all cases of a run. It is displayed as a summary listing
+/// the failed checks with their diffs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub cases: Vec<CaseReport>,
+}
+
+impl Report {
+    /// Returns the reports of the cases with at least one failed check.
+    pub fn failures(&self) -> impl Iterator<Item = &CaseReport> {
+        self.cases.iter().filter(|case| !case.passed())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for case in self.failures() {
+            writeln!(f, "{} ({})", case.id, case.name)?;
+
+            for (check, outcome) in &case.outcomes {
+                if let Outcome::Failed(message) = outcome {
+                    writeln!(f, "  {check} failed:")?;
+
+                    for line in message.lines() {
+                        writeln!(f, "    {line}")?;
+                    }
+                }
+            }
+        }
+
+        let failed = self.failures().count();
+        write!(f, "{} passed, {failed} failed", self.cases.len() - failed)
+    }
+}
+
+/// Loads all cases of the test suite in the directory `dir`, sorted by
+/// their id. Directories without an `in.yaml` file or numbered variants are
+/// skipped.
+pub fn load_cases(dir: &Path) -> Result<Vec<Case>, Error> {
+    let mut cases = Vec::new();
+
+    for path in read_dir(dir)? {
+        let id = file_name(&path);
+
+        if path.join("in.yaml").is_file() {
+            cases.push(Case::load(&path, id)?);
+            continue;
+        }
+
+        for variant in read_dir(&path)? {
+            if variant.join("in.yaml").is_file() {
+                let id = format!("{id}/{}", file_name(&variant));
+                cases.push(Case::load(&variant, id)?);
+            }
+        }
+    }
+
+    cases.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(cases)
+}
+
+/// Runs all `cases`, resolving the parsed input using `options`.
+pub fn run(cases: &[Case], options: &ResolveOptions) -> Report {
+    Report {
+        cases: cases.iter().map(|case| case.run(options)).collect(),
+    }
+}
+
+impl Case {
+    /// Loads the case from the directory `dir`.
+    pub fn load(dir: &Path, id: impl Into<String>) -> Result<Self, Error> {
+        let read = |name: &str| -> Result<Option<String>, Error> {
+            let path = dir.join(name);
+
+            match fs::read_to_string(&path) {
+                Ok(content) => Ok(Some(content)),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err).context(ReadSnafu { path }),
+            }
+        };
+
+        let input = read("in.yaml")?.ok_or_else(|| {
+            MissingInputSnafu {
+                path: dir.to_path_buf(),
+            }
+            .build()
+        })?;
+
+        Ok(Self {
+            id: id.into(),
+            name: read("===")?.unwrap_or_default().trim().into(),
+            input,
+            events: read("test.event")?,
+            output: read("out.yaml")?,
+            error: dir.join("error").exists(),
+        })
+    }
+
+    /// Runs all checks of the case, resolving the parsed input using
+    /// `options`.
+    pub fn run(&self, options: &ResolveOptions) -> CaseReport {
+        let mut outcomes = Vec::new();
+        let parsed = Cst::parse(self.input.as_str())
+            .map_err(|err| err.to_string())
+            .and_then(|cst| match cst.to_stream_with(options) {
+                Ok(stream) => Ok((cst, stream)),
+                Err(err) => Err(err.to_string()),
+            });
+
+        let (cst, stream) = match (parsed, self.error) {
+            (Ok(_), true) => {
+                outcomes.push((Check::Parse, Outcome::Failed("expected an error".into())));
+                return self.report(outcomes);
+            }
+            (Err(_), true) => {
+                outcomes.push((Check::Parse, Outcome::Passed));
+                return self.report(outcomes);
+            }
+            (Err(err), false) => {
+                outcomes.push((Check::Parse, Outcome::Failed(err)));
+                return self.report(outcomes);
+            }
+            (Ok(parsed), false) => parsed,
+        };
+
+        outcomes.push((Check::Parse, Outcome::Passed));
+
+        let events = match &self.events {
+            Some(expected) => compare_lines(expected, &cst.to_test_suite_string()),
+            None => Outcome::Skipped,
+        };
+        outcomes.push((Check::Events, events));
+
+        let round_trip = emit(&stream)
+            .and_then(|output| resolve(&output, options))
+            .map_or_else(Outcome::Failed, |emitted| compare_streams(&stream, emitted));
+        outcomes.push((Check::RoundTrip, round_trip));
+
+        let output = match &self.output {
+            Some(output) => resolve(output, options).map_or_else(Outcome::Failed, |expected| {
+                compare_streams(&stream, expected)
+            }),
+            None => Outcome::Skipped,
+        };
+        outcomes.push((Check::Output, output));
+
+        self.report(outcomes)
+    }
+
+    fn report(&self, outcomes: Vec<(Check, Outcome)>) -> CaseReport {
+        CaseReport {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            outcomes,
+        }
+    }
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let entries = fs::read_dir(dir).context(ReadSnafu { path: dir })?;
+    let mut paths = Vec::new();
+
+    for entry in entries {
+        let path = entry.context(ReadSnafu { path: dir })?.path();
+        if path.is_dir() {
+            paths.push(path);
+        }
+    }
+
+    Ok(paths)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn emit(stream: &Stream) -> Result<String, String> {
+    let mut output = String::new();
+
+    Emitter::new(stream.clone().into_events(), EmitterOptions::default())
+        .emit(&mut output)
+        .map_err(|err| format!("failed to emit: {err}"))?;
+
+    Ok(output)
+}
+
+fn resolve(input: &str, options: &ResolveOptions) -> Result<Stream, String> {
+    Cst::parse(input)
+        .map_err(|err| format!("failed to parse: {err}"))?
+        .to_stream_with(options)
+        .map_err(|err| format!("failed to resolve: {err}"))
+}
+
+fn compare_streams(expected: &Stream, actual: Stream) -> Outcome {
+    let differences = diff_events(
+        expected.clone().into_events(),
+        actual.into_events(),
+        DIFFERENCE_LIMIT,
+    );
+
+    match differences.is_empty() {
+        true => Outcome::Passed,
+        false => Outcome::Failed(
+            differences
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+    }
+}
+
+/// Compares the lines of `expected` and `actual`. On mismatch, the outcome
+/// contains a line diff, where missing lines are prefixed with `-` and
+/// unexpected lines with `+`.
+fn compare_lines(expected: &str, actual: &str) -> Outcome {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+
+    if expected == actual {
+        return Outcome::Passed;
+    }
+
+    // The lengths of the longest common subsequences of all suffixes
+    let mut lengths = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lengths[i][j] = match expected[i] == actual[j] {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::new();
+
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            diff.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if j == actual.len()
+            || (i < expected.len() && lengths[i + 1][j] >= lengths[i][j + 1])
+        {
+            diff.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+
+    Outcome::Failed(diff.join("\n"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_case(dir: &Path, files: &[(&str, &str)]) {
+        fs::create_dir_all(dir).unwrap();
+
+        for (name, content) in files {
+            fs::write(dir.join(name), content).unwrap();
+        }
+    }
+
+    #[test]
+    fn harness() {
+        let dir = std::env::temp_dir().join(format!("yaml-ast-conformance-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        write_case(
+            &dir.join("AAAA"),
+            &[
+                ("===", "Simple mapping\n"),
+                ("in.yaml", "a: 'b'\n"),
+                (
+                    "test.event",
+                    "+STR\n+DOC\n+MAP\n=VAL :a\n=VAL 'b\n-MAP\n-DOC\n-STR\n",
+                ),
+                ("out.yaml", "a: b\n"),
+            ],
+        );
+        write_case(
+            &dir.join("BBBB/00"),
+            &[
+                ("in.yaml", "- 1\n"),
+                (
+                    "test.event",
+                    "+STR\n+DOC\n+SEQ\n=VAL :2\n-SEQ\n-DOC\n-STR\n",
+                ),
+            ],
+        );
+        write_case(&dir.join("BBBB/01"), &[("in.yaml", "[a\n"), ("error", "")]);
+
+        let cases = load_cases(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let ids: Vec<_> = cases.iter().map(|case| case.id.as_str()).collect();
+        assert_eq!(ids, ["AAAA", "BBBB/00", "BBBB/01"]);
+
+        let report = run(&cases, &ResolveOptions::default());
+        assert!(report.cases[0].passed());
+        assert!(report.cases[2].passed());
+        assert_eq!(
+            report.cases[1].outcomes[1],
+            (
+                Check::Events,
+                Outcome::Failed(
+                    "  +STR\n  +DOC\n  +SEQ\n- =VAL :2\n+ =VAL :1\n  -SEQ\n  -DOC\n  -STR".into()
+                )
+            )
+        );
+        assert!(report.to_string().ends_with("2 passed, 1 failed"));
+    }
+}
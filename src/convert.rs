@@ -0,0 +1,542 @@
+//! Conversions from plain Rust data into [`Node`]s.
+//!
+//! The [`ToNode`] trait provides a lightweight, serde-independent way to turn
+//! common Rust types into YAML nodes. It is implemented for primitives,
+//! strings, std collections, tuples, [`Option`] and [`Result`]. Types which
+//! only implement [`Display`](fmt::Display) or [`Debug`](fmt::Debug) can be
+//! converted on a best-effort basis by wrapping them in [`DisplayNode`] or
+//! [`DebugNode`].
+//!
+//! Owned values can also be converted using the [`From`] implementations of
+//! [`Node`], which keeps tree construction short:
+//!
+//! ```
+//! use yaml_ast::{Mapping, Node};
+//!
+//! let node = Node::from(Mapping::from([
+//!     ("name".into(), "web".into()),
+//!     ("replicas".into(), 3.into()),
+//!     ("ports".into(), vec![80, 443].into()),
+//! ]));
+//! ```
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+    fmt,
+    rc::Rc,
+    sync::Arc,
+};
+
+use crate::{
+    float::Float, integer::BigInteger, tag::Tagged, timestamp::Timestamp, Mapping, Node, Sequence,
+};
+
+/// This trait is used to turn (borrowed) Rust data into a YAML [`Node`].
+///
+/// The conversion is infallible. Values which cannot be represented exactly
+/// by a node variant are converted on a best-effort basis, e.g. [`char`]s
+/// are represented by a string.
+pub trait ToNode {
+    /// Converts `self` into a YAML [`Node`].
+    fn to_node(&self) -> Node;
+}
+
+/// Wraps a value implementing [`Display`](fmt::Display) and converts it into
+/// a [`Node::String`] using its display representation.
+#[derive(Debug)]
+pub struct DisplayNode<T>(pub T);
+
+impl<T> ToNode for DisplayNode<T>
+where
+    T: fmt::Display,
+{
+    fn to_node(&self) -> Node {
+        Node::String(self.0.to_string())
+    }
+}
+
+/// Wraps a value implementing [`Debug`](fmt::Debug) and converts it into a
+/// [`Node::String`] using its debug representation.
+#[derive(Debug)]
+pub struct DebugNode<T>(pub T);
+
+impl<T> ToNode for DebugNode<T>
+where
+    T: fmt::Debug,
+{
+    fn to_node(&self) -> Node {
+        Node::String(format!("{:?}", self.0))
+    }
+}
+
+impl ToNode for Node {
+    fn to_node(&self) -> Node {
+        self.clone()
+    }
+}
+
+impl ToNode for () {
+    fn to_node(&self) -> Node {
+        Node::Null
+    }
+}
+
+impl ToNode for bool {
+    fn to_node(&self) -> Node {
+        Node::Boolean(*self)
+    }
+}
+
+impl ToNode for char {
+    fn to_node(&self) -> Node {
+        Node::String(self.to_string())
+    }
+}
+
+impl ToNode for str {
+    fn to_node(&self) -> Node {
+        Node::String(self.into())
+    }
+}
+
+impl ToNode for String {
+    fn to_node(&self) -> Node {
+        Node::String(self.clone())
+    }
+}
+
+impl ToNode for Cow<'_, str> {
+    fn to_node(&self) -> Node {
+        Node::String(self.to_string())
+    }
+}
+
+macro_rules! impl_to_node_int {
+    ($($ty:ty),*) => {
+        $(
+            impl ToNode for $ty {
+                fn to_node(&self) -> Node {
+                    match i64::try_from(*self) {
+                        Ok(i) => Node::Integer(i),
+                        Err(_) => Node::BigInteger(BigInteger::from(*self)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_to_node_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_to_node_float {
+    ($($ty:ty),*) => {
+        $(
+            impl ToNode for $ty {
+                fn to_node(&self) -> Node {
+                    Node::FloatingPoint(Float::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+impl_to_node_float!(f32, f64);
+
+impl<T> ToNode for &T
+where
+    T: ToNode + ?Sized,
+{
+    fn to_node(&self) -> Node {
+        (**self).to_node()
+    }
+}
+
+impl<T> ToNode for &mut T
+where
+    T: ToNode + ?Sized,
+{
+    fn to_node(&self) -> Node {
+        (**self).to_node()
+    }
+}
+
+impl<T> ToNode for Box<T>
+where
+    T: ToNode + ?Sized,
+{
+    fn to_node(&self) -> Node {
+        (**self).to_node()
+    }
+}
+
+impl<T> ToNode for Rc<T>
+where
+    T: ToNode + ?Sized,
+{
+    fn to_node(&self) -> Node {
+        (**self).to_node()
+    }
+}
+
+impl<T> ToNode for Arc<T>
+where
+    T: ToNode + ?Sized,
+{
+    fn to_node(&self) -> Node {
+        (**self).to_node()
+    }
+}
+
+impl<T> ToNode for Option<T>
+where
+    T: ToNode,
+{
+    fn to_node(&self) -> Node {
+        match self {
+            Some(value) => value.to_node(),
+            None => Node::Null,
+        }
+    }
+}
+
+/// Results are represented as a mapping with a single `Ok` or `Err` key,
+/// which mirrors the externally tagged representation commonly used for
+/// enums.
+impl<T, E> ToNode for Result<T, E>
+where
+    T: ToNode,
+    E: ToNode,
+{
+    fn to_node(&self) -> Node {
+        let (key, value) = match self {
+            Ok(value) => ("Ok", value.to_node()),
+            Err(err) => ("Err", err.to_node()),
+        };
+
+        Node::Mapping(Mapping::from([(Node::String(key.into()), value)]))
+    }
+}
+
+fn sequence_to_node<'a, T, I>(items: I) -> Node
+where
+    T: ToNode + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    Node::Sequence(items.into_iter().map(ToNode::to_node).collect::<Sequence>())
+}
+
+fn mapping_to_node<'a, K, V, I>(pairs: I) -> Node
+where
+    K: ToNode + 'a,
+    V: ToNode + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+{
+    Node::Mapping(
+        pairs
+            .into_iter()
+            .map(|(k, v)| (k.to_node(), v.to_node()))
+            .collect::<Mapping>(),
+    )
+}
+
+impl<T> ToNode for [T]
+where
+    T: ToNode,
+{
+    fn to_node(&self) -> Node {
+        sequence_to_node(self)
+    }
+}
+
+impl<T, const N: usize> ToNode for [T; N]
+where
+    T: ToNode,
+{
+    fn to_node(&self) -> Node {
+        sequence_to_node(self)
+    }
+}
+
+impl<T> ToNode for Vec<T>
+where
+    T: ToNode,
+{
+    fn to_node(&self) -> Node {
+        sequence_to_node(self)
+    }
+}
+
+impl<T> ToNode for VecDeque<T>
+where
+    T: ToNode,
+{
+    fn to_node(&self) -> Node {
+        sequence_to_node(self)
+    }
+}
+
+impl<T> ToNode for LinkedList<T>
+where
+    T: ToNode,
+{
+    fn to_node(&self) -> Node {
+        sequence_to_node(self)
+    }
+}
+
+impl<T> ToNode for BTreeSet<T>
+where
+    T: ToNode,
+{
+    fn to_node(&self) -> Node {
+        sequence_to_node(self)
+    }
+}
+
+/// The order of the items in the resulting sequence follows the iteration
+/// order of the set, which is unspecified.
+impl<T, S> ToNode for HashSet<T, S>
+where
+    T: ToNode,
+{
+    fn to_node(&self) -> Node {
+        sequence_to_node(self)
+    }
+}
+
+impl<K, V> ToNode for BTreeMap<K, V>
+where
+    K: ToNode,
+    V: ToNode,
+{
+    fn to_node(&self) -> Node {
+        mapping_to_node(self)
+    }
+}
+
+/// The order of the pairs in the resulting mapping follows the iteration
+/// order of the map, which is unspecified.
+impl<K, V, S> ToNode for HashMap<K, V, S>
+where
+    K: ToNode,
+    V: ToNode,
+{
+    fn to_node(&self) -> Node {
+        mapping_to_node(self)
+    }
+}
+
+macro_rules! impl_to_node_tuple {
+    ($(($($name:ident),+)),+) => {
+        $(
+            /// Tuples are represented as a sequence of their elements.
+            impl<$($name),+> ToNode for ($($name,)+)
+            where
+                $($name: ToNode),+
+            {
+                #[allow(non_snake_case)]
+                fn to_node(&self) -> Node {
+                    let ($($name,)+) = self;
+                    Node::Sequence(Sequence::from([$($name.to_node()),+]))
+                }
+            }
+        )+
+    };
+}
+
+impl_to_node_tuple!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+    (A, B, C, D, E, F, G),
+    (A, B, C, D, E, F, G, H)
+);
+
+macro_rules! impl_from_scalar {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Node {
+                fn from(value: $ty) -> Self {
+                    value.to_node()
+                }
+            }
+        )*
+    };
+}
+
+impl_from_scalar!(
+    &str,
+    String,
+    Cow<'_, str>,
+    char,
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    f32,
+    f64
+);
+
+impl From<Mapping> for Node {
+    fn from(mapping: Mapping) -> Self {
+        Node::Mapping(mapping)
+    }
+}
+
+impl From<Float> for Node {
+    fn from(float: Float) -> Self {
+        Node::FloatingPoint(float)
+    }
+}
+
+impl From<Timestamp> for Node {
+    fn from(timestamp: Timestamp) -> Self {
+        Node::Timestamp(timestamp)
+    }
+}
+
+impl From<Tagged> for Node {
+    fn from(tagged: Tagged) -> Self {
+        Node::Tagged(Box::new(tagged))
+    }
+}
+
+impl<T> From<Option<T>> for Node
+where
+    T: Into<Node>,
+{
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Node::Null, Into::into)
+    }
+}
+
+impl<T> From<Vec<T>> for Node
+where
+    T: Into<Node>,
+{
+    fn from(items: Vec<T>) -> Self {
+        Node::Sequence(items.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Collects the nodes into a sequence node.
+impl FromIterator<Node> for Node {
+    fn from_iter<T: IntoIterator<Item = Node>>(iter: T) -> Self {
+        Node::Sequence(iter.into_iter().collect())
+    }
+}
+
+impl<K, V> From<BTreeMap<K, V>> for Node
+where
+    K: Into<Node>,
+    V: Into<Node>,
+{
+    fn from(map: BTreeMap<K, V>) -> Self {
+        Node::Mapping(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+/// The order of the pairs in the resulting mapping follows the iteration
+/// order of the map, which is unspecified.
+impl<K, V, S> From<HashMap<K, V, S>> for Node
+where
+    K: Into<Node>,
+    V: Into<Node>,
+{
+    fn from(map: HashMap<K, V, S>) -> Self {
+        Node::Mapping(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scalars() {
+        assert!(matches!(true.to_node(), Node::Boolean(true)));
+        assert!(matches!(42u8.to_node(), Node::Integer(42)));
+        assert!(
+            matches!(u64::MAX.to_node(), Node::BigInteger(ref i) if i.to_string() == "18446744073709551615")
+        );
+        assert!(matches!(1.5f64.to_node(), Node::FloatingPoint(ref f) if f.to_string() == "1.5"));
+        assert!(
+            matches!(f64::NEG_INFINITY.to_node(), Node::FloatingPoint(ref f) if f.to_string() == "-.inf")
+        );
+        assert!(matches!(None::<u8>.to_node(), Node::Null));
+        assert!(matches!(DisplayNode(1.5).to_node(), Node::String(ref s) if s == "1.5"));
+        assert!(matches!(DebugNode("a").to_node(), Node::String(ref s) if s == "\"a\""));
+    }
+
+    #[test]
+    fn collections() {
+        let map = BTreeMap::from([("a", vec![1, 2]), ("b", vec![])]);
+
+        match &map.to_node() {
+            Node::Mapping(pairs) => {
+                assert_eq!(pairs.len(), 2);
+                assert!(matches!(&pairs[0].key, Node::String(k) if k == "a"));
+                assert!(matches!(&pairs[0].value, Node::Sequence(s) if s.len() == 2));
+            }
+            node => panic!("expected mapping, got {node:?}"),
+        }
+
+        match &("a", 1, Err::<(), _>("boom")).to_node() {
+            Node::Sequence(items) => {
+                assert_eq!(items.len(), 3);
+                assert!(matches!(&items[2], Node::Mapping(m) if m.len() == 1));
+            }
+            node => panic!("expected sequence, got {node:?}"),
+        }
+    }
+
+    #[test]
+    fn from() {
+        assert_eq!(Node::from("a"), Node::String("a".into()));
+        assert_eq!(Node::from(3u32), Node::Integer(3));
+        assert_eq!(Node::from(None::<bool>), Node::Null);
+        assert_eq!(
+            Node::from(vec![Some(1), None]),
+            Node::Sequence(Sequence::from([Node::Integer(1), Node::Null]))
+        );
+        assert_eq!(
+            Node::from(HashMap::from([("a", 1.5)])),
+            Node::Mapping(Mapping::from([(
+                Node::String("a".into()),
+                Node::FloatingPoint(Float::new(1.5))
+            )]))
+        );
+        assert_eq!(
+            (1..3).map(Node::from).collect::<Node>(),
+            Node::Sequence(Sequence::from([Node::Integer(1), Node::Integer(2)]))
+        );
+        assert_eq!(
+            Mapping::from([
+                ("singleNode".into(), false.into()),
+                ("roles".into(), vec!["master", "ingest"].into()),
+            ]),
+            Mapping::from([
+                (Node::String("singleNode".into()), Node::Boolean(false)),
+                (
+                    Node::String("roles".into()),
+                    Node::Sequence(Sequence::from([
+                        Node::String("master".into()),
+                        Node::String("ingest".into()),
+                    ])),
+                ),
+            ])
+        );
+    }
+}
@@ -0,0 +1,1525 @@
+//! A lossless concrete syntax tree (CST) of a YAML character stream.
+//!
+//! In contrast to the AST ([`Stream`], [`Document`] and [`Node`]), the CST
+//! keeps the original source text around and records where each node is
+//! located in it. Rendering a CST reproduces the input byte for byte, including
+//! whitespace, quoting styles, blank lines, comments and key order. Edits only
+//! touch the text of the replaced node, which keeps diffs against the original
+//! file minimal.
+//!
+//! ```
+//! use yaml_ast::{cst::Cst, Node};
+//!
+//! let mut cst = Cst::parse("# Replicas\nreplicas: 1 # scaled\nname: 'web'\n").unwrap();
+//! let span = cst.documents()[0].root().unwrap().get("replicas").unwrap().span;
+//!
+//! cst.replace(span, &Node::Integer(3)).unwrap();
+//! assert_eq!(cst.to_string(), "# Replicas\nreplicas: 3 # scaled\nname: 'web'\n");
+//! ```
+use std::{collections::HashMap, fmt, ops::ControlFlow};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    directive::Directive,
+    emitter::{
+        break_lines, double_quoted, key_segment, needs_quotes, shorthand_tag, Emitter,
+        EmitterOptions, LineBreaker, CORE_TAG_PREFIX,
+    },
+    events::display_path,
+    events::{test_suite_node, Event, EventReceiver, IntoEvents, PathSegment},
+    float::Float,
+    lexical::is_lexical_form,
+    limits::{Limit, Limits},
+    parser::{self, Span},
+    schema::{resolve_core, CoreSchema, Schema},
+    style::Style,
+    tag::Tagged,
+    Document, ItemComments, Kind, Mapping, MappingPair, Node, Sequence, Stream,
+};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("failed to parse the character stream"))]
+    Parse { source: parser::Error },
+
+    #[snafu(display("failed to emit the stream"))]
+    Emit { source: crate::emitter::Error },
+
+    #[snafu(display("the alias {name:?} refers to an unknown anchor"))]
+    UnknownAlias { name: String },
+
+    #[snafu(display(
+        "the alias {name:?} at {} refers to its enclosing node at {}, which forms a cycle",
+        display_path(path),
+        display_path(anchor_path)
+    ))]
+    AliasCycle {
+        name: String,
+
+        /// The path of the alias.
+        path: Vec<PathSegment>,
+
+        /// The path of the anchored node containing the alias.
+        anchor_path: Vec<PathSegment>,
+    },
+
+    #[snafu(display("the scalar {value:?} is not a valid {tag}"))]
+    InvalidTaggedScalar { value: String, tag: String },
+
+    #[snafu(display("no node is located at {span}"))]
+    NodeNotFound { span: Span },
+
+    #[snafu(display("expected a single document, found {count}"))]
+    MultipleDocuments { count: usize },
+
+    #[snafu(display("exceeded the {limit} at {}", display_path(path)))]
+    LimitExceeded {
+        limit: Limit,
+        path: Vec<PathSegment>,
+    },
+}
+
+/// The style used to write a scalar in the source text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarStyle {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    Literal,
+    Folded,
+}
+
+/// The style used to write a collection in the source text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectionStyle {
+    Block,
+    Flow,
+}
+
+/// A comment including the leading `#`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comment {
+    pub span: Span,
+    pub text: String,
+}
+
+/// Comments and blank lines preceding a mapping pair or sequence item.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trivia {
+    pub comments: Vec<Comment>,
+    pub blank_lines: usize,
+}
+
+/// A node of the CST. The `span` covers the content of the node, excluding
+/// its properties (tag and anchor).
+#[derive(Clone, Debug)]
+pub struct CstNode {
+    pub span: Span,
+    pub anchor: Option<String>,
+    pub tag: Option<String>,
+    pub kind: CstKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum CstKind {
+    /// A scalar with its decoded value, i.e. with escapes and line folding
+    /// applied.
+    Scalar {
+        style: ScalarStyle,
+        value: String,
+    },
+    Alias(String),
+    Sequence {
+        style: CollectionStyle,
+        items: Vec<CstItem>,
+    },
+    Mapping {
+        style: CollectionStyle,
+        pairs: Vec<CstPair>,
+    },
+}
+
+/// An item of a sequence with the comments and blank lines around it.
+#[derive(Clone, Debug)]
+pub struct CstItem {
+    pub node: CstNode,
+    pub leading: Trivia,
+    pub trailing_comment: Option<Comment>,
+}
+
+/// A key/value pair of a mapping with the comments and blank lines around it.
+#[derive(Clone, Debug)]
+pub struct CstPair {
+    pub key: CstNode,
+    pub value: CstNode,
+    pub leading: Trivia,
+    pub trailing_comment: Option<Comment>,
+
+    /// Indicates that the key is written using the explicit key indicator
+    /// `?`.
+    pub explicit_key: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CstDocument {
+    pub span: Span,
+    pub directives: Vec<Directive>,
+    pub explicit_start: bool,
+    pub explicit_end: bool,
+    pub root: Option<CstNode>,
+
+    /// The number of blank lines preceding the document start marker.
+    pub blank_lines: usize,
+
+    /// All comments of the document in source order, including comments
+    /// attached to pairs and items.
+    pub comments: Vec<Comment>,
+}
+
+impl Comment {
+    /// Returns the text of the comment without the leading `#` and the
+    /// space following it.
+    pub fn content(&self) -> String {
+        let text = self.text.strip_prefix('#').unwrap_or(&self.text);
+        text.strip_prefix(' ').unwrap_or(text).to_string()
+    }
+}
+
+impl CstDocument {
+    pub fn root(&self) -> Option<&CstNode> {
+        self.root.as_ref()
+    }
+
+    /// Pushes the events of the document, from its directives to the
+    /// [`Event::DocumentEnd`], into the `receiver`. See
+    /// [`Cst::push_events`].
+    pub fn push_events<R: EventReceiver + ?Sized>(
+        &self,
+        receiver: &mut R,
+    ) -> ControlFlow<R::Break> {
+        let span = Some(self.span);
+
+        for directive in &self.directives {
+            receiver.on_event(Event::Directive(directive.clone()), span)?;
+        }
+
+        receiver.on_event(Event::DocumentStart, span)?;
+        match &self.root {
+            Some(root) => root.push_events(receiver)?,
+            None => receiver.on_event(Event::scalar("", None), span)?,
+        }
+        receiver.on_event(Event::DocumentEnd, span)
+    }
+}
+
+impl CstNode {
+    pub fn kind(&self) -> Kind {
+        match self.kind {
+            CstKind::Scalar { .. } => Kind::Scalar,
+            CstKind::Alias(_) => Kind::Alias,
+            CstKind::Sequence { .. } => Kind::Sequence,
+            CstKind::Mapping { .. } => Kind::Mapping,
+        }
+    }
+
+    /// Returns the explicit tag of the node resolved into a full tag, e.g.
+    /// `tag:yaml.org,2002:str` for `!!str`. Returns [`None`] if the node has
+    /// no tag or the non-specific tag `!`, which leaves the resolution to
+    /// the schema.
+    pub fn resolved_tag(&self) -> Option<String> {
+        self.tag
+            .as_deref()
+            .filter(|tag| *tag != "!")
+            .map(resolve_tag)
+    }
+
+    pub fn anchor(&self) -> Option<&str> {
+        self.anchor.as_deref()
+    }
+
+    /// Appends the events of the node in the notation of the yaml-test-suite
+    /// to `lines`.
+    fn push_test_suite_lines(&self, lines: &mut Vec<String>) {
+        let anchor = self.anchor.as_deref();
+        let tag = self.tag.as_deref().map(|tag| match tag {
+            "!" => tag.into(),
+            tag => resolve_tag(tag),
+        });
+        let tag = tag.as_deref();
+
+        match &self.kind {
+            CstKind::Scalar { style, value } => {
+                let style = match style {
+                    ScalarStyle::Plain => ':',
+                    ScalarStyle::SingleQuoted => '\'',
+                    ScalarStyle::DoubleQuoted => '"',
+                    ScalarStyle::Literal => '|',
+                    ScalarStyle::Folded => '>',
+                };
+
+                lines.push(test_suite_node("=VAL", anchor, tag, Some((style, value))));
+            }
+            CstKind::Alias(name) => lines.push(format!("=ALI *{name}")),
+            CstKind::Sequence { style, items } => {
+                let indicator = match style {
+                    CollectionStyle::Block => "+SEQ",
+                    CollectionStyle::Flow => "+SEQ []",
+                };
+
+                lines.push(test_suite_node(indicator, anchor, tag, None));
+                for item in items {
+                    item.node.push_test_suite_lines(lines);
+                }
+                lines.push("-SEQ".into());
+            }
+            CstKind::Mapping { style, pairs } => {
+                let indicator = match style {
+                    CollectionStyle::Block => "+MAP",
+                    CollectionStyle::Flow => "+MAP {}",
+                };
+
+                lines.push(test_suite_node(indicator, anchor, tag, None));
+                for pair in pairs {
+                    pair.key.push_test_suite_lines(lines);
+                    pair.value.push_test_suite_lines(lines);
+                }
+                lines.push("-MAP".into());
+            }
+        }
+    }
+
+    fn push_events<R: EventReceiver + ?Sized>(&self, receiver: &mut R) -> ControlFlow<R::Break> {
+        let span = Some(self.span);
+        let tag = self.resolved_tag();
+        let anchor = self.anchor.clone();
+
+        match &self.kind {
+            CstKind::Scalar { style, value } => {
+                let event = Event::Scalar {
+                    value: value.clone(),
+                    tag,
+                    anchor,
+                    style: Some(*style),
+                };
+
+                receiver.on_event(event, span)
+            }
+            CstKind::Alias(name) => receiver.on_event(Event::Alias(name.clone()), span),
+            CstKind::Sequence { style, items } => {
+                let event = Event::SequenceStart {
+                    tag,
+                    anchor,
+                    style: Some(*style),
+                };
+
+                receiver.on_event(event, span)?;
+                for item in items {
+                    item.node.push_events(receiver)?;
+                }
+                receiver.on_event(Event::SequenceEnd, span)
+            }
+            CstKind::Mapping { style, pairs } => {
+                let event = Event::MappingStart {
+                    tag,
+                    anchor,
+                    style: Some(*style),
+                };
+
+                receiver.on_event(event, span)?;
+                for pair in pairs {
+                    pair.key.push_events(receiver)?;
+                    pair.value.push_events(receiver)?;
+                }
+                receiver.on_event(Event::MappingEnd, span)
+            }
+        }
+    }
+
+    /// Returns the name of the anchor this node refers to, if it is an
+    /// alias.
+    pub fn alias(&self) -> Option<&str> {
+        match &self.kind {
+            CstKind::Alias(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of nodes of the subtree starting at this node,
+    /// including the node itself. Aliases count as a single node.
+    pub fn node_count(&self) -> usize {
+        let children: usize = match &self.kind {
+            CstKind::Scalar { .. } | CstKind::Alias(_) => 0,
+            CstKind::Sequence { items, .. } => {
+                items.iter().map(|item| item.node.node_count()).sum()
+            }
+            CstKind::Mapping { pairs, .. } => pairs
+                .iter()
+                .map(|pair| pair.key.node_count() + pair.value.node_count())
+                .sum(),
+        };
+
+        children + 1
+    }
+
+    /// Returns `true` if the node is an empty plain scalar, i.e. a value which
+    /// was completely omitted in the source.
+    pub fn is_empty(&self) -> bool {
+        matches!(&self.kind, CstKind::Scalar { style: ScalarStyle::Plain, value } if value.is_empty())
+    }
+
+    /// Returns the value of the first pair whose key is a scalar equal to
+    /// `key`. Returns [`None`] if the node is not a mapping.
+    pub fn get(&self, key: &str) -> Option<&CstNode> {
+        match &self.kind {
+            CstKind::Mapping { pairs, .. } => pairs
+                .iter()
+                .find(
+                    |pair| matches!(&pair.key.kind, CstKind::Scalar { value, .. } if value == key),
+                )
+                .map(|pair| &pair.value),
+            _ => None,
+        }
+    }
+
+    /// Returns the item at `index`. Returns [`None`] if the node is not a
+    /// sequence.
+    pub fn item(&self, index: usize) -> Option<&CstNode> {
+        match &self.kind {
+            CstKind::Sequence { items, .. } => items.get(index).map(|item| &item.node),
+            _ => None,
+        }
+    }
+
+    /// Returns the node (this node or any descendant) whose span is equal to
+    /// `span`.
+    pub fn find(&self, span: Span) -> Option<&CstNode> {
+        if self.span == span {
+            return Some(self);
+        }
+
+        match &self.kind {
+            CstKind::Sequence { items, .. } => items.iter().find_map(|item| item.node.find(span)),
+            CstKind::Mapping { pairs, .. } => pairs
+                .iter()
+                .find_map(|pair| pair.key.find(span).or_else(|| pair.value.find(span))),
+            _ => None,
+        }
+    }
+
+    /// Converts the CST node into an AST [`Node`] using the `options`.
+    /// Aliases are resolved by copying the node stored in `anchors`. The
+    /// lexical forms of plain null and boolean scalars, like `True`, and the
+    /// comments of sequence items are stored in the `document` by their
+    /// `path`, see [`Document::lexical_forms`] and
+    /// [`Document::item_comments`]. So are the [`Document::styles`] if
+    /// [`ResolveOptions::keep_styles`] is set.
+    fn to_node(
+        &self,
+        anchors: &mut Anchors,
+        options: &ResolveOptions,
+        path: &mut Vec<PathSegment>,
+        document: &mut Document,
+    ) -> Result<Node, Error> {
+        let start = anchors.resolved;
+        if !matches!(self.kind, CstKind::Alias(_)) {
+            let depth = match self.kind {
+                CstKind::Scalar { .. } => 0,
+                _ => 1,
+            };
+            anchors.count(1, depth, path)?;
+        }
+
+        // An alias refers to the most recent node with the anchor, which is
+        // still being resolved for aliases nested in it
+        let is_open = match (&self.anchor, &self.kind) {
+            (Some(anchor), CstKind::Sequence { .. } | CstKind::Mapping { .. }) => {
+                anchors.nodes.remove(anchor);
+                anchors.open.push((anchor.clone(), path.clone()));
+                true
+            }
+            _ => false,
+        };
+
+        let node = match &self.kind {
+            CstKind::Scalar { style, value } => {
+                let node = resolve_scalar(*style, value, self.tag.as_deref(), &*options.schema)?;
+
+                if *style == ScalarStyle::Plain && self.tag.is_none() && is_lexical_form(value) {
+                    document.lexical_forms.insert(path.clone(), value.clone());
+                }
+
+                if options.keep_styles
+                    && matches!(style, ScalarStyle::Literal | ScalarStyle::Folded)
+                {
+                    document
+                        .styles
+                        .insert(path.clone(), Style::new().with_scalar(*style));
+                }
+
+                node
+            }
+            CstKind::Alias(name) => {
+                let anchored = anchors
+                    .nodes
+                    .get(name)
+                    .ok_or_else(|| anchors.unresolved_alias(name, path))?;
+                let (node, size, depth) = (anchored.node.clone(), anchored.size, anchored.depth);
+
+                anchors.count(size, depth, path)?;
+                node
+            }
+            CstKind::Sequence { items, .. } => Node::Sequence(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        path.push(PathSegment::Index(index));
+
+                        let comments = ItemComments {
+                            comments: item.leading.comments.iter().map(Comment::content).collect(),
+                            comment: item.trailing_comment.as_ref().map(Comment::content),
+                        };
+                        if !comments.is_empty() {
+                            document.item_comments.insert(path.clone(), comments);
+                        }
+
+                        let node = item.node.to_node(anchors, options, path, document);
+                        path.pop();
+                        node
+                    })
+                    .collect::<Result<Sequence, _>>()?,
+            ),
+            CstKind::Mapping { pairs, .. } => Node::Mapping(
+                pairs
+                    .iter()
+                    .map(|pair| {
+                        // Keys keep no lexical forms or comments, they are
+                        // only written as they resolve
+                        let key = pair
+                            .key
+                            .to_node(anchors, options, path, &mut Document::new())?;
+
+                        path.push(key_segment(&key));
+                        let value = pair.value.to_node(anchors, options, path, document);
+                        path.pop();
+                        let value = value?;
+                        let mut mapping_pair =
+                            MappingPair::new(key, value).with_blank_lines(pair.leading.blank_lines);
+                        mapping_pair.comments =
+                            pair.leading.comments.iter().map(Comment::content).collect();
+                        mapping_pair.comment = pair.trailing_comment.as_ref().map(Comment::content);
+                        mapping_pair.explicit_key = pair.explicit_key;
+
+                        Ok(mapping_pair)
+                    })
+                    .collect::<Result<Mapping, _>>()?,
+            ),
+        };
+
+        if let CstKind::Sequence { style, .. } | CstKind::Mapping { style, .. } = &self.kind {
+            if options.keep_styles && *style == CollectionStyle::Flow {
+                document
+                    .styles
+                    .insert(path.clone(), Style::new().with_collection(*style));
+            }
+        }
+
+        let node = match self.tag.as_deref() {
+            Some(tag) if !matches!(self.kind, CstKind::Scalar { .. } | CstKind::Alias(_)) => {
+                match tag {
+                    "!" | "!!map" | "!!seq" => node,
+                    tag => Node::Tagged(Box::new(Tagged::new(resolve_tag(tag), node))),
+                }
+            }
+            _ => node,
+        };
+
+        if is_open {
+            anchors.open.pop();
+        }
+
+        if let Some(anchor) = &self.anchor {
+            let anchored = AnchoredNode {
+                size: anchors.resolved - start,
+                depth: depth(&node),
+                node: node.clone(),
+            };
+            anchors.nodes.insert(anchor.clone(), anchored);
+        }
+
+        Ok(node)
+    }
+}
+
+/// A node defined with an anchor while resolving a [`Cst`], with its number
+/// of nodes and nesting depth.
+struct AnchoredNode {
+    node: Node,
+    size: usize,
+    depth: usize,
+}
+
+/// The anchored nodes of a stream and the number of nodes resolved so far,
+/// which is limited by the [`Limits`].
+struct Anchors {
+    nodes: HashMap<String, AnchoredNode>,
+    resolved: usize,
+    max_depth: Option<usize>,
+
+    /// The anchors of the collections being resolved with their paths,
+    /// from the outermost to the innermost.
+    open: Vec<(String, Vec<PathSegment>)>,
+
+    /// The maximum number of resolved nodes with the limit it results from.
+    max_nodes: Option<(usize, Limit)>,
+}
+
+impl Anchors {
+    /// Creates the anchors for resolving a stream of `nodes` CST nodes.
+    fn new(limits: &Limits, nodes: usize) -> Self {
+        let max_nodes = limits.max_nodes.map(|max| (max, Limit::Nodes(max)));
+        let max_expanded = limits
+            .max_alias_expansion
+            .map(|factor| (nodes.saturating_mul(factor), Limit::AliasExpansion(factor)));
+
+        Self {
+            nodes: HashMap::new(),
+            resolved: 0,
+            max_depth: limits.max_depth,
+            open: Vec::new(),
+            max_nodes: match (max_nodes, max_expanded) {
+                (Some(a), Some(b)) => Some(if a.0 <= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+
+    /// Returns the error of an alias at `path` whose anchor `name` is not
+    /// defined: the anchor is either unknown or belongs to a collection
+    /// containing the alias.
+    fn unresolved_alias(&self, name: &str, path: &[PathSegment]) -> Error {
+        match self.open.iter().rfind(|(anchor, _)| anchor == name) {
+            Some((_, anchor_path)) => AliasCycleSnafu {
+                name,
+                path,
+                anchor_path: anchor_path.clone(),
+            }
+            .build(),
+            None => UnknownAliasSnafu { name }.build(),
+        }
+    }
+
+    /// Counts a resolved node of `size` nodes and `depth` at `path`.
+    fn count(&mut self, size: usize, depth: usize, path: &[PathSegment]) -> Result<(), Error> {
+        self.resolved += size;
+
+        if let Some(max_depth) = self.max_depth.filter(|max| path.len() + depth > *max) {
+            return LimitExceededSnafu {
+                limit: Limit::Depth(max_depth),
+                path,
+            }
+            .fail();
+        }
+
+        match self.max_nodes {
+            Some((max_nodes, limit)) if self.resolved > max_nodes => {
+                LimitExceededSnafu { limit, path }.fail()
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the nesting depth of the collections of `node`, 0 for scalars.
+fn depth(node: &Node) -> usize {
+    match node {
+        Node::Mapping(mapping) => {
+            1 + mapping
+                .pairs()
+                .map(|pair| depth(&pair.key).max(depth(&pair.value)))
+                .max()
+                .unwrap_or_default()
+        }
+        Node::Sequence(items) => 1 + items.iter().map(depth).max().unwrap_or_default(),
+        Node::Tagged(tagged) => depth(&tagged.node),
+        _ => 0,
+    }
+}
+
+/// Resolves the `tag` as written in the source into a full tag. Verbatim tags
+/// are unwrapped and the secondary handle `!!` is expanded, all other tags are
+/// kept as they are.
+fn resolve_tag(tag: &str) -> String {
+    if let Some(verbatim) = tag.strip_prefix("!<").and_then(|t| t.strip_suffix('>')) {
+        return verbatim.into();
+    }
+
+    match tag.strip_prefix("!!") {
+        Some(suffix) => format!("{CORE_TAG_PREFIX}{suffix}"),
+        None => tag.into(),
+    }
+}
+
+/// Resolves a scalar into a typed node. Plain scalars without a tag are
+/// resolved using the `schema`, all other untagged scalars are strings.
+/// Application-specific tags are resolved by the `schema` as well.
+fn resolve_scalar(
+    style: ScalarStyle,
+    value: &str,
+    tag: Option<&str>,
+    schema: &dyn Schema,
+) -> Result<Node, Error> {
+    let invalid = || {
+        InvalidTaggedScalarSnafu {
+            value,
+            tag: tag.unwrap_or_default(),
+        }
+        .build()
+    };
+
+    let custom = tag
+        .filter(|tag| *tag != "!" && !tag.starts_with("!!"))
+        .and_then(|tag| schema.resolve_tagged(tag, value));
+
+    if let Some(resolved) = custom {
+        return resolved.map_err(|_| invalid());
+    }
+
+    let node = match tag {
+        Some("!!str") => Node::String(value.into()),
+        Some("!!null") => Node::Null,
+        Some("!!bool") => match resolve_core(value) {
+            node @ Node::Boolean(_) => node,
+            _ => return Err(invalid()),
+        },
+        Some("!!int") => match resolve_core(value) {
+            node @ (Node::Integer(_) | Node::BigInteger(_)) => node,
+            _ => return Err(invalid()),
+        },
+        Some("!!float") => match resolve_core(value) {
+            node @ Node::FloatingPoint(_) => node,
+            Node::Integer(i) => Node::FloatingPoint(Float::new(i as f64)),
+            Node::BigInteger(ref i) => {
+                Node::FloatingPoint(Float::new(i.to_string().parse().map_err(|_| invalid())?))
+            }
+            _ => return Err(invalid()),
+        },
+        Some("!!timestamp") => Node::Timestamp(value.parse().map_err(|_| invalid())?),
+        Some("!!binary") => {
+            let data: String = value.split_whitespace().collect();
+            Node::Binary(BASE64.decode(data).map_err(|_| invalid())?)
+        }
+        Some(tag) if tag != "!" => Node::Tagged(Box::new(Tagged::new(
+            resolve_tag(tag),
+            Node::String(value.into()),
+        ))),
+        None if style == ScalarStyle::Plain => schema.resolve_plain(value),
+        _ => Node::String(value.into()),
+    };
+
+    Ok(node)
+}
+
+/// Controls the visibility of anchors across the documents of a stream when
+/// converting a [`Cst`] into an AST.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnchorScope {
+    /// Anchors are only visible in the document which defines them, as
+    /// required by the YAML specification.
+    #[default]
+    Document,
+
+    /// Anchors are visible in the document which defines them and in all
+    /// following documents. An alias refers to the most recent definition of
+    /// the anchor and is resolved by duplicating the anchored node. This
+    /// allows shared definitions in multi-document bundles, but is not
+    /// standard YAML: other processors reject such aliases.
+    Stream,
+}
+
+/// These options control the conversion of a [`Cst`] into an AST.
+///
+/// It provides a builder to selectively customize individual settings. If no
+/// customization is required, use [`ResolveOptions::default()`].
+#[derive(Debug)]
+pub struct ResolveOptions {
+    pub anchor_scope: AnchorScope,
+
+    /// The schema used to resolve plain scalars and application-specific
+    /// tags. Defaults to the [`CoreSchema`].
+    pub schema: Box<dyn Schema>,
+
+    /// The limits of the resolved AST, which protect against documents
+    /// whose aliases expand to an enormous number of nodes.
+    pub limits: Limits,
+
+    /// Keeps the flow style of collections and the literal and folded
+    /// styles of block scalars in [`Document::styles`], which the emitter
+    /// follows. Disabled by default, which leaves all styles to the
+    /// [`EmitterOptions`].
+    pub keep_styles: bool,
+}
+
+impl Default for ResolveOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl ResolveOptions {
+    pub fn builder() -> ResolveOptionsBuilder {
+        ResolveOptionsBuilder::default()
+    }
+}
+
+pub struct ResolveOptionsBuilder {
+    anchor_scope: AnchorScope,
+    schema: Box<dyn Schema>,
+    limits: Limits,
+    keep_styles: bool,
+}
+
+impl Default for ResolveOptionsBuilder {
+    fn default() -> Self {
+        Self {
+            anchor_scope: AnchorScope::default(),
+            schema: Box::new(CoreSchema),
+            limits: Limits::default(),
+            keep_styles: false,
+        }
+    }
+}
+
+impl ResolveOptionsBuilder {
+    /// Sets the visibility of anchors. See [`AnchorScope`].
+    pub fn anchor_scope(mut self, anchor_scope: AnchorScope) -> Self {
+        self.anchor_scope = anchor_scope;
+        self
+    }
+
+    /// Sets the schema used to resolve scalars.
+    pub fn schema(mut self, schema: impl Schema + 'static) -> Self {
+        self.schema = Box::new(schema);
+        self
+    }
+
+    /// Sets the limits of the resolved AST. See [`ResolveOptions::limits`].
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Keeps the styles of the CST. See [`ResolveOptions::keep_styles`].
+    pub fn keep_styles(mut self, keep_styles: bool) -> Self {
+        self.keep_styles = keep_styles;
+        self
+    }
+
+    pub fn build(self) -> ResolveOptions {
+        ResolveOptions {
+            anchor_scope: self.anchor_scope,
+            schema: self.schema,
+            limits: self.limits,
+            keep_styles: self.keep_styles,
+        }
+    }
+}
+
+/// A lossless concrete syntax tree of a YAML character stream. See the
+/// [module](self) documentation for details.
+#[derive(Clone, Debug)]
+pub struct Cst {
+    source: String,
+    documents: Vec<CstDocument>,
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Cst {
+    /// Parses the character stream `input` into a CST.
+    pub fn parse(input: impl Into<String>) -> Result<Self, parser::Error> {
+        let source = input.into();
+        let documents = parser::parse(&source)?;
+
+        Ok(Self { source, documents })
+    }
+
+    /// Decodes the character stream `bytes`, which may be encoded in UTF-8,
+    /// UTF-16 or UTF-32, and parses it into a CST. See [`parser::decode`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, parser::Error> {
+        Self::parse(parser::decode(bytes)?)
+    }
+
+    /// Parses the character stream `input` into a CST according to
+    /// `options`, e.g. rejecting tabs in indentation.
+    pub fn parse_with(
+        input: impl Into<String>,
+        options: &parser::ParseOptions,
+    ) -> Result<Self, parser::Error> {
+        let source = input.into();
+        let documents = parser::parse_with(&source, options)?;
+
+        Ok(Self { source, documents })
+    }
+
+    /// Parses the character stream `input` into a CST, recovering from
+    /// syntax errors by skipping the lines containing them. The CST keeps
+    /// the original source text, including the skipped lines. See
+    /// [`parser::parse_lenient`].
+    pub fn parse_lenient(
+        input: impl Into<String>,
+        options: &parser::ParseOptions,
+    ) -> Result<(Self, Vec<parser::RecoveredError>), parser::Error> {
+        let source = input.into();
+        let (documents, errors) = parser::parse_lenient(&source, options)?;
+
+        Ok((Self { source, documents }, errors))
+    }
+
+    /// Emits the AST `stream` using `options` and parses the result into a
+    /// CST.
+    pub fn from_stream(stream: Stream, options: EmitterOptions) -> Result<Self, Error> {
+        let mut output = String::new();
+        Emitter::new(stream.into_events(), options)
+            .emit(&mut output)
+            .context(EmitSnafu)?;
+
+        Self::parse(output).context(ParseSnafu)
+    }
+
+    /// Converts the CST into an AST [`Stream`]. Blank lines and comments
+    /// of mapping pairs, as well as the lexical forms of null and boolean
+    /// scalars, are kept. All other presentation details, like styles, are
+    /// dropped.
+    pub fn to_stream(&self) -> Result<Stream, Error> {
+        self.to_stream_with(&ResolveOptions::default())
+    }
+
+    /// Converts the CST into an AST [`Stream`], resolving scalars and aliases
+    /// according to `options`.
+    pub fn to_stream_with(&self, options: &ResolveOptions) -> Result<Stream, Error> {
+        let mut stream = Stream::new();
+        let nodes = self
+            .documents
+            .iter()
+            .filter_map(|document| document.root.as_ref())
+            .map(CstNode::node_count)
+            .sum();
+        let mut anchors = Anchors::new(&options.limits, nodes);
+
+        for cst_document in &self.documents {
+            let mut document = Document::new();
+            if options.anchor_scope == AnchorScope::Document {
+                anchors.nodes.clear();
+            }
+            document.blank_lines = cst_document.blank_lines;
+
+            for directive in &cst_document.directives {
+                document.push_directive(directive.clone());
+            }
+
+            if let Some(root) = &cst_document.root {
+                let root = root.to_node(&mut anchors, options, &mut Vec::new(), &mut document)?;
+                document.push_node(root);
+            }
+
+            stream.push_document(document);
+        }
+
+        Ok(stream)
+    }
+
+    /// Formats the parsed stream in the event notation of the
+    /// yaml-test-suite, one event per line. In contrast to
+    /// [`to_test_suite_string`](crate::events::to_test_suite_string), the
+    /// scalar styles, anchor names, explicit document markers and tags are
+    /// written as in the source, which makes the output comparable to the
+    /// `test.event` files of the suite.
+    pub fn to_test_suite_string(&self) -> String {
+        let mut lines = vec!["+STR".to_string()];
+
+        for document in &self.documents {
+            lines.push(match document.explicit_start {
+                true => "+DOC ---".into(),
+                false => "+DOC".into(),
+            });
+
+            match &document.root {
+                Some(root) => root.push_test_suite_lines(&mut lines),
+                None => lines.push("=VAL :".into()),
+            }
+
+            lines.push(match document.explicit_end {
+                true => "-DOC ...".into(),
+                false => "-DOC".into(),
+            });
+        }
+
+        lines.push("-STR".into());
+        lines.join("\n") + "\n"
+    }
+
+    /// Pushes the events of the parsed stream into the `receiver`, see
+    /// [`EventReceiver`]. Node events are passed with the span of the node,
+    /// document events with the span of the document.
+    ///
+    /// The events are the serialization as written in the source: scalars
+    /// keep their style, explicit tags are resolved into full tags and all
+    /// other tags are left to the receiver. Comments are not included.
+    pub fn push_events<R: EventReceiver + ?Sized>(
+        &self,
+        receiver: &mut R,
+    ) -> ControlFlow<R::Break> {
+        receiver.on_event(Event::StreamStart, None)?;
+
+        for document in &self.documents {
+            document.push_events(receiver)?;
+        }
+
+        receiver.on_event(Event::StreamEnd, None)
+    }
+
+    /// Returns the original (or edited) source text.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn documents(&self) -> &[CstDocument] {
+        &self.documents
+    }
+
+    /// Replaces the node located at `span` with `node`. Only the text of the
+    /// replaced node changes, everything around it is kept as is. Scalars
+    /// keep their quoting style where possible, collections are written in
+    /// flow style.
+    pub fn replace(&mut self, span: Span, node: &Node) -> Result<(), Error> {
+        let target = self
+            .documents
+            .iter()
+            .filter_map(|document| document.root.as_ref())
+            .find_map(|root| root.find(span))
+            .ok_or_else(|| NodeNotFoundSnafu { span }.build())?;
+
+        let mut text = render_replacement(target, node);
+
+        // An omitted value directly follows the colon, e.g. `key:`
+        if target.is_empty() && span.start > 0 && !self.source[..span.start].ends_with(' ') {
+            text.insert(0, ' ');
+        }
+
+        let mut source = self.source.clone();
+        source.replace_range(span.start..span.end, &text);
+
+        *self = Self::parse(source).context(ParseSnafu)?;
+        Ok(())
+    }
+
+    /// Normalizes all comments, so that regenerated files don't accumulate
+    /// comment noise over time:
+    ///
+    /// - trailing whitespace after comments is removed,
+    /// - the text of a comment is separated from the `#` by exactly one
+    ///   space, e.g. `#foo` becomes `# foo`,
+    /// - runs of identical comments on consecutive lines, like a generated
+    ///   header which was prepended multiple times, are collapsed into one.
+    ///
+    /// The AST doesn't carry comments, which is why this pass operates on the
+    /// CST.
+    pub fn normalize_comments(&mut self) -> Result<(), Error> {
+        let mut comments: Vec<&Comment> = self
+            .documents
+            .iter()
+            .flat_map(|document| &document.comments)
+            .collect();
+        comments.sort_by_key(|comment| comment.span.start);
+        comments.dedup_by_key(|comment| comment.span.start);
+
+        // The range of the previous comment which occupies a whole line,
+        // including its line break, and its normalized text
+        let mut previous: Option<(usize, usize, String)> = None;
+        let mut edits = Vec::new();
+
+        for comment in comments {
+            let line_start = self.source[..comment.span.start]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
+            let line_end = self.source[comment.span.end..]
+                .find('\n')
+                .map_or(self.source.len(), |i| comment.span.end + i);
+
+            let text = normalize_comment(&comment.text);
+            let indent = &self.source[line_start..comment.span.start];
+
+            if !indent.trim().is_empty() {
+                previous = None;
+                edits.push((comment.span.start, line_end, text));
+                continue;
+            }
+
+            let next_line = (line_end + 1).min(self.source.len());
+            match &previous {
+                Some((end, previous_indent, previous_text))
+                    if *end == line_start
+                        && *previous_indent == indent.len()
+                        && *previous_text == text =>
+                {
+                    edits.push((line_start, next_line, String::new()));
+                }
+                _ => edits.push((comment.span.start, line_end, text.clone())),
+            }
+
+            previous = Some((next_line, indent.len(), text));
+        }
+
+        let mut source = self.source.clone();
+        for (start, end, text) in edits.into_iter().rev() {
+            source.replace_range(start..end, &text);
+        }
+
+        *self = Self::parse(source).context(ParseSnafu)?;
+        Ok(())
+    }
+
+    /// Breaks comments which occupy a whole line and exceed `width`
+    /// characters into multiple comment lines with the same indentation,
+    /// using `breaker`. Short comments are not joined.
+    pub fn reflow_comments(
+        &mut self,
+        width: usize,
+        breaker: &impl LineBreaker,
+    ) -> Result<(), Error> {
+        let mut edits = Vec::new();
+
+        for comment in self
+            .documents
+            .iter()
+            .flat_map(|document| &document.comments)
+        {
+            let line_start = self.source[..comment.span.start]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
+            let indent = &self.source[line_start..comment.span.start];
+
+            if !indent.trim().is_empty()
+                || indent.chars().count() + comment.text.chars().count() <= width
+            {
+                continue;
+            }
+
+            let rest = comment.text.trim_start_matches('#');
+            let marker = &comment.text[..comment.text.len() - rest.len()];
+            let prefix_len = indent.chars().count() + marker.len() + 1;
+
+            let lines = break_lines(breaker, rest.trim(), width.saturating_sub(prefix_len));
+            if lines.len() > 1 {
+                let separator = format!("\n{indent}{marker} ");
+                let text = format!("{marker} {}", lines.join(&separator));
+                edits.push((comment.span, text));
+            }
+        }
+
+        let mut source = self.source.clone();
+        edits.sort_by_key(|(span, _)| span.start);
+        edits.dedup_by_key(|(span, _)| span.start);
+
+        for (span, text) in edits.into_iter().rev() {
+            source.replace_range(span.start..span.end, &text);
+        }
+
+        *self = Self::parse(source).context(ParseSnafu)?;
+        Ok(())
+    }
+}
+
+/// Returns the comment `text` with exactly one space between the leading
+/// `#`s and the text, and without trailing whitespace.
+fn normalize_comment(text: &str) -> String {
+    let rest = text.trim_start_matches('#');
+    let marker = &text[..text.len() - rest.len()];
+
+    match rest.trim() {
+        "" => marker.to_string(),
+        rest => format!("{marker} {rest}"),
+    }
+}
+
+/// Renders `node` as the replacement of the `target` node.
+fn render_replacement(target: &CstNode, node: &Node) -> String {
+    let style = match &target.kind {
+        CstKind::Scalar { style, .. } => *style,
+        _ => ScalarStyle::Plain,
+    };
+
+    match (node, style) {
+        (Node::String(s), ScalarStyle::SingleQuoted) if !s.contains('\n') => {
+            format!("'{}'", s.replace('\'', "''"))
+        }
+        (Node::String(s), ScalarStyle::DoubleQuoted) => double_quoted(s),
+        _ => render_flow(node),
+    }
+}
+
+/// Renders `node` in flow style on a single line.
+pub(crate) fn render_flow(node: &Node) -> String {
+    match node {
+        Node::Mapping(mapping) => {
+            let pairs: Vec<_> = mapping
+                .pairs()
+                .map(|pair| format!("{}: {}", render_flow(&pair.key), render_flow(&pair.value)))
+                .collect();
+            format!("{{{}}}", pairs.join(", "))
+        }
+        Node::Sequence(sequence) => {
+            let items: Vec<_> = sequence.iter().map(render_flow).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Node::String(s) if needs_quotes(s, true) => double_quoted(s),
+        Node::String(s) => s.clone(),
+        Node::Null => "null".into(),
+        Node::Boolean(b) => b.to_string(),
+        Node::Integer(i) => i.to_string(),
+        Node::BigInteger(i) => i.to_string(),
+        Node::FloatingPoint(f) => f.to_string(),
+        Node::Binary(b) => format!("!!binary {}", BASE64.encode(b)),
+        Node::Timestamp(t) => format!("!!timestamp {t}"),
+        Node::Tagged(tagged) => {
+            format!(
+                "{} {}",
+                shorthand_tag(&tagged.tag),
+                render_flow(&tagged.node)
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        emitter::GreedyLineBreaker,
+        schema::{CustomSchema, JsonSchema},
+        yaml,
+    };
+
+    use super::*;
+
+    const SOURCE: &str = r#"# Cluster settings
+clusterName:   "opensearch-cluster"
+
+nodeGroup: 'master'   # primary group
+roles:
+  - master
+  - ingest # optional
+limits: {cpu: 2, memory: 4Gi}
+description: |
+  Multi-line
+  text
+"#;
+
+    #[test]
+    fn lossless() {
+        let cst = Cst::parse(SOURCE).unwrap();
+        assert_eq!(cst.to_string(), SOURCE);
+
+        let root = cst.documents()[0].root().unwrap();
+        let CstKind::Mapping { pairs, .. } = &root.kind else {
+            panic!("expected mapping")
+        };
+
+        assert_eq!(pairs.len(), 5);
+        assert_eq!(pairs[0].leading.comments[0].text, "# Cluster settings");
+        assert_eq!(pairs[1].leading.blank_lines, 1);
+        assert_eq!(
+            pairs[1].trailing_comment.as_ref().unwrap().text,
+            "# primary group"
+        );
+    }
+
+    #[test]
+    fn edit_preserves_formatting() {
+        let mut cst = Cst::parse(SOURCE).unwrap();
+        let root = cst.documents()[0].root().unwrap();
+
+        let group = root.get("nodeGroup").unwrap().span;
+        cst.replace(group, &Node::String("data".into())).unwrap();
+
+        let role = cst.documents()[0]
+            .root()
+            .unwrap()
+            .get("roles")
+            .unwrap()
+            .item(1)
+            .unwrap()
+            .span;
+        cst.replace(role, &Node::String("true".into())).unwrap();
+
+        assert_eq!(
+            cst.to_string(),
+            SOURCE
+                .replace("'master'", "'data'")
+                .replace("- ingest", "- \"true\"")
+        );
+    }
+
+    #[test]
+    fn blank_lines_to_ast() {
+        let stream = Cst::parse("---\na: 1\n\n\nb: 2\n...\n\n---\nc: 3\n")
+            .unwrap()
+            .to_stream()
+            .unwrap();
+
+        let cst = Cst::from_stream(stream, EmitterOptions::default()).unwrap();
+        assert_eq!(
+            cst.to_string(),
+            "---\na: 1\n\n\nb: 2\n...\n\n---\nc: 3\n...\n"
+        );
+    }
+
+    #[test]
+    fn to_ast() {
+        let stream = Cst::parse(SOURCE).unwrap().to_stream().unwrap();
+        let cst = Cst::from_stream(stream, EmitterOptions::default()).unwrap();
+        let root = cst.to_stream().unwrap().0.remove(0).nodes.remove(0);
+
+        assert_eq!(
+            root,
+            Node::Mapping(Mapping::from([
+                (
+                    Node::String("clusterName".into()),
+                    Node::String("opensearch-cluster".into())
+                ),
+                (
+                    Node::String("nodeGroup".into()),
+                    Node::String("master".into())
+                ),
+                (
+                    Node::String("roles".into()),
+                    Node::Sequence(Sequence::from([
+                        Node::String("master".into()),
+                        Node::String("ingest".into())
+                    ]))
+                ),
+                (
+                    Node::String("limits".into()),
+                    Node::Mapping(Mapping::from([
+                        (Node::String("cpu".into()), Node::Integer(2)),
+                        (Node::String("memory".into()), Node::String("4Gi".into())),
+                    ]))
+                ),
+                (
+                    Node::String("description".into()),
+                    Node::String("Multi-line\ntext\n".into())
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn anchor_scope() {
+        let cst =
+            Cst::parse("---\ndefaults: &defaults {cpu: 1}\n---\nlimits: *defaults\n").unwrap();
+        assert!(matches!(
+            cst.to_stream(),
+            Err(Error::UnknownAlias { name }) if name == "defaults"
+        ));
+
+        let options = ResolveOptions::builder()
+            .anchor_scope(AnchorScope::Stream)
+            .build();
+        let stream = cst.to_stream_with(&options).unwrap();
+        let limits = Node::Mapping(Mapping::from([(
+            Node::String("limits".into()),
+            Node::Mapping(Mapping::from([(
+                Node::String("cpu".into()),
+                Node::Integer(1),
+            )])),
+        )]));
+        assert_eq!(stream.get(1).unwrap().nodes, [limits]);
+    }
+
+    #[test]
+    fn alias_cycle() {
+        let cst = Cst::parse("root: &a\n  b:\n    - *a\n").unwrap();
+        match cst.to_stream() {
+            Err(Error::AliasCycle {
+                name,
+                path,
+                anchor_path,
+            }) => {
+                assert_eq!(name, "a");
+                assert_eq!(display_path(&path), "root.b[0]");
+                assert_eq!(display_path(&anchor_path), "root");
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+
+        // Aliases of a nested node with the same anchor are no cycle
+        let cst = Cst::parse("- &a [&a x, *a]\n- *a\n").unwrap();
+        assert_eq!(
+            cst.to_stream().unwrap().get(0).unwrap().nodes,
+            [yaml!([["x", "x"], ["x", "x"]])]
+        );
+    }
+
+    #[test]
+    fn schema() {
+        let cst = Cst::parse("a: yes\nb: ~\nc: !port 80\n").unwrap();
+        let options = ResolveOptions::builder()
+            .schema(
+                CustomSchema::new(JsonSchema)
+                    .with_tag("!port", |value| value.parse().ok().map(Node::Integer)),
+            )
+            .build();
+
+        let stream = cst.to_stream_with(&options).unwrap();
+        assert_eq!(
+            stream.get(0).unwrap().nodes,
+            [Node::Mapping(Mapping::from([
+                (Node::String("a".into()), Node::String("yes".into())),
+                (Node::String("b".into()), Node::String("~".into())),
+                (Node::String("c".into()), Node::Integer(80)),
+            ]))]
+        );
+
+        let cst = Cst::parse("c: !port http\n").unwrap();
+        assert!(matches!(
+            cst.to_stream_with(&options),
+            Err(Error::InvalidTaggedScalar { .. })
+        ));
+    }
+
+    #[test]
+    fn tagged_nodes() {
+        let source = "bucket: !Ref Logs\nname: !Sub\n  - ${AWS::StackName}-logs\n  - {}\n";
+        let stream = Cst::parse(source).unwrap().to_stream().unwrap();
+        let root = stream.get(0).unwrap().nodes[0].clone();
+
+        assert_eq!(
+            root,
+            Node::Mapping(Mapping::from([
+                (
+                    Node::String("bucket".into()),
+                    Node::Tagged(Box::new(Tagged::new("!Ref", Node::String("Logs".into())))),
+                ),
+                (
+                    Node::String("name".into()),
+                    Node::Tagged(Box::new(Tagged::new(
+                        "!Sub",
+                        Node::Sequence(Sequence::from([
+                            Node::String("${AWS::StackName}-logs".into()),
+                            Node::Mapping(Mapping::new()),
+                        ])),
+                    ))),
+                ),
+            ]))
+        );
+
+        let cst = Cst::from_stream(stream, EmitterOptions::default()).unwrap();
+        assert_eq!(
+            cst.to_string(),
+            "---\nbucket: !Ref Logs\nname: !Sub\n  - ${AWS::StackName}-logs\n  - {}\n...\n"
+        );
+        assert_eq!(cst.to_stream().unwrap().get(0).unwrap().nodes[0], root);
+    }
+
+    #[test]
+    fn test_suite_notation() {
+        let cst = Cst::parse("--- !!map\na: &x 'b'\nc: [*x, !e \"d\\te\"]\n...\n").unwrap();
+
+        assert_eq!(
+            cst.to_test_suite_string(),
+            "+STR
++DOC ---
++MAP <tag:yaml.org,2002:map>
+=VAL :a
+=VAL &x 'b
+=VAL :c
++SEQ []
+=ALI *x
+=VAL <!e> \"d\\te
+-SEQ
+-MAP
+-DOC ...
+-STR
+"
+        );
+    }
+
+    #[test]
+    fn identity() {
+        let cst = Cst::parse(
+            "a: &port !!int 80
+b: *port
+c: !Ref x
+d: ! 1
+",
+        )
+        .unwrap();
+        let root = cst.documents()[0].root().unwrap();
+        let identity = |key: &str| {
+            let node = root.get(key).unwrap();
+            (
+                node.kind(),
+                node.resolved_tag(),
+                node.anchor(),
+                node.alias(),
+            )
+        };
+
+        assert_eq!(
+            identity("a"),
+            (
+                Kind::Scalar,
+                Some("tag:yaml.org,2002:int".into()),
+                Some("port"),
+                None
+            )
+        );
+        assert_eq!(identity("b"), (Kind::Alias, None, None, Some("port")));
+        assert_eq!(
+            identity("c"),
+            (Kind::Scalar, Some("!Ref".into()), None, None)
+        );
+        assert_eq!(identity("d"), (Kind::Scalar, None, None, None));
+        assert_eq!(root.kind(), Kind::Mapping);
+
+        let stream = cst.to_stream().unwrap();
+        let node = &stream.get(0).unwrap().nodes[0];
+        let ref_node = node.get_path("c").unwrap();
+        assert_eq!(ref_node.tag(), Some("!Ref"));
+        assert_eq!(ref_node.uri(), "!Ref");
+        assert_eq!(ref_node.resolved_tag(), "tag:yaml.org,2002:str");
+        assert_eq!(node.get_path("a").unwrap().tag(), None);
+    }
+
+    #[test]
+    fn normalize_comments() {
+        let source = "# Generated by tool\n# Generated by tool   \n#Do not edit\n---\na: 1   #note  \n  \t\n# b\n\n# b\nb:\n  - 1 ##  c\n  - 2\n";
+        let mut cst = Cst::parse(source).unwrap();
+        cst.normalize_comments().unwrap();
+
+        assert_eq!(
+            cst.to_string(),
+            "# Generated by tool\n# Do not edit\n---\na: 1   # note\n  \t\n# b\n\n# b\nb:\n  - 1 ## c\n  - 2\n"
+        );
+    }
+
+    #[test]
+    fn reflow_comments() {
+        let source = "# A short comment\nspec:\n  # The number of replicas, see https://example.com/docs/replicas\n  replicas: 3 # this trailing comment is not reflowed at all\n";
+        let mut cst = Cst::parse(source).unwrap();
+        cst.reflow_comments(30, &GreedyLineBreaker).unwrap();
+
+        assert_eq!(
+            cst.to_string(),
+            "# A short comment\nspec:\n  # The number of replicas,\n  # see\n  # https://example.com/docs/replicas\n  replicas: 3 # this trailing comment is not reflowed at all\n"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Implementations of [`miette::Diagnostic`] for the errors of the parser
+//! and the emitter, which adds error codes and help texts to them. Attach
+//! the source text to parse errors with
+//! [`Error::with_source`](parser::Error::with_source) to label the position
+//! of the error in an excerpt of the source.
+//!
+//! This is only available when the `miette` feature is enabled.
+use std::fmt::Display;
+
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+
+use crate::{emitter, parser};
+
+fn boxed<'a>(text: impl Display + 'a) -> Option<Box<dyn Display + 'a>> {
+    Some(Box::new(text))
+}
+
+impl Diagnostic for parser::Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        boxed(match self {
+            parser::Error::Syntax { .. } => "yaml_ast::parser::syntax",
+            parser::Error::LimitExceeded { .. } => "yaml_ast::parser::limit_exceeded",
+            parser::Error::Encoding { .. } => "yaml_ast::parser::encoding",
+            parser::Error::Read { .. } => "yaml_ast::parser::read",
+        })
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        match self {
+            parser::Error::Syntax { .. } | parser::Error::Read { .. } => None,
+            parser::Error::LimitExceeded { .. } => boxed(
+                "the limits protect against malicious input, raise them in the parse options \
+                 if the input is trusted",
+            ),
+            parser::Error::Encoding { .. } => {
+                boxed("YAML character streams must be encoded in UTF-8, UTF-16 or UTF-32")
+            }
+        }
+    }
+}
+
+impl Diagnostic for parser::SourceError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error().code()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error().help()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source_code)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let label = match self.error() {
+            parser::Error::Syntax { message, .. } => message.clone(),
+            parser::Error::LimitExceeded { limit, .. } => format!("exceeded the {limit}"),
+            parser::Error::Encoding { .. } | parser::Error::Read { .. } => return None,
+        };
+
+        let span = LabeledSpan::at_offset(self.offset()?, label);
+        Some(Box::new(std::iter::once(span)))
+    }
+}
+
+impl Diagnostic for emitter::Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        boxed(match self {
+            emitter::Error::Write { .. } => "yaml_ast::emitter::write",
+            emitter::Error::Truncated { .. } => "yaml_ast::emitter::truncated",
+            emitter::Error::Unrepresentable { .. } => "yaml_ast::emitter::unrepresentable",
+            emitter::Error::InvalidOptions { .. } => "yaml_ast::emitter::invalid_options",
+            emitter::Error::NotJsonCompatible { .. } => "yaml_ast::emitter::not_json_compatible",
+        })
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        match self {
+            emitter::Error::Write { .. } | emitter::Error::Truncated { .. } => None,
+            emitter::Error::Unrepresentable { .. } => boxed(
+                "set the unrepresentable policy of the emitter options to drop or stringify \
+                 the construct",
+            ),
+            emitter::Error::InvalidOptions { .. } => {
+                boxed("build the emitter options with the builder to catch invalid combinations")
+            }
+            emitter::Error::NotJsonCompatible { .. } => boxed(
+                "disable the JSON-compatible mode of the emitter options if the output is only \
+                 read as YAML",
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn diagnostics() {
+        let source = "a: 1\nb: [2, 3\n";
+        let error = parse(source).unwrap_err().with_source(source);
+
+        assert_eq!(
+            error.code().unwrap().to_string(),
+            "yaml_ast::parser::syntax"
+        );
+
+        let labels: Vec<_> = error.labels().unwrap().collect();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].offset(), source.len());
+        assert!(Diagnostic::source_code(&error).is_some());
+    }
+}
@@ -0,0 +1,516 @@
+//! Structural comparison of two [`Node`]s or [`Document`]s.
+//!
+//! In contrast to [`diff_events`](crate::events::diff_events), which reports
+//! the first differences of two event streams by position, [`diff_nodes`]
+//! produces a complete change set. Mapping pairs are matched by their keys,
+//! sequence items by their value, which makes it possible to detect moved
+//! items. The [`DiffOptions`] decide whether comments, key order and the
+//! formatting of scalars are considered as well.
+//!
+//! ```
+//! use yaml_ast::{diff::{diff_nodes, ChangeKind, DiffOptions}, yaml};
+//!
+//! let left = yaml!({replicas: 1, ports: [80, 443]});
+//! let right = yaml!({replicas: 3, ports: [443, 80], tls: true});
+//!
+//! let changes = diff_nodes(&left, &right, &DiffOptions::default());
+//! let changes: Vec<_> = changes.iter().map(ToString::to_string).collect();
+//!
+//! assert_eq!(changes, [
+//!     "replicas: changed from 1 to 3",
+//!     "ports[1]: moved from position 0 to 1",
+//!     "tls: added",
+//! ]);
+//! ```
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    cst::render_flow,
+    emitter::key_segment,
+    events::{display_path, PathSegment},
+    schema::resolve_core,
+    Document, Mapping, MappingPair, Node,
+};
+
+/// The kind of a [`Change`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChangeKind {
+    /// The node is only present in the right tree.
+    Added(Node),
+
+    /// The node is only present in the left tree.
+    Removed(Node),
+
+    /// The node is present in both trees, but differs.
+    Changed { from: Node, to: Node },
+
+    /// The node is present in both trees, but at another position of its
+    /// collection. The path of the change refers to the new position.
+    Moved { from: usize, to: usize },
+
+    /// The comments of the mapping pair or document changed. Leading
+    /// comments are followed by the inline comment.
+    CommentsChanged { from: Vec<String>, to: Vec<String> },
+
+    /// The scalar is equal in both trees, but written differently, e.g.
+    /// `1.0` and `1.00` or `true` and `True`.
+    FormatChanged { from: String, to: String },
+}
+
+/// A single change between two trees, located by the path to the node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub path: Vec<PathSegment>,
+    pub kind: ChangeKind,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", display_path(&self.path))?;
+
+        match &self.kind {
+            ChangeKind::Added(_) => write!(f, "added"),
+            ChangeKind::Removed(_) => write!(f, "removed"),
+            ChangeKind::Changed { from, to } => {
+                write!(
+                    f,
+                    "changed from {} to {}",
+                    render_flow(from),
+                    render_flow(to)
+                )
+            }
+            ChangeKind::Moved { from, to } => {
+                write!(f, "moved from position {from} to {to}")
+            }
+            ChangeKind::CommentsChanged { .. } => write!(f, "comments changed"),
+            ChangeKind::FormatChanged { from, to } => {
+                write!(f, "format changed from {from} to {to}")
+            }
+        }
+    }
+}
+
+/// These options control which differences are reported.
+///
+/// It provides a builder to selectively customize individual settings. If no
+/// customization is required, use [`DiffOptions::default()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Ignores changed comments of mapping pairs and documents.
+    pub ignore_comments: bool,
+
+    /// Ignores reordered mapping pairs. Moved sequence items are still
+    /// reported.
+    pub ignore_key_order: bool,
+
+    /// Ignores scalars which are equal, but written differently, like
+    /// `1.0` and `1.00`.
+    pub ignore_scalar_format: bool,
+}
+
+impl DiffOptions {
+    pub fn builder() -> DiffOptionsBuilder {
+        DiffOptionsBuilder::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DiffOptionsBuilder {
+    ignore_comments: bool,
+    ignore_key_order: bool,
+    ignore_scalar_format: bool,
+}
+
+impl DiffOptionsBuilder {
+    /// See [`DiffOptions::ignore_comments`].
+    pub fn ignore_comments(mut self, ignore_comments: bool) -> Self {
+        self.ignore_comments = ignore_comments;
+        self
+    }
+
+    /// See [`DiffOptions::ignore_key_order`].
+    pub fn ignore_key_order(mut self, ignore_key_order: bool) -> Self {
+        self.ignore_key_order = ignore_key_order;
+        self
+    }
+
+    /// See [`DiffOptions::ignore_scalar_format`].
+    pub fn ignore_scalar_format(mut self, ignore_scalar_format: bool) -> Self {
+        self.ignore_scalar_format = ignore_scalar_format;
+        self
+    }
+
+    pub fn build(self) -> DiffOptions {
+        DiffOptions {
+            ignore_comments: self.ignore_comments,
+            ignore_key_order: self.ignore_key_order,
+            ignore_scalar_format: self.ignore_scalar_format,
+        }
+    }
+}
+
+/// Returns all changes between the `left` and `right` node, in the order of
+/// the right node.
+pub fn diff_nodes(left: &Node, right: &Node, options: &DiffOptions) -> Vec<Change> {
+    let mut differ = Differ::new(options, None);
+    differ.diff(left, right);
+    differ.changes
+}
+
+/// Returns all changes between the root nodes of the `left` and `right`
+/// document, including their comments and the lexical forms of scalars,
+/// see [`Document::lexical_forms`]. Missing root nodes are compared as
+/// `null`.
+pub fn diff_documents(left: &Document, right: &Document, options: &DiffOptions) -> Vec<Change> {
+    let mut differ = Differ::new(options, Some((&left.lexical_forms, &right.lexical_forms)));
+
+    if !options.ignore_comments && left.comments != right.comments {
+        differ.report(ChangeKind::CommentsChanged {
+            from: left.comments.clone(),
+            to: right.comments.clone(),
+        });
+    }
+
+    let null = Node::Null;
+    differ.diff(
+        left.nodes.first().unwrap_or(&null),
+        right.nodes.first().unwrap_or(&null),
+    );
+    differ.changes
+}
+
+type LexicalForms = HashMap<Vec<PathSegment>, String>;
+
+struct Differ<'a> {
+    options: &'a DiffOptions,
+    forms: Option<(&'a LexicalForms, &'a LexicalForms)>,
+    path: Vec<PathSegment>,
+    changes: Vec<Change>,
+}
+
+impl<'a> Differ<'a> {
+    fn new(options: &'a DiffOptions, forms: Option<(&'a LexicalForms, &'a LexicalForms)>) -> Self {
+        Self {
+            options,
+            forms,
+            path: Vec::new(),
+            changes: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, kind: ChangeKind) {
+        self.changes.push(Change {
+            path: self.path.clone(),
+            kind,
+        });
+    }
+
+    fn diff(&mut self, left: &Node, right: &Node) {
+        match (left, right) {
+            (Node::Mapping(left), Node::Mapping(right)) => self.diff_mappings(left, right),
+            (Node::Sequence(left), Node::Sequence(right)) => self.diff_sequences(left, right),
+            (Node::Tagged(l), Node::Tagged(r)) if l.tag == r.tag => self.diff(&l.node, &r.node),
+            (left, right) if left == right => self.diff_format(left, right),
+            (left, right) => self.report(ChangeKind::Changed {
+                from: left.clone(),
+                to: right.clone(),
+            }),
+        }
+    }
+
+    fn diff_format(&mut self, left: &Node, right: &Node) {
+        if self.options.ignore_scalar_format {
+            return;
+        }
+
+        let (from, to) = match (left, right) {
+            (Node::FloatingPoint(l), Node::FloatingPoint(r)) => (l.to_string(), r.to_string()),
+            (Node::Null | Node::Boolean(_), _) => match self.forms {
+                Some((left_forms, right_forms)) => (
+                    lexical_form(left, left_forms.get(&self.path)),
+                    lexical_form(right, right_forms.get(&self.path)),
+                ),
+                None => return,
+            },
+            _ => return,
+        };
+
+        if from != to {
+            self.report(ChangeKind::FormatChanged { from, to });
+        }
+    }
+
+    fn diff_mappings(&mut self, left: &Mapping, right: &Mapping) {
+        fn find(mapping: &Mapping, key: &Node) -> Option<usize> {
+            mapping.pairs().position(|pair| pair.key == *key)
+        }
+
+        for pair in left.pairs() {
+            if find(right, &pair.key).is_none() {
+                self.path.push(key_segment(&pair.key));
+                self.report(ChangeKind::Removed(pair.value.clone()));
+                self.path.pop();
+            }
+        }
+
+        // The positions of the common keys in the left mapping, in the
+        // order of the right mapping
+        let common: Vec<usize> = right
+            .pairs()
+            .filter_map(|pair| find(left, &pair.key))
+            .collect();
+        let in_order = longest_increasing(&common);
+
+        for (index, pair) in right.pairs().enumerate() {
+            self.path.push(key_segment(&pair.key));
+
+            match find(left, &pair.key) {
+                Some(left_index) => {
+                    if !self.options.ignore_key_order && !in_order.contains(&left_index) {
+                        self.report(ChangeKind::Moved {
+                            from: left_index,
+                            to: index,
+                        });
+                    }
+
+                    self.diff_comments(&left[left_index], pair);
+                    self.diff(&left[left_index].value, &pair.value);
+                }
+                None => self.report(ChangeKind::Added(pair.value.clone())),
+            }
+
+            self.path.pop();
+        }
+    }
+
+    fn diff_comments(&mut self, left: &MappingPair, right: &MappingPair) {
+        let comments = |pair: &MappingPair| -> Vec<String> {
+            pair.comments.iter().chain(&pair.comment).cloned().collect()
+        };
+
+        let (from, to) = (comments(left), comments(right));
+        if !self.options.ignore_comments && from != to {
+            self.report(ChangeKind::CommentsChanged { from, to });
+        }
+    }
+
+    fn diff_sequences(&mut self, left: &[Node], right: &[Node]) {
+        // Items which are part of the longest common subsequence are
+        // unchanged, equal items outside of it were moved
+        let mut matches: Vec<Option<usize>> = vec![None; right.len()];
+        for (left_index, right_index) in common_subsequence(left, right) {
+            matches[right_index] = Some(left_index);
+        }
+
+        let mut unmatched: Vec<usize> = (0..left.len())
+            .filter(|index| !matches.contains(&Some(*index)))
+            .collect();
+        let mut moved = Vec::new();
+
+        for (right_index, item) in right.iter().enumerate() {
+            if matches[right_index].is_some() {
+                continue;
+            }
+
+            if let Some(position) = unmatched.iter().position(|i| left[*i] == *item) {
+                let left_index = unmatched.remove(position);
+                matches[right_index] = Some(left_index);
+                moved.push(right_index);
+            }
+        }
+
+        for (index, item) in right.iter().enumerate() {
+            self.path.push(PathSegment::Index(index));
+
+            match matches[index] {
+                Some(left_index) => {
+                    if moved.contains(&index) {
+                        self.report(ChangeKind::Moved {
+                            from: left_index,
+                            to: index,
+                        });
+                    }
+
+                    self.diff(&left[left_index], item);
+                }
+                // Remaining items at the same position changed in place
+                None if unmatched.contains(&index) => {
+                    unmatched.retain(|i| *i != index);
+                    self.diff(&left[index], item);
+                }
+                None => self.report(ChangeKind::Added(item.clone())),
+            }
+
+            self.path.pop();
+        }
+
+        for index in unmatched {
+            self.path.push(PathSegment::Index(index));
+            self.report(ChangeKind::Removed(left[index].clone()));
+            self.path.pop();
+        }
+    }
+}
+
+/// Returns the lexical form of the null or boolean `node`, falling back to
+/// its default form if the `form` doesn't resolve to it.
+fn lexical_form(node: &Node, form: Option<&String>) -> String {
+    match form {
+        Some(form) if resolve_core(form) == *node => form.clone(),
+        _ => match node {
+            Node::Boolean(b) => b.to_string(),
+            _ => "null".into(),
+        },
+    }
+}
+
+/// Returns the index pairs of the longest common subsequence of `left` and
+/// `right`.
+fn common_subsequence(left: &[Node], right: &[Node]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+
+    for (i, l) in left.iter().enumerate().rev() {
+        for (j, r) in right.iter().enumerate().rev() {
+            lengths[i][j] = match l == r {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = Vec::new();
+
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    pairs
+}
+
+/// Returns the values of the longest strictly increasing subsequence of
+/// `values`.
+fn longest_increasing(values: &[usize]) -> Vec<usize> {
+    let mut lengths = vec![1; values.len()];
+    let mut previous = vec![None; values.len()];
+
+    for i in 0..values.len() {
+        for j in 0..i {
+            if values[j] < values[i] && lengths[j] + 1 > lengths[i] {
+                lengths[i] = lengths[j] + 1;
+                previous[i] = Some(j);
+            }
+        }
+    }
+
+    let mut index = (0..values.len()).max_by_key(|i| (lengths[*i], std::cmp::Reverse(*i)));
+    let mut sequence = Vec::new();
+
+    while let Some(i) = index {
+        sequence.push(values[i]);
+        index = previous[i];
+    }
+
+    sequence.reverse();
+    sequence
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{float::Float, yaml};
+
+    #[test]
+    fn sequences() {
+        let left = yaml!([1, 2, 3, {a: 1}]);
+        let right = yaml!([3, 1, 2, {a: 2}, 5]);
+
+        let changes = diff_nodes(&left, &right, &DiffOptions::default());
+        let changes: Vec<_> = changes.iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            changes,
+            [
+                "[0]: moved from position 2 to 0",
+                "[3].a: changed from 1 to 2",
+                "[4]: added",
+            ]
+        );
+    }
+
+    #[test]
+    fn options() {
+        let left = Node::Mapping(Mapping::from_iter([
+            MappingPair::new("a".into(), Node::FloatingPoint("1.00".parse().unwrap())),
+            MappingPair::new("b".into(), 1.into()).with_comment("one"),
+        ]));
+        let right = Node::Mapping(Mapping::from_iter([
+            MappingPair::new("b".into(), 1.into()),
+            MappingPair::new("a".into(), Node::FloatingPoint(Float::new(1.0))),
+        ]));
+
+        let changes = diff_nodes(&left, &right, &DiffOptions::default());
+        let change = |key: &str, kind| Change {
+            path: vec![PathSegment::Key(key.into())],
+            kind,
+        };
+
+        assert_eq!(
+            changes,
+            [
+                change(
+                    "b",
+                    ChangeKind::CommentsChanged {
+                        from: vec!["one".into()],
+                        to: vec![],
+                    }
+                ),
+                change("a", ChangeKind::Moved { from: 0, to: 1 }),
+                change(
+                    "a",
+                    ChangeKind::FormatChanged {
+                        from: "1.00".into(),
+                        to: "1.0".into(),
+                    }
+                ),
+            ]
+        );
+
+        let options = DiffOptions::builder()
+            .ignore_comments(true)
+            .ignore_key_order(true)
+            .ignore_scalar_format(true)
+            .build();
+        assert!(diff_nodes(&left, &right, &options).is_empty());
+    }
+
+    #[test]
+    fn documents() {
+        let left: Document = "# Settings\nenabled: True\n".parse().unwrap();
+        let right: Document = "enabled: true\n".parse().unwrap();
+
+        let changes = diff_documents(&left, &right, &DiffOptions::default());
+        let changes: Vec<_> = changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "enabled: comments changed",
+                "enabled: format changed from True to true"
+            ]
+        );
+
+        let options = DiffOptions::builder()
+            .ignore_comments(true)
+            .ignore_scalar_format(true)
+            .build();
+        assert!(diff_documents(&left, &right, &options).is_empty());
+    }
+}
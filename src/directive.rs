@@ -0,0 +1,190 @@
+//! Structured `%YAML` and `%TAG` directives.
+//!
+//! Directives are instructions to the YAML processor and precede the start
+//! marker (`---`) of a document.
+//!
+//! See <https://yaml.org/spec/1.2.2/#68-directives>
+use std::{fmt, str::FromStr};
+
+use snafu::{ensure, OptionExt, Snafu};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("directives must start with '%'"))]
+    MissingIndicator,
+
+    #[snafu(display("the directive is missing a name after '%'"))]
+    MissingName,
+
+    #[snafu(display("the directive %{name} is missing a parameter"))]
+    MissingParameter { name: String },
+
+    #[snafu(display("the directive %{name} has too many parameters"))]
+    TooManyParameters { name: String },
+
+    #[snafu(display("invalid YAML version {version:?}, expected <major>.<minor>"))]
+    InvalidVersion { version: String },
+
+    #[snafu(display("invalid tag handle {handle:?}, expected '!', '!!' or '!name!'"))]
+    InvalidTagHandle { handle: String },
+}
+
+/// A single directive of a [`Document`](crate::Document).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Directive {
+    /// The `%YAML <major>.<minor>` directive.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#681-yaml-directives>
+    YamlVersion(u32, u32),
+
+    /// The `%TAG <handle> <prefix>` directive, which declares a tag handle
+    /// which can be used as a shorthand for tags starting with `prefix`.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#682-tag-directives>
+    TagHandle { handle: String, prefix: String },
+
+    /// Any other directive. These are reserved for future use by the YAML
+    /// specification and should be ignored by processors.
+    Reserved {
+        name: String,
+        parameters: Vec<String>,
+    },
+}
+
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Directive::YamlVersion(major, minor) => write!(f, "%YAML {major}.{minor}"),
+            Directive::TagHandle { handle, prefix } => write!(f, "%TAG {handle} {prefix}"),
+            Directive::Reserved { name, parameters } => {
+                write!(f, "%{name}")?;
+
+                for parameter in parameters {
+                    write!(f, " {parameter}")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Directive {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix('%').context(MissingIndicatorSnafu)?;
+        ensure!(!s.starts_with(char::is_whitespace), MissingNameSnafu);
+
+        let mut parts = s.split_whitespace();
+        let name = parts.next().context(MissingNameSnafu)?.to_string();
+
+        let mut parameter = || parts.next().context(MissingParameterSnafu { name: &name });
+
+        let directive = match name.as_str() {
+            "YAML" => {
+                let version = parameter()?;
+                let parsed = version
+                    .split_once('.')
+                    .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)));
+
+                let (major, minor) = parsed.context(InvalidVersionSnafu { version })?;
+                Directive::YamlVersion(major, minor)
+            }
+            "TAG" => {
+                let handle = parameter()?;
+                let prefix = parameter()?;
+                Directive::tag_handle(handle, prefix)?
+            }
+            _ => {
+                return Ok(Directive::Reserved {
+                    name,
+                    parameters: parts.map(Into::into).collect(),
+                })
+            }
+        };
+
+        ensure!(parts.next().is_none(), TooManyParametersSnafu { name });
+        Ok(directive)
+    }
+}
+
+impl Directive {
+    /// Creates the `%YAML 1.2` directive.
+    pub fn yaml_1_2() -> Self {
+        Self::YamlVersion(1, 2)
+    }
+
+    /// Creates a `%TAG` directive, declaring `handle` as a shorthand for
+    /// `prefix`. Fails if `handle` isn't `!`, `!!` or `!name!`.
+    pub fn tag_handle(handle: impl Into<String>, prefix: impl Into<String>) -> Result<Self, Error> {
+        let handle = handle.into();
+        ensure!(is_tag_handle(&handle), InvalidTagHandleSnafu { handle });
+
+        Ok(Self::TagHandle {
+            handle,
+            prefix: prefix.into(),
+        })
+    }
+}
+
+/// Returns `true` if `handle` is a primary (`!`), secondary (`!!`) or named
+/// (`!name!`) tag handle.
+fn is_tag_handle(handle: &str) -> bool {
+    match handle {
+        "!" | "!!" => true,
+        _ => handle
+            .strip_prefix('!')
+            .and_then(|h| h.strip_suffix('!'))
+            .is_some_and(|name| name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let directives = [
+            ("%YAML 1.2", Directive::yaml_1_2()),
+            (
+                "%TAG !e! tag:example.com,2000:app/",
+                Directive::tag_handle("!e!", "tag:example.com,2000:app/").unwrap(),
+            ),
+            (
+                "%FOO bar baz",
+                Directive::Reserved {
+                    name: "FOO".into(),
+                    parameters: vec!["bar".into(), "baz".into()],
+                },
+            ),
+        ];
+
+        for (input, directive) in directives {
+            assert_eq!(input.parse::<Directive>(), Ok(directive.clone()));
+            assert_eq!(directive.to_string(), input);
+        }
+
+        assert!("YAML 1.2".parse::<Directive>().is_err());
+        assert!("%YAML".parse::<Directive>().is_err());
+        assert!("%YAML 1".parse::<Directive>().is_err());
+        assert!("%YAML 1.2 1.3".parse::<Directive>().is_err());
+        assert!("%TAG e! tag:example.com,2000:"
+            .parse::<Directive>()
+            .is_err());
+
+        assert_eq!("%".parse::<Directive>(), Err(Error::MissingName));
+        assert_eq!("% YAML 1.2".parse::<Directive>(), Err(Error::MissingName));
+
+        for handle in ["!e", "e!", "!e.x!", "!!!"] {
+            assert_eq!(
+                format!("%TAG {handle} tag:example.com,2000:").parse::<Directive>(),
+                Err(Error::InvalidTagHandle {
+                    handle: handle.into()
+                })
+            );
+        }
+        assert!(Directive::tag_handle("!e", "tag:example.com,2000:").is_err());
+    }
+}
@@ -0,0 +1,386 @@
+//! Graphviz (DOT) exports of streams, CSTs and trees, e.g. to visualize
+//! which nodes an alias-heavy document shares.
+//!
+//! Every document and node becomes a vertex of the graph, collections are
+//! connected to their values by edges labeled with the key or index. Tags
+//! and anchors are part of the labels of the nodes. Aliases only exist in
+//! the [`Cst`], where they are drawn as dashed edges to the anchored node,
+//! and shared nodes only exist in a [`Tree`], where they have multiple
+//! incoming edges. Render the output with e.g. `dot -Tsvg`.
+//!
+//! ```
+//! use yaml_ast::cst::Cst;
+//!
+//! let cst = Cst::parse("base: &base {a: 1}\nprod: *base\n").unwrap();
+//! assert_eq!(
+//!     cst.to_dot(),
+//!     "\
+//! digraph yaml {
+//!   n0 [label=\"Document 0\", shape=box];
+//!   n1 [label=\"Mapping\"];
+//!   n0 -> n1;
+//!   n2 [label=\"&base Mapping\"];
+//!   n1 -> n2 [label=\"base\"];
+//!   n3 [label=\"1\", shape=plaintext];
+//!   n2 -> n3 [label=\"a\"];
+//!   n1 -> n2 [label=\"prod\", style=dashed];
+//! }
+//! "
+//! );
+//! ```
+use std::collections::HashMap;
+
+use crate::{
+    cst::{Cst, CstKind, CstNode},
+    emitter::key_segment,
+    events::PathSegment,
+    tree::{NodeId, Tree, TreeNode},
+    Node, Stream,
+};
+
+/// Writes the vertices and edges of a graph.
+struct Dot {
+    output: String,
+    vertices: usize,
+}
+
+impl Dot {
+    fn new() -> Self {
+        Self {
+            output: "digraph yaml {\n".into(),
+            vertices: 0,
+        }
+    }
+
+    /// Adds a vertex with the `label` and an edge from the `parent` to it,
+    /// labeled with the key or index. Returns the vertex.
+    fn vertex(&mut self, label: &str, scalar: bool, parent: Option<(usize, String)>) -> usize {
+        let vertex = self.vertices;
+        self.vertices += 1;
+
+        let shape = match scalar {
+            true => ", shape=plaintext",
+            false => "",
+        };
+        self.output.push_str(&format!(
+            "  n{vertex} [label=\"{}\"{shape}];\n",
+            escape(label)
+        ));
+
+        if let Some((parent, label)) = parent {
+            self.edge(parent, vertex, &label, false);
+        }
+
+        vertex
+    }
+
+    fn document(&mut self, index: usize) -> usize {
+        let vertex = self.vertices;
+        self.vertices += 1;
+
+        self.output.push_str(&format!(
+            "  n{vertex} [label=\"Document {index}\", shape=box];\n"
+        ));
+        vertex
+    }
+
+    fn edge(&mut self, from: usize, to: usize, label: &str, dashed: bool) {
+        let mut attributes = Vec::new();
+        if !label.is_empty() {
+            attributes.push(format!("label=\"{}\"", escape(label)));
+        }
+        if dashed {
+            attributes.push("style=dashed".into());
+        }
+
+        match attributes.is_empty() {
+            true => self.output.push_str(&format!("  n{from} -> n{to};\n")),
+            false => self.output.push_str(&format!(
+                "  n{from} -> n{to} [{}];\n",
+                attributes.join(", ")
+            )),
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.output.push_str("}\n");
+        self.output
+    }
+}
+
+/// Escapes the `label` for a quoted DOT string.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Returns the label of a scalar node, where strings are quoted.
+fn scalar_label(node: &Node) -> String {
+    match node {
+        Node::String(value) => format!("{value:?}"),
+        node => match key_segment(node) {
+            PathSegment::Key(key) => key,
+            PathSegment::Index(index) => index.to_string(),
+        },
+    }
+}
+
+/// Returns the label of the edge to the value of the pair with the `key`.
+fn key_label(key: &Node) -> String {
+    match key_segment(key) {
+        PathSegment::Key(key) => key,
+        PathSegment::Index(index) => index.to_string(),
+    }
+}
+
+impl Stream {
+    /// Returns the documents and their nodes as a DOT graph. The aliases of
+    /// the stream are resolved, use [`Cst::to_dot`] to show them.
+    ///
+    /// ```
+    /// use yaml_ast::Stream;
+    ///
+    /// let stream: Stream = "ports: [80]\n".parse().unwrap();
+    /// assert_eq!(
+    ///     stream.to_dot(),
+    ///     "\
+    /// digraph yaml {
+    ///   n0 [label=\"Document 0\", shape=box];
+    ///   n1 [label=\"Mapping\"];
+    ///   n0 -> n1;
+    ///   n2 [label=\"Sequence\"];
+    ///   n1 -> n2 [label=\"ports\"];
+    ///   n3 [label=\"80\", shape=plaintext];
+    ///   n2 -> n3 [label=\"0\"];
+    /// }
+    /// "
+    /// );
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new();
+
+        for (index, document) in self.iter().enumerate() {
+            let vertex = dot.document(index);
+            for node in &document.nodes {
+                node_vertex(&mut dot, node, Some((vertex, String::new())));
+            }
+        }
+
+        dot.finish()
+    }
+}
+
+fn node_vertex(dot: &mut Dot, node: &Node, parent: Option<(usize, String)>) {
+    let mut tags = String::new();
+    let mut node = node;
+    while let Node::Tagged(tagged) = node {
+        tags.push_str(&tagged.tag);
+        tags.push(' ');
+        node = &tagged.node;
+    }
+
+    match node {
+        Node::Mapping(mapping) => {
+            let vertex = dot.vertex(&format!("{tags}Mapping"), false, parent);
+            for pair in mapping.pairs() {
+                node_vertex(dot, &pair.value, Some((vertex, key_label(&pair.key))));
+            }
+        }
+        Node::Sequence(items) => {
+            let vertex = dot.vertex(&format!("{tags}Sequence"), false, parent);
+            for (index, item) in items.iter().enumerate() {
+                node_vertex(dot, item, Some((vertex, index.to_string())));
+            }
+        }
+        scalar => {
+            dot.vertex(&format!("{tags}{}", scalar_label(scalar)), true, parent);
+        }
+    }
+}
+
+impl Cst {
+    /// Returns the documents and their nodes as a DOT graph, where aliases
+    /// are dashed edges to the anchored node. Aliases of unknown anchors
+    /// are drawn as vertices of their own.
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new();
+
+        for (index, document) in self.documents().iter().enumerate() {
+            let vertex = dot.document(index);
+            if let Some(root) = document.root() {
+                cst_vertex(&mut dot, root, (vertex, String::new()), &mut HashMap::new());
+            }
+        }
+
+        dot.finish()
+    }
+}
+
+fn cst_vertex(
+    dot: &mut Dot,
+    node: &CstNode,
+    parent: (usize, String),
+    anchors: &mut HashMap<String, usize>,
+) {
+    if let CstKind::Alias(name) = &node.kind {
+        match anchors.get(name) {
+            Some(anchor) => dot.edge(parent.0, *anchor, &parent.1, true),
+            None => {
+                dot.vertex(&format!("*{name}"), false, Some(parent));
+            }
+        }
+        return;
+    }
+
+    let mut label = String::new();
+    if let Some(anchor) = &node.anchor {
+        label.push_str(&format!("&{anchor} "));
+    }
+    if let Some(tag) = &node.tag {
+        label.push_str(&format!("{tag} "));
+    }
+
+    let vertex = match &node.kind {
+        CstKind::Scalar { value, .. } => {
+            label.push_str(value);
+            dot.vertex(&label, true, Some(parent))
+        }
+        CstKind::Sequence { items, .. } => {
+            label.push_str("Sequence");
+            let vertex = dot.vertex(&label, false, Some(parent));
+            for (index, item) in items.iter().enumerate() {
+                cst_vertex(dot, &item.node, (vertex, index.to_string()), anchors);
+            }
+            vertex
+        }
+        CstKind::Mapping { pairs, .. } => {
+            label.push_str("Mapping");
+            let vertex = dot.vertex(&label, false, Some(parent));
+            for pair in pairs {
+                let key = match &pair.key.kind {
+                    CstKind::Scalar { value, .. } => value.clone(),
+                    _ => "?".into(),
+                };
+                cst_vertex(dot, &pair.value, (vertex, key), anchors);
+            }
+            vertex
+        }
+        CstKind::Alias(_) => unreachable!(),
+    };
+
+    // An anchor is only visible after its node, which is why nested
+    // aliases of the same anchor can't refer to it
+    if let Some(anchor) = &node.anchor {
+        anchors.insert(anchor.clone(), vertex);
+    }
+}
+
+impl Tree {
+    /// Returns the nodes reachable from the root as a DOT graph. Shared
+    /// nodes are drawn once, with an edge from every collection which
+    /// refers to them, cycles are drawn as edges back to the ancestor.
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new();
+
+        if let Some(root) = self.root() {
+            tree_vertex(self, &mut dot, root, None, &mut HashMap::new());
+        }
+
+        dot.finish()
+    }
+}
+
+fn tree_vertex(
+    tree: &Tree,
+    dot: &mut Dot,
+    id: NodeId,
+    parent: Option<(usize, String)>,
+    vertices: &mut HashMap<NodeId, usize>,
+) {
+    if let Some(vertex) = vertices.get(&id) {
+        if let Some((parent, label)) = parent {
+            dot.edge(parent, *vertex, &label, false);
+        }
+        return;
+    }
+
+    // The vertex is known before the children, which draws cycles as edges
+    // back to the ancestor
+    let vertex = match tree.get(id) {
+        TreeNode::Mapping(_) => dot.vertex("Mapping", false, parent),
+        TreeNode::Sequence(_) => dot.vertex("Sequence", false, parent),
+        TreeNode::Tagged(tag, _) => dot.vertex(tag, false, parent),
+        TreeNode::Scalar(scalar) => dot.vertex(&scalar_label(scalar), true, parent),
+    };
+    vertices.insert(id, vertex);
+
+    match tree.get(id) {
+        TreeNode::Mapping(pairs) => {
+            for (key, value) in pairs {
+                let label = match tree.get(*key) {
+                    TreeNode::Scalar(key) => key_label(key),
+                    _ => "?".into(),
+                };
+                tree_vertex(tree, dot, *value, Some((vertex, label)), vertices);
+            }
+        }
+        TreeNode::Sequence(items) => {
+            for (index, item) in items.iter().enumerate() {
+                let parent = Some((vertex, index.to_string()));
+                tree_vertex(tree, dot, *item, parent, vertices);
+            }
+        }
+        TreeNode::Tagged(_, node) => {
+            tree_vertex(tree, dot, *node, Some((vertex, String::new())), vertices);
+        }
+        TreeNode::Scalar(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    #[test]
+    fn to_dot() {
+        let cst = Cst::parse("a: !t \"x\\\"y\"\nb: *missing\n---\n- &s 1\n- *s\n").unwrap();
+        assert_eq!(
+            cst.to_dot(),
+            "\
+digraph yaml {
+  n0 [label=\"Document 0\", shape=box];
+  n1 [label=\"Mapping\"];
+  n0 -> n1;
+  n2 [label=\"!t x\\\"y\", shape=plaintext];
+  n1 -> n2 [label=\"a\"];
+  n3 [label=\"*missing\"];
+  n1 -> n3 [label=\"b\"];
+  n4 [label=\"Document 1\", shape=box];
+  n5 [label=\"Sequence\"];
+  n4 -> n5;
+  n6 [label=\"&s 1\", shape=plaintext];
+  n5 -> n6 [label=\"0\"];
+  n5 -> n6 [label=\"1\", style=dashed];
+}
+"
+        );
+
+        let mut tree = Tree::new();
+        let shared = tree.insert(yaml!({x: 1}));
+        let root = tree.push(TreeNode::Sequence(vec![shared, shared]));
+        tree.set_root(root);
+        assert_eq!(
+            tree.to_dot(),
+            "\
+digraph yaml {
+  n0 [label=\"Sequence\"];
+  n1 [label=\"Mapping\"];
+  n0 -> n1 [label=\"0\"];
+  n2 [label=\"1\", shape=plaintext];
+  n1 -> n2 [label=\"x\"];
+  n0 -> n1 [label=\"1\"];
+}
+"
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Indented dumps of the AST and the CST for debugging, e.g. to include in
+//! bug reports. Each line describes a single node by its type and value,
+//! its children follow below it.
+//!
+//! [`Node::dump_tree`] shows the tags of the nodes. [`Cst::dump_tree`]
+//! additionally shows the anchors, aliases, styles and spans of the nodes
+//! as written in the source text.
+//!
+//! ```
+//! use yaml_ast::{cst::Cst, Node};
+//!
+//! let node: Node = "image: !ref nginx\nports: [80]\n".parse().unwrap();
+//! assert_eq!(
+//!     node.dump_tree(),
+//!     "\
+//! Mapping (2 pairs)
+//! |-Pair
+//! | |-String \"image\"
+//! | `-Tagged !ref
+//! |   `-String \"nginx\"
+//! `-Pair
+//!   |-String \"ports\"
+//!   `-Sequence (1 item)
+//!     `-Integer 80
+//! "
+//! );
+//!
+//! let cst = Cst::parse("a: &x 1\nb: *x\n").unwrap();
+//! assert_eq!(
+//!     cst.dump_tree(),
+//!     "\
+//! Document 0..14
+//! `-Mapping block 0..13
+//!   |-Pair
+//!   | |-Scalar plain 0..1 \"a\"
+//!   | `-Scalar plain 6..7 &x \"1\"
+//!   `-Pair
+//!     |-Scalar plain 8..9 \"b\"
+//!     `-Alias 11..13 *x
+//! "
+//! );
+//! ```
+use crate::{
+    cst::{CollectionStyle, Cst, CstKind, CstNode, ScalarStyle},
+    Node,
+};
+
+/// A node of the dump with the nodes below it.
+struct Tree {
+    label: String,
+    children: Vec<Tree>,
+}
+
+impl Tree {
+    fn leaf(label: impl Into<String>) -> Self {
+        Self::new(label, Vec::new())
+    }
+
+    fn new(label: impl Into<String>, children: Vec<Tree>) -> Self {
+        Self {
+            label: label.into(),
+            children,
+        }
+    }
+
+    /// Writes the tree, where `prefix` continues the branches of the
+    /// ancestors.
+    fn write(&self, output: &mut String, prefix: &str) {
+        output.push_str(&self.label);
+        output.push('\n');
+
+        for (index, child) in self.children.iter().enumerate() {
+            let is_last = index + 1 == self.children.len();
+            let (branch, continuation) = match is_last {
+                true => ("`-", "  "),
+                false => ("|-", "| "),
+            };
+
+            output.push_str(prefix);
+            output.push_str(branch);
+            child.write(output, &format!("{prefix}{continuation}"));
+        }
+    }
+
+    fn render(trees: &[Tree]) -> String {
+        let mut output = String::new();
+        for tree in trees {
+            tree.write(&mut output, "");
+        }
+
+        output
+    }
+}
+
+/// Returns `count` with the singular or plural of `noun`.
+fn count(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {noun}"),
+        count => format!("{count} {noun}s"),
+    }
+}
+
+impl Node {
+    /// Returns an indented dump of the node and all nodes within it,
+    /// including their tags, e.g. `Tagged !ref` followed by the tagged
+    /// node. Use [`Cst::dump_tree`] to include the presentation details.
+    pub fn dump_tree(&self) -> String {
+        Tree::render(&[node_tree(self)])
+    }
+}
+
+fn node_tree(node: &Node) -> Tree {
+    match node {
+        Node::Mapping(mapping) => {
+            let pairs = mapping
+                .pairs()
+                .map(|pair| Tree::new("Pair", vec![node_tree(&pair.key), node_tree(&pair.value)]))
+                .collect();
+            Tree::new(format!("Mapping ({})", count(mapping.len(), "pair")), pairs)
+        }
+        Node::Sequence(items) => Tree::new(
+            format!("Sequence ({})", count(items.len(), "item")),
+            items.iter().map(node_tree).collect(),
+        ),
+        Node::String(value) => Tree::leaf(format!("String {value:?}")),
+        Node::Null => Tree::leaf("Null"),
+        Node::Boolean(value) => Tree::leaf(format!("Boolean {value}")),
+        Node::Integer(value) => Tree::leaf(format!("Integer {value}")),
+        Node::BigInteger(value) => Tree::leaf(format!("BigInteger {value}")),
+        Node::FloatingPoint(value) => Tree::leaf(format!("FloatingPoint {value}")),
+        Node::Binary(bytes) => Tree::leaf(format!("Binary ({})", count(bytes.len(), "byte"))),
+        Node::Timestamp(value) => Tree::leaf(format!("Timestamp {value}")),
+        Node::Tagged(tagged) => Tree::new(
+            format!("Tagged {}", tagged.tag),
+            vec![node_tree(&tagged.node)],
+        ),
+    }
+}
+
+impl Cst {
+    /// Returns an indented dump of all documents and their nodes, including
+    /// the anchors, aliases, tags, styles and spans of the nodes, e.g.
+    /// `Scalar plain 6..7 &x "1"`.
+    pub fn dump_tree(&self) -> String {
+        let documents: Vec<_> = self
+            .documents()
+            .iter()
+            .map(|document| {
+                Tree::new(
+                    format!("Document {}", document.span),
+                    document.root().map(cst_tree).into_iter().collect(),
+                )
+            })
+            .collect();
+
+        Tree::render(&documents)
+    }
+}
+
+impl CstNode {
+    /// Returns an indented dump of the node and all nodes within it, see
+    /// [`Cst::dump_tree`].
+    pub fn dump_tree(&self) -> String {
+        Tree::render(&[cst_tree(self)])
+    }
+}
+
+fn cst_tree(node: &CstNode) -> Tree {
+    let (mut label, value, children) = match &node.kind {
+        CstKind::Scalar { style, value } => {
+            let style = match style {
+                ScalarStyle::Plain => "plain",
+                ScalarStyle::SingleQuoted => "single-quoted",
+                ScalarStyle::DoubleQuoted => "double-quoted",
+                ScalarStyle::Literal => "literal",
+                ScalarStyle::Folded => "folded",
+            };
+            (
+                format!("Scalar {style}"),
+                Some(format!("{value:?}")),
+                Vec::new(),
+            )
+        }
+        CstKind::Alias(name) => ("Alias".into(), Some(format!("*{name}")), Vec::new()),
+        CstKind::Sequence { style, items } => (
+            format!("Sequence {}", collection_style(*style)),
+            None,
+            items.iter().map(|item| cst_tree(&item.node)).collect(),
+        ),
+        CstKind::Mapping { style, pairs } => (
+            format!("Mapping {}", collection_style(*style)),
+            None,
+            pairs
+                .iter()
+                .map(|pair| Tree::new("Pair", vec![cst_tree(&pair.key), cst_tree(&pair.value)]))
+                .collect(),
+        ),
+    };
+
+    label.push_str(&format!(" {}", node.span));
+    if let Some(anchor) = &node.anchor {
+        label.push_str(&format!(" &{anchor}"));
+    }
+    if let Some(tag) = &node.tag {
+        label.push_str(&format!(" {tag}"));
+    }
+    if let Some(value) = value {
+        label.push_str(&format!(" {value}"));
+    }
+
+    Tree::new(label, children)
+}
+
+fn collection_style(style: CollectionStyle) -> &'static str {
+    match style {
+        CollectionStyle::Block => "block",
+        CollectionStyle::Flow => "flow",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    #[test]
+    fn dump_tree() {
+        let node = yaml!({a: [null, true, 1.5], b: {}});
+        assert_eq!(
+            node.dump_tree(),
+            "\
+Mapping (2 pairs)
+|-Pair
+| |-String \"a\"
+| `-Sequence (3 items)
+|   |-Null
+|   |-Boolean true
+|   `-FloatingPoint 1.5
+`-Pair
+  |-String \"b\"
+  `-Mapping (0 pairs)
+"
+        );
+
+        let cst = Cst::parse("--- !!seq\n- 'a'\n- [b]\n---\n").unwrap();
+        assert_eq!(
+            cst.dump_tree(),
+            "\
+Document 0..22
+`-Sequence block 10..21 !!seq
+  |-Scalar single-quoted 12..15 \"a\"
+  `-Sequence flow 18..21
+    `-Scalar plain 19..20 \"b\"
+Document 22..26
+"
+        );
+    }
+}
@@ -0,0 +1,583 @@
+//! Comment-preserving edits of [`Document`]s.
+//!
+//! Nodes are addressed by dot-separated paths like [`Node::get_path`], e.g.
+//! `spec.containers.0.image`. Edits keep the presentation of everything
+//! around them: replaced values keep the comments and blank lines of their
+//! mapping pair or sequence item, deleted pairs take their own comments
+//! with them and pass their blank lines on to the following pair, the items
+//! following an inserted or deleted sequence item move, and the
+//! [`lexical_forms`](Document::lexical_forms),
+//! [`item_comments`](Document::item_comments),
+//! [`provenance`](Document::provenance) and [`styles`](Document::styles) of
+//! the document follow the nodes they belong to.
+//!
+//! ```
+//! use yaml_ast::Document;
+//!
+//! let mut document: Document =
+//!     "# Image\nimage: nginx\n\n# Deprecated\nlegacy: true\nports: [80]\n"
+//!         .parse()
+//!         .unwrap();
+//!
+//! document.set("image", "nginx:1.27").unwrap();
+//! document.set("resources.limits.cpu", 1).unwrap();
+//! document.delete("legacy").unwrap();
+//! document.rename_key("ports", "containerPorts").unwrap();
+//!
+//! assert_eq!(
+//!     document.to_string(),
+//!     "---\n# Image\nimage: nginx:1.27\n\ncontainerPorts:\n  - 80\nresources:\n  limits:\n    cpu: 1\n...\n"
+//! );
+//! ```
+use std::collections::HashMap;
+
+use snafu::{ensure, OptionExt, Snafu};
+
+use crate::{
+    events::PathSegment, provenance::Provenance, style::Style, Document, ItemComments, Mapping,
+    Node,
+};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("the document has no root node"))]
+    MissingRoot,
+
+    #[snafu(display("no node is located at {path:?}"))]
+    NotFound { path: String },
+
+    #[snafu(display("the {found} at {path:?} is not a mapping or sequence"))]
+    NotACollection { path: String, found: String },
+
+    #[snafu(display("the node at {path:?} is not the value of a mapping pair"))]
+    NotAPair { path: String },
+
+    #[snafu(display("the path {path:?} doesn't refer to a sequence item"))]
+    NotAnItem { path: String },
+
+    #[snafu(display("the key {key:?} already exists next to {path:?}"))]
+    KeyExists { path: String, key: String },
+}
+
+impl Document {
+    /// Sets the node at the dot-separated `path` to `value` and returns the
+    /// previous node, if any.
+    ///
+    /// Missing keys are appended to their mapping, creating the mappings
+    /// along the path as needed. Sequence items are addressed by their index,
+    /// where the length of the sequence appends an item. An empty path
+    /// replaces the root node.
+    pub fn set(&mut self, path: &str, value: impl Into<Node>) -> Result<Option<Node>, Error> {
+        let value = value.into();
+        let segments = split(path);
+
+        let Some(root) = self.nodes.first_mut() else {
+            ensure!(segments.is_empty(), MissingRootSnafu);
+            self.nodes.push(value);
+            return Ok(None);
+        };
+
+        let mut node = root;
+        let mut node_path = Vec::new();
+        let mut created = false;
+
+        for (position, segment) in segments.iter().enumerate() {
+            let is_last = position + 1 == segments.len();
+
+            node = match untagged_mut(node) {
+                Node::Mapping(mapping) => {
+                    let key = Node::String(segment.to_string());
+                    let index = match mapping.position(&key) {
+                        Some(index) => index,
+                        None => {
+                            let value = match is_last {
+                                true => Node::Null,
+                                false => Node::Mapping(Mapping::new()),
+                            };
+
+                            created = true;
+                            mapping.push_pair(key, value);
+                            mapping.len() - 1
+                        }
+                    };
+
+                    node_path.push(PathSegment::Key(segment.to_string()));
+                    let Some(value) = mapping.value_at_mut(index) else {
+                        unreachable!()
+                    };
+
+                    value
+                }
+                Node::Sequence(items) => {
+                    let index = segment
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|index| *index < items.len() || (is_last && *index == items.len()))
+                        .context(NotFoundSnafu { path })?;
+
+                    if index == items.len() {
+                        created = true;
+                        items.push(Node::Null);
+                    }
+
+                    node_path.push(PathSegment::Index(index));
+                    &mut items[index]
+                }
+                node => {
+                    return NotACollectionSnafu {
+                        path: segments[..position].join("."),
+                        found: node.description(),
+                    }
+                    .fail()
+                }
+            };
+        }
+
+        let previous = std::mem::replace(node, value);
+        self.forget_replaced(&node_path);
+
+        Ok((!created).then_some(previous))
+    }
+
+    /// Inserts `value` as the sequence item at the dot-separated `path`,
+    /// e.g. `args.0`, where the length of the sequence appends an item.
+    ///
+    /// The items from the index on move down together with their
+    /// [`item_comments`](Document::item_comments), lexical forms,
+    /// provenance and styles. Unlike [`Vec::insert`] on the sequence, this
+    /// keeps the comments of the existing items on their items.
+    pub fn insert(&mut self, path: &str, value: impl Into<Node>) -> Result<(), Error> {
+        let mut segments = split(path);
+        let last = segments.pop().context(NotAnItemSnafu { path })?;
+
+        let (parent, mut parent_path) = self.parent_mut(&segments, path)?;
+        let Node::Sequence(items) = parent else {
+            return NotAnItemSnafu { path }.fail();
+        };
+
+        let index = last
+            .parse::<usize>()
+            .ok()
+            .filter(|index| *index <= items.len())
+            .context(NotFoundSnafu { path })?;
+        items.insert(index, value.into());
+
+        parent_path.push(PathSegment::Index(index));
+        self.shift_inserted(&parent_path);
+
+        Ok(())
+    }
+
+    /// Deletes the node at the dot-separated `path` and returns it.
+    ///
+    /// Deleting the value of a mapping pair removes the whole pair together
+    /// with its comments. Its blank lines are kept by the following pair, so
+    /// that groups of pairs stay separated. The items following a deleted
+    /// sequence item move up, together with their item comments. An empty
+    /// path deletes the root node.
+    pub fn delete(&mut self, path: &str) -> Result<Node, Error> {
+        ensure!(!self.nodes.is_empty(), MissingRootSnafu);
+
+        let mut segments = split(path);
+        let Some(last) = segments.pop() else {
+            self.forget_removed(&[]);
+            return Ok(self.nodes.remove(0));
+        };
+
+        let (parent, mut parent_path) = self.parent_mut(&segments, path)?;
+        match parent {
+            Node::Mapping(mapping) => {
+                let index = mapping
+                    .position(&Node::String(last.into()))
+                    .context(NotFoundSnafu { path })?;
+                let pair = mapping.remove(index);
+
+                if let Some(next) = mapping[index..].first_mut() {
+                    next.blank_lines = next.blank_lines.max(pair.blank_lines);
+                }
+
+                parent_path.push(PathSegment::Key(last.into()));
+                self.forget_removed(&parent_path);
+
+                Ok(pair.value)
+            }
+            Node::Sequence(items) => {
+                let index = last
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|index| *index < items.len())
+                    .context(NotFoundSnafu { path })?;
+                let item = items.remove(index);
+
+                parent_path.push(PathSegment::Index(index));
+                self.forget_removed(&parent_path);
+
+                Ok(item)
+            }
+            node => NotACollectionSnafu {
+                path: segments.join("."),
+                found: node.description(),
+            }
+            .fail(),
+        }
+    }
+
+    /// Renames the key of the mapping pair whose value is located at the
+    /// dot-separated `path` to `key`. The pair keeps its position, comments
+    /// and blank lines. Fails if the mapping already contains `key`.
+    pub fn rename_key(&mut self, path: &str, key: impl Into<String>) -> Result<(), Error> {
+        let key = key.into();
+        let mut segments = split(path);
+        let last = segments.pop().context(NotAPairSnafu { path })?;
+
+        let (parent, parent_path) = self.parent_mut(&segments, path)?;
+        let Node::Mapping(mapping) = parent else {
+            return NotAPairSnafu { path }.fail();
+        };
+
+        let index = mapping
+            .position(&Node::String(last.into()))
+            .context(NotFoundSnafu { path })?;
+
+        if key == last {
+            return Ok(());
+        }
+
+        ensure!(
+            !mapping.contains_key(&Node::String(key.clone())),
+            KeyExistsSnafu { path, key }
+        );
+        mapping[index].key = Node::String(key.clone());
+
+        let depth = parent_path.len();
+        let old = PathSegment::Key(last.into());
+        let rename = |mut path: Vec<PathSegment>| {
+            if path.starts_with(&parent_path) && path.get(depth) == Some(&old) {
+                path[depth] = PathSegment::Key(key.clone());
+            }
+
+            Some(path)
+        };
+        self.remap_paths(rename);
+
+        Ok(())
+    }
+
+    /// Moves the entries of the [`lexical_forms`](Document::lexical_forms),
+    /// [`item_comments`](Document::item_comments),
+    /// [`provenance`](Document::provenance) and [`styles`](Document::styles)
+    /// to the paths returned by `f`, dropping the entries for which it
+    /// returns [`None`].
+    pub(crate) fn remap_paths(&mut self, f: impl Fn(Vec<PathSegment>) -> Option<Vec<PathSegment>>) {
+        remap(&mut self.lexical_forms, &f);
+        remap(&mut self.item_comments, &f);
+        remap(&mut self.provenance, &f);
+        remap(&mut self.styles, &f);
+    }
+
+    /// Drops the entries of the node at `path`, which was replaced, and of
+    /// its descendants. The comments of a sequence item stay with the item.
+    pub(crate) fn forget_replaced(&mut self, path: &[PathSegment]) {
+        let outside = |entry: Vec<PathSegment>| (!entry.starts_with(path)).then_some(entry);
+
+        remap(&mut self.lexical_forms, outside);
+        remap(&mut self.item_comments, |entry| {
+            (entry.len() == path.len() || !entry.starts_with(path)).then_some(entry)
+        });
+        remap(&mut self.provenance, outside);
+        remap(&mut self.styles, outside);
+    }
+
+    /// Drops the entries of the node at `path`, which was removed, and of
+    /// its descendants. The entries of the following items of a sequence
+    /// move up.
+    pub(crate) fn forget_removed(&mut self, path: &[PathSegment]) {
+        let Some((&PathSegment::Index(index), parent)) = path.split_last() else {
+            return self.remap_paths(|entry| (!entry.starts_with(path)).then_some(entry));
+        };
+
+        let depth = parent.len();
+        self.remap_paths(|mut entry| {
+            if !entry.starts_with(parent) {
+                return Some(entry);
+            }
+
+            match entry.get_mut(depth) {
+                Some(PathSegment::Index(i)) if *i == index => None,
+                Some(PathSegment::Index(i)) if *i > index => {
+                    *i -= 1;
+                    Some(entry)
+                }
+                _ => Some(entry),
+            }
+        });
+    }
+
+    /// Returns the entries of the node at `path` and of its descendants by
+    /// their path relative to the node, e.g. to move them along with the
+    /// node. The node keeps the provenance inherited from its ancestors.
+    pub(crate) fn tables_at(&self, path: &[PathSegment]) -> Tables {
+        fn relative<T: Clone>(
+            entries: &HashMap<Vec<PathSegment>, T>,
+            path: &[PathSegment],
+        ) -> HashMap<Vec<PathSegment>, T> {
+            entries
+                .iter()
+                .filter_map(|(entry, value)| {
+                    Some((entry.strip_prefix(path)?.to_vec(), value.clone()))
+                })
+                .collect()
+        }
+
+        let mut provenance = relative(&self.provenance, path);
+        if let Some(inherited) = self.provenance_at(path) {
+            provenance
+                .entry(Vec::new())
+                .or_insert_with(|| inherited.clone());
+        }
+
+        Tables {
+            lexical_forms: relative(&self.lexical_forms, path),
+            item_comments: relative(&self.item_comments, path),
+            provenance,
+            styles: relative(&self.styles, path),
+        }
+    }
+
+    /// Inserts the `tables` returned by [`Document::tables_at`] for the node
+    /// which is now located at `path`.
+    pub(crate) fn insert_tables(&mut self, path: &[PathSegment], tables: Tables) {
+        fn absolute<T>(
+            entries: &mut HashMap<Vec<PathSegment>, T>,
+            path: &[PathSegment],
+            tables: HashMap<Vec<PathSegment>, T>,
+        ) {
+            entries.extend(
+                tables
+                    .into_iter()
+                    .map(|(entry, value)| ([path, &entry].concat(), value)),
+            );
+        }
+
+        absolute(&mut self.lexical_forms, path, tables.lexical_forms);
+        absolute(&mut self.item_comments, path, tables.item_comments);
+        absolute(&mut self.provenance, path, tables.provenance);
+        absolute(&mut self.styles, path, tables.styles);
+    }
+
+    /// Moves the entries of the sequence items at and after the item which
+    /// was inserted at `path` one item down.
+    pub(crate) fn shift_inserted(&mut self, path: &[PathSegment]) {
+        let Some((&PathSegment::Index(index), parent)) = path.split_last() else {
+            return;
+        };
+
+        let depth = parent.len();
+        self.remap_paths(|mut entry| {
+            if !entry.starts_with(parent) {
+                return Some(entry);
+            }
+
+            if let Some(PathSegment::Index(i)) = entry.get_mut(depth) {
+                if *i >= index {
+                    *i += 1;
+                }
+            }
+
+            Some(entry)
+        });
+    }
+
+    /// Returns the (untagged) node at the `segments` of `path` and its path
+    /// within the document.
+    fn parent_mut(
+        &mut self,
+        segments: &[&str],
+        path: &str,
+    ) -> Result<(&mut Node, Vec<PathSegment>), Error> {
+        let mut node = self.nodes.first_mut().context(MissingRootSnafu)?;
+        let mut node_path = Vec::new();
+
+        for segment in segments {
+            node = match untagged_mut(node) {
+                Node::Mapping(mapping) => {
+                    node_path.push(PathSegment::Key(segment.to_string()));
+                    mapping.get_mut(segment)
+                }
+                Node::Sequence(items) => {
+                    let index = segment.parse::<usize>().ok();
+                    node_path.extend(index.map(PathSegment::Index));
+                    index.and_then(|index| items.get_mut(index))
+                }
+                _ => None,
+            }
+            .context(NotFoundSnafu { path })?;
+        }
+
+        Ok((untagged_mut(node), node_path))
+    }
+}
+
+/// The entries of a node and its descendants in the
+/// [`lexical_forms`](Document::lexical_forms),
+/// [`item_comments`](Document::item_comments),
+/// [`provenance`](Document::provenance) and [`styles`](Document::styles),
+/// see [`Document::tables_at`].
+#[derive(Debug, Default)]
+pub(crate) struct Tables {
+    lexical_forms: HashMap<Vec<PathSegment>, String>,
+    item_comments: HashMap<Vec<PathSegment>, ItemComments>,
+    provenance: HashMap<Vec<PathSegment>, Provenance>,
+    styles: HashMap<Vec<PathSegment>, Style>,
+}
+
+impl Tables {
+    /// Drops the item comments of the node itself, e.g. if it replaces a
+    /// sequence item which keeps its comments.
+    pub(crate) fn without_item_comments(mut self) -> Self {
+        self.item_comments.remove(&[][..]);
+        self
+    }
+}
+
+/// Splits the dot-separated `path` into its segments. The empty path has no
+/// segments.
+fn split(path: &str) -> Vec<&str> {
+    match path.is_empty() {
+        true => Vec::new(),
+        false => path.split('.').collect(),
+    }
+}
+
+/// Moves the `entries` to the paths returned by `f`, dropping the entries
+/// for which it returns [`None`].
+fn remap<T>(
+    entries: &mut HashMap<Vec<PathSegment>, T>,
+    f: impl Fn(Vec<PathSegment>) -> Option<Vec<PathSegment>>,
+) {
+    *entries = std::mem::take(entries)
+        .into_iter()
+        .filter_map(|(path, entry)| f(path).map(|path| (path, entry)))
+        .collect();
+}
+
+fn untagged_mut(mut node: &mut Node) -> &mut Node {
+    while let Node::Tagged(tagged) = node {
+        node = &mut tagged.node;
+    }
+
+    node
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    #[test]
+    fn edit() {
+        let mut document: Document = "\
+args:
+  # first
+  - a
+  # second
+  - b
+  - c
+flags: {debug: True}
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(document.delete("args.0"), Ok(Node::from("a")));
+        assert_eq!(
+            document.set("flags.debug", false),
+            Ok(Some(Node::Boolean(true)))
+        );
+        assert_eq!(document.set("args.2", "d"), Ok(None));
+        assert_eq!(
+            document.to_string(),
+            "---\nargs:\n  # second\n  - b\n  - c\n  - d\nflags:\n  debug: false\n...\n"
+        );
+
+        assert_eq!(
+            document.set("args.0.name", 1),
+            Err(Error::NotACollection {
+                path: "args.0".into(),
+                found: "string".into(),
+            })
+        );
+        assert_eq!(
+            document.rename_key("args", "flags"),
+            Err(Error::KeyExists {
+                path: "args".into(),
+                key: "flags".into(),
+            })
+        );
+        assert_eq!(
+            document.rename_key("args.1", "x"),
+            Err(Error::NotAPair {
+                path: "args.1".into()
+            })
+        );
+        assert_eq!(
+            document.delete("flags.verbose"),
+            Err(Error::NotFound {
+                path: "flags.verbose".into()
+            })
+        );
+
+        document.rename_key("args", "command").unwrap();
+        assert_eq!(
+            document.nodes,
+            [yaml!({command: ["b", "c", "d"], flags: {debug: false}})]
+        );
+        assert_eq!(
+            document.item_comments.keys().collect::<Vec<_>>(),
+            [&vec![
+                PathSegment::Key("command".into()),
+                PathSegment::Index(0)
+            ]]
+        );
+    }
+
+    #[test]
+    fn item_comments() {
+        let mut document: Document = "\
+args:
+  # leading a
+  - a # ia
+  - ~ # ib
+  - c # ic
+"
+        .parse()
+        .unwrap();
+
+        document.insert("args.0", "z").unwrap();
+        document.insert("args.4", "d").unwrap();
+        assert_eq!(document.delete("args.2"), Ok(Node::Null));
+        assert_eq!(
+            document.to_string(),
+            "---\nargs:\n  - z\n  # leading a\n  - a # ia\n  - c # ic\n  - d\n...\n"
+        );
+
+        document.insert("args.1", yaml!([1])).unwrap();
+        document.delete("args.0").unwrap();
+        assert_eq!(
+            document.to_string(),
+            "---\nargs:\n  - - 1\n  # leading a\n  - a # ia\n  - c # ic\n  - d\n...\n"
+        );
+
+        assert_eq!(
+            document.insert("args.6", 1),
+            Err(Error::NotFound {
+                path: "args.6".into()
+            })
+        );
+        assert_eq!(
+            document.insert("args", 1),
+            Err(Error::NotAnItem {
+                path: "args".into()
+            })
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt::{self, Write};
+
+/// Marks the start of an inline comment in the output if comments are
+/// aligned, see [`EmitterOptions::comment_column`](super::EmitterOptions::comment_column).
+/// It is a non-printable character, which never appears verbatim in a YAML
+/// character stream.
+pub(crate) const COMMENT_MARKER: char = '\u{1}';
+
+/// A writer which replaces each [`COMMENT_MARKER`] with the spaces needed
+/// to start the following comment at the `column`. Comments on lines which
+/// already extend beyond it are separated by a single space, comments on
+/// their own line are not indented.
+pub(crate) struct AlignedWriter<'a, W> {
+    inner: &'a mut W,
+    column: usize,
+
+    /// The column of the next character written to `inner`.
+    current: usize,
+}
+
+impl<'a, W: Write> AlignedWriter<'a, W> {
+    pub(crate) fn new(inner: &'a mut W, column: usize) -> Self {
+        Self {
+            inner,
+            column,
+            current: 0,
+        }
+    }
+
+    fn write_text(&mut self, text: &str) -> fmt::Result {
+        self.inner.write_str(text)?;
+
+        self.current = match text.rfind('\n') {
+            Some(index) => text[index + 1..].chars().count(),
+            None => self.current + text.chars().count(),
+        };
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for AlignedWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut rest = s;
+
+        while let Some(index) = rest.find(COMMENT_MARKER) {
+            self.write_text(&rest[..index])?;
+
+            let padding = match self.current {
+                0 => 0,
+                current if current < self.column => self.column - current,
+                _ => 1,
+            };
+            self.write_text(&" ".repeat(padding))?;
+
+            rest = &rest[index + COMMENT_MARKER.len_utf8()..];
+        }
+
+        self.write_text(rest)
+    }
+}
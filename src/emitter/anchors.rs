@@ -0,0 +1,421 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    emitter::EventIter,
+    events::{to_test_suite_string, Event, PathSegment},
+};
+
+/// Names the anchors generated for repeated nodes, see
+/// [`EmitterOptions::anchor_threshold`].
+///
+/// The emitter keeps the generated names valid and unique: whitespace and
+/// flow indicators are replaced by `_` and names which are already in use,
+/// by anchors of the input or previously generated ones, get a numeric
+/// suffix, e.g. `ports-2`.
+///
+/// [`EmitterOptions::anchor_threshold`]: super::EmitterOptions::anchor_threshold
+pub trait AnchorNamer: fmt::Debug {
+    /// Returns the name of the anchor of the repeated `node`.
+    fn name(&self, node: &AnchorNode<'_>) -> String;
+}
+
+/// A repeated node which is written once with a generated anchor and
+/// referenced by aliases afterwards.
+#[derive(Clone, Copy, Debug)]
+pub struct AnchorNode<'a> {
+    /// The number of anchors generated before this one in the stream.
+    pub index: usize,
+
+    /// The path of the anchored node within its document.
+    pub path: &'a [PathSegment],
+
+    /// The events of the node, from its start to its end event.
+    pub events: &'a [Event],
+}
+
+/// Numbers the anchors in the order they are generated, e.g. `a1`, `a2` for
+/// the prefix `a`. This is the default, without prefix.
+#[derive(Clone, Debug, Default)]
+pub struct SequentialAnchors {
+    pub prefix: String,
+}
+
+impl AnchorNamer for SequentialAnchors {
+    fn name(&self, node: &AnchorNode<'_>) -> String {
+        format!("{}{}", self.prefix, node.index + 1)
+    }
+}
+
+/// Names anchors by a hash of the content of the node, so that the names
+/// stay the same when unrelated parts of the stream change. The hash is
+/// written as `length` hexadecimal digits, at most 16.
+#[derive(Clone, Copy, Debug)]
+pub struct ContentHashAnchors {
+    pub length: usize,
+}
+
+impl Default for ContentHashAnchors {
+    fn default() -> Self {
+        Self { length: 8 }
+    }
+}
+
+impl AnchorNamer for ContentHashAnchors {
+    fn name(&self, node: &AnchorNode<'_>) -> String {
+        // FNV-1a, which is stable across platforms and releases
+        let hash = to_test_suite_string(node.events)
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+
+        let mut name = format!("{hash:016x}");
+        name.truncate(self.length.max(1));
+        name
+    }
+}
+
+/// Names anchors by the path of the node, joining its keys and indices with
+/// `-`, e.g. `spec-containers-0` for `spec.containers[0]`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PathAnchors;
+
+impl AnchorNamer for PathAnchors {
+    fn name(&self, node: &AnchorNode<'_>) -> String {
+        if node.path.is_empty() {
+            return "root".into();
+        }
+
+        node.path
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Key(key) => key.clone(),
+                PathSegment::Index(index) => index.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+}
+
+/// Names anchors by calling the wrapped function, e.g.
+/// `AnchorNameFn(|node: &AnchorNode<'_>| format!("node{}", node.index))`.
+#[derive(Clone, Copy)]
+pub struct AnchorNameFn<F>(pub F);
+
+impl<F> fmt::Debug for AnchorNameFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AnchorNameFn").finish_non_exhaustive()
+    }
+}
+
+impl<F> AnchorNamer for AnchorNameFn<F>
+where
+    F: Fn(&AnchorNode<'_>) -> String,
+{
+    fn name(&self, node: &AnchorNode<'_>) -> String {
+        (self.0)(node)
+    }
+}
+
+/// Replaces the characters which anchors can't contain by `_`.
+///
+/// See <https://yaml.org/spec/1.2.2/#rule-ns-anchor-char>
+fn sanitize_anchor(name: String) -> String {
+    let is_invalid = |c: char| c.is_whitespace() || matches!(c, ',' | '[' | ']' | '{' | '}');
+
+    match name.is_empty() {
+        true => "anchor".into(),
+        false if name.contains(is_invalid) => name.replace(is_invalid, "_"),
+        false => name,
+    }
+}
+
+/// Returns the number of events of the node starting at `start`.
+fn node_len(events: &[Event], start: usize) -> usize {
+    let mut depth = 0;
+
+    for (len, event) in events[start..].iter().enumerate() {
+        match event {
+            Event::SequenceStart { .. } | Event::MappingStart { .. } => depth += 1,
+            Event::SequenceEnd | Event::MappingEnd => depth -= 1,
+            _ => {}
+        }
+
+        if depth == 0 {
+            return len + 1;
+        }
+    }
+
+    events.len() - start
+}
+
+fn set_anchor(event: &mut Event, anchor: Option<String>) {
+    if let Event::Scalar { anchor: node, .. }
+    | Event::SequenceStart { anchor: node, .. }
+    | Event::MappingStart { anchor: node, .. } = event
+    {
+        *node = anchor;
+    }
+}
+
+/// Balances anchors and aliases, see [`EmitterOptions::anchor_threshold`].
+///
+/// Aliases of anchored nodes with less than `threshold` events are replaced
+/// by a copy of the node. Repeated collections with at least `threshold`
+/// events are written once with an anchor and referenced by aliases
+/// afterwards.
+///
+/// [`EmitterOptions::anchor_threshold`]: super::EmitterOptions::anchor_threshold
+pub(crate) fn balance_anchors(
+    events: Vec<Event>,
+    threshold: usize,
+    namer: &dyn AnchorNamer,
+) -> Vec<Event> {
+    let events = inline_small_aliases(events, threshold);
+    alias_repeated_nodes(events, threshold, namer)
+}
+
+fn inline_small_aliases(events: Vec<Event>, threshold: usize) -> Vec<Event> {
+    let mut targets: HashMap<&str, &[Event]> = HashMap::new();
+    for (start, event) in events.iter().enumerate() {
+        if let Some(anchor) = event.anchor() {
+            targets.insert(anchor, &events[start..start + node_len(&events, start)]);
+        }
+    }
+
+    let small = |anchor: &str| {
+        targets
+            .get(anchor)
+            .is_some_and(|node| node.len() < threshold)
+    };
+    let used: HashSet<&str> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Alias(anchor) if !small(anchor) => Some(anchor.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let mut inlined = Vec::with_capacity(events.len());
+    for event in &events {
+        match event {
+            Event::Alias(anchor) if small(anchor) => {
+                // Copies don't define anchors, aliases refer to the original
+                inlined.extend(targets[anchor.as_str()].iter().cloned().map(|mut event| {
+                    set_anchor(&mut event, None);
+                    event
+                }));
+            }
+            event => {
+                let mut event = event.clone();
+                if event.anchor().is_some_and(|anchor| !used.contains(anchor)) {
+                    set_anchor(&mut event, None);
+                }
+                inlined.push(event);
+            }
+        }
+    }
+
+    inlined
+}
+
+fn alias_repeated_nodes(
+    events: Vec<Event>,
+    threshold: usize,
+    namer: &dyn AnchorNamer,
+) -> Vec<Event> {
+    // Generated anchors skip the anchors already in use
+    let mut used: HashSet<String> = events
+        .iter()
+        .filter_map(|event| event.anchor().map(String::from))
+        .collect();
+    let mut generated = 0;
+
+    // Tracks the paths of the input events, for the names of the anchors
+    let mut paths = EventIter::new(events.clone());
+    let mut output: Vec<Event> = Vec::with_capacity(events.len());
+
+    // Candidates grouped by their length as (input start, output start,
+    // path)
+    let mut candidates: HashMap<usize, Vec<(usize, usize, Vec<PathSegment>)>> = HashMap::new();
+    let mut index = 0;
+
+    while index < events.len() {
+        let event = &events[index];
+        paths.next();
+
+        // Anchors are scoped to their document
+        if *event == Event::DocumentStart {
+            candidates.clear();
+        }
+
+        let is_collection = event.starts_collection() && event.anchor().is_none();
+        let len = if is_collection {
+            node_len(&events, index)
+        } else {
+            0
+        };
+
+        if is_collection && len >= threshold {
+            let node = &events[index..index + len];
+            let original = candidates.get(&len).and_then(|starts| {
+                starts
+                    .iter()
+                    .find(|(start, ..)| &events[*start..*start + len] == node)
+            });
+
+            if let Some((start, output_start, path)) = original {
+                let anchor = match output[*output_start].anchor() {
+                    Some(anchor) => anchor.to_string(),
+                    None => {
+                        let name = namer.name(&AnchorNode {
+                            index: generated,
+                            path,
+                            events: &events[*start..*start + len],
+                        });
+                        let anchor = unique_anchor(sanitize_anchor(name), &used);
+                        generated += 1;
+                        used.insert(anchor.clone());
+                        set_anchor(&mut output[*output_start], Some(anchor.clone()));
+                        anchor
+                    }
+                };
+
+                output.push(Event::Alias(anchor));
+                for _ in 1..len {
+                    paths.next();
+                }
+                index += len;
+                continue;
+            }
+
+            candidates
+                .entry(len)
+                .or_default()
+                .push((index, output.len(), paths.node_path()));
+        }
+
+        output.push(event.clone());
+        index += 1;
+    }
+
+    output
+}
+
+/// Appends the first numeric suffix to `name` which results in an anchor
+/// that isn't `used` yet.
+fn unique_anchor(name: String, used: &HashSet<String>) -> String {
+    if !used.contains(&name) {
+        return name;
+    }
+
+    (2..)
+        .map(|n: usize| format!("{name}-{n}"))
+        .find(|anchor| !used.contains(anchor))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scalar(value: &str) -> Event {
+        Event::scalar(value, None)
+    }
+
+    #[test]
+    fn balance() {
+        let mapping = [
+            Event::mapping_start(),
+            scalar("b"),
+            scalar("c"),
+            Event::MappingEnd,
+        ];
+        let anchored = |anchor: &str| Event::SequenceStart {
+            tag: None,
+            anchor: Some(anchor.into()),
+            style: None,
+        };
+        let events = [
+            vec![anchored("1"), scalar("a"), Event::SequenceEnd],
+            vec![Event::Alias("1".into())],
+            mapping.to_vec(),
+            mapping.to_vec(),
+        ]
+        .concat();
+
+        assert_eq!(
+            balance_anchors(events, 4, &SequentialAnchors::default()),
+            [
+                Event::sequence_start(),
+                scalar("a"),
+                Event::SequenceEnd,
+                Event::sequence_start(),
+                scalar("a"),
+                Event::SequenceEnd,
+                Event::MappingStart {
+                    tag: None,
+                    anchor: Some("1".into()),
+                    style: None,
+                },
+                scalar("b"),
+                scalar("c"),
+                Event::MappingEnd,
+                Event::Alias("1".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn anchor_names() {
+        let repeated = [
+            Event::mapping_start(),
+            scalar("a"),
+            scalar("b"),
+            Event::MappingEnd,
+        ];
+        let events = [
+            vec![Event::DocumentStart, Event::mapping_start()],
+            vec![scalar("x y")],
+            repeated.to_vec(),
+            vec![
+                scalar("other"),
+                Event::MappingStart {
+                    tag: None,
+                    anchor: Some("x_y".into()),
+                    style: None,
+                },
+            ],
+            vec![scalar("c"), scalar("d"), Event::MappingEnd],
+            vec![scalar("z")],
+            repeated.to_vec(),
+            vec![scalar("w"), Event::Alias("x_y".into())],
+            vec![Event::MappingEnd, Event::DocumentEnd],
+        ]
+        .concat();
+
+        let names = |namer: &dyn AnchorNamer| {
+            balance_anchors(events.clone(), 4, namer)
+                .iter()
+                .filter_map(|event| match event {
+                    Event::Alias(anchor) if anchor != "x_y" => Some(anchor.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let sequential = SequentialAnchors { prefix: "a".into() };
+        assert_eq!(names(&sequential), ["a1"]);
+        assert_eq!(names(&PathAnchors), ["x_y-2"]);
+        assert_eq!(names(&ContentHashAnchors { length: 6 })[0].len(), 6);
+        assert_eq!(
+            names(&AnchorNameFn(|node: &AnchorNode<'_>| {
+                format!("{}-{}", node.index, node.events.len())
+            })),
+            ["0-4"]
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use std::{collections::HashSet, fmt};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use crate::{
+    emitter::{
+        event_iter, needs_quotes, sanitize, Emitter, EmitterOptions, Error, GreedyLineBreaker,
+        Handling, SequentialAnchors, YamlVersion,
+    },
+    events::{display_path, PathSegment},
+    schema::{is_ambiguous_core, is_ambiguous_yaml_1_1},
+    Kind, Node, Stream,
+};
+
+/// The severity of a [`Diagnostic`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Informs about a decision of the emitter, e.g. that a string is
+    /// quoted.
+    Info,
+
+    /// The output is valid YAML, but likely not what was intended.
+    Warning,
+
+    /// The emitter cannot produce valid YAML for this node.
+    Error,
+}
+
+/// The reason why a string scalar is emitted with quotes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QuoteReason {
+    /// The plain scalar would resolve to another type under the Core schema,
+    /// e.g. `true` or `42`.
+    Ambiguous,
+
+    /// The plain scalar would resolve to another type under YAML 1.1, e.g.
+    /// `yes` or `1:30`. See [`EmitterOptions::yaml_version`].
+    AmbiguousYaml1_1,
+
+    /// The string contains characters which are not allowed in plain
+    /// scalars, like leading indicators, `: ` or control characters.
+    Syntax,
+
+    /// The string contains non-ASCII characters, which are escaped. See
+    /// [`EmitterOptions::escape_non_ascii`].
+    NonAscii,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiagnosticKind {
+    /// A document contains more than one root node. All but the first node
+    /// will be emitted as invalid YAML.
+    MultipleRootNodes { count: usize },
+
+    /// A key occurs more than once in a mapping.
+    DuplicateKey { key: Node },
+
+    /// A mapping key is a collection, which is written in flow style as an
+    /// explicit key, e.g. `? [1, 2]`.
+    ComplexKey { kind: Kind },
+
+    /// A string scalar is emitted with quotes.
+    QuotedString { value: String, reason: QuoteReason },
+
+    /// Emitting fails at this node with the error `message`, e.g. because
+    /// of an [`UnrepresentablePolicy`](super::UnrepresentablePolicy) which
+    /// rejects the node.
+    Unemittable { message: String },
+}
+
+/// A single finding of [`Emitter::check`], located by the index of the
+/// document and the path to the node within it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub document: usize,
+    pub path: Vec<PathSegment>,
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(
+            f,
+            "{severity}: document {}: {}",
+            self.document,
+            display_path(&self.path)
+        )?;
+
+        match &self.kind {
+            DiagnosticKind::MultipleRootNodes { count } => {
+                write!(f, " document has {count} root nodes")
+            }
+            DiagnosticKind::DuplicateKey { key } => write!(f, " duplicate key {key:?}"),
+            DiagnosticKind::ComplexKey { kind } => write!(f, " explicit {kind:?} key"),
+            DiagnosticKind::QuotedString { value, reason } => {
+                write!(f, " string {value:?} is quoted ({reason:?})")
+            }
+            DiagnosticKind::Unemittable { message } => write!(f, " cannot be emitted: {message}"),
+        }
+    }
+}
+
+impl Emitter<'_> {
+    /// Performs the same analysis as emitting the `stream` with `options`,
+    /// but only returns the diagnostics instead of producing any output.
+    ///
+    /// This is useful to validate the generation of (large) streams in CI,
+    /// e.g. to ensure that no duplicate keys are emitted. The stream is
+    /// emitted into a sink to find out whether emitting fails, e.g. due to
+    /// the [`UnrepresentablePolicy`](super::UnrepresentablePolicy) or the
+    /// [JSON-compatible](EmitterOptions::json_compatible) mode, which is
+    /// reported as [`DiagnosticKind::Unemittable`].
+    pub fn check(stream: &Stream, options: &EmitterOptions) -> Vec<Diagnostic> {
+        let mut checker = Checker {
+            options,
+            document: 0,
+            path: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+
+        for (index, document) in stream.iter().enumerate() {
+            checker.document = index;
+
+            if document.nodes.len() > 1 {
+                checker.report(
+                    Severity::Error,
+                    DiagnosticKind::MultipleRootNodes {
+                        count: document.nodes.len(),
+                    },
+                );
+            }
+
+            for node in &document.nodes {
+                checker.check_node(node);
+            }
+        }
+
+        if let Some(diagnostic) = Self::dry_run(stream, options) {
+            checker.diagnostics.push(diagnostic);
+        }
+
+        checker.diagnostics
+    }
+
+    /// Emits the `stream` into a sink with the decisions of
+    /// [`Emitter::emit`]. Returns the diagnostic of the error which stops
+    /// emitting, if any.
+    fn dry_run(stream: &Stream, options: &EmitterOptions) -> Option<Diagnostic> {
+        // Anchors are named before emitting and line breaks don't decide
+        // whether emitting fails, so the boxed options are left at their
+        // defaults
+        let events = event_iter(stream.events_iter(), options);
+        let mut emitter = Emitter::with_event_iter(
+            events,
+            EmitterOptions {
+                line_breaker: Box::new(GreedyLineBreaker),
+                anchor_namer: Box::new(SequentialAnchors::default()),
+                ..*options
+            },
+        );
+
+        let unemittable = |emitter: &Emitter<'_>, error: Error| {
+            let message = match &error {
+                Error::InvalidOptions { source } => format!("{error}: {source}"),
+                error => error.to_string(),
+            };
+
+            Diagnostic {
+                severity: Severity::Error,
+                document: emitter.documents.saturating_sub(1),
+                path: emitter.events.node_path(),
+                kind: DiagnosticKind::Unemittable { message },
+            }
+        };
+
+        if let Err(error) = emitter.emit_prefix(&mut Sink) {
+            return Some(unemittable(&emitter, error));
+        }
+
+        while let Some(event) = emitter.events.next() {
+            if let Err(error) = emitter.emit_event(&mut Sink, event) {
+                return Some(unemittable(&emitter, error));
+            }
+        }
+
+        None
+    }
+}
+
+/// A writer which discards the output, see [`Emitter::check`].
+struct Sink;
+
+impl fmt::Write for Sink {
+    fn write_str(&mut self, _: &str) -> fmt::Result {
+        Ok(())
+    }
+}
+
+struct Checker<'a> {
+    options: &'a EmitterOptions,
+    document: usize,
+    path: Vec<PathSegment>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// A step of [`Checker::check_node`].
+enum Step<'a> {
+    Node(&'a Node),
+
+    /// Enters the pair with the `key`, which is a `duplicate` of a previous
+    /// key of the mapping.
+    Pair {
+        key: &'a Node,
+        duplicate: bool,
+    },
+
+    /// Enters the sequence item at the `index`.
+    Item(usize),
+
+    /// Leaves the current pair or sequence item.
+    Leave,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, severity: Severity, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            document: self.document,
+            path: self.path.clone(),
+            kind,
+        });
+    }
+
+    /// Checks the `node` and its descendants. Like in the emitter, nested
+    /// collections are tracked on a stack instead of recursion.
+    fn check_node(&mut self, node: &Node) {
+        let mut steps = vec![Step::Node(node)];
+
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Node(Node::Mapping(mapping)) => {
+                    let mut keys = HashSet::new();
+                    let pairs: Vec<_> = mapping
+                        .pairs()
+                        .map(|pair| (pair, !keys.insert(&pair.key)))
+                        .collect();
+
+                    for (pair, duplicate) in pairs.into_iter().rev() {
+                        steps.extend([
+                            Step::Leave,
+                            Step::Node(&pair.value),
+                            Step::Node(&pair.key),
+                            Step::Pair {
+                                key: &pair.key,
+                                duplicate,
+                            },
+                        ]);
+                    }
+                }
+                Step::Node(Node::Sequence(items)) => {
+                    for (index, item) in items.iter().enumerate().rev() {
+                        steps.extend([Step::Leave, Step::Node(item), Step::Item(index)]);
+                    }
+                }
+                Step::Node(Node::Tagged(tagged)) => steps.push(Step::Node(&tagged.node)),
+                Step::Node(Node::String(value)) if !self.options.canonical => {
+                    // Strings with non-printable characters are reported by
+                    // the dry run if the policy rejects them
+                    let handling = self.options.unrepresentable.non_printable;
+                    let Ok(value) = sanitize(value.clone(), false, handling) else {
+                        continue;
+                    };
+
+                    if let Some(reason) = quote_reason(&value, self.options) {
+                        self.report(
+                            Severity::Info,
+                            DiagnosticKind::QuotedString { value, reason },
+                        );
+                    }
+                }
+                Step::Node(_) => {}
+                Step::Pair { key, duplicate } => {
+                    self.path.push(key_segment(key));
+
+                    if duplicate {
+                        self.report(
+                            Severity::Error,
+                            DiagnosticKind::DuplicateKey { key: key.clone() },
+                        );
+                    }
+
+                    // Other policies drop or stringify complex keys
+                    if !matches!(key.kind(), Kind::Scalar)
+                        && !self.options.canonical
+                        && self.options.unrepresentable.non_string_keys == Handling::Keep
+                    {
+                        self.report(
+                            Severity::Info,
+                            DiagnosticKind::ComplexKey { kind: key.kind() },
+                        );
+                    }
+                }
+                Step::Item(index) => self.path.push(PathSegment::Index(index)),
+                Step::Leave => {
+                    self.path.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Returns why the string `value` is quoted in block style, or [`None`] if
+/// it is emitted as a plain scalar.
+fn quote_reason(value: &str, options: &EmitterOptions) -> Option<QuoteReason> {
+    if is_ambiguous_core(value) {
+        Some(QuoteReason::Ambiguous)
+    } else if needs_quotes(value, false) {
+        Some(QuoteReason::Syntax)
+    } else if options.yaml_version == YamlVersion::V1_1 && is_ambiguous_yaml_1_1(value) {
+        Some(QuoteReason::AmbiguousYaml1_1)
+    } else if options.escape_non_ascii && !value.is_ascii() {
+        Some(QuoteReason::NonAscii)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn key_segment(key: &Node) -> PathSegment {
+    match key {
+        Node::String(s) => PathSegment::Key(s.clone()),
+        Node::FloatingPoint(f) => PathSegment::Key(f.to_string()),
+        Node::Null => PathSegment::Key("null".into()),
+        Node::Binary(b) => PathSegment::Key(BASE64.encode(b)),
+        Node::Timestamp(t) => PathSegment::Key(t.to_string()),
+        Node::Boolean(b) => PathSegment::Key(b.to_string()),
+        Node::Integer(i) => PathSegment::Key(i.to_string()),
+        Node::BigInteger(i) => PathSegment::Key(i.to_string()),
+        Node::Mapping(_) | Node::Sequence(_) => PathSegment::Key("?".into()),
+        Node::Tagged(tagged) => key_segment(&tagged.node),
+    }
+}
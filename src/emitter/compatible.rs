@@ -0,0 +1,145 @@
+use crate::{
+    emitter::{state::State, Emitter, Error, NotJsonCompatibleSnafu, CORE_TAG_PREFIX, FLOAT_TAG},
+    events::Event,
+    float::Float,
+    schema::resolve_core,
+    Node,
+};
+
+/// The core tags which have an equivalent JSON type.
+const JSON_TAGS: [&str; 7] = ["str", "null", "bool", "int", "float", "seq", "map"];
+
+impl Emitter<'_> {
+    /// Fails if the node starting with `event` uses a construct which has no
+    /// JSON equivalent, see [`EmitterOptions::json_compatible`].
+    ///
+    /// [`EmitterOptions::json_compatible`]: super::EmitterOptions::json_compatible
+    pub(super) fn check_json_compatible(&mut self, event: &Event) -> Result<(), Error> {
+        if !event.starts_node() {
+            return Ok(());
+        }
+
+        let is_key = matches!(
+            self.states.current(),
+            Some(State::Mapping(true) | State::FlowMapping { is_key: true, .. })
+        );
+
+        let construct = match event {
+            Event::Alias(anchor) => Some(format!("alias *{anchor}")),
+            event if event.anchor().is_some() => {
+                Some(format!("anchor &{}", event.anchor().unwrap_or_default()))
+            }
+            event if event.tag().is_some_and(|tag| !is_json_tag(tag)) => {
+                Some(format!("tag {}", event.tag().unwrap_or_default()))
+            }
+            Event::Scalar { tag, .. }
+                if is_key
+                    && tag
+                        .as_deref()
+                        .is_some_and(|tag| tag != "tag:yaml.org,2002:str") =>
+            {
+                Some("non-string key".into())
+            }
+            Event::SequenceStart { .. } | Event::MappingStart { .. } if is_key => {
+                Some("non-string key".into())
+            }
+            Event::Scalar { value, .. } if value.contains('\n') => Some("multiline string".into()),
+            Event::Scalar { value, tag, .. } if is_non_finite(value, tag.as_deref()) => {
+                Some(format!("float {value}"))
+            }
+            _ => None,
+        };
+
+        match construct {
+            Some(construct) => NotJsonCompatibleSnafu {
+                construct,
+                path: self.events.node_path(),
+            }
+            .fail(),
+            None => Ok(()),
+        }
+    }
+}
+
+fn is_json_tag(tag: &str) -> bool {
+    tag.strip_prefix(CORE_TAG_PREFIX)
+        .is_some_and(|suffix| JSON_TAGS.contains(&suffix))
+}
+
+/// Returns `true` if the scalar `value` is a float which isn't finite, like
+/// `.nan` or `-.inf`, which JSON numbers can't represent.
+fn is_non_finite(value: &str, tag: Option<&str>) -> bool {
+    match tag {
+        Some(FLOAT_TAG) => value
+            .parse::<Float>()
+            .is_ok_and(|float| !float.value().is_finite()),
+        Some(_) => false,
+        None => {
+            matches!(resolve_core(value), Node::FloatingPoint(ref float) if !float.value().is_finite())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        emitter::{Emitter, EmitterOptions, Error},
+        events::{display_path, IntoEvents},
+        float::Float,
+        tag::Tagged,
+        yaml, Document, Node, Stream,
+    };
+
+    fn emit(node: Node) -> Result<String, Error> {
+        let options = EmitterOptions::builder()
+            .json_compatible(true)
+            .build()
+            .unwrap();
+        let stream = Stream::from_documents(vec![Document {
+            nodes: vec![node],
+            ..Default::default()
+        }]);
+
+        let mut output = String::new();
+        Emitter::new(stream.into_events(), options).emit(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn json_compatible() {
+        assert_eq!(
+            emit(yaml!({name: "web", ports: [80, 443], ratio: 0.5})).unwrap(),
+            "---\nname: web\nports:\n  - 80\n  - 443\nratio: 0.5\n...\n"
+        );
+
+        let cases = [
+            (yaml!({a: {b: [1, "x\ny"]}}), "multiline string", "a.b[1]"),
+            (yaml!({a: {b: 1, 3: 4}}), "non-string key", "a"),
+            (
+                yaml!({a: (Node::FloatingPoint(Float::new(f64::NAN)))}),
+                "float .nan",
+                "a",
+            ),
+            (
+                yaml!({a: (Node::Tagged(Box::new(Tagged::new("!Ref", "b".into()))))}),
+                "tag !Ref",
+                "a",
+            ),
+            (
+                yaml!({a: (Node::Binary(vec![1]))}),
+                "tag tag:yaml.org,2002:binary",
+                "a",
+            ),
+        ];
+
+        for (node, expected, expected_path) in cases {
+            match emit(node) {
+                Err(Error::NotJsonCompatible { construct, path }) => {
+                    assert_eq!(construct, expected);
+                    assert_eq!(display_path(&path), expected_path);
+                }
+                result => panic!("unexpected result {result:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt::{self, Write};
+
+/// A writer which writes each line feed as `\r\n`, see
+/// [`LineEnding::CrLf`](super::LineEnding::CrLf). The emitter writes line
+/// breaks in scalars escaped or as line breaks of block scalars, which is
+/// why all line feeds it writes are line endings.
+pub(crate) struct CrLfWriter<'a, W> {
+    inner: &'a mut W,
+}
+
+impl<'a, W: Write> CrLfWriter<'a, W> {
+    pub(crate) fn new(inner: &'a mut W) -> Self {
+        Self { inner }
+    }
+}
+
+impl<W: Write> Write for CrLfWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut lines = s.split('\n');
+
+        if let Some(first) = lines.next() {
+            self.inner.write_str(first)?;
+        }
+
+        for line in lines {
+            self.inner.write_str("\r\n")?;
+            self.inner.write_str(line)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! Line breaking of long text, used to write folded block scalars and to
+//! reflow comments.
+//!
+//! The algorithm is pluggable via the [`LineBreaker`] trait, e.g. to never
+//! break inside URLs or to follow language-specific rules. The default
+//! [`GreedyLineBreaker`] fills each line with as many words as possible.
+//!
+//! ```
+//! use yaml_ast::{
+//!     emitter::{Emitter, EmitterOptions},
+//!     events::IntoEvents,
+//!     Document, Mapping, Stream,
+//! };
+//!
+//! let document = Document::from_mapping(Mapping::from([(
+//!     "description".into(),
+//!     "The quick brown fox jumps over the lazy dog".into(),
+//! )]));
+//!
+//! let options = EmitterOptions::builder().line_width(Some(20)).build().unwrap();
+//! let mut output = String::new();
+//! Emitter::new(Stream::from_documents(vec![document]).into_events(), options)
+//!     .emit(&mut output)
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     output,
+//!     "---\ndescription: >-\n  The quick brown fox\n  jumps over the lazy\n  dog\n...\n"
+//! );
+//! ```
+use std::fmt;
+
+/// Decides where a single line of text is broken into multiple lines.
+pub trait LineBreaker: fmt::Debug {
+    /// Returns the byte offsets of the spaces at which `text` is broken, so
+    /// that the resulting lines are at most `width` characters long where
+    /// possible. `text` doesn't contain line breaks.
+    ///
+    /// Only single spaces between two other characters can be replaced by a
+    /// line break without changing the content. Offsets of all other
+    /// characters are ignored.
+    fn break_points(&self, text: &str, width: usize) -> Vec<usize>;
+}
+
+/// Breaks text at the last space which keeps the line within the width.
+/// Words longer than the width are never broken, which also keeps URLs
+/// intact.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GreedyLineBreaker;
+
+impl LineBreaker for GreedyLineBreaker {
+    fn break_points(&self, text: &str, width: usize) -> Vec<usize> {
+        let mut points = Vec::new();
+        let mut line_len = 0;
+        let mut offset = 0;
+
+        for word in text.split(' ') {
+            let len = word.chars().count();
+
+            if offset == 0 {
+                line_len = len;
+            } else if line_len > 0 && len > 0 && line_len + 1 + len > width {
+                points.push(offset - 1);
+                line_len = len;
+            } else {
+                line_len += 1 + len;
+            }
+
+            offset += word.len() + 1;
+        }
+
+        points
+    }
+}
+
+/// Splits `text` into lines at the break points returned by `breaker`.
+/// Invalid break points are skipped.
+pub(crate) fn break_lines<'a>(
+    breaker: &dyn LineBreaker,
+    text: &'a str,
+    width: usize,
+) -> Vec<&'a str> {
+    let bytes = text.as_bytes();
+    let mut lines = Vec::new();
+    let mut start = 0;
+
+    for point in breaker.break_points(text, width) {
+        let is_valid = point > start
+            && point + 1 < bytes.len()
+            && bytes[point] == b' '
+            && !matches!(bytes[point - 1], b' ' | b'\t')
+            && !matches!(bytes[point + 1], b' ' | b'\t');
+
+        if is_valid {
+            lines.push(&text[start..point]);
+            start = point + 1;
+        }
+    }
+
+    lines.push(&text[start..]);
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Breaks after every word.
+    #[derive(Debug)]
+    struct Everywhere;
+
+    impl LineBreaker for Everywhere {
+        fn break_points(&self, text: &str, _: usize) -> Vec<usize> {
+            text.match_indices(' ').map(|(i, _)| i).collect()
+        }
+    }
+
+    #[test]
+    fn break_lines() {
+        let text = "see https://example.com/a/very/long/path for details";
+        assert_eq!(
+            super::break_lines(&GreedyLineBreaker, text, 10),
+            [
+                "see",
+                "https://example.com/a/very/long/path",
+                "for",
+                "details"
+            ]
+        );
+        assert_eq!(
+            super::break_lines(&GreedyLineBreaker, "a b c", 3),
+            ["a b", "c"]
+        );
+
+        // Double spaces can't be folded
+        assert_eq!(super::break_lines(&Everywhere, "a  b c", 1), ["a  b", "c"]);
+    }
+}
@@ -1,30 +1,208 @@
-use crate::events::Event;
+use std::{collections::VecDeque, fmt};
 
-#[derive(Debug)]
-pub struct EventIter {
-    events: Vec<Event>,
-    index: usize,
+use crate::events::{Event, PathSegment};
+
+/// The events of an emitter with a small lookahead buffer. Events are
+/// pulled from the underlying iterator as they are consumed or peeked at,
+/// which is why the complete event stream is never held in memory.
+pub struct EventIter<'a> {
+    events: Box<dyn Iterator<Item = Event> + 'a>,
+    buffer: VecDeque<Event>,
+    position: usize,
+
+    /// The open collections before the last consumed event, see
+    /// [`EventIter::node_path`].
+    frames: Vec<Frame>,
+    last: Option<Step>,
+}
+
+impl fmt::Debug for EventIter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventIter")
+            .field("buffer", &self.buffer)
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
 }
 
-impl EventIter {
-    pub fn new(events: Vec<Event>) -> Self {
-        Self { events, index: 0 }
+impl<'a> EventIter<'a> {
+    pub fn new<I>(events: I) -> Self
+    where
+        I: IntoIterator<Item = Event>,
+        I::IntoIter: 'a,
+    {
+        Self {
+            events: Box::new(events.into_iter()),
+            buffer: VecDeque::new(),
+            position: 0,
+            frames: Vec::new(),
+            last: None,
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<&Event> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the event `n` positions after the next event.
+    pub fn peek_nth(&mut self, n: usize) -> Option<&Event> {
+        while self.buffer.len() <= n {
+            self.buffer.push_back(self.events.next()?);
+        }
+
+        self.buffer.get(n)
+    }
+
+    /// Returns the number of remaining events of the node starting with
+    /// `first`, which was already consumed. The remaining events are
+    /// buffered.
+    pub fn rest_len(&mut self, first: &Event) -> usize {
+        if !matches!(
+            first,
+            Event::SequenceStart { .. } | Event::MappingStart { .. }
+        ) {
+            return 0;
+        }
+
+        let mut depth = 1;
+        let mut len = 0;
+
+        while let Some(event) = self.peek_nth(len) {
+            match event {
+                Event::SequenceStart { .. } | Event::MappingStart { .. } => depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => depth -= 1,
+                _ => {}
+            }
+
+            len += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+
+        len
+    }
+
+    /// Skips the remaining events of the node starting with `first`, which
+    /// was already consumed.
+    pub fn skip_rest(&mut self, first: &Event) {
+        for _ in 0..self.rest_len(first) {
+            self.next();
+        }
+    }
+
+    /// Skips the next node, including all of its children.
+    pub fn skip_node(&mut self) {
+        if let Some(first) = self.next() {
+            self.skip_rest(&first);
+        }
     }
 
-    pub fn next(&mut self) -> Option<Event> {
-        let event = self.events.get(self.index);
-        self.index += 1;
-        event.cloned()
+    /// Returns the number of consumed events.
+    pub fn position(&self) -> usize {
+        self.position
     }
 
-    pub fn peek(&self) -> Option<&Event> {
-        self.events.get(self.index)
+    /// Returns the path of the node starting with the last consumed event
+    /// within its document. Keys are located by the path of their mapping
+    /// and end events by the path of their collection.
+    pub fn node_path(&self) -> Vec<PathSegment> {
+        let mut frames = self.frames.as_slice();
+        if matches!(self.last, Some(Step::Pop)) {
+            frames = &frames[..frames.len().saturating_sub(1)];
+        }
+
+        frames
+            .iter()
+            .filter_map(|frame| match (frame.is_mapping, frame.children % 2) {
+                (true, 1) => Some(frame.key.clone()),
+                (true, _) => None,
+                (false, _) => Some(PathSegment::Index(frame.children)),
+            })
+            .collect()
     }
 
-    pub fn peek_as(&self, ty: Event) -> Option<&Event> {
-        match self.events.get(self.index) {
+    pub fn peek_as(&mut self, ty: Event) -> Option<&Event> {
+        match self.peek() {
             Some(e) if e == &ty => Some(e),
             _ => None,
         }
     }
+
+    /// Applies the previously consumed event to the open collections and
+    /// records how the consumed `event` changes them.
+    fn track(&mut self, event: &Event) {
+        fn complete(frames: &mut [Frame], segment: impl FnOnce() -> PathSegment) {
+            if let Some(frame) = frames.last_mut() {
+                if frame.is_mapping && frame.children % 2 == 0 {
+                    frame.key = segment();
+                }
+
+                frame.children += 1;
+            }
+        }
+
+        let unknown_key = || PathSegment::Key("?".into());
+
+        match self.last.take() {
+            Some(Step::Clear) => self.frames.clear(),
+            Some(Step::Complete(key)) => {
+                complete(&mut self.frames, || key.unwrap_or_else(unknown_key))
+            }
+            Some(Step::Push(is_mapping)) => self.frames.push(Frame {
+                is_mapping,
+                children: 0,
+                key: unknown_key(),
+            }),
+            Some(Step::Pop) => {
+                self.frames.pop();
+                complete(&mut self.frames, unknown_key);
+            }
+            None => {}
+        }
+
+        // Only the values of keys are needed for the path
+        let is_key = self
+            .frames
+            .last()
+            .is_some_and(|frame| frame.is_mapping && frame.children % 2 == 0);
+
+        self.last = match event {
+            Event::DocumentStart => Some(Step::Clear),
+            Event::Scalar { value, .. } => Some(Step::Complete(
+                is_key.then(|| PathSegment::Key(value.clone())),
+            )),
+            Event::Alias(_) => Some(Step::Complete(None)),
+            Event::SequenceStart { .. } => Some(Step::Push(false)),
+            Event::MappingStart { .. } => Some(Step::Push(true)),
+            Event::SequenceEnd | Event::MappingEnd => Some(Step::Pop),
+            _ => None,
+        };
+    }
+}
+
+/// An open collection, see [`EventIter::node_path`].
+struct Frame {
+    is_mapping: bool,
+    children: usize,
+    key: PathSegment,
+}
+
+/// The change of the open collections caused by an event.
+enum Step {
+    Clear,
+    Complete(Option<PathSegment>),
+    Push(bool),
+    Pop,
+}
+
+impl Iterator for EventIter<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.buffer.pop_front().or_else(|| self.events.next())?;
+        self.position += 1;
+        self.track(&event);
+        Some(event)
+    }
 }
@@ -0,0 +1,384 @@
+use std::{collections::HashMap, fmt::Write};
+
+use snafu::{OptionExt, ResultExt};
+
+use crate::{
+    emitter::{Error, UnrepresentableSnafu, WriteSnafu, CORE_TAG_PREFIX, FLOAT_TAG},
+    events::Event,
+    float::Float,
+    schema::resolve_core,
+    Node,
+};
+
+/// The layout of the JSON output of the [`JsonEmitter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonStyle {
+    /// Writes each document on a single line without any whitespace.
+    Compact,
+
+    /// Writes each item and pair on its own line, indented by the given
+    /// number of spaces per level.
+    Pretty(usize),
+}
+
+impl Default for JsonStyle {
+    fn default() -> Self {
+        Self::Pretty(2)
+    }
+}
+
+/// An emitter which writes the event stream as JSON instead of YAML. Each
+/// document is written as a separate JSON value followed by a newline, empty
+/// documents are written as `null`.
+///
+/// Comments, blank lines, directives and tags have no JSON equivalent and are
+/// dropped. Aliases are replaced by a copy of the anchored collection. Scalars
+/// are written as JSON nulls, booleans and numbers based on their tag, or the
+/// Core schema if they don't have one. Non-string keys, recursive aliases and
+/// floats which aren't finite result in an error.
+#[derive(Debug)]
+pub struct JsonEmitter {
+    events: Vec<Event>,
+    style: JsonStyle,
+
+    /// The position of the first event of each anchored node of the current
+    /// document.
+    anchors: HashMap<String, usize>,
+
+    /// The anchors of the collections which are currently being written.
+    /// An alias of one of them would expand infinitely.
+    open_anchors: Vec<String>,
+}
+
+impl JsonEmitter {
+    /// Creates a new JSON emitter for the event stream using the provided
+    /// `style`.
+    pub fn new(events: impl IntoIterator<Item = Event>, style: JsonStyle) -> Self {
+        let events = events
+            .into_iter()
+            .filter(|event| {
+                !matches!(
+                    event,
+                    Event::Comment(_)
+                        | Event::InlineComment(_)
+                        | Event::ExplicitKey
+                        | Event::BlankLines(_)
+                        | Event::Directive(_)
+                )
+            })
+            .collect();
+
+        Self {
+            events,
+            style,
+            anchors: HashMap::new(),
+            open_anchors: Vec::new(),
+        }
+    }
+
+    /// Emits the documents of the event stream as JSON to the `writer`.
+    pub fn emit(mut self, writer: &mut impl Write) -> Result<(), Error> {
+        let mut pos = 0;
+
+        while pos < self.events.len() {
+            if self.events[pos] != Event::DocumentStart {
+                pos += 1;
+                continue;
+            }
+
+            self.anchors.clear();
+            pos += 1;
+
+            match self.events.get(pos) {
+                Some(Event::DocumentEnd) | None => writer.write_str("null").context(WriteSnafu)?,
+                Some(_) => pos = self.emit_node(writer, pos, 0)?,
+            }
+
+            writer.write_char('\n').context(WriteSnafu)?;
+        }
+
+        Ok(())
+    }
+
+    /// Emits the node starting at `pos` and returns the position of the
+    /// first event after it.
+    fn emit_node(
+        &mut self,
+        writer: &mut impl Write,
+        pos: usize,
+        level: usize,
+    ) -> Result<usize, Error> {
+        match &self.events[pos] {
+            Event::Scalar {
+                value, tag, anchor, ..
+            } => {
+                if let Some(anchor) = anchor {
+                    self.anchors.insert(anchor.clone(), pos);
+                }
+
+                writer
+                    .write_str(&json_scalar(value, tag.as_deref())?)
+                    .context(WriteSnafu)?;
+                Ok(pos + 1)
+            }
+            Event::Alias(anchor) => {
+                let start = *self.anchors.get(anchor).context(UnrepresentableSnafu {
+                    construct: format!("alias *{anchor} of an unknown anchor"),
+                })?;
+
+                if self.open_anchors.contains(anchor) {
+                    return UnrepresentableSnafu {
+                        construct: format!("recursive alias *{anchor}"),
+                    }
+                    .fail();
+                }
+
+                self.emit_node(writer, start, level)?;
+                Ok(pos + 1)
+            }
+            Event::SequenceStart { anchor, .. } => {
+                let anchor = anchor.clone();
+                self.emit_collection(writer, pos, anchor, level, ('[', ']'))
+            }
+            Event::MappingStart { anchor, .. } => {
+                let anchor = anchor.clone();
+                self.emit_collection(writer, pos, anchor, level, ('{', '}'))
+            }
+            event => UnrepresentableSnafu {
+                construct: format!("unexpected event {event:?}"),
+            }
+            .fail(),
+        }
+    }
+
+    fn emit_collection(
+        &mut self,
+        writer: &mut impl Write,
+        mut pos: usize,
+        anchor: Option<String>,
+        level: usize,
+        (open, close): (char, char),
+    ) -> Result<usize, Error> {
+        if let Some(anchor) = &anchor {
+            self.anchors.insert(anchor.clone(), pos);
+            self.open_anchors.push(anchor.clone());
+        }
+
+        let is_mapping = open == '{';
+        let mut is_empty = true;
+        writer.write_char(open).context(WriteSnafu)?;
+        pos += 1;
+
+        while !matches!(
+            self.events.get(pos),
+            Some(Event::SequenceEnd | Event::MappingEnd) | None
+        ) {
+            if !is_empty {
+                writer.write_char(',').context(WriteSnafu)?;
+            }
+
+            self.emit_line_break(writer, level + 1)?;
+            is_empty = false;
+
+            if is_mapping {
+                pos = self.emit_key(writer, pos)?;
+            }
+
+            pos = self.emit_node(writer, pos, level + 1)?;
+        }
+
+        if !is_empty {
+            self.emit_line_break(writer, level)?;
+        }
+
+        writer.write_char(close).context(WriteSnafu)?;
+
+        if anchor.is_some() {
+            self.open_anchors.pop();
+        }
+
+        Ok(pos + 1)
+    }
+
+    fn emit_key(&mut self, writer: &mut impl Write, pos: usize) -> Result<usize, Error> {
+        let key = match &self.events[pos] {
+            Event::Scalar { value, tag, .. }
+                if tag.as_deref().is_none_or(|tag| {
+                    tag == format!("{CORE_TAG_PREFIX}str") || !tag.starts_with(CORE_TAG_PREFIX)
+                }) =>
+            {
+                json_string(value)
+            }
+            _ => {
+                return UnrepresentableSnafu {
+                    construct: "non-string key",
+                }
+                .fail()
+            }
+        };
+
+        let separator = match self.style {
+            JsonStyle::Compact => ":",
+            JsonStyle::Pretty(_) => ": ",
+        };
+
+        write!(writer, "{key}{separator}").context(WriteSnafu)?;
+        Ok(pos + 1)
+    }
+
+    fn emit_line_break(&self, writer: &mut impl Write, level: usize) -> Result<(), Error> {
+        if let JsonStyle::Pretty(indent_size) = self.style {
+            write!(writer, "\n{}", " ".repeat(indent_size * level)).context(WriteSnafu)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the JSON representation of the scalar `value` with the resolved
+/// `tag`. Values which don't match their tag are written as strings.
+fn json_scalar(value: &str, tag: Option<&str>) -> Result<String, Error> {
+    let suffix = match tag {
+        Some(tag) => match tag.strip_prefix(CORE_TAG_PREFIX) {
+            Some(suffix @ ("null" | "bool" | "int" | "float")) => suffix,
+            _ => return Ok(json_string(value)),
+        },
+        None => "",
+    };
+
+    if suffix == "float" {
+        return match value.parse::<Float>() {
+            Ok(float) if float.value().is_finite() => Ok(float.value().to_string()),
+            Ok(_) => UnrepresentableSnafu {
+                construct: format!("float {value}"),
+            }
+            .fail(),
+            Err(_) => Ok(json_string(value)),
+        };
+    }
+
+    let json = match (suffix, resolve_core(value)) {
+        ("" | "null", Node::Null) => "null".into(),
+        ("" | "bool", Node::Boolean(b)) => b.to_string(),
+        ("" | "int", Node::Integer(i)) => i.to_string(),
+        ("" | "int", Node::BigInteger(ref i)) => i.to_string(),
+        ("", Node::FloatingPoint(ref float)) => {
+            return json_scalar(&float.to_string(), Some(FLOAT_TAG));
+        }
+        _ => json_string(value),
+    };
+
+    Ok(json)
+}
+
+/// Returns `value` as a JSON string, escaping quotes, backslashes and all
+/// control characters.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc8259#section-7>
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c < ' ' => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{events::IntoEvents, yaml, Document, Node, Stream};
+
+    fn to_events(node: Node) -> Vec<Event> {
+        let document = Document {
+            nodes: vec![node],
+            ..Default::default()
+        };
+
+        Stream::from_iter([document]).into_events()
+    }
+
+    fn emit(events: impl IntoIterator<Item = Event>, style: JsonStyle) -> Result<String, Error> {
+        let mut output = String::new();
+        JsonEmitter::new(events, style).emit(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn styles() {
+        let node = yaml!({name: "web \"1\"", ports: [80, 443], tls: null, ratio: 0.5, empty: []});
+        let events = to_events(node);
+
+        assert_eq!(
+            emit(events.iter().cloned(), JsonStyle::Compact).unwrap(),
+            "{\"name\":\"web \\\"1\\\"\",\"ports\":[80,443],\"tls\":null,\"ratio\":0.5,\"empty\":[]}\n"
+        );
+        assert_eq!(
+            emit(events, JsonStyle::Pretty(2)).unwrap(),
+            "{\n  \"name\": \"web \\\"1\\\"\",\n  \"ports\": [\n    80,\n    443\n  ],\n  \"tls\": null,\n  \"ratio\": 0.5,\n  \"empty\": []\n}\n"
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let scalar = |value: &str| Event::scalar(value, None);
+        let anchored = |anchor: &str| Event::SequenceStart {
+            tag: None,
+            anchor: Some(anchor.into()),
+            style: None,
+        };
+
+        let events = vec![
+            Event::StreamStart,
+            Event::DocumentStart,
+            Event::sequence_start(),
+            anchored("a"),
+            scalar("0x10"),
+            scalar("yes"),
+            Event::SequenceEnd,
+            Event::Alias("a".into()),
+            Event::SequenceEnd,
+            Event::DocumentEnd,
+            Event::DocumentStart,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ];
+
+        assert_eq!(
+            emit(events, JsonStyle::Compact).unwrap(),
+            "[[16,\"yes\"],[16,\"yes\"]]\nnull\n"
+        );
+
+        let recursive = vec![
+            Event::DocumentStart,
+            anchored("a"),
+            Event::Alias("a".into()),
+            Event::SequenceEnd,
+            Event::DocumentEnd,
+        ];
+
+        assert!(emit(recursive, JsonStyle::Compact).is_err());
+    }
+
+    #[test]
+    fn unrepresentable() {
+        let node = yaml!({(1): "one"});
+        let events = to_events(node);
+        assert!(emit(events, JsonStyle::Compact).is_err());
+
+        let node = yaml!([(f64::NAN)]);
+        let events = to_events(node);
+        assert!(emit(events, JsonStyle::Compact).is_err());
+    }
+}
@@ -3,70 +3,612 @@ use std::fmt::Write;
 use snafu::{ResultExt, Snafu};
 
 use crate::{
-    emitter::state::{State, States},
-    events::Event,
+    directive::Directive,
+    emitter::{
+        align::{AlignedWriter, COMMENT_MARKER},
+        crlf::CrLfWriter,
+        state::{State, States},
+    },
+    events::{display_path, CollectionStyle, Event, PathSegment, ScalarStyle},
+    float::{Float, FloatFormat},
+    schema::{is_ambiguous_yaml_1_1, resolve_core},
+    trailer::{self, Signer},
+    Node,
 };
 
+mod align;
+mod anchors;
+mod check;
+mod compatible;
+mod crlf;
+mod fold;
 mod iter;
+mod json;
 mod options;
+mod receiver;
+mod scalar;
+mod size;
 mod state;
 
+pub use anchors::{
+    AnchorNameFn, AnchorNamer, AnchorNode, ContentHashAnchors, PathAnchors, SequentialAnchors,
+};
+pub use check::*;
+pub use fold::*;
 pub use iter::*;
+pub use json::*;
 pub use options::*;
+pub use receiver::*;
+pub use scalar::*;
+pub use size::*;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("failed to write to output"))]
     Write { source: std::fmt::Error },
+
+    #[snafu(display(
+        "failed to write to output after {documents} documents and {events} events, at {}",
+        display_path(path)
+    ))]
+    Truncated {
+        /// The number of documents which were written completely.
+        documents: usize,
+
+        /// The number of events which were written completely.
+        events: usize,
+
+        /// The path of the node which was being written in its document.
+        path: Vec<PathSegment>,
+        source: std::fmt::Error,
+    },
+
+    #[snafu(display("the {construct} cannot be represented in the output"))]
+    Unrepresentable { construct: String },
+
+    #[snafu(display("the emitter options are invalid"))]
+    InvalidOptions { source: OptionsError },
+
+    #[snafu(display(
+        "the {construct} at {} is not compatible with JSON",
+        display_path(path)
+    ))]
+    NotJsonCompatible {
+        construct: String,
+        path: Vec<PathSegment>,
+    },
 }
 
 #[derive(Debug)]
-pub struct Emitter {
+pub struct Emitter<'a> {
     indent_level: usize,
 
-    // TODO (Techassi): The state(s) need to be managed as a stack to keep track
-    // of mapping / sequence recursion depths
-    states: States,
+    /// Indicates that the next node continues the current line, e.g. the
+    /// first key of a mapping nested inside a sequence item.
+    inline: bool,
 
+    /// The tag handles declared by `%TAG` directives of the current document
+    /// as `(handle, prefix)` pairs.
+    tag_handles: Vec<(String, String)>,
+
+    /// Indicates that a `%YAML` directive was emitted for the current
+    /// document.
+    has_version: bool,
+
+    /// Indicates that any directive was emitted for the current document,
+    /// which requires the document start marker.
+    has_directives: bool,
+
+    /// The number of documents started so far.
+    documents: usize,
+
+    /// Indicates that the next mapping key is written using the explicit
+    /// key indicator, see [`Event::ExplicitKey`].
+    explicit_key: bool,
+
+    states: States,
     options: EmitterOptions,
-    events: EventIter,
+    events: EventIter<'a>,
 }
 
-impl Emitter {
+impl<'a> Emitter<'a> {
     /// Creates a new emitter which will emit characters based on the event
-    /// stream using the provided `ident_size`.
-    pub fn new(events: Vec<Event>, options: EmitterOptions) -> Self {
-        let events = EventIter::new(events);
+    /// stream using the provided `options`.
+    ///
+    /// Events are pulled from the `events` as they are emitted, only a few
+    /// are buffered to look ahead. Together with lazily produced events, like
+    /// [`Stream::into_events_iter`](crate::Stream::into_events_iter) or the
+    /// borrowing [`Stream::events_iter`](crate::Stream::events_iter), large
+    /// trees can be emitted without holding all of their events in memory.
+    /// [`EmitterOptions::anchor_threshold`] requires to collect all events.
+    pub fn new<I>(events: I, options: EmitterOptions) -> Self
+    where
+        I: IntoIterator<Item = Event>,
+        I::IntoIter: 'a,
+    {
+        let events = event_iter(events, &options);
+        Self::with_event_iter(events, options)
+    }
 
+    fn with_event_iter(events: EventIter<'a>, options: EmitterOptions) -> Self {
         Self {
             states: States::new(),
             indent_level: 0,
+            inline: false,
+            tag_handles: Vec::new(),
+            has_version: false,
+            has_directives: false,
+            documents: 0,
+            explicit_key: false,
             options,
             events,
         }
     }
+}
 
+impl Emitter<'_> {
     /// Emits a human-friendly YAML character stream to the `writer`.
+    ///
+    /// If the `writer` fails, the returned [`Error::Truncated`] reports how
+    /// much of the stream was written completely and the path of the node
+    /// which was being written.
     pub fn emit(mut self, writer: &mut impl Write) -> Result<(), Error> {
+        self.emit_prefix(writer)?;
+        self.emit_formatted(writer)?;
+
+        assert!(self.states.is_empty());
+        Ok(())
+    }
+
+    /// Checks the options and writes the byte order mark.
+    fn emit_prefix(&self, writer: &mut impl Write) -> Result<(), Error> {
+        self.options.validate().context(InvalidOptionsSnafu)?;
+
+        if self.options.byte_order_mark {
+            writer.write_char('\u{feff}').context(WriteSnafu)?;
+        }
+
+        Ok(())
+    }
+
+    /// Emits the pending events with the configured line ending.
+    fn emit_formatted(&mut self, writer: &mut impl Write) -> Result<(), Error> {
+        match self.options.line_ending {
+            LineEnding::Lf => self.emit_aligned(writer),
+            LineEnding::CrLf => self.emit_aligned(&mut CrLfWriter::new(writer)),
+        }
+    }
+
+    fn emit_aligned(&mut self, writer: &mut impl Write) -> Result<(), Error> {
+        match self.options.comment_column {
+            Some(column) => self.emit_events(&mut AlignedWriter::new(writer, column)),
+            None => self.emit_events(writer),
+        }
+    }
+
+    fn emit_events(&mut self, writer: &mut impl Write) -> Result<(), Error> {
+        let mut documents = 0;
+
         while let Some(event) = self.events.next() {
-            match event {
-                Event::StreamStart => self.states.push(State::Stream),
-                Event::StreamEnd => self.states.pop(),
-                Event::DocumentStart => self.emit_document_start(writer)?,
-                Event::DocumentEnd => self.emit_document_end(writer)?,
-                Event::Alias(_) => todo!(),
-                Event::Scalar(value) => self.emit_scalar(writer, &value)?,
-                Event::SequenceStart(_) => self.emit_sequence_start(),
-                Event::SequenceEnd => self.emit_sequence_end(),
-                Event::MappingStart(_) => self.emit_mapping_start(writer)?,
-                Event::MappingEnd => self.emit_mapping_end(),
+            let index = self.events.position() - 1;
+            let is_document_end = event == Event::DocumentEnd;
+
+            match self.emit_event(writer, event) {
+                Err(Error::Write { source }) => {
+                    return Err(Error::Truncated {
+                        documents,
+                        events: index,
+                        path: self.events.node_path(),
+                        source,
+                    })
+                }
+                result => result?,
+            }
+
+            if is_document_end {
+                documents += 1;
             }
         }
 
-        assert!(self.states.is_empty());
         Ok(())
     }
 
+    fn emit_event(&mut self, writer: &mut impl Write, event: Event) -> Result<(), Error> {
+        if self.options.json_compatible {
+            self.check_json_compatible(&event)?;
+        }
+
+        let Some(event) = self.apply_policy(event)? else {
+            return Ok(());
+        };
+
+        match event {
+            Event::StreamStart => self.states.push(State::Stream),
+            Event::StreamEnd => self.states.pop(),
+            Event::DocumentStart => self.emit_document_start(writer)?,
+            Event::DocumentEnd => self.emit_document_end(writer)?,
+            Event::Alias(anchor) => self.emit_alias(writer, &anchor)?,
+            Event::Scalar {
+                value,
+                tag,
+                anchor,
+                style,
+            } => self.emit_scalar(writer, &value, tag.as_deref(), anchor.as_deref(), style)?,
+            Event::SequenceStart { tag, anchor, style } => {
+                self.emit_sequence_start(writer, tag, anchor, style)?
+            }
+            Event::SequenceEnd => self.emit_sequence_end(writer)?,
+            Event::MappingStart { tag, anchor, style } => {
+                self.emit_mapping_start(writer, tag, anchor, style)?
+            }
+            Event::MappingEnd => self.emit_mapping_end(writer)?,
+            Event::BlankLines(n) => self.emit_blank_lines(writer, n)?,
+            Event::Comment(text) => {
+                let text = self.sanitize(text, true)?;
+                self.emit_comment(writer, &text)?
+            }
+            // Inline comments are consumed together with the preceding
+            // node, all others have no line to be placed on.
+            Event::InlineComment(_) => {}
+            Event::ExplicitKey => self.explicit_key = true,
+            Event::Directive(directive) => self.emit_directive(writer, directive)?,
+        }
+
+        Ok(())
+    }
+
+    /// Emits the stream like [`Emitter::emit`] and appends a trailer comment
+    /// with the checksum computed by `signer` to each document. Use
+    /// [`trailer::verify`] to detect manual edits of the output.
+    pub fn emit_signed(
+        mut self,
+        writer: &mut impl Write,
+        signer: &impl Signer,
+    ) -> Result<(), Error> {
+        // Only documents with a start marker are signed
+        self.options.document_markers = DocumentMarkers::Explicit;
+        let mut output = String::new();
+        self.emit(&mut output)?;
+
+        writer
+            .write_str(&trailer::sign(&output, signer))
+            .context(WriteSnafu)
+    }
+
+    /// Applies the [`UnrepresentablePolicy`] to the node starting with
+    /// `event`. Returns the (possibly replaced) event, or [`None`] if the
+    /// node was dropped.
+    fn apply_policy(&mut self, event: Event) -> Result<Option<Event>, Error> {
+        let policy = self.options.unrepresentable;
+        let is_node = event.starts_node();
+
+        if let Event::Comment(_) = &event {
+            return match policy.comments {
+                Handling::Keep | Handling::Stringify => Ok(Some(event)),
+                Handling::Error => UnrepresentableSnafu {
+                    construct: "comment",
+                }
+                .fail(),
+                Handling::Drop => Ok(None),
+            };
+        }
+
+        if !is_node {
+            return Ok(Some(event));
+        }
+
+        let is_key = matches!(
+            self.states.current(),
+            Some(State::Mapping(true) | State::FlowMapping { is_key: true, .. })
+        );
+
+        if is_key {
+            let is_string = match &event {
+                Event::Scalar { tag, .. } => tag.as_deref().is_none_or(|tag| {
+                    tag == format!("{CORE_TAG_PREFIX}str") || !tag.starts_with(CORE_TAG_PREFIX)
+                }),
+                _ => false,
+            };
+
+            let value_offset = self.events.rest_len(&event);
+            let is_alias = |event: Option<&Event>| matches!(event, Some(Event::Alias(_)));
+            let drop_alias = policy.aliases == Handling::Drop
+                && (is_alias(Some(&event)) || is_alias(self.events.peek_nth(value_offset)));
+
+            if drop_alias || (!is_string && policy.non_string_keys == Handling::Drop) {
+                self.events.skip_rest(&event);
+                self.events.skip_node();
+                self.explicit_key = false;
+                return Ok(None);
+            }
+
+            if !is_string {
+                match policy.non_string_keys {
+                    Handling::Error => {
+                        return UnrepresentableSnafu {
+                            construct: "non-string key",
+                        }
+                        .fail()
+                    }
+                    Handling::Stringify => {
+                        return Ok(Some(Event::scalar(self.flow_text(event), None)))
+                    }
+                    Handling::Keep | Handling::Drop => {}
+                }
+            }
+        }
+
+        let event = match event {
+            Event::Scalar {
+                value,
+                tag,
+                anchor,
+                style,
+            } if !value.chars().all(is_printable) => Event::Scalar {
+                value: self.sanitize(value, false)?,
+                tag,
+                anchor,
+                style,
+            },
+            event => event,
+        };
+
+        let event = match event {
+            Event::Alias(anchor) => match policy.aliases {
+                Handling::Keep => Event::Alias(anchor),
+                Handling::Error => {
+                    return UnrepresentableSnafu {
+                        construct: format!("alias *{anchor}"),
+                    }
+                    .fail()
+                }
+                Handling::Drop => return Ok(None),
+                Handling::Stringify => Event::scalar(format!("*{anchor}"), None),
+            },
+            Event::Scalar {
+                value,
+                tag: Some(tag),
+                anchor,
+                style,
+            } if !tag.starts_with(CORE_TAG_PREFIX) => {
+                let (value, tag) = match policy.tags {
+                    Handling::Keep => (value, Some(tag)),
+                    Handling::Error => {
+                        return UnrepresentableSnafu {
+                            construct: format!("tag {}", self.shorthand_tag(&tag)),
+                        }
+                        .fail()
+                    }
+                    Handling::Drop => (value, None),
+                    Handling::Stringify => (format!("{} {value}", self.shorthand_tag(&tag)), None),
+                };
+
+                Event::Scalar {
+                    value,
+                    tag,
+                    anchor,
+                    style,
+                }
+            }
+            // Collections can't be stringified, their tags are dropped
+            Event::SequenceStart {
+                tag: Some(tag),
+                anchor,
+                style,
+            } => Event::SequenceStart {
+                tag: self.collection_tag(tag)?,
+                anchor,
+                style,
+            },
+            Event::MappingStart {
+                tag: Some(tag),
+                anchor,
+                style,
+            } => Event::MappingStart {
+                tag: self.collection_tag(tag)?,
+                anchor,
+                style,
+            },
+            event => event,
+        };
+
+        Ok(Some(event))
+    }
+
+    /// Applies the [`UnrepresentablePolicy::tags`] handling to the `tag` of
+    /// a collection.
+    fn collection_tag(&self, tag: String) -> Result<Option<String>, Error> {
+        match self.options.unrepresentable.tags {
+            Handling::Keep => Ok(Some(tag)),
+            Handling::Error => UnrepresentableSnafu {
+                construct: format!("tag {}", self.shorthand_tag(&tag)),
+            }
+            .fail(),
+            Handling::Drop | Handling::Stringify => Ok(None),
+        }
+    }
+
+    /// Applies the [`UnrepresentablePolicy::non_printable`] handling to the
+    /// `text` of a string or comment.
+    fn sanitize(&self, text: String, is_comment: bool) -> Result<String, Error> {
+        sanitize(text, is_comment, self.options.unrepresentable.non_printable)
+    }
+
+    /// Consumes the node starting with `first` and returns its compact flow
+    /// style representation, e.g. `{a: 1, b: [2, 3]}`. Nested collections
+    /// are tracked on a stack instead of recursion.
+    fn flow_text(&mut self, first: Event) -> String {
+        let mut text = String::new();
+
+        // Whether each open collection is a mapping, with the number of its
+        // nodes written so far
+        let mut open: Vec<(bool, usize)> = Vec::new();
+        let mut after_alias = false;
+        let mut next = Some(first);
+
+        while let Some(event) = next.take() {
+            if event.starts_node() {
+                if let Some((is_mapping, count)) = open.last_mut() {
+                    match (*count, *is_mapping) {
+                        (0, _) => {}
+                        // Anchors can contain ':', which requires a space
+                        // before the value indicator.
+                        (count, true) if count % 2 == 1 && after_alias => text.push_str(" : "),
+                        (count, true) if count % 2 == 1 => text.push_str(": "),
+                        _ => text.push_str(", "),
+                    }
+                    *count += 1;
+                }
+            }
+
+            after_alias = matches!(event, Event::Alias(_));
+
+            match event {
+                Event::Scalar {
+                    value,
+                    tag,
+                    anchor,
+                    style,
+                } => text.push_str(&self.flow_scalar(
+                    &value,
+                    tag.as_deref(),
+                    anchor.as_deref(),
+                    style,
+                )),
+                Event::Alias(anchor) => text.push_str(&format!("*{anchor}")),
+                Event::SequenceStart { tag, anchor, .. } => {
+                    text.push_str(&self.properties(anchor.as_deref(), tag.as_deref()));
+                    text.push('[');
+                    open.push((false, 0));
+                }
+                Event::MappingStart { tag, anchor, .. } => {
+                    text.push_str(&self.properties(anchor.as_deref(), tag.as_deref()));
+                    text.push('{');
+                    open.push((true, 0));
+                }
+                Event::SequenceEnd => {
+                    text.push(']');
+                    open.pop();
+                }
+                Event::MappingEnd => {
+                    text.push('}');
+                    open.pop();
+                }
+                _ => {}
+            }
+
+            if !open.is_empty() {
+                next = self.events.next();
+            }
+        }
+
+        text
+    }
+
+    /// Returns the flow style representation of a scalar with its
+    /// properties.
+    fn flow_scalar(
+        &self,
+        value: &str,
+        tag: Option<&str>,
+        anchor: Option<&str>,
+        style: Option<ScalarStyle>,
+    ) -> String {
+        let is_string = tag.is_none_or(|tag| {
+            tag == format!("{CORE_TAG_PREFIX}str") || !tag.starts_with(CORE_TAG_PREFIX)
+        });
+        let tag = tag.filter(|tag| !is_implicit_tag(tag));
+        let properties = self.properties(anchor, tag);
+
+        // Block scalars can't be written in flow style
+        let value = match style {
+            _ if !is_string => value.to_string(),
+            Some(ScalarStyle::SingleQuoted) => self
+                .single_quoted(value)
+                .unwrap_or_else(|| self.double_quoted(value)),
+            Some(ScalarStyle::DoubleQuoted) => self.double_quoted(value),
+            _ if self.needs_quotes(value, true) => self.quoted(value),
+            _ => value.to_string(),
+        };
+
+        format!("{properties}{value}")
+    }
+
+    /// Returns `true` if the collection whose start event was just consumed
+    /// is small enough to be written in flow style, see
+    /// [`EmitterOptions::flow_threshold`]. Its events are only peeked.
+    fn is_small_collection(&mut self, is_mapping: bool) -> bool {
+        let Some(threshold) = self.options.flow_threshold else {
+            return false;
+        };
+        let max_nodes = match is_mapping {
+            true => threshold * 2,
+            false => threshold,
+        };
+
+        // Only collections of scalars without comments qualify, the flow
+        // style would drop the comments
+        let mut nodes = Vec::new();
+        loop {
+            match self.events.peek_nth(nodes.len()) {
+                Some(Event::SequenceEnd | Event::MappingEnd) => break,
+                Some(event @ (Event::Scalar { .. } | Event::Alias(_)))
+                    if nodes.len() < max_nodes =>
+                {
+                    nodes.push(event.clone())
+                }
+                _ => return false,
+            }
+        }
+
+        let texts: Vec<_> = nodes
+            .into_iter()
+            .map(|event| match event {
+                Event::Scalar {
+                    value,
+                    tag,
+                    anchor,
+                    style,
+                } => self.flow_scalar(&value, tag.as_deref(), anchor.as_deref(), style),
+                Event::Alias(anchor) => format!("*{anchor}"),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        let text = match is_mapping {
+            true => texts
+                .chunks(2)
+                .map(|pair| pair.join(": "))
+                .collect::<Vec<_>>()
+                .join(", "),
+            false => texts.join(", "),
+        };
+
+        // The brackets surround the nodes
+        let width = self.options.indent_size * self.indent_level + text.chars().count() + 2;
+        self.options
+            .line_width
+            .is_none_or(|line_width| width <= line_width)
+    }
+
+    /// Returns the properties of a node followed by a space, i.e. the
+    /// `anchor` and the shorthand notation of the `tag`, or an empty string
+    /// if the node has neither.
+    fn properties(&self, anchor: Option<&str>, tag: Option<&str>) -> String {
+        let mut properties = String::new();
+
+        if let Some(anchor) = anchor {
+            properties.push_str(&format!("&{anchor} "));
+        }
+
+        if let Some(tag) = tag {
+            properties.push_str(&self.shorthand_tag(tag));
+            properties.push(' ');
+        }
+
+        properties
+    }
+
     fn emit_indent(&self, writer: &mut impl Write) -> Result<(), Error> {
         writer
             .write_str(
@@ -77,83 +619,1961 @@ impl Emitter {
         Ok(())
     }
 
+    /// Emits up to [`EmitterOptions::max_blank_lines`] blank lines. Blank
+    /// lines are skipped in canonical mode and when the next node continues
+    /// the current line.
+    fn emit_blank_lines(&mut self, writer: &mut impl Write, n: usize) -> Result<(), Error> {
+        if self.options.canonical || self.inline {
+            return Ok(());
+        }
+
+        let n = self.options.max_blank_lines.map_or(n, |max| n.min(max));
+        writer.write_str(&"\n".repeat(n)).context(WriteSnafu)
+    }
+
+    /// Emits a comment on its own line. Comments are skipped in canonical
+    /// mode and in places where they can't be written on their own line,
+    /// e.g. between a key and its value.
+    fn emit_comment(&mut self, writer: &mut impl Write, text: &str) -> Result<(), Error> {
+        if self.options.canonical {
+            return Ok(());
+        }
+
+        let indent = match self.states.current() {
+            Some(State::Stream | State::Document) => String::new(),
+            Some(State::Sequence | State::Mapping(true)) => {
+                " ".repeat(self.options.indent_size * self.indent_level)
+            }
+            _ => return Ok(()),
+        };
+
+        for line in text.split('\n') {
+            // The first key of a mapping nested inside a sequence item
+            // continues the line of the entry indicator, e.g. `- # comment`.
+            let indent = if std::mem::take(&mut self.inline) {
+                ""
+            } else {
+                &indent
+            };
+
+            let line = line.trim_end();
+            let separator = if line.is_empty() { "" } else { " " };
+            writeln!(writer, "{indent}#{separator}{line}").context(WriteSnafu)?;
+        }
+
+        Ok(())
+    }
+
+    /// Consumes the inline comment following the current node, if any, and
+    /// returns it formatted as ` # <text>`. Returns an empty string if there
+    /// is none or comments are skipped.
+    fn take_inline_comment(&mut self) -> Result<String, Error> {
+        if !matches!(self.events.peek(), Some(Event::InlineComment(_))) {
+            return Ok(String::new());
+        }
+
+        let Some(Event::InlineComment(text)) = self.events.next() else {
+            unreachable!()
+        };
+
+        match self.options.unrepresentable.comments {
+            _ if self.options.canonical => Ok(String::new()),
+            Handling::Keep | Handling::Stringify => {
+                let separator = match self.options.comment_column {
+                    Some(_) => COMMENT_MARKER,
+                    None => ' ',
+                };
+
+                let text = self.sanitize(text, true)?;
+                Ok(format!("{separator}# {}", text.replace('\n', " ")))
+            }
+            Handling::Error => UnrepresentableSnafu {
+                construct: "comment",
+            }
+            .fail(),
+            Handling::Drop => Ok(String::new()),
+        }
+    }
+
+    fn emit_directive(
+        &mut self,
+        writer: &mut impl Write,
+        directive: Directive,
+    ) -> Result<(), Error> {
+        writeln!(writer, "{directive}").context(WriteSnafu)?;
+        self.has_directives = true;
+
+        match directive {
+            Directive::YamlVersion(..) => self.has_version = true,
+            Directive::TagHandle { handle, prefix } => self.tag_handles.push((handle, prefix)),
+            Directive::Reserved { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns the shorthand notation of the tag `uri`. Handles declared by
+    /// `%TAG` directives of the current document take precedence over the
+    /// default handles. If multiple prefixes match, the longest one is used.
+    fn shorthand_tag(&self, uri: &str) -> String {
+        self.tag_handles
+            .iter()
+            .filter_map(|(handle, prefix)| {
+                let suffix = uri.strip_prefix(prefix.as_str())?;
+                (!suffix.is_empty()).then(|| (prefix.len(), format!("{handle}{suffix}")))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map_or_else(|| shorthand_tag(uri), |(_, tag)| tag)
+    }
+
     fn emit_document_start(&mut self, writer: &mut impl Write) -> Result<(), Error> {
-        writeln!(writer, "---").context(WriteSnafu)?;
+        if self.options.canonical && !self.has_version {
+            let version = match self.options.yaml_version {
+                YamlVersion::V1_1 => "1.1",
+                YamlVersion::V1_2 => "1.2",
+            };
+            writeln!(writer, "%YAML {version}").context(WriteSnafu)?;
+            self.has_directives = true;
+        }
+
+        // Documents without a start marker must have content, otherwise
+        // they would vanish
+        let is_required = self.has_directives
+            || self.documents > 0
+            || matches!(self.events.peek(), Some(Event::DocumentEnd));
+
+        let has_start = match self.options.document_markers {
+            DocumentMarkers::Explicit | DocumentMarkers::Start => true,
+            DocumentMarkers::Separator => is_required,
+        };
+
+        if has_start {
+            writeln!(writer, "---").context(WriteSnafu)?;
+        }
+
+        self.documents += 1;
         self.states.push(State::Document);
         Ok(())
     }
 
     fn emit_document_end(&mut self, writer: &mut impl Write) -> Result<(), Error> {
-        writeln!(writer, "...").context(WriteSnafu)?;
+        if self.options.document_markers == DocumentMarkers::Explicit
+            || self.next_document_has_directives()
+        {
+            writeln!(writer, "...").context(WriteSnafu)?;
+        }
+
+        self.tag_handles.clear();
+        self.has_version = false;
+        self.has_directives = false;
         self.states.pop();
         Ok(())
     }
 
-    fn emit_scalar(&mut self, writer: &mut impl Write, value: &str) -> Result<(), Error> {
+    /// Returns `true` if the next document starts with directives, which
+    /// requires the end marker of the current document.
+    fn next_document_has_directives(&mut self) -> bool {
+        let mut index = 0;
+        loop {
+            match self.events.peek_nth(index) {
+                Some(Event::Comment(_) | Event::BlankLines(_)) => index += 1,
+                Some(Event::Directive(_)) => return true,
+                _ => return false,
+            }
+        }
+    }
+
+    /// Emits everything which precedes a node (scalar or collection) in the
+    /// current context, like indentation, sequence entry indicators and
+    /// flow separators.
+    fn emit_node_prefix(&mut self, writer: &mut impl Write) -> Result<(), Error> {
         match self.states.current_mut() {
             State::Stream => todo!(),
-            State::Document => todo!(),
-            State::Sequence => self.emit_sequence_item(writer, value)?,
+            State::Document => Ok(()),
+            State::Sequence => {
+                if !std::mem::take(&mut self.inline) {
+                    self.emit_indent(writer)?;
+                }
+
+                writer.write_char('-').context(WriteSnafu)
+            }
             State::Mapping(is_key) => {
+                if *is_key && !std::mem::take(&mut self.inline) {
+                    self.emit_indent(writer)?;
+                }
+
+                Ok(())
+            }
+            State::FlowSequence { first } => {
+                let separator = if std::mem::replace(first, false) {
+                    "\n"
+                } else {
+                    ",\n"
+                };
+                writer.write_str(separator).context(WriteSnafu)?;
+                self.emit_indent(writer)
+            }
+            State::FlowMapping { first, is_key } => {
                 if *is_key {
-                    *is_key = false;
-                    self.emit_mapping_key(writer, value)?;
+                    let separator = if std::mem::replace(first, false) {
+                        "\n"
+                    } else {
+                        ",\n"
+                    };
+                    writer.write_str(separator).context(WriteSnafu)?;
+                    self.emit_indent(writer)?;
+                    writer.write_str("? ").context(WriteSnafu)
                 } else {
-                    *is_key = true;
-                    self.emit_mapping_value(writer, value)?
+                    writer.write_char('\n').context(WriteSnafu)?;
+                    self.emit_indent(writer)?;
+                    writer.write_str(": ").context(WriteSnafu)
                 }
             }
         }
+    }
+
+    /// Advances the state of the parent collection after a complete node
+    /// (scalar or collection) was emitted.
+    fn complete_node(&mut self) {
+        self.explicit_key = false;
+
+        match self.states.current_mut() {
+            State::Mapping(is_key) | State::FlowMapping { is_key, .. } => *is_key = !*is_key,
+            _ => {}
+        }
+    }
+
+    fn emit_scalar(
+        &mut self,
+        writer: &mut impl Write,
+        value_text: &str,
+        tag: Option<&str>,
+        anchor: Option<&str>,
+        style: Option<ScalarStyle>,
+    ) -> Result<(), Error> {
+        let format = self.options.float_format;
+        let formatted = (tag == Some(FLOAT_TAG) && format != FloatFormat::Preserve)
+            .then(|| value_text.parse::<Float>().ok())
+            .flatten()
+            .map(|float| float.format(format));
 
+        // Preserved lexical forms, like `True`, are only kept if requested
+        let normalize = self.options.normalize_scalars || self.options.canonical;
+        let normalized = match tag.and_then(|tag| tag.strip_prefix(CORE_TAG_PREFIX)) {
+            Some("null" | "bool") if normalize => match resolve_core(value_text) {
+                Node::Null => Some("null".to_string()),
+                Node::Boolean(b) => Some(b.to_string()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let value = formatted.or(normalized);
+        let value = value.as_deref().unwrap_or(value_text);
+
+        self.emit_node_prefix(writer)?;
+        let anchor = self.properties(anchor, None);
+
+        if self.options.canonical {
+            writer.write_str(&anchor).context(WriteSnafu)?;
+            self.emit_canonical_scalar(writer, value, tag)?;
+
+            if let State::Document = self.states.current_mut() {
+                writer.write_char('\n').context(WriteSnafu)?;
+            }
+        } else if tag == Some(BINARY_TAG) {
+            self.emit_binary_scalar(writer, &anchor, value)?;
+        } else if let Some(block) = self.block_scalar(value, tag, style) {
+            self.write_block_scalar(writer, &format!("{anchor}{block}"))?;
+        } else {
+            // Core tags are implied by the value, all other tags are
+            // written explicitly.
+            let custom_tag = tag.filter(|tag| !is_implicit_tag(tag));
+            let is_string = custom_tag.is_some()
+                || tag.is_none_or(|tag| tag == format!("{CORE_TAG_PREFIX}str"));
+
+            let needs_quotes = self.needs_quotes(value, false)
+                || (self.options.yaml_version == YamlVersion::V1_1 && is_ambiguous_yaml_1_1(value));
+
+            // Quoting other scalars would turn them into strings
+            let value = match style {
+                _ if !is_string => value.to_string(),
+                Some(ScalarStyle::SingleQuoted) => self
+                    .single_quoted(value)
+                    .unwrap_or_else(|| self.double_quoted(value)),
+                Some(ScalarStyle::DoubleQuoted) => self.double_quoted(value),
+                _ if needs_quotes => self.quoted(value),
+                _ => value.to_string(),
+            };
+
+            let properties = self.properties(None, custom_tag);
+            self.write_block_scalar(writer, &format!("{anchor}{properties}{value}"))?;
+        }
+
+        self.complete_node();
         Ok(())
     }
 
-    fn emit_sequence_item(&self, writer: &mut impl Write, value: &str) -> Result<(), Error> {
-        self.emit_indent(writer)?;
-        writeln!(writer, "- {}", value).context(WriteSnafu)
+    /// Returns `true` if the string `value` must be quoted, see
+    /// [`needs_quotes`] and [`EmitterOptions::escape_non_ascii`].
+    fn needs_quotes(&self, value: &str, flow: bool) -> bool {
+        needs_quotes(value, flow) || (self.options.escape_non_ascii && !value.is_ascii())
     }
 
-    fn emit_mapping_key(&self, writer: &mut impl Write, value: &str) -> Result<(), Error> {
-        if let Some(Event::SequenceStart(_)) = self.events.peek() {
-            writeln!(writer, "{}: ", value).context(WriteSnafu)
-        } else {
-            write!(writer, "{}: ", value).context(WriteSnafu)
+    /// Quotes the `value` in the [`EmitterOptions::quote_style`].
+    fn quoted(&self, value: &str) -> String {
+        match self.options.quote_style {
+            QuoteStyle::Double => self.double_quoted(value),
+            QuoteStyle::Single => self
+                .single_quoted(value)
+                .unwrap_or_else(|| self.double_quoted(value)),
         }
     }
 
-    fn emit_mapping_value(&self, writer: &mut impl Write, value: &str) -> Result<(), Error> {
-        writeln!(writer, "{}", value).context(WriteSnafu)
+    fn double_quoted(&self, value: &str) -> String {
+        match self.options.escape_non_ascii {
+            true => double_quoted_ascii(value),
+            false => double_quoted(value),
+        }
     }
 
-    fn emit_sequence_start(&mut self) {
-        self.indent_level += 1;
-        self.states.push(State::Sequence)
+    fn single_quoted(&self, value: &str) -> Option<String> {
+        match self.options.escape_non_ascii && !value.is_ascii() {
+            true => None,
+            false => single_quoted(value),
+        }
     }
 
-    fn emit_sequence_end(&mut self) {
-        if self.indent_level > 0 {
-            self.indent_level -= 1;
+    /// Returns `true` if the next node is a value in block context, which
+    /// can be written as a block scalar.
+    fn is_block_value(&self) -> bool {
+        matches!(
+            self.states.current(),
+            Some(State::Document | State::Sequence | State::Mapping(false))
+        )
+    }
+
+    /// Returns the block scalar of a string `value` in the preferred
+    /// literal or folded `style`, or folded if it exceeds the line width.
+    /// Returns [`None`] if the value should be written as a flow scalar.
+    fn block_scalar(
+        &self,
+        value: &str,
+        tag: Option<&str>,
+        style: Option<ScalarStyle>,
+    ) -> Option<String> {
+        let indent = " ".repeat(self.options.indent_size * (self.indent_level + 1));
+        let indented = |lines: Vec<&str>| -> Vec<String> {
+            lines
+                .into_iter()
+                .map(|line| match line.is_empty() {
+                    true => String::new(),
+                    false => format!("{indent}{line}"),
+                })
+                .collect()
+        };
+
+        let is_literal = match style {
+            Some(ScalarStyle::Literal) => true,
+            Some(ScalarStyle::Folded) => false,
+            Some(_) => return None,
+            None => {
+                let lines = indented(self.fold_lines(value, tag)?);
+                return Some(format!(">-\n{}", lines.join("\n")));
+            }
+        };
+
+        let is_string = tag.is_none_or(|tag| tag == format!("{CORE_TAG_PREFIX}str"));
+        let content = value.trim_end_matches('\n');
+        let lines: Vec<_> = content.split('\n').collect();
+
+        // The indentation is detected from the first non-empty line, more
+        // indented lines of folded scalars aren't folded
+        let is_representable = is_string
+            && self.is_block_value()
+            && !content.is_empty()
+            && !content
+                .chars()
+                .any(|c| (c.is_control() && c != '\n' && c != '\t') || must_escape(c))
+            && (content.is_ascii() || !self.options.escape_non_ascii)
+            && match is_literal {
+                true => lines
+                    .iter()
+                    .find(|line| !line.is_empty())
+                    .is_some_and(|line| !line.starts_with([' ', '\t'])),
+                false => !lines.iter().any(|line| line.starts_with([' ', '\t'])),
+            };
+
+        if !is_representable {
+            return None;
         }
 
-        self.states.pop()
+        let (indicator, lines) =
+            match is_literal {
+                true => ('|', lines),
+                // Line breaks of folded scalars are written as empty lines, long
+                // lines are broken
+                false => {
+                    let mut folded = Vec::new();
+                    for (index, line) in lines.into_iter().enumerate() {
+                        if index > 0 {
+                            folded.push("");
+                        }
+
+                        match self.options.line_width {
+                            Some(width) if line.chars().count() > width => folded.extend(
+                                break_lines(self.options.line_breaker.as_ref(), line, width),
+                            ),
+                            _ => folded.push(line),
+                        }
+                    }
+                    ('>', folded)
+                }
+            };
+
+        let trailing_breaks = value.len() - content.len();
+        let chomping = match trailing_breaks {
+            0 => "-",
+            1 => "",
+            _ => "+",
+        };
+
+        let mut lines = indented(lines);
+        lines.extend(std::iter::repeat_n(
+            String::new(),
+            trailing_breaks.saturating_sub(1),
+        ));
+
+        Some(format!("{indicator}{chomping}\n{}", lines.join("\n")))
     }
 
-    fn emit_mapping_start(&mut self, writer: &mut impl Write) -> Result<(), Error> {
-        if let Some(Event::MappingStart(_)) = self.events.peek() {
+    /// Returns the lines of a string `value` which exceeds
+    /// [`EmitterOptions::line_width`] and can be written as a folded block
+    /// scalar, broken by [`EmitterOptions::line_breaker`]. Returns [`None`]
+    /// if the value should be written as a flow scalar instead.
+    fn fold_lines<'a>(&self, value: &'a str, tag: Option<&str>) -> Option<Vec<&'a str>> {
+        let width = self.options.line_width?;
+        let is_string = tag.is_none_or(|tag| tag == format!("{CORE_TAG_PREFIX}str"));
+        let is_value = self.is_block_value();
+
+        // Folded scalars can't represent leading whitespace without an
+        // indentation indicator and trailing whitespace is easily lost when
+        // editing the file.
+        let is_foldable = !value.starts_with([' ', '\t'])
+            && !value.ends_with([' ', '\t'])
+            && !value.chars().any(|c| c.is_control() || must_escape(c))
+            && (value.is_ascii() || !self.options.escape_non_ascii);
+
+        if !is_string || !is_value || !is_foldable || value.chars().count() <= width {
+            return None;
+        }
+
+        let lines = break_lines(self.options.line_breaker.as_ref(), value, width);
+        (lines.len() > 1).then_some(lines)
+    }
+
+    /// Writes the base64-encoded `value` as a literal block scalar, wrapped
+    /// at [`BINARY_LINE_WIDTH`] characters. Keys are written on a single
+    /// line, because block scalars can't be used as implicit keys.
+    fn emit_binary_scalar(
+        &mut self,
+        writer: &mut impl Write,
+        anchor: &str,
+        value: &str,
+    ) -> Result<(), Error> {
+        let is_key = matches!(self.states.current(), Some(State::Mapping(true)));
+
+        if is_key || value.is_empty() {
+            let value = format!("{anchor}!!binary {}", double_quoted(value));
+            return self.write_block_scalar(writer, &value);
+        }
+
+        let indent = " ".repeat(self.options.indent_size * (self.indent_level + 1));
+        let lines: Vec<_> = value
+            .as_bytes()
+            .chunks(BINARY_LINE_WIDTH)
+            .map(|chunk| format!("{indent}{}", String::from_utf8_lossy(chunk)))
+            .collect();
+
+        self.write_block_scalar(writer, &format!("{anchor}!!binary |\n{}", lines.join("\n")))
+    }
+
+    /// Writes the (already quoted) scalar `value` in block context, followed
+    /// by the key indicator if the scalar is a mapping key.
+    fn write_block_scalar(&mut self, writer: &mut impl Write, value: &str) -> Result<(), Error> {
+        let explicit_key = self.explicit_key || value.chars().count() > MAX_IMPLICIT_KEY_LENGTH;
+
+        match self.states.current_mut() {
+            // Aliases can contain ':', which requires a space before the key
+            // indicator.
+            State::Mapping(true) if explicit_key => self.write_explicit_key(writer, value),
+            State::Mapping(true) if value.starts_with('*') => {
+                write!(writer, "{value} :").context(WriteSnafu)
+            }
+            State::Mapping(true) => write!(writer, "{value}:").context(WriteSnafu),
+            state => {
+                let separator = match state {
+                    State::Sequence | State::Mapping(false) => " ",
+                    _ => "",
+                };
+
+                // Comments of block scalars follow the header, e.g. `|`
+                let comment = self.take_inline_comment()?;
+                match value.split_once('\n') {
+                    Some((header, rest)) => {
+                        writeln!(writer, "{separator}{header}{comment}\n{rest}")
+                    }
+                    None => writeln!(writer, "{separator}{value}{comment}"),
+                }
+                .context(WriteSnafu)
+            }
+        }
+    }
+
+    /// Writes the single-line `key` using the explicit key indicator `?`,
+    /// followed by the value indicator on the next line, e.g. `? [1, 2]` and
+    /// `: value`.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#rule-c-l-block-map-explicit-entry>
+    fn write_explicit_key(&mut self, writer: &mut impl Write, key: &str) -> Result<(), Error> {
+        writeln!(writer, "? {key}").context(WriteSnafu)?;
+        self.emit_indent(writer)?;
+        writer.write_char(':').context(WriteSnafu)
+    }
+
+    fn emit_alias(&mut self, writer: &mut impl Write, anchor: &str) -> Result<(), Error> {
+        self.emit_node_prefix(writer)?;
+
+        if self.options.canonical {
+            write!(writer, "*{anchor}").context(WriteSnafu)?;
+
+            if let State::Document = self.states.current_mut() {
+                writer.write_char('\n').context(WriteSnafu)?;
+            }
+        } else {
+            self.write_block_scalar(writer, &format!("*{anchor}"))?;
+        }
+
+        self.complete_node();
+        Ok(())
+    }
+
+    fn emit_canonical_scalar(
+        &self,
+        writer: &mut impl Write,
+        value: &str,
+        tag: Option<&str>,
+    ) -> Result<(), Error> {
+        let tag = self.shorthand_tag(tag.unwrap_or(&format!("{CORE_TAG_PREFIX}str")));
+
+        if tag == "!!null" {
+            write!(writer, "{tag} \"\"").context(WriteSnafu)
+        } else {
+            write!(writer, "{tag} {}", self.double_quoted(value)).context(WriteSnafu)
+        }
+    }
+
+    /// Emits the start of a collection with its properties. Empty
+    /// collections are emitted as `[]` or `{}` and their end event is
+    /// consumed immediately. Collections in flow `style` and small
+    /// collections, see [`EmitterOptions::flow_threshold`], are emitted
+    /// completely, comments inside of them are dropped.
+    fn emit_collection_start(
+        &mut self,
+        writer: &mut impl Write,
+        is_mapping: bool,
+        tag: Option<String>,
+        anchor: Option<String>,
+        style: Option<CollectionStyle>,
+    ) -> Result<(), Error> {
+        self.emit_node_prefix(writer)?;
+
+        let is_block = matches!(
+            self.states.current(),
+            Some(State::Document | State::Sequence | State::Mapping(_))
+        );
+
+        // Collection keys are written in flow style as explicit keys
+        let is_key = matches!(self.states.current(), Some(State::Mapping(true)));
+
+        let is_flow = style == Some(CollectionStyle::Flow)
+            || (style.is_none() && is_block && self.is_small_collection(is_mapping));
+
+        if (is_key || is_flow) && is_block && !self.options.canonical {
+            let start = match is_mapping {
+                true => Event::MappingStart { tag, anchor, style },
+                false => Event::SequenceStart { tag, anchor, style },
+            };
+
+            let text = self.flow_text(start);
+            match is_key {
+                true => self.write_explicit_key(writer, &text)?,
+                false => self.write_block_scalar(writer, &text)?,
+            }
+
+            self.complete_node();
+            return Ok(());
+        }
+
+        let tag = tag.map(|tag| self.shorthand_tag(&tag));
+        let anchor = anchor.map(|anchor| format!("&{anchor}"));
+        let comment = self.take_inline_comment()?;
+        let is_root = matches!(self.states.current(), Some(State::Document));
+        let is_empty = matches!(
+            self.events.peek(),
+            Some(Event::SequenceEnd) | Some(Event::MappingEnd)
+        );
+
+        if self.options.canonical {
+            let (default_tag, open) = if is_mapping {
+                ("!!map", "{")
+            } else {
+                ("!!seq", "[")
+            };
+            let tag = tag.as_deref().unwrap_or(default_tag);
+            match anchor {
+                Some(anchor) => write!(writer, "{anchor} {tag} {open}"),
+                None => write!(writer, "{tag} {open}"),
+            }
+            .context(WriteSnafu)?;
+
+            if is_empty {
+                self.events.next();
+                let close = if is_mapping { "}" } else { "]" };
+                writer.write_str(close).context(WriteSnafu)?;
+
+                if is_root {
+                    writer.write_char('\n').context(WriteSnafu)?;
+                }
+
+                self.complete_node();
+                return Ok(());
+            }
+
+            self.indent_level += 1;
+            self.states.push(if is_mapping {
+                State::FlowMapping {
+                    first: true,
+                    is_key: true,
+                }
+            } else {
+                State::FlowSequence { first: true }
+            });
+
+            return Ok(());
+        }
+
+        // Anchors and tags are the properties of the collection
+        let tag = match (anchor, tag) {
+            (Some(anchor), Some(tag)) => Some(format!("{anchor} {tag}")),
+            (anchor, tag) => anchor.or(tag),
+        };
+
+        if is_empty {
+            self.events.next();
+            let empty = if is_mapping { "{}" } else { "[]" };
+            let empty = match &tag {
+                Some(tag) => format!("{tag} {empty}"),
+                None => empty.to_string(),
+            };
+
+            if is_root {
+                writeln!(writer, "{empty}{comment}").context(WriteSnafu)?;
+            } else {
+                writeln!(writer, " {empty}{comment}").context(WriteSnafu)?;
+            }
+
+            self.complete_node();
+            return Ok(());
+        }
+
+        match (self.states.current(), &tag) {
+            // Tagged collections start on the line after their tag.
+            (Some(State::Document), Some(tag)) => {
+                writeln!(writer, "{tag}{comment}").context(WriteSnafu)?
+            }
+            (Some(State::Sequence | State::Mapping(false)), Some(tag)) => {
+                writeln!(writer, " {tag}{comment}").context(WriteSnafu)?
+            }
+            (Some(State::Document), None) if !comment.is_empty() => {
+                writeln!(writer, "{}", comment.trim_start()).context(WriteSnafu)?
+            }
+            (Some(State::Sequence), None) if !comment.is_empty() => {
+                writeln!(writer, "{comment}").context(WriteSnafu)?
+            }
+            // Nested collections inside sequence items start on the same
+            // line as the entry indicator, padded to the indentation size.
+            (Some(State::Sequence), None) => {
+                let padding = self.options.indent_size.saturating_sub(1).max(1);
+                writer.write_str(&" ".repeat(padding)).context(WriteSnafu)?;
+                self.inline = true;
+            }
+            (Some(State::Mapping(false)), None) => {
+                writeln!(writer, "{comment}").context(WriteSnafu)?
+            }
+            _ => {}
+        }
+
+        if !is_root && !self.is_unindented_sequence(is_mapping) {
             self.indent_level += 1;
-            self.emit_indent(writer)?
         }
 
-        self.states.push(State::Mapping(true));
+        self.states.push(if is_mapping {
+            State::Mapping(true)
+        } else {
+            State::Sequence
+        });
+
         Ok(())
     }
 
-    fn emit_mapping_end(&mut self) {
-        if self.indent_level > 0 {
+    fn emit_collection_end(&mut self, writer: &mut impl Write) -> Result<(), Error> {
+        let is_flow = matches!(
+            self.states.current(),
+            Some(State::FlowSequence { .. }) | Some(State::FlowMapping { .. })
+        );
+        let is_mapping = matches!(
+            self.states.current(),
+            Some(State::Mapping(_)) | Some(State::FlowMapping { .. })
+        );
+
+        // TODO (Techassi): Assert that the popped state is the state we expected
+        self.states.pop();
+
+        let is_root = matches!(self.states.current(), Some(State::Document));
+        if is_flow || (!is_root && !self.is_unindented_sequence(is_mapping)) {
             self.indent_level -= 1;
         }
 
-        // TODO (Techassi): Assert that the popped state is the state we expected
-        self.states.pop()
+        if is_flow {
+            writer.write_char('\n').context(WriteSnafu)?;
+            self.emit_indent(writer)?;
+            writer
+                .write_char(if is_mapping { '}' } else { ']' })
+                .context(WriteSnafu)?;
+
+            if is_root {
+                writer.write_char('\n').context(WriteSnafu)?;
+            }
+        }
+
+        self.complete_node();
+        Ok(())
+    }
+
+    /// Returns `true` if the block collection which starts or ends in the
+    /// current state is a sequence whose entries start at the indentation of
+    /// its key, see [`EmitterOptions::indent_sequences`].
+    fn is_unindented_sequence(&self, is_mapping: bool) -> bool {
+        !is_mapping
+            && !self.options.indent_sequences
+            && matches!(self.states.current(), Some(State::Mapping(false)))
+    }
+
+    fn emit_sequence_start(
+        &mut self,
+        writer: &mut impl Write,
+        tag: Option<String>,
+        anchor: Option<String>,
+        style: Option<CollectionStyle>,
+    ) -> Result<(), Error> {
+        self.emit_collection_start(writer, false, tag, anchor, style)
+    }
+
+    fn emit_sequence_end(&mut self, writer: &mut impl Write) -> Result<(), Error> {
+        self.emit_collection_end(writer)
+    }
+
+    fn emit_mapping_start(
+        &mut self,
+        writer: &mut impl Write,
+        tag: Option<String>,
+        anchor: Option<String>,
+        style: Option<CollectionStyle>,
+    ) -> Result<(), Error> {
+        self.emit_collection_start(writer, true, tag, anchor, style)
+    }
+
+    fn emit_mapping_end(&mut self, writer: &mut impl Write) -> Result<(), Error> {
+        self.emit_collection_end(writer)
+    }
+}
+
+/// Returns the iterator the emitter pulls the `events` from. Balancing the
+/// anchors requires to collect all events.
+/// Applies the `handling` of non-printable characters to the `text` of a
+/// string or comment, see [`UnrepresentablePolicy::non_printable`].
+fn sanitize(text: String, is_comment: bool, handling: Handling) -> Result<String, Error> {
+    let Some(c) = text.chars().find(|c| !is_printable(*c)) else {
+        return Ok(text);
+    };
+
+    match handling {
+        Handling::Error => UnrepresentableSnafu {
+            construct: format!("non-printable character U+{:04X}", c as u32),
+        }
+        .fail(),
+        Handling::Drop => Ok(text.chars().filter(|c| is_printable(*c)).collect()),
+        Handling::Keep | Handling::Stringify if is_comment => {
+            Ok(text.chars().filter(|c| is_printable(*c)).collect())
+        }
+        Handling::Keep | Handling::Stringify => Ok(text),
+    }
+}
+
+fn event_iter<'a, I>(events: I, options: &EmitterOptions) -> EventIter<'a>
+where
+    I: IntoIterator<Item = Event>,
+    I::IntoIter: 'a,
+{
+    match options.anchor_threshold {
+        Some(threshold) => EventIter::new(anchors::balance_anchors(
+            events.into_iter().collect(),
+            threshold,
+            options.anchor_namer.as_ref(),
+        )),
+        None => EventIter::new(events),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        cst::{AnchorScope, Cst, ResolveOptions},
+        events::IntoEvents,
+        style::Style,
+        timestamp::Timestamp,
+        yaml, Document, Mapping, MappingPair, Node, Sequence, Stream,
+    };
+
+    use super::*;
+
+    fn emit(node: Node, options: EmitterOptions) -> String {
+        let mut stream = Stream::new();
+        stream.push_document(Document {
+            nodes: vec![node],
+            ..Default::default()
+        });
+
+        let mut output = String::new();
+        Emitter::new(stream.into_events(), options)
+            .emit(&mut output)
+            .unwrap();
+        output
+    }
+
+    fn fixture() -> Node {
+        Node::Mapping(Mapping::from([
+            (Node::String("name".into()), Node::String("a \"b\"".into())),
+            (
+                Node::String("roles".into()),
+                Node::Sequence(Sequence::from([
+                    Node::Mapping(Mapping::from([
+                        (Node::String("id".into()), Node::Integer(1)),
+                        (Node::String("tags".into()), Node::Sequence(Sequence::new())),
+                    ])),
+                    Node::Null,
+                ])),
+            ),
+            (
+                Node::String("nested".into()),
+                Node::Mapping(Mapping::from([(
+                    Node::String("enabled".into()),
+                    Node::Boolean(true),
+                )])),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn block() {
+        let output = emit(fixture(), EmitterOptions::default());
+        assert_eq!(
+            output,
+            "---\nname: a \"b\"\nroles:\n  - id: 1\n    tags: []\n  - null\nnested:\n  enabled: true\n...\n"
+        );
+    }
+
+    #[test]
+    fn non_string_keys() {
+        let map = Node::Mapping(Mapping::from([
+            (Node::Null, Node::String("a".into())),
+            (
+                Node::FloatingPoint(".NaN".parse().unwrap()),
+                Node::String("b".into()),
+            ),
+            (
+                Node::FloatingPoint("-.Inf".parse().unwrap()),
+                Node::String("c".into()),
+            ),
+        ]));
+
+        let output = emit(map, EmitterOptions::default());
+        assert_eq!(output, "---\nnull: a\n.nan: b\n-.inf: c\n...\n");
+    }
+
+    #[test]
+    fn complex_keys() {
+        let key = Node::Mapping(Mapping::from([(
+            Node::String("x".into()),
+            Node::Sequence(Sequence::from([Node::Integer(1)])),
+        )]));
+        let map = Node::Sequence(Sequence::from([Node::Mapping(Mapping::from([
+            (key, Node::Mapping(Mapping::from([("b".into(), 1.into())]))),
+            ("k".repeat(1025).into(), Node::Null),
+            ("c".into(), 2.into()),
+        ]))]));
+
+        let output = emit(map.clone(), EmitterOptions::default());
+        assert_eq!(
+            output,
+            format!(
+                "---\n- ? {{x: [1]}}\n  :\n    b: 1\n  ? {}\n  : null\n  c: 2\n...\n",
+                "k".repeat(1025)
+            )
+        );
+
+        let stream: Stream = output.parse().unwrap();
+        assert_eq!(stream.get(0).unwrap().nodes, [map]);
+    }
+
+    #[test]
+    fn explicit_keys() {
+        let map = Node::Mapping(Mapping::from_iter([
+            MappingPair::new("a".into(), 1.into())
+                .with_leading_comment("first")
+                .with_explicit_key(true),
+            MappingPair::new("b".into(), yaml!({c: [2]})).with_explicit_key(true),
+            MappingPair::new("d".into(), 3.into()),
+        ]));
+
+        let output = emit(map, EmitterOptions::default());
+        assert_eq!(
+            output,
+            "---\n# first\n? a\n: 1\n? b\n:\n  c:\n    - 2\nd: 3\n...\n"
+        );
+
+        let stream: Stream = output.parse().unwrap();
+        let Node::Mapping(mapping) = &stream.get(0).unwrap().nodes[0] else {
+            panic!("expected a mapping");
+        };
+        let explicit: Vec<_> = mapping.pairs().map(|pair| pair.explicit_key).collect();
+        assert_eq!(explicit, [true, true, false]);
+    }
+
+    #[test]
+    fn blank_lines() {
+        let mut mapping = Mapping::from([(Node::String("a".into()), Node::Integer(1))]);
+        mapping
+            .push(MappingPair::new(Node::String("b".into()), Node::Integer(2)).with_blank_lines(2));
+
+        let output = emit(Node::Mapping(mapping.clone()), EmitterOptions::default());
+        assert_eq!(output, "---\na: 1\n\n\nb: 2\n...\n");
+
+        let options = EmitterOptions::builder()
+            .max_blank_lines(Some(1))
+            .build()
+            .unwrap();
+        let output = emit(Node::Mapping(mapping), options);
+        assert_eq!(output, "---\na: 1\n\nb: 2\n...\n");
+    }
+
+    #[test]
+    fn canonical() {
+        let options = EmitterOptions::builder().canonical(true).build().unwrap();
+        let output = emit(fixture(), options);
+        assert_eq!(
+            output,
+            r#"%YAML 1.2
+---
+!!map {
+  ? !!str "name"
+  : !!str "a \"b\"",
+  ? !!str "roles"
+  : !!seq [
+    !!map {
+      ? !!str "id"
+      : !!int "1",
+      ? !!str "tags"
+      : !!seq []
+    },
+    !!null ""
+  ],
+  ? !!str "nested"
+  : !!map {
+    ? !!str "enabled"
+    : !!bool "true"
+  }
+}
+...
+"#
+        );
+    }
+
+    #[test]
+    fn directives() {
+        let mut document = Document::from_mapping(Mapping::from([(
+            Node::String("a".into()),
+            Node::Integer(1),
+        )]));
+        document
+            .push_directive(Directive::yaml_1_2())
+            .push_directive(Directive::tag_handle("!e!", "tag:example.com,2000:").unwrap());
+
+        let mut events = Stream::from_documents(vec![document]).into_events();
+        events.insert(events.len() - 3, Event::scalar("b", None));
+        events.insert(
+            events.len() - 3,
+            Event::scalar("x y", Some("tag:example.com,2000:point".into())),
+        );
+
+        let mut output = String::new();
+        Emitter::new(events.clone(), EmitterOptions::default())
+            .emit(&mut output)
+            .unwrap();
+        assert_eq!(
+            output,
+            "%YAML 1.2\n%TAG !e! tag:example.com,2000:\n---\na: 1\nb: !e!point x y\n...\n"
+        );
+
+        let mut output = String::new();
+        let options = EmitterOptions::builder().canonical(true).build().unwrap();
+        Emitter::new(events, options).emit(&mut output).unwrap();
+        assert!(output.starts_with("%YAML 1.2\n%TAG !e! tag:example.com,2000:\n---\n"));
+        assert!(output.contains(": !e!point \"x y\""));
+    }
+
+    #[test]
+    fn yaml_1_1_quoting() {
+        let node = Node::Mapping(Mapping::from([
+            (Node::String("enabled".into()), Node::String("on".into())),
+            (Node::String("duration".into()), Node::String("1:30".into())),
+            (Node::String("no".into()), Node::Boolean(false)),
+        ]));
+
+        let output = emit(node.clone(), EmitterOptions::default());
+        assert_eq!(output, "---\nenabled: on\nduration: 1:30\nno: false\n...\n");
+
+        let options = EmitterOptions::builder()
+            .yaml_version(YamlVersion::V1_1)
+            .build()
+            .unwrap();
+        let output = emit(node, options);
+        assert_eq!(
+            output,
+            "---\nenabled: \"on\"\nduration: \"1:30\"\n\"no\": false\n...\n"
+        );
+    }
+
+    #[test]
+    fn check() {
+        let mut document = Document::from_mapping(Mapping::from([
+            (Node::String("a".into()), Node::String("yes".into())),
+            (
+                Node::String("a".into()),
+                Node::FloatingPoint("1.5".parse().unwrap()),
+            ),
+            (
+                Node::String("b".into()),
+                Node::Sequence(Sequence::from([Node::String("- x".into())])),
+            ),
+            (Node::Sequence(Sequence::new()), Node::Null),
+        ]));
+        document.push_node(Node::Null);
+
+        let stream = Stream::from_documents(vec![document]);
+        let options = EmitterOptions::builder()
+            .yaml_version(YamlVersion::V1_1)
+            .build()
+            .unwrap();
+
+        let diagnostics: Vec<_> = Emitter::check(&stream, &options)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(
+            diagnostics,
+            [
+                "error: document 0: . document has 2 root nodes",
+                "info: document 0: a string \"yes\" is quoted (AmbiguousYaml1_1)",
+                "error: document 0: a duplicate key String(\"a\")",
+                "info: document 0: b[0] string \"- x\" is quoted (Syntax)",
+                "info: document 0: ? explicit Sequence key",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_unemittable() {
+        // Emitting fails and checking reports the error at the same node
+        let check = |node: Node, options: fn() -> EmitterOptions| {
+            let stream = Stream::from_documents(vec![
+                Document::from_mapping(Mapping::new()),
+                Document {
+                    nodes: vec![node],
+                    ..Default::default()
+                },
+            ]);
+
+            let result =
+                Emitter::new(stream.clone().into_events(), options()).emit(&mut String::new());
+            assert!(result.is_err());
+
+            let diagnostics: Vec<_> = Emitter::check(&stream, &options())
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            diagnostics
+        };
+
+        assert_eq!(
+            check(yaml!({a: {b: [1, "x\ny"]}}), || EmitterOptions::builder()
+                .json_compatible(true)
+                .build()
+                .unwrap()),
+            [
+                "info: document 1: a.b[1] string \"x\\ny\" is quoted (Syntax)",
+                "error: document 1: a.b[1] cannot be emitted: the multiline string at a.b[1] \
+                 is not compatible with JSON",
+            ]
+        );
+
+        assert_eq!(
+            check(yaml!({a: "\u{7}"}), || EmitterOptions::builder()
+                .unrepresentable(UnrepresentablePolicy {
+                    non_printable: Handling::Error,
+                    ..Default::default()
+                })
+                .build()
+                .unwrap()),
+            [
+                "error: document 1: a cannot be emitted: the non-printable character U+0007 \
+              cannot be represented in the output"
+            ]
+        );
+
+        assert_eq!(
+            check(yaml!({a: 1}), || EmitterOptions {
+                indent_size: 0,
+                ..Default::default()
+            }),
+            [
+                "error: document 0: . cannot be emitted: the emitter options are invalid: \
+              the indent size 0 is not between 1 and 9"
+            ]
+        );
+
+        // Dropped non-printable characters don't require quotes
+        let options = EmitterOptions::builder()
+            .unrepresentable(UnrepresentablePolicy {
+                non_printable: Handling::Drop,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let stream = Stream::from_documents(vec![Document::from_mapping(Mapping::from([(
+            Node::String("a".into()),
+            Node::String("b\u{7}".into()),
+        )]))]);
+        assert_eq!(Emitter::check(&stream, &options), []);
+    }
+
+    #[test]
+    fn unrepresentable() {
+        let scalar = |value: &str, tag: Option<&str>| {
+            Event::scalar(value, tag.map(|tag| format!("{CORE_TAG_PREFIX}{tag}")))
+        };
+
+        let events = vec![
+            Event::StreamStart,
+            Event::DocumentStart,
+            Event::mapping_start(),
+            scalar("bucket", None),
+            Event::scalar("Logs", Some("!Ref".into())),
+            scalar("1", Some("int")),
+            scalar("one", None),
+            Event::sequence_start(),
+            scalar("a", None),
+            scalar("2", Some("int")),
+            Event::SequenceEnd,
+            scalar("pair", None),
+            scalar("copy", None),
+            Event::Alias("1".into()),
+            Event::MappingEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ];
+
+        let emit_with = |policy| {
+            let mut output = String::new();
+            let options = EmitterOptions::builder()
+                .unrepresentable(policy)
+                .build()
+                .unwrap();
+            Emitter::new(events.clone(), options)
+                .emit(&mut output)
+                .map(|_| output)
+        };
+
+        assert_eq!(
+            emit_with(UnrepresentablePolicy::all(Handling::Stringify)).unwrap(),
+            "---\nbucket: \"!Ref Logs\"\n\"1\": one\n\"[a, 2]\": pair\ncopy: \"*1\"\n...\n"
+        );
+        assert_eq!(
+            emit_with(UnrepresentablePolicy::all(Handling::Drop)).unwrap(),
+            "---\nbucket: Logs\n...\n"
+        );
+        assert!(matches!(
+            emit_with(UnrepresentablePolicy::all(Handling::Error)),
+            Err(Error::Unrepresentable { construct }) if construct == "tag !Ref"
+        ));
+    }
+
+    #[test]
+    fn binary() {
+        let node = Node::Mapping(Mapping::from([
+            (Node::String("data".into()), Node::Binary((0..64).collect())),
+            (Node::String("empty".into()), Node::Binary(Vec::new())),
+        ]));
+
+        let output = emit(node.clone(), EmitterOptions::default());
+        assert_eq!(
+            output,
+            "---\ndata: !!binary |\n  AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4vMDEyMzQ1Njc4\n  OTo7PD0+Pw==\nempty: !!binary \"\"\n...\n"
+        );
+
+        let stream = Cst::parse(&output).unwrap().to_stream().unwrap();
+        assert_eq!(stream.get(0).unwrap().nodes, vec![node]);
+    }
+
+    #[test]
+    fn timestamps() {
+        let timestamp = Timestamp::date(2001, 12, 14).with_time(21, 59, 43);
+        let node = Node::Mapping(Mapping::from([
+            (Node::String("created".into()), Node::Timestamp(timestamp)),
+            (
+                Node::String("date".into()),
+                Node::Timestamp(Timestamp::date(2002, 12, 14)),
+            ),
+            (Node::Timestamp(timestamp), Node::String("key".into())),
+        ]));
+
+        let options = EmitterOptions::builder()
+            .unrepresentable(UnrepresentablePolicy {
+                non_string_keys: Handling::Stringify,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let output = emit(node.clone(), options);
+        assert_eq!(
+            output,
+            "---\ncreated: !!timestamp 2001-12-14T21:59:43Z\ndate: !!timestamp 2002-12-14\n\"!!timestamp 2001-12-14T21:59:43Z\": key\n...\n"
+        );
+
+        let output = emit(node.clone(), EmitterOptions::default());
+        let stream = Cst::parse(&output).unwrap().to_stream().unwrap();
+        assert_eq!(stream.get(0).unwrap().nodes, vec![node]);
+    }
+
+    #[test]
+    fn float_format() {
+        let node = Node::Sequence(Sequence::from([
+            Node::FloatingPoint("1.50".parse().unwrap()),
+            Node::FloatingPoint(Float::new(1500.0)),
+            Node::FloatingPoint(Float::new(f64::NAN)),
+        ]));
+
+        let output = |float_format| {
+            emit(
+                node.clone(),
+                EmitterOptions::builder()
+                    .float_format(float_format)
+                    .build()
+                    .unwrap(),
+            )
+        };
+
+        assert_eq!(
+            output(FloatFormat::Preserve),
+            "---\n- 1.50\n- 1500.0\n- .nan\n...\n"
+        );
+        assert_eq!(
+            output(FloatFormat::Fixed(2)),
+            "---\n- 1.50\n- 1500.00\n- .nan\n...\n"
+        );
+        assert_eq!(
+            output(FloatFormat::Scientific),
+            "---\n- 1.5e0\n- 1.5e3\n- .nan\n...\n"
+        );
+    }
+
+    #[test]
+    fn folded_strings() {
+        let long = "a long string which is folded";
+        let node = Node::Mapping(Mapping::from([
+            (
+                long.into(),
+                Node::Sequence(Sequence::from([
+                    long.into(),
+                    " leading space which is not folded".into(),
+                ])),
+            ),
+            ("short".into(), "not folded".into()),
+        ]));
+
+        let output = emit(
+            node.clone(),
+            EmitterOptions::builder()
+                .line_width(Some(12))
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(
+            output,
+            "---\na long string which is folded:\n  - >-\n    a long\n    string which\n    is folded\n  - \" leading space which is not folded\"\nshort: not folded\n...\n"
+        );
+
+        let stream = Cst::parse(&output).unwrap().to_stream().unwrap();
+        assert_eq!(stream.get(0).unwrap().nodes, vec![node]);
+    }
+
+    #[test]
+    fn comments() {
+        let source = "---\n# Cluster settings\nclusterName: opensearch # name\nroles: # all roles\n  - master\nlimits: {} # none\n...\n";
+        let stream = Cst::parse(source).unwrap().to_stream().unwrap();
+        let mut output = String::new();
+        Emitter::new(stream.into_events(), EmitterOptions::default())
+            .emit(&mut output)
+            .unwrap();
+        assert_eq!(output, source);
+
+        let item = Mapping::builder()
+            .leading_comment("first\nsecond")
+            .key("name")
+            .value("a")
+            .key("value")
+            .value(Node::Binary(b"text".to_vec()))
+            .comment("binary")
+            .build();
+        let node = Node::Sequence(Sequence::from([Node::Mapping(item)]));
+
+        assert_eq!(
+            emit(node.clone(), EmitterOptions::default()),
+            "---\n- # first\n  # second\n  name: a\n  value: !!binary | # binary\n    dGV4dA==\n...\n"
+        );
+
+        let options = EmitterOptions::builder()
+            .unrepresentable(UnrepresentablePolicy {
+                comments: Handling::Drop,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        assert_eq!(
+            emit(node, options),
+            "---\n- name: a\n  value: !!binary |\n    dGV4dA==\n...\n"
+        );
+    }
+
+    #[test]
+    fn comment_column() {
+        let source = "---\nname: opensearch # name\nroles: # all roles\n  - master\nclusterManagerTimeout: 30s # too long\n...\n";
+        let stream = Cst::parse(source).unwrap().to_stream().unwrap();
+        let mut output = String::new();
+        Emitter::new(
+            stream.into_events(),
+            EmitterOptions::builder()
+                .comment_column(Some(20))
+                .build()
+                .unwrap(),
+        )
+        .emit(&mut output)
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "---\nname: opensearch    # name\nroles:              # all roles\n  - master\nclusterManagerTimeout: 30s # too long\n...\n"
+        );
+    }
+
+    #[test]
+    fn indent_size() {
+        let node = yaml!({a: {b: [1]}});
+
+        assert_eq!(
+            emit(
+                node.clone(),
+                EmitterOptions::builder().indent_size(4).build().unwrap()
+            ),
+            "---\na:\n    b:\n        - 1\n...\n"
+        );
+
+        for indent_size in [0, 10] {
+            let mut output = String::new();
+            let result = Emitter::new(
+                node.clone().into_events(),
+                EmitterOptions {
+                    indent_size,
+                    ..Default::default()
+                },
+            )
+            .emit(&mut output);
+
+            assert!(matches!(
+                result,
+                Err(Error::InvalidOptions {
+                    source: OptionsError::IndentSize { indent_size: size }
+                }) if size == indent_size
+            ));
+            assert!(output.is_empty());
+        }
+    }
+
+    #[test]
+    fn invalid_options() {
+        assert_eq!(
+            EmitterOptions::builder()
+                .indent_size(0)
+                .build()
+                .unwrap_err(),
+            OptionsError::IndentSize { indent_size: 0 }
+        );
+        assert_eq!(
+            EmitterOptions::builder()
+                .indent_size(4)
+                .line_width(Some(3))
+                .build()
+                .unwrap_err(),
+            OptionsError::LineWidth {
+                line_width: 3,
+                indent_size: 4
+            }
+        );
+        assert_eq!(
+            EmitterOptionsBuilder::from(EmitterOptions::canonical())
+                .json_compatible(true)
+                .build()
+                .unwrap_err(),
+            OptionsError::CanonicalJson
+        );
+
+        let options = EmitterOptions {
+            quote_style: QuoteStyle::Single,
+            ..EmitterOptions::canonical()
+        };
+        let result = Emitter::new(yaml!(1).into_events(), options).emit(&mut String::new());
+        assert!(matches!(
+            result,
+            Err(Error::InvalidOptions {
+                source: OptionsError::CanonicalQuotes
+            })
+        ));
+    }
+
+    #[test]
+    fn flow_threshold() {
+        let source = "\
+ports:
+  - 80
+  - 443
+env:
+  a: 1
+  b: x, y
+args:
+  - x # comment
+  - y
+nested:
+  - [1]
+long:
+  - aaaaaaaaaa
+  - bbbbbbbbbb
+many: [1, 2, 3]
+kept: [1]
+";
+        let mut document: Document = source.parse().unwrap();
+        document
+            .set_style("kept", Style::new().with_collection(CollectionStyle::Block))
+            .unwrap();
+
+        let options = EmitterOptions::builder()
+            .flow_threshold(Some(2))
+            .line_width(Some(20))
+            .build()
+            .unwrap();
+        let mut output = String::new();
+        Emitter::new(document.into_events(), options)
+            .emit(&mut output)
+            .unwrap();
+
+        assert_eq!(
+            output,
+            "\
+---
+ports: [80, 443]
+env: {a: 1, b: \"x, y\"}
+args:
+  - x # comment
+  - y
+nested:
+  - [1]
+long:
+  - aaaaaaaaaa
+  - bbbbbbbbbb
+many:
+  - 1
+  - 2
+  - 3
+kept:
+  - 1
+...
+"
+        );
+    }
+
+    #[test]
+    fn presets() {
+        let emit_stream = |source: &str, options: EmitterOptions| {
+            let stream: Stream = source.parse().unwrap();
+            let mut output = String::new();
+            Emitter::new(stream.into_events(), options)
+                .emit(&mut output)
+                .unwrap();
+
+            let reparsed: Stream = output.parse().unwrap();
+            assert_eq!(reparsed.len(), source.parse::<Stream>().unwrap().len());
+            output
+        };
+
+        let source =
+            "kind: Service\nports:\n  - port: 80\n    name: \"on\"\n  - 443\n---\na: [b]\n";
+        assert_eq!(
+            emit_stream(source, EmitterOptions::k8s()),
+            "---\nkind: Service\nports:\n- port: 80\n  name: 'on'\n- 443\n---\na:\n- b\n"
+        );
+        assert_eq!(
+            emit_stream(source, EmitterOptions::github_actions()),
+            "kind: Service\nports:\n  - port: 80\n    name: on\n  - 443\n---\na:\n  - b\n"
+        );
+        assert_eq!(
+            emit_stream("a: 1\n\n\nb: \"it's\"\n", EmitterOptions::compact()),
+            "a: 1\nb: it's\n"
+        );
+        assert_eq!(
+            emit_stream("a: ~\n", EmitterOptions::canonical()),
+            "%YAML 1.2\n---\n!!map {\n  ? !!str \"a\"\n  : !!null \"\"\n}\n...\n"
+        );
+
+        // Required markers are kept
+        assert_eq!(
+            emit_stream(
+                "---\n...\n%YAML 1.2\n---\na: 1\n",
+                EmitterOptions::github_actions()
+            ),
+            "---\n...\n%YAML 1.2\n---\na: 1\n"
+        );
+
+        let options = EmitterOptionsBuilder::from(EmitterOptions::k8s())
+            .indent_sequences(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            emit_stream("a: [\"yes\"]\n", options),
+            "---\na:\n  - 'yes'\n"
+        );
+    }
+
+    #[test]
+    fn line_ending() {
+        let source = "---\r\n# Settings\r\nname: a # name\r\nscript: \"x\\ny\\n\"\r\n...\r\n";
+        let stream = Cst::parse(source).unwrap().to_stream().unwrap();
+        let options = EmitterOptions::builder()
+            .line_ending(LineEnding::detect(source))
+            .build()
+            .unwrap();
+
+        let mut output = String::new();
+        Emitter::new(stream.into_events(), options)
+            .emit(&mut output)
+            .unwrap();
+        assert_eq!(output, source);
+
+        assert_eq!(LineEnding::detect("a: 1\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("a: 1"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let input: Vec<u8> = "\u{feff}a: 1\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let stream = Cst::from_bytes(&input).unwrap().to_stream().unwrap();
+
+        let mut output = String::new();
+        Emitter::new(stream.clone().into_events(), EmitterOptions::default())
+            .emit(&mut output)
+            .unwrap();
+        assert_eq!(output, "---\na: 1\n...\n");
+
+        let mut output = String::new();
+        Emitter::new(
+            stream.into_events(),
+            EmitterOptions::builder()
+                .byte_order_mark(true)
+                .build()
+                .unwrap(),
+        )
+        .emit(&mut output)
+        .unwrap();
+        assert_eq!(output, "\u{feff}---\na: 1\n...\n");
+    }
+
+    #[test]
+    fn escaping() {
+        let value = "a\x07\x1b\u{85}\u{a0}\u{2028}\u{feff}\u{fffe}é😀";
+        assert_eq!(
+            double_quoted(value),
+            "\"a\\a\\e\\N\u{a0}\\L\\uFEFF\\uFFFE\u{e9}😀\""
+        );
+        assert_eq!(
+            double_quoted_ascii(value),
+            "\"a\\a\\e\\N\\_\\L\\uFEFF\\uFFFE\\xE9\\U0001F600\""
+        );
+
+        let node = yaml!({name: "café", list: ["a\u{2028}b"]});
+        let options = EmitterOptions::builder()
+            .escape_non_ascii(true)
+            .build()
+            .unwrap();
+        let output = emit(node.clone(), options);
+        assert_eq!(
+            output,
+            "---\nname: \"caf\\xE9\"\nlist:\n  - \"a\\Lb\"\n...\n"
+        );
+
+        let stream = Cst::parse(&output).unwrap().to_stream().unwrap();
+        assert_eq!(stream.get(0).unwrap().nodes, vec![node]);
+    }
+
+    #[test]
+    fn non_printable() {
+        let node = Node::Mapping(
+            Mapping::builder()
+                .key("bell")
+                .value("ring\x07")
+                .comment("beep\x07")
+                .build(),
+        );
+
+        assert_eq!(
+            emit(node.clone(), EmitterOptions::default()),
+            "---\nbell: \"ring\\a\" # beep\n...\n"
+        );
+
+        let policy = |non_printable| UnrepresentablePolicy {
+            non_printable,
+            ..Default::default()
+        };
+        assert_eq!(
+            emit(
+                node.clone(),
+                EmitterOptions::builder()
+                    .unrepresentable(policy(Handling::Drop))
+                    .build()
+                    .unwrap()
+            ),
+            "---\nbell: ring # beep\n...\n"
+        );
+
+        let mut output = String::new();
+        let result = Emitter::new(
+            vec![Event::StreamStart, Event::DocumentStart]
+                .into_iter()
+                .chain(node.into_events())
+                .chain([Event::DocumentEnd, Event::StreamEnd])
+                .collect::<Vec<_>>(),
+            EmitterOptions::builder()
+                .unrepresentable(policy(Handling::Error))
+                .build()
+                .unwrap(),
+        )
+        .emit(&mut output);
+        assert!(matches!(
+            result,
+            Err(Error::Unrepresentable { construct }) if construct == "non-printable character U+0007"
+        ));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        const DEPTH: usize = 100_000;
+
+        let nested = || (0..DEPTH).fold(Node::Integer(1), |node, _| Node::Sequence(vec![node]));
+
+        let output = emit(nested(), EmitterOptions::default());
+        assert_eq!(output, format!("---\n{}1\n...\n", "- ".repeat(DEPTH)));
+
+        // Borrowed nodes are checked and displayed without copying them
+        let stream = Stream::from_documents(vec![Document::from_mapping(Mapping::from([(
+            Node::String("a".into()),
+            nested(),
+        )]))]);
+        assert_eq!(Emitter::check(&stream, &EmitterOptions::default()), []);
+        assert_eq!(
+            stream.to_string(),
+            format!("---\na:\n  {}1\n...\n", "- ".repeat(DEPTH))
+        );
+        assert_eq!(nested().to_string(), format!("{}1\n", "- ".repeat(DEPTH)));
+        drop(stream);
+
+        // Stringified collections are written as explicit keys if they
+        // exceed the length of implicit keys
+        let mut mapping = Mapping::new();
+        mapping.push_pair(nested(), Node::Null);
+        let options = EmitterOptions::builder()
+            .unrepresentable(UnrepresentablePolicy {
+                non_string_keys: Handling::Stringify,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+
+        let output = emit(Node::Mapping(mapping), options);
+        assert_eq!(
+            output,
+            format!(
+                "---\n? \"{}1{}\"\n: null\n...\n",
+                "[".repeat(DEPTH),
+                "]".repeat(DEPTH)
+            )
+        );
+    }
+
+    #[test]
+    fn lazy_events() {
+        /// Counts the written lines without keeping the output.
+        struct LineCounter(usize);
+
+        impl Write for LineCounter {
+            fn write_str(&mut self, s: &str) -> std::fmt::Result {
+                self.0 += s.matches('\n').count();
+                Ok(())
+            }
+        }
+
+        const ITEMS: usize = 100_000;
+
+        let items = (0..ITEMS).flat_map(|i| {
+            [
+                Event::mapping_start(),
+                Event::scalar("id", None),
+                Event::scalar(i.to_string(), None),
+                Event::MappingEnd,
+            ]
+        });
+        let events = [
+            Event::StreamStart,
+            Event::DocumentStart,
+            Event::sequence_start(),
+        ]
+        .into_iter()
+        .chain(items)
+        .chain([Event::SequenceEnd, Event::DocumentEnd, Event::StreamEnd]);
+
+        let mut counter = LineCounter(0);
+        Emitter::new(events, EmitterOptions::default())
+            .emit(&mut counter)
+            .unwrap();
+        assert_eq!(counter.0, ITEMS + 2);
+
+        let stream = Stream::from_documents(vec![Document {
+            nodes: vec![fixture()],
+            ..Default::default()
+        }]);
+        assert_eq!(
+            stream.clone().into_events_iter().collect::<Vec<_>>(),
+            stream.into_events()
+        );
+    }
+
+    #[test]
+    fn event_properties() {
+        let styled = |value: &str, style| Event::Scalar {
+            value: value.into(),
+            tag: None,
+            anchor: None,
+            style: Some(style),
+        };
+
+        let events = [
+            Event::StreamStart,
+            Event::DocumentStart,
+            Event::mapping_start(),
+            Event::scalar("name", None),
+            Event::Scalar {
+                value: "web".into(),
+                tag: None,
+                anchor: Some("name".into()),
+                style: Some(ScalarStyle::SingleQuoted),
+            },
+            Event::scalar("alias", None),
+            Event::Alias("name".into()),
+            Event::scalar("ports", None),
+            Event::SequenceStart {
+                tag: Some("!Ports".into()),
+                anchor: Some("ports".into()),
+                style: Some(CollectionStyle::Flow),
+            },
+            Event::scalar("80", Some(format!("{CORE_TAG_PREFIX}int"))),
+            styled("443", ScalarStyle::DoubleQuoted),
+            Event::SequenceEnd,
+            Event::scalar("script", None),
+            styled("set -e\nmake\n", ScalarStyle::Literal),
+            Event::scalar("note", None),
+            styled("first\nsecond", ScalarStyle::Folded),
+            Event::scalar("fallback", None),
+            styled("it's\u{7}", ScalarStyle::SingleQuoted),
+            Event::MappingEnd,
+            Event::DocumentEnd,
+            Event::StreamEnd,
+        ];
+
+        let mut output = String::new();
+        Emitter::new(events, EmitterOptions::default())
+            .emit(&mut output)
+            .unwrap();
+        assert_eq!(
+            output,
+            "---
+name: &name 'web'
+alias: *name
+ports: &ports !Ports [80, \"443\"]
+script: |
+  set -e
+  make
+note: >-
+  first
+
+  second
+fallback: \"it's\\a\"
+...
+"
+        );
+
+        let stream = Cst::parse(&output).unwrap().to_stream().unwrap();
+        assert_eq!(
+            stream.get(0).unwrap().nodes[0].get_path("note"),
+            Some(&Node::String("first\nsecond".into()))
+        );
+    }
+
+    #[test]
+    fn receiver() {
+        let stream: Stream = "# a\n---\na: &x [1, 2]\nb: *x\n---\n- c # d\n"
+            .parse()
+            .unwrap();
+        let options = || {
+            EmitterOptions::builder()
+                .line_ending(LineEnding::CrLf)
+                .comment_column(Some(8))
+                .anchor_threshold(Some(1))
+                .build()
+                .unwrap()
+        };
+
+        let mut expected = String::new();
+        Emitter::new(stream.clone().into_events(), options())
+            .emit(&mut expected)
+            .unwrap();
+
+        let mut receiver = Emitter::receiver(String::new(), options());
+        assert!(stream.push_events(&mut receiver).is_continue());
+        assert_eq!(receiver.finish().unwrap(), expected);
+    }
+
+    #[test]
+    fn anchor_threshold() {
+        let limits = yaml!({cpu: 1, memory: "1Gi"});
+        let node = yaml!({
+            web: {limits: limits.clone(), ports: [80]},
+            worker: {limits: limits, ports: [81]},
+        });
+
+        let output = emit(
+            node.clone(),
+            EmitterOptions::builder()
+                .anchor_threshold(Some(6))
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(
+            output,
+            "---\nweb:\n  limits: &1\n    cpu: 1\n    memory: 1Gi\n  ports:\n    - 80\nworker:\n  limits: *1\n  ports:\n    - 81\n...\n"
+        );
+
+        let stream = Cst::parse(&output)
+            .unwrap()
+            .to_stream_with(
+                &ResolveOptions::builder()
+                    .anchor_scope(AnchorScope::Document)
+                    .build(),
+            )
+            .unwrap();
+        assert_eq!(stream.get(0).unwrap().nodes, vec![node]);
+    }
+
+    #[test]
+    fn truncated() {
+        /// Fails as soon as more than `limit` bytes would have been written.
+        struct LimitedWriter {
+            output: String,
+            limit: usize,
+        }
+
+        impl Write for LimitedWriter {
+            fn write_str(&mut self, s: &str) -> std::fmt::Result {
+                if self.output.len() + s.len() > self.limit {
+                    return Err(std::fmt::Error);
+                }
+
+                self.output.push_str(s);
+                Ok(())
+            }
+        }
+
+        let mut stream = Stream::new();
+        stream.push_document(Document::from_mapping(Mapping::from([(
+            "a".into(),
+            yaml!([1, 2]),
+        )])));
+        stream.push_document(Document::from_mapping(Mapping::from([(
+            "b".into(),
+            yaml!({c: [1, "second"]}),
+        )])));
+
+        let mut writer = LimitedWriter {
+            output: String::new(),
+            limit: 45,
+        };
+        let result =
+            Emitter::new(stream.into_events(), EmitterOptions::default()).emit(&mut writer);
+
+        let Err(Error::Truncated {
+            documents,
+            events,
+            path,
+            ..
+        }) = result
+        else {
+            panic!("expected a truncated output, got {result:?}");
+        };
+
+        assert_eq!(
+            writer.output,
+            "---\na:\n  - 1\n  - 2\n...\n---\nb:\n  c:\n    - 1\n"
+        );
+        assert_eq!((documents, events), (1, 17));
+        assert_eq!(display_path(&path), "b.c[1]");
     }
 }
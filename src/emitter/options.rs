@@ -1,15 +1,271 @@
+use snafu::{ensure, Snafu};
+
+use crate::{
+    emitter::{AnchorNamer, GreedyLineBreaker, LineBreaker, SequentialAnchors},
+    float::FloatFormat,
+};
+
+/// Invalid combinations of [`EmitterOptions`], see
+/// [`EmitterOptions::validate`].
+#[derive(Debug, PartialEq, Snafu)]
+pub enum OptionsError {
+    #[snafu(display("the indent size {indent_size} is not between 1 and 9"))]
+    IndentSize { indent_size: usize },
+
+    #[snafu(display("the line width {line_width} is smaller than the indent size {indent_size}"))]
+    LineWidth {
+        line_width: usize,
+        indent_size: usize,
+    },
+
+    #[snafu(display("the canonical form can't be JSON-compatible, it tags every node"))]
+    CanonicalJson,
+
+    #[snafu(display("the canonical form requires double-quoted scalars"))]
+    CanonicalQuotes,
+}
+
 /// These options control the emitter behavior.
 ///
-/// It provides a builder to selectively customize individual settings. If no
-/// customization is required, use [`EmitterOptions::default()`].
+/// It provides a builder to selectively customize individual settings, which
+/// validates the combination of the options, see
+/// [`EmitterOptions::validate`]. If no customization is required, use
+/// [`EmitterOptions::default()`].
 #[derive(Debug)]
 pub struct EmitterOptions {
+    /// The number of spaces per indentation level. It must be between 1 and
+    /// 9, the range of the indentation indicators of block scalars, or
+    /// emitting fails with an [`OptionsError::IndentSize`], see
+    /// [`EmitterOptions::validate`].
     pub indent_size: usize,
+
+    /// Indents block sequences which are the value of a mapping pair, e.g.
+    /// `ports:\n  - 80`. Otherwise, their entries start at the indentation
+    /// of the key, e.g. `ports:\n- 80`, like the output of `kubectl`.
+    pub indent_sequences: bool,
+
+    /// The quotes of strings which must be quoted, e.g. `"yes"` or `'yes'`.
+    /// Strings which can't be single-quoted, like multiline strings, are
+    /// always double-quoted.
+    pub quote_style: QuoteStyle,
+
+    /// The document markers `---` and `...` written around each document.
+    /// Markers which are required, e.g. after directives, are always
+    /// written.
+    pub document_markers: DocumentMarkers,
+
+    /// Emits the canonical form of the YAML stream: every node carries an
+    /// explicit tag, scalars are double-quoted and collections use the fully
+    /// explicit flow style. This is useful for hashing and comparing
+    /// documents.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#canonical-form>
+    pub canonical: bool,
+
+    /// The maximum number of consecutive blank lines emitted between mapping
+    /// pairs and documents. [`None`] reproduces all blank lines, `Some(0)`
+    /// removes them.
+    pub max_blank_lines: Option<usize>,
+
+    /// The YAML version of the consumers of the output. When targeting
+    /// YAML 1.1, strings which 1.1 processors would resolve to other types,
+    /// like `yes`, `off` or `0777`, are quoted as well.
+    pub yaml_version: YamlVersion,
+
+    /// Controls how constructs which the target can't represent are handled,
+    /// e.g. when emitting for restrictive consumers.
+    pub unrepresentable: UnrepresentablePolicy,
+
+    /// The textual format of floats. By default, parsed floats keep their
+    /// original text.
+    pub float_format: FloatFormat,
+
+    /// The preferred maximum width of lines. Longer strings are written as
+    /// folded block scalars, broken by the [`EmitterOptions::line_breaker`].
+    /// [`None`] never breaks lines.
+    pub line_width: Option<usize>,
+
+    /// The algorithm used to break long lines. Defaults to the
+    /// [`GreedyLineBreaker`].
+    pub line_breaker: Box<dyn LineBreaker>,
+
+    /// Balances anchors and aliases by the size of the aliased node, counted
+    /// in events. Aliases of nodes smaller than the threshold are replaced
+    /// by a copy of the node. Repeated collections of at least this size are
+    /// written once with an anchor and referenced by aliases afterwards.
+    /// [`None`] keeps anchors and aliases as they are.
+    pub anchor_threshold: Option<usize>,
+
+    /// Writes block collections of at most this many scalar items or pairs
+    /// in flow style, e.g. `ports: [80, 443]`, if they fit into the
+    /// [`EmitterOptions::line_width`]. Collections containing other
+    /// collections or comments, and collections with a style of their own,
+    /// see [`Style`](crate::style::Style), keep their style. [`None`] only
+    /// uses the flow style if requested.
+    pub flow_threshold: Option<usize>,
+
+    /// Names the anchors generated for repeated collections, see
+    /// [`EmitterOptions::anchor_threshold`]. Defaults to the
+    /// [`SequentialAnchors`] `1`, `2`, ...
+    pub anchor_namer: Box<dyn AnchorNamer>,
+
+    /// Writes null and boolean scalars as `null`, `true` and `false`, even
+    /// if the document preserved another lexical form, like `~` or `True`.
+    /// See [`Document::lexical_forms`](crate::Document::lexical_forms).
+    pub normalize_scalars: bool,
+
+    /// Aligns inline comments, like `key: value # comment`, so that the `#`
+    /// starts at this column, counted in characters from 0. Comments of
+    /// lines which extend beyond the column are separated by a single
+    /// space. [`None`] separates all inline comments by a single space.
+    pub comment_column: Option<usize>,
+
+    /// The line ending written after each line. Use [`LineEnding::detect`]
+    /// to preserve the line ending of a parsed input.
+    pub line_ending: LineEnding,
+
+    /// Starts the output with a byte order mark (`U+FEFF`), which some
+    /// Windows tools expect. Byte order marks of parsed inputs are never
+    /// written otherwise.
+    pub byte_order_mark: bool,
+
+    /// Writes ASCII-only output: strings containing non-ASCII characters
+    /// are double-quoted with these characters escaped, e.g. `"caf\xE9"`.
+    /// By default, they are written as raw UTF-8.
+    pub escape_non_ascii: bool,
+
+    /// Guarantees that the output has the same meaning when read as JSON
+    /// data: keys are strings and there are no tags, anchors, aliases,
+    /// multiline strings or floats which aren't finite. Emitting fails with
+    /// an [`Error::NotJsonCompatible`](super::Error::NotJsonCompatible) at
+    /// the first node which violates this.
+    pub json_compatible: bool,
+}
+
+/// The handling of a construct which cannot be represented by the target.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Handling {
+    /// Emits the construct unchanged.
+    #[default]
+    Keep,
+
+    /// Aborts emitting with an [`Error::Unrepresentable`](super::Error).
+    Error,
+
+    /// Removes the construct. Dropping a mapping key or value drops the whole
+    /// pair.
+    Drop,
+
+    /// Replaces the construct with a string representation of it.
+    Stringify,
+}
+
+/// Per-construct [`Handling`] of constructs which cannot be represented by
+/// the target. By default, all constructs are kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UnrepresentablePolicy {
+    /// Application-specific (non-core) tags, like `!Ref`. Dropping a tag
+    /// keeps the tagged value, stringifying it prepends the tag to the value.
+    pub tags: Handling,
+
+    /// Aliases, like `*default`. Stringified aliases become `"*<anchor>"`.
+    pub aliases: Handling,
+
+    /// Mapping keys which are not strings, like integers or collections.
+    /// Stringified collections are written in flow style, e.g. `"[1, 2]"`.
+    pub non_string_keys: Handling,
+
+    /// Comments, see [`Event::Comment`](crate::events::Event::Comment).
+    /// Comments are already text, stringifying them keeps them unchanged.
+    pub comments: Handling,
+
+    /// Non-printable characters in strings and comments, like control
+    /// characters. Kept or stringified characters of strings are written as
+    /// escape sequences of double-quoted scalars. Comments can't contain
+    /// escape sequences, their non-printable characters are always dropped
+    /// unless they result in an error.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#51-character-set>
+    pub non_printable: Handling,
+}
+
+impl UnrepresentablePolicy {
+    /// Uses the same `handling` for all constructs.
+    pub fn all(handling: Handling) -> Self {
+        Self {
+            tags: handling,
+            aliases: handling,
+            non_string_keys: handling,
+            comments: handling,
+            non_printable: handling,
+        }
+    }
+}
+
+/// The quotes of strings which must be quoted, see
+/// [`EmitterOptions::quote_style`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    #[default]
+    Double,
+    Single,
+}
+
+/// The document markers written by the emitter, see
+/// [`EmitterOptions::document_markers`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DocumentMarkers {
+    /// Writes the start marker `---` and the end marker `...` for every
+    /// document.
+    #[default]
+    Explicit,
+
+    /// Writes the start marker `---` for every document, e.g. like `helm
+    /// template`.
+    Start,
+
+    /// Only writes the start marker `---` to separate documents, i.e. a
+    /// stream with a single document has no markers at all.
+    Separator,
+}
+
+/// The line ending written by the emitter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// A line feed (`\n`), used on Unix-like systems.
+    #[default]
+    Lf,
+
+    /// A carriage return followed by a line feed (`\r\n`), used on Windows.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Detects the line ending of `input` by its first line break. Inputs
+    /// without line breaks use [`LineEnding::Lf`].
+    pub fn detect(input: &str) -> Self {
+        match input.find('\n') {
+            Some(index) if input[..index].ends_with('\r') => Self::CrLf,
+            _ => Self::Lf,
+        }
+    }
+}
+
+/// The YAML version targeted by the emitter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum YamlVersion {
+    /// YAML 1.1, which is still used by many Go tools, e.g. Kubernetes.
+    V1_1,
+
+    #[default]
+    V1_2,
 }
 
 impl Default for EmitterOptions {
     fn default() -> Self {
-        Self::builder().build()
+        Self::builder()
+            .build()
+            .expect("the default options are valid")
     }
 }
 
@@ -17,22 +273,335 @@ impl EmitterOptions {
     pub fn builder() -> EmitterOptionsBuilder {
         EmitterOptionsBuilder::default()
     }
+
+    /// Checks that the options can be combined: the indent size is between
+    /// 1 and 9, the line width fits at least one indentation level and the
+    /// [canonical](EmitterOptions::canonical) form is neither
+    /// JSON-compatible nor single-quoted. Options built by the
+    /// [`EmitterOptionsBuilder`] are always valid, options constructed
+    /// directly are validated when emitting.
+    ///
+    /// The combinations are checked at runtime rather than ruled out by the
+    /// types: the fields stay public, so that presets like
+    /// [`EmitterOptions::k8s`] can be adjusted with the struct update
+    /// syntax, and the indent size and line width are plain numbers, whose
+    /// constraints depend on each other.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        let indent_size = self.indent_size;
+        ensure!(
+            (1..=9).contains(&indent_size),
+            IndentSizeSnafu { indent_size }
+        );
+
+        if let Some(line_width) = self.line_width {
+            ensure!(
+                line_width >= indent_size,
+                LineWidthSnafu {
+                    line_width,
+                    indent_size
+                }
+            );
+        }
+
+        if self.canonical {
+            ensure!(!self.json_compatible, CanonicalJsonSnafu);
+            ensure!(self.quote_style == QuoteStyle::Double, CanonicalQuotesSnafu);
+        }
+
+        Ok(())
+    }
+
+    /// The style of Kubernetes manifests as written by `kubectl` and
+    /// `helm`: sequences aren't indented, strings are single-quoted and
+    /// every document starts with `---`. Strings which YAML 1.1 would
+    /// resolve to other types, like `on`, are quoted for Go tools.
+    ///
+    /// Presets are customized like any other options, e.g.
+    /// `EmitterOptions { line_width: Some(80), ..EmitterOptions::k8s() }`,
+    /// or by turning them into a builder with [`EmitterOptionsBuilder::from`].
+    pub fn k8s() -> Self {
+        Self::builder()
+            .indent_sequences(false)
+            .quote_style(QuoteStyle::Single)
+            .document_markers(DocumentMarkers::Start)
+            .yaml_version(YamlVersion::V1_1)
+            .build()
+            .expect("the preset is valid")
+    }
+
+    /// The style of GitHub Actions workflows: indented sequences,
+    /// single-quoted strings and no document markers.
+    pub fn github_actions() -> Self {
+        Self::builder()
+            .quote_style(QuoteStyle::Single)
+            .document_markers(DocumentMarkers::Separator)
+            .build()
+            .expect("the preset is valid")
+    }
+
+    /// A compact style without blank lines, indentation of sequences and
+    /// unnecessary document markers.
+    pub fn compact() -> Self {
+        Self::builder()
+            .indent_sequences(false)
+            .document_markers(DocumentMarkers::Separator)
+            .max_blank_lines(Some(0))
+            .build()
+            .expect("the preset is valid")
+    }
+
+    /// The canonical form, see [`EmitterOptions::canonical`]. Null and
+    /// boolean scalars are normalized as well.
+    pub fn canonical() -> Self {
+        Self::builder()
+            .canonical(true)
+            .normalize_scalars(true)
+            .build()
+            .expect("the preset is valid")
+    }
+}
+
+impl From<EmitterOptions> for EmitterOptionsBuilder {
+    /// Starts a builder from the `options`, e.g. from a preset like
+    /// [`EmitterOptions::k8s`].
+    fn from(options: EmitterOptions) -> Self {
+        Self {
+            indent_size: options.indent_size,
+            indent_sequences: options.indent_sequences,
+            quote_style: options.quote_style,
+            document_markers: options.document_markers,
+            canonical: options.canonical,
+            max_blank_lines: options.max_blank_lines,
+            yaml_version: options.yaml_version,
+            unrepresentable: options.unrepresentable,
+            float_format: options.float_format,
+            line_width: options.line_width,
+            line_breaker: options.line_breaker,
+            anchor_threshold: options.anchor_threshold,
+            flow_threshold: options.flow_threshold,
+            anchor_namer: options.anchor_namer,
+            normalize_scalars: options.normalize_scalars,
+            comment_column: options.comment_column,
+            line_ending: options.line_ending,
+            byte_order_mark: options.byte_order_mark,
+            escape_non_ascii: options.escape_non_ascii,
+            json_compatible: options.json_compatible,
+        }
+    }
 }
 
 pub struct EmitterOptionsBuilder {
     indent_size: usize,
+    indent_sequences: bool,
+    quote_style: QuoteStyle,
+    document_markers: DocumentMarkers,
+    canonical: bool,
+    max_blank_lines: Option<usize>,
+    yaml_version: YamlVersion,
+    unrepresentable: UnrepresentablePolicy,
+    float_format: FloatFormat,
+    line_width: Option<usize>,
+    line_breaker: Box<dyn LineBreaker>,
+    anchor_threshold: Option<usize>,
+    flow_threshold: Option<usize>,
+    anchor_namer: Box<dyn AnchorNamer>,
+    normalize_scalars: bool,
+    comment_column: Option<usize>,
+    line_ending: LineEnding,
+    byte_order_mark: bool,
+    escape_non_ascii: bool,
+    json_compatible: bool,
 }
 
 impl Default for EmitterOptionsBuilder {
     fn default() -> Self {
-        Self { indent_size: 2 }
+        Self {
+            indent_size: 2,
+            indent_sequences: true,
+            quote_style: QuoteStyle::default(),
+            document_markers: DocumentMarkers::default(),
+            canonical: false,
+            max_blank_lines: None,
+            yaml_version: YamlVersion::default(),
+            unrepresentable: UnrepresentablePolicy::default(),
+            float_format: FloatFormat::default(),
+            line_width: None,
+            line_breaker: Box::new(GreedyLineBreaker),
+            anchor_threshold: None,
+            flow_threshold: None,
+            anchor_namer: Box::new(SequentialAnchors::default()),
+            normalize_scalars: false,
+            comment_column: None,
+            line_ending: LineEnding::default(),
+            byte_order_mark: false,
+            escape_non_ascii: false,
+            json_compatible: false,
+        }
     }
 }
 
 impl EmitterOptionsBuilder {
-    pub fn build(self) -> EmitterOptions {
-        EmitterOptions {
+    /// Sets the number of spaces per indentation level. See
+    /// [`EmitterOptions::indent_size`].
+    pub fn indent_size(mut self, indent_size: usize) -> Self {
+        self.indent_size = indent_size;
+        self
+    }
+
+    /// Enables or disables the indentation of sequences in mappings. See
+    /// [`EmitterOptions::indent_sequences`].
+    pub fn indent_sequences(mut self, indent_sequences: bool) -> Self {
+        self.indent_sequences = indent_sequences;
+        self
+    }
+
+    /// Sets the quotes of strings which must be quoted. See
+    /// [`EmitterOptions::quote_style`].
+    pub fn quote_style(mut self, quote_style: QuoteStyle) -> Self {
+        self.quote_style = quote_style;
+        self
+    }
+
+    /// Sets the written document markers. See
+    /// [`EmitterOptions::document_markers`].
+    pub fn document_markers(mut self, document_markers: DocumentMarkers) -> Self {
+        self.document_markers = document_markers;
+        self
+    }
+
+    /// Enables or disables the canonical output mode.
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Limits the number of consecutive blank lines. See
+    /// [`EmitterOptions::max_blank_lines`].
+    pub fn max_blank_lines(mut self, max_blank_lines: Option<usize>) -> Self {
+        self.max_blank_lines = max_blank_lines;
+        self
+    }
+
+    /// Sets the targeted YAML version. See [`EmitterOptions::yaml_version`].
+    pub fn yaml_version(mut self, yaml_version: YamlVersion) -> Self {
+        self.yaml_version = yaml_version;
+        self
+    }
+
+    /// Sets the handling of unrepresentable constructs. See
+    /// [`UnrepresentablePolicy`].
+    pub fn unrepresentable(mut self, unrepresentable: UnrepresentablePolicy) -> Self {
+        self.unrepresentable = unrepresentable;
+        self
+    }
+
+    /// Sets the textual format of floats. See [`FloatFormat`].
+    pub fn float_format(mut self, float_format: FloatFormat) -> Self {
+        self.float_format = float_format;
+        self
+    }
+
+    /// Sets the preferred maximum width of lines. See
+    /// [`EmitterOptions::line_width`].
+    pub fn line_width(mut self, line_width: Option<usize>) -> Self {
+        self.line_width = line_width;
+        self
+    }
+
+    /// Sets the algorithm used to break long lines. See [`LineBreaker`].
+    pub fn line_breaker(mut self, line_breaker: impl LineBreaker + 'static) -> Self {
+        self.line_breaker = Box::new(line_breaker);
+        self
+    }
+
+    /// Sets the size threshold of anchored nodes. See
+    /// [`EmitterOptions::anchor_threshold`].
+    pub fn anchor_threshold(mut self, anchor_threshold: Option<usize>) -> Self {
+        self.anchor_threshold = anchor_threshold;
+        self
+    }
+
+    /// Sets the maximum size of collections written in flow style. See
+    /// [`EmitterOptions::flow_threshold`].
+    pub fn flow_threshold(mut self, flow_threshold: Option<usize>) -> Self {
+        self.flow_threshold = flow_threshold;
+        self
+    }
+
+    /// Sets the naming strategy of generated anchors. See [`AnchorNamer`].
+    pub fn anchor_namer(mut self, anchor_namer: impl AnchorNamer + 'static) -> Self {
+        self.anchor_namer = Box::new(anchor_namer);
+        self
+    }
+
+    /// Enables or disables normalizing null and boolean scalars. See
+    /// [`EmitterOptions::normalize_scalars`].
+    pub fn normalize_scalars(mut self, normalize_scalars: bool) -> Self {
+        self.normalize_scalars = normalize_scalars;
+        self
+    }
+
+    /// Sets the column of inline comments. See
+    /// [`EmitterOptions::comment_column`].
+    pub fn comment_column(mut self, comment_column: Option<usize>) -> Self {
+        self.comment_column = comment_column;
+        self
+    }
+
+    /// Sets the line ending. See [`EmitterOptions::line_ending`].
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Enables or disables the byte order mark. See
+    /// [`EmitterOptions::byte_order_mark`].
+    pub fn byte_order_mark(mut self, byte_order_mark: bool) -> Self {
+        self.byte_order_mark = byte_order_mark;
+        self
+    }
+
+    /// Enables or disables escaping non-ASCII characters. See
+    /// [`EmitterOptions::escape_non_ascii`].
+    pub fn escape_non_ascii(mut self, escape_non_ascii: bool) -> Self {
+        self.escape_non_ascii = escape_non_ascii;
+        self
+    }
+
+    /// Enables or disables the JSON-compatible mode. See
+    /// [`EmitterOptions::json_compatible`].
+    pub fn json_compatible(mut self, json_compatible: bool) -> Self {
+        self.json_compatible = json_compatible;
+        self
+    }
+
+    /// Builds the options, failing if they can't be combined. See
+    /// [`EmitterOptions::validate`].
+    pub fn build(self) -> Result<EmitterOptions, OptionsError> {
+        let options = EmitterOptions {
             indent_size: self.indent_size,
-        }
+            indent_sequences: self.indent_sequences,
+            quote_style: self.quote_style,
+            document_markers: self.document_markers,
+            canonical: self.canonical,
+            max_blank_lines: self.max_blank_lines,
+            yaml_version: self.yaml_version,
+            unrepresentable: self.unrepresentable,
+            float_format: self.float_format,
+            line_width: self.line_width,
+            line_breaker: self.line_breaker,
+            anchor_threshold: self.anchor_threshold,
+            flow_threshold: self.flow_threshold,
+            anchor_namer: self.anchor_namer,
+            normalize_scalars: self.normalize_scalars,
+            comment_column: self.comment_column,
+            line_ending: self.line_ending,
+            byte_order_mark: self.byte_order_mark,
+            escape_non_ascii: self.escape_non_ascii,
+            json_compatible: self.json_compatible,
+        };
+
+        options.validate()?;
+        Ok(options)
     }
 }
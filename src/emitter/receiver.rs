@@ -0,0 +1,115 @@
+use std::{fmt::Write, ops::ControlFlow};
+
+use crate::{
+    emitter::{event_iter, Emitter, EmitterOptions, Error},
+    events::{Event, EventReceiver},
+    parser::Span,
+};
+
+/// An emitter which receives the events pushed into it, see
+/// [`EventReceiver`] and [`Emitter::receiver`].
+///
+/// The emitter looks ahead to decide how to write a node, which is why the
+/// events of each document are buffered until the document ends. Only then
+/// the document is written to the writer.
+#[derive(Debug)]
+pub struct EmitterReceiver<W> {
+    emitter: Emitter<'static>,
+    writer: W,
+    buffer: Vec<Event>,
+
+    /// Indicates that the byte order mark was written.
+    started: bool,
+
+    /// The number of documents and events written completely.
+    documents: usize,
+    events: usize,
+}
+
+impl Emitter<'_> {
+    /// Creates an emitter which writes the events pushed into it to the
+    /// `writer` using the provided `options`. The receiver breaks with the
+    /// first error, call [`EmitterReceiver::finish`] to write the remaining
+    /// events and get the writer back.
+    ///
+    /// ```
+    /// use yaml_ast::{
+    ///     emitter::{Emitter, EmitterOptions},
+    ///     events::IntoEvents,
+    ///     Stream,
+    /// };
+    ///
+    /// let stream: Stream = "a: [1, 2]".parse().unwrap();
+    /// let mut receiver = Emitter::receiver(String::new(), EmitterOptions::default());
+    /// let _ = stream.push_events(&mut receiver);
+    ///
+    /// assert_eq!(receiver.finish().unwrap(), "---\na:\n  - 1\n  - 2\n...\n");
+    /// ```
+    pub fn receiver<W: Write>(writer: W, options: EmitterOptions) -> EmitterReceiver<W> {
+        EmitterReceiver {
+            emitter: Emitter::new(Vec::new(), options),
+            writer,
+            buffer: Vec::new(),
+            started: false,
+            documents: 0,
+            events: 0,
+        }
+    }
+}
+
+impl<W: Write> EmitterReceiver<W> {
+    /// Writes the buffered events, e.g. of an unfinished document, and
+    /// returns the writer.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if !self.started {
+            self.emitter.emit_prefix(&mut self.writer)?;
+            self.started = true;
+        }
+
+        let events = std::mem::take(&mut self.buffer);
+        let len = events.len();
+        self.emitter.events = event_iter(events, &self.emitter.options);
+
+        match self.emitter.emit_formatted(&mut self.writer) {
+            Err(Error::Truncated {
+                documents,
+                events,
+                path,
+                source,
+            }) => Err(Error::Truncated {
+                documents: self.documents + documents,
+                events: self.events + events,
+                path,
+                source,
+            }),
+            result => {
+                self.events += len;
+                result
+            }
+        }
+    }
+}
+
+impl<W: Write> EventReceiver for EmitterReceiver<W> {
+    type Break = Error;
+
+    fn on_event(&mut self, event: Event, _span: Option<Span>) -> ControlFlow<Self::Break> {
+        let is_document_end = event == Event::DocumentEnd;
+        self.buffer.push(event);
+
+        if is_document_end {
+            if let Err(error) = self.flush() {
+                return ControlFlow::Break(error);
+            }
+
+            self.documents += 1;
+        }
+
+        ControlFlow::Continue(())
+    }
+}
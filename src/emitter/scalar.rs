@@ -0,0 +1,162 @@
+use crate::schema::is_ambiguous_core;
+
+/// The prefix of all tags defined by the YAML specification. Tags starting
+/// with this prefix can be written using the secondary tag handle `!!`.
+pub const CORE_TAG_PREFIX: &str = "tag:yaml.org,2002:";
+
+/// The tag of floating point numbers.
+pub const FLOAT_TAG: &str = "tag:yaml.org,2002:float";
+
+/// The tag of binary data, which is encoded using base64.
+///
+/// See <https://yaml.org/type/binary.html>
+pub const BINARY_TAG: &str = "tag:yaml.org,2002:binary";
+
+/// The maximum length of implicit keys, in characters. Longer keys are
+/// written using the explicit key indicator `?`.
+///
+/// See <https://yaml.org/spec/1.2.2/#example-invalid-implicit-keys>
+pub const MAX_IMPLICIT_KEY_LENGTH: usize = 1024;
+
+/// Returns `true` if the tag `uri` is implied by the value of plain scalars
+/// under the Core schema and can therefore be omitted in block style.
+pub fn is_implicit_tag(uri: &str) -> bool {
+    uri.strip_prefix(CORE_TAG_PREFIX)
+        .is_some_and(|suffix| matches!(suffix, "str" | "null" | "bool" | "int" | "float"))
+}
+
+/// The maximum length of the lines of base64-encoded binary data.
+pub const BINARY_LINE_WIDTH: usize = 76;
+
+/// Returns the shorthand notation of the tag `uri`. Core tags are written
+/// using the `!!` handle, local tags (starting with `!`) as they are and all
+/// other tags are written verbatim.
+///
+/// See <https://yaml.org/spec/1.2.2/#691-node-tags>
+pub fn shorthand_tag(uri: &str) -> String {
+    match uri.strip_prefix(CORE_TAG_PREFIX) {
+        Some(suffix) => format!("!!{suffix}"),
+        None if uri.starts_with('!') => uri.into(),
+        None => format!("!<{uri}>"),
+    }
+}
+
+/// Returns `value` as a double-quoted scalar, escaping all characters which
+/// cannot appear verbatim inside double quotes. All other characters,
+/// including non-ASCII ones, are written as they are.
+///
+/// See <https://yaml.org/spec/1.2.2/#731-double-quoted-style>
+pub fn double_quoted(value: &str) -> String {
+    quote(value, false)
+}
+
+/// Returns `value` as a double-quoted scalar like [`double_quoted`], but
+/// escapes all non-ASCII characters as well, e.g. `é` as `\xE9` and `😀` as
+/// `\U0001F600`. The result only contains ASCII characters.
+pub fn double_quoted_ascii(value: &str) -> String {
+    quote(value, true)
+}
+
+/// Returns `value` as a single-quoted scalar, or [`None`] if it contains
+/// line breaks or characters which must be escaped, which can't be
+/// represented in single quotes.
+///
+/// See <https://yaml.org/spec/1.2.2/#732-single-quoted-style>
+pub fn single_quoted(value: &str) -> Option<String> {
+    let is_representable = !value
+        .chars()
+        .any(|c| (c.is_control() && c != '\t') || must_escape(c));
+
+    is_representable.then(|| format!("'{}'", value.replace('\'', "''")))
+}
+
+/// Returns `true` if the character `c` can't appear verbatim in a
+/// character stream or is easily mangled by other tools, like byte order
+/// marks and the line breaks of YAML 1.1 (`U+0085`, `U+2028` and
+/// `U+2029`). These characters must be written as escape sequences.
+///
+/// See <https://yaml.org/spec/1.2.2/#51-character-set>
+pub fn must_escape(c: char) -> bool {
+    !is_printable(c) || matches!(c, '\u{85}' | '\u{2028}' | '\u{2029}' | '\u{feff}')
+}
+
+/// Returns `true` if the character `c` is printable as defined by the YAML
+/// specification. Other characters, like most control characters, can only
+/// appear as escape sequences of double-quoted scalars.
+///
+/// See <https://yaml.org/spec/1.2.2/#51-character-set>
+pub fn is_printable(c: char) -> bool {
+    matches!(
+        c,
+        '\t' | '\n' | '\r' | ' '..='~' | '\u{85}' | '\u{a0}'..='\u{d7ff}' | '\u{e000}'..='\u{fffd}' | '\u{10000}'..
+    )
+}
+
+fn quote(value: &str, ascii: bool) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\0' => quoted.push_str("\\0"),
+            '\x07' => quoted.push_str("\\a"),
+            '\x08' => quoted.push_str("\\b"),
+            '\t' => quoted.push_str("\\t"),
+            '\n' => quoted.push_str("\\n"),
+            '\x0b' => quoted.push_str("\\v"),
+            '\x0c' => quoted.push_str("\\f"),
+            '\r' => quoted.push_str("\\r"),
+            '\x1b' => quoted.push_str("\\e"),
+            '\u{85}' => quoted.push_str("\\N"),
+            '\u{a0}' if ascii => quoted.push_str("\\_"),
+            '\u{2028}' => quoted.push_str("\\L"),
+            '\u{2029}' => quoted.push_str("\\P"),
+            c if must_escape(c) || (ascii && !c.is_ascii()) => {
+                let code_point = c as u32;
+
+                match code_point {
+                    0..=0xff => quoted.push_str(&format!("\\x{code_point:02X}")),
+                    0x100..=0xffff => quoted.push_str(&format!("\\u{code_point:04X}")),
+                    _ => quoted.push_str(&format!("\\U{code_point:08X}")),
+                }
+            }
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Returns `true` if the string `value` cannot be written as a plain scalar
+/// without changing its content or type. In `flow` context, the flow
+/// indicators `,[]{}` are not allowed either.
+///
+/// See <https://yaml.org/spec/1.2.2/#733-plain-style>
+pub fn needs_quotes(value: &str, flow: bool) -> bool {
+    let mut chars = value.chars();
+    let (first, second) = match (chars.next(), chars.next()) {
+        (Some(first), second) => (first, second),
+        (None, _) => return true,
+    };
+
+    let indicator = match first {
+        '-' | '?' | ':' => second.is_none_or(|c| c == ' ' || (flow && ",[]{}".contains(c))),
+        ',' | '[' | ']' | '{' | '}' | '#' | '&' | '*' | '!' | '|' | '>' | '\'' | '"' | '%'
+        | '@' | '`' => true,
+        _ => false,
+    };
+
+    indicator
+        || is_ambiguous_core(value)
+        || value.starts_with("---")
+        || value.starts_with("...")
+        || value.starts_with([' ', '\t'])
+        || value.ends_with([' ', '\t', ':'])
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.chars().any(|c| c.is_control() || must_escape(c))
+        || (flow && value.contains([',', '[', ']', '{', '}']))
+}
@@ -0,0 +1,91 @@
+use std::fmt::{self, Write};
+
+use crate::emitter::{Emitter, Error};
+
+/// The size of the output of an emitter, see [`Emitter::measure`].
+///
+/// The size is a writer itself, which counts the characters written to it
+/// instead of storing them. This also allows to measure the output of an
+/// [`EmitterReceiver`](super::EmitterReceiver).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OutputSize {
+    /// The length of the output in bytes, encoded as UTF-8.
+    pub bytes: usize,
+
+    /// The number of line breaks of the output. A last line which isn't
+    /// terminated by a line break is not counted, which never happens for
+    /// complete streams.
+    pub lines: usize,
+}
+
+impl Write for OutputSize {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.bytes += s.len();
+        self.lines += s.matches('\n').count();
+        Ok(())
+    }
+}
+
+impl Emitter<'_> {
+    /// Walks the event stream and returns the size the output would have,
+    /// without allocating it. Use it to pre-size buffers or to enforce size
+    /// limits before emitting the stream.
+    ///
+    /// The emitter is consumed, create another one with the same events and
+    /// options to emit the output.
+    ///
+    /// ```
+    /// use yaml_ast::{
+    ///     emitter::{Emitter, EmitterOptions, OutputSize},
+    ///     events::IntoEvents,
+    ///     Stream,
+    /// };
+    ///
+    /// // ---\na:\n  - 1\n  - 2\n...\n
+    /// let stream: Stream = "a: [1, 2]".parse().unwrap();
+    /// let emitter = Emitter::new(stream.into_events(), EmitterOptions::default());
+    ///
+    /// assert_eq!(emitter.measure().unwrap(), OutputSize { bytes: 23, lines: 5 });
+    /// ```
+    pub fn measure(self) -> Result<OutputSize, Error> {
+        let mut size = OutputSize::default();
+        self.emit(&mut size)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        emitter::{EmitterOptions, LineEnding},
+        events::IntoEvents,
+        Stream,
+    };
+
+    #[test]
+    fn measure() {
+        let stream: Stream = "---\nname: größe # comment\nitems: [a, b]\n---\n- |\n  x\n  y\n"
+            .parse()
+            .unwrap();
+        let options = || {
+            EmitterOptions::builder()
+                .byte_order_mark(true)
+                .line_ending(LineEnding::CrLf)
+                .build()
+                .unwrap()
+        };
+
+        let mut output = String::new();
+        Emitter::new(stream.clone().into_events(), options())
+            .emit(&mut output)
+            .unwrap();
+
+        let size = Emitter::new(stream.into_events(), options())
+            .measure()
+            .unwrap();
+        assert_eq!(size.bytes, output.len());
+        assert_eq!(size.lines, output.lines().count());
+    }
+}
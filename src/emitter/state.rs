@@ -6,6 +6,10 @@ impl States {
         Self(Vec::new())
     }
 
+    pub fn current(&self) -> Option<&State> {
+        self.0.last()
+    }
+
     pub fn current_mut(&mut self) -> &mut State {
         // TODO (Techassi): Handle unwrap
         self.0.last_mut().unwrap()
@@ -25,7 +29,6 @@ impl States {
     }
 }
 
-// TODO (Techassi): Also handle flow style
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum State {
     #[default]
@@ -33,4 +36,11 @@ pub enum State {
     Document,
     Sequence,
     Mapping(bool),
+    FlowSequence {
+        first: bool,
+    },
+    FlowMapping {
+        first: bool,
+        is_key: bool,
+    },
 }
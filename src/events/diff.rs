@@ -0,0 +1,601 @@
+//! Structural comparison of two event streams.
+//!
+//! [`diff_events`] walks two event streams in lockstep and reports the first
+//! differences it encounters. Only the current path is kept in memory, which
+//! makes it possible to compare very large streams (e.g. bundles of many
+//! manifests) without building an AST for either side.
+use std::{fmt, iter::Peekable};
+
+use crate::events::Event;
+
+/// A single segment of the path to a node within a document.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// The value of the mapping pair with the given (scalar) key. Complex
+    /// keys are represented by `?`.
+    Key(String),
+
+    /// The item at the given index of a sequence.
+    Index(usize),
+}
+
+/// Formats the `path` as dot-separated keys and bracketed indices, e.g.
+/// `spec.containers[0].image`. The empty path is formatted as `.`. Keys
+/// which are empty or contain dots, brackets or quotes are written as
+/// quoted strings in brackets, e.g. `labels["app.kubernetes.io/name"]`.
+pub(crate) fn display_path(path: &[PathSegment]) -> String {
+    format_path(path, |index| index.to_string())
+}
+
+/// Formats the `path` like [`display_path`], where `index` formats the
+/// indices of sequence items, e.g. as `*` for any item.
+pub(crate) fn format_path(path: &[PathSegment], index: impl Fn(usize) -> String) -> String {
+    if path.is_empty() {
+        return ".".into();
+    }
+
+    let mut display = String::new();
+
+    for (position, segment) in path.iter().enumerate() {
+        match segment {
+            PathSegment::Key(key) if key.is_empty() || key.contains(['.', '[', ']', '"']) => {
+                display.push_str(&format!("[{key:?}]"))
+            }
+            PathSegment::Key(key) if position == 0 => display.push_str(key),
+            PathSegment::Key(key) => display.push_str(&format!(".{key}")),
+            PathSegment::Index(i) => display.push_str(&format!("[{}]", index(*i))),
+        }
+    }
+
+    display
+}
+
+/// Returns `true` if the dot-separated `pattern` matches the whole `path`.
+/// A `*` within a segment matches any number of characters and a `**`
+/// segment matches any number of segments. Sequence items are matched by
+/// their index.
+pub(crate) fn matches_path(pattern: &str, path: &[PathSegment]) -> bool {
+    fn matches(pattern: &[&str], path: &[PathSegment]) -> bool {
+        match pattern.split_first() {
+            None => path.is_empty(),
+            Some((&"**", rest)) => (0..=path.len()).any(|skip| matches(rest, &path[skip..])),
+            Some((segment, rest)) => path.split_first().is_some_and(|(first, path)| {
+                let first = match first {
+                    PathSegment::Key(key) => key.clone(),
+                    PathSegment::Index(index) => index.to_string(),
+                };
+                matches_segment(segment, &first) && matches(rest, path)
+            }),
+        }
+    }
+
+    let pattern: Vec<&str> = match pattern.is_empty() {
+        true => Vec::new(),
+        false => pattern.split('.').collect(),
+    };
+    matches(&pattern, path)
+}
+
+/// Returns `true` if the `pattern`, where `*` matches any number of
+/// characters, matches the whole `segment`.
+fn matches_segment(pattern: &str, segment: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == segment,
+        Some((prefix, rest)) => {
+            let Some(segment) = segment.strip_prefix(prefix) else {
+                return false;
+            };
+
+            segment
+                .char_indices()
+                .map(|(index, _)| index)
+                .chain([segment.len()])
+                .any(|index| matches_segment(rest, &segment[index..]))
+        }
+    }
+}
+
+/// The kind of a difference between two event streams.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DifferenceKind {
+    /// The node is present in both streams, but differs. The events are the
+    /// first events of the node on the left and right side respectively.
+    Changed { left: Event, right: Event },
+
+    /// The node (or document) is only present in the right stream.
+    Added(Event),
+
+    /// The node (or document) is only present in the left stream.
+    Removed(Event),
+}
+
+/// A structural difference between two event streams, located by the index
+/// of the document and the path to the node within it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference {
+    pub document: usize,
+    pub path: Vec<PathSegment>,
+    pub kind: DifferenceKind,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "document {}: {}",
+            self.document,
+            display_path(&self.path)
+        )?;
+
+        match &self.kind {
+            DifferenceKind::Changed { left, right } => {
+                write!(f, " changed from {left:?} to {right:?}")
+            }
+            DifferenceKind::Added(event) => write!(f, " added {event:?}"),
+            DifferenceKind::Removed(event) => write!(f, " removed {event:?}"),
+        }
+    }
+}
+
+/// Compares the two event streams `a` and `b` and returns at most `limit`
+/// structural differences.
+///
+/// The streams are consumed lazily and comparison stops as soon as `limit`
+/// differences are found. Sequence items and mapping pairs are compared by
+/// position. Presentation-only events, like [`Event::BlankLines`], comments and
+/// [`Event::Directive`], are ignored. Tags of collections as well as the
+/// anchors and styles of all nodes are ignored as well.
+pub fn diff_events<A, B>(a: A, b: B, limit: usize) -> Vec<Difference>
+where
+    A: IntoIterator<Item = Event>,
+    B: IntoIterator<Item = Event>,
+{
+    let mut differ = Differ {
+        left: Side::new(a),
+        right: Side::new(b),
+        document: 0,
+        path: Vec::new(),
+        differences: Vec::new(),
+        limit,
+    };
+
+    differ.diff_stream();
+    differ.differences
+}
+
+/// One side of the comparison, skipping presentation-only events.
+struct Side<I>
+where
+    I: Iterator<Item = Event>,
+{
+    events: Peekable<I>,
+}
+
+impl<I> Side<I>
+where
+    I: Iterator<Item = Event>,
+{
+    fn new(events: impl IntoIterator<Item = Event, IntoIter = I>) -> Self {
+        Self {
+            events: events.into_iter().peekable(),
+        }
+    }
+
+    fn skip_presentation(&mut self) {
+        while matches!(
+            self.events.peek(),
+            Some(
+                Event::BlankLines(_)
+                    | Event::Comment(_)
+                    | Event::InlineComment(_)
+                    | Event::ExplicitKey
+                    | Event::Directive(_)
+            )
+        ) {
+            self.events.next();
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Event> {
+        self.skip_presentation();
+        self.events.peek()
+    }
+
+    fn next(&mut self) -> Option<Event> {
+        self.skip_presentation();
+        self.events.next()
+    }
+
+    /// Skips the remainder of a collection whose start event was already
+    /// consumed, including the matching end event.
+    fn skip_rest(&mut self) {
+        let mut depth = 1;
+
+        while depth > 0 {
+            match self.next() {
+                Some(Event::SequenceStart { .. } | Event::MappingStart { .. }) => depth += 1,
+                Some(Event::SequenceEnd | Event::MappingEnd) => depth -= 1,
+                Some(_) => {}
+                None => break,
+            }
+        }
+    }
+
+    /// Skips a complete node and returns its first event.
+    fn skip_node(&mut self) -> Option<Event> {
+        let event = self.next()?;
+
+        if is_collection_start(&event) {
+            self.skip_rest();
+        }
+
+        Some(event)
+    }
+
+    /// Skips a complete document, including its end event.
+    fn skip_document(&mut self) {
+        while let Some(event) = self.next() {
+            if event == Event::DocumentEnd {
+                break;
+            }
+        }
+    }
+
+    fn at(&mut self, event: &Event) -> bool {
+        self.peek() == Some(event)
+    }
+
+    fn at_end(&mut self) -> bool {
+        matches!(
+            self.peek(),
+            None | Some(Event::SequenceEnd | Event::MappingEnd | Event::DocumentEnd)
+        )
+    }
+}
+
+struct Differ<A, B>
+where
+    A: Iterator<Item = Event>,
+    B: Iterator<Item = Event>,
+{
+    left: Side<A>,
+    right: Side<B>,
+    document: usize,
+    path: Vec<PathSegment>,
+    differences: Vec<Difference>,
+    limit: usize,
+}
+
+impl<A, B> Differ<A, B>
+where
+    A: Iterator<Item = Event>,
+    B: Iterator<Item = Event>,
+{
+    fn is_done(&self) -> bool {
+        self.differences.len() >= self.limit
+    }
+
+    fn report(&mut self, kind: DifferenceKind) {
+        if !self.is_done() {
+            self.differences.push(Difference {
+                document: self.document,
+                path: self.path.clone(),
+                kind,
+            });
+        }
+    }
+
+    fn diff_stream(&mut self) {
+        if self.left.at(&Event::StreamStart) {
+            self.left.next();
+        }
+
+        if self.right.at(&Event::StreamStart) {
+            self.right.next();
+        }
+
+        while !self.is_done() {
+            let left = self.left.at(&Event::DocumentStart);
+            let right = self.right.at(&Event::DocumentStart);
+
+            match (left, right) {
+                (true, true) => {
+                    self.left.next();
+                    self.right.next();
+                    self.diff_document();
+                }
+                (true, false) => {
+                    self.left.skip_document();
+                    self.report(DifferenceKind::Removed(Event::DocumentStart));
+                }
+                (false, true) => {
+                    self.right.skip_document();
+                    self.report(DifferenceKind::Added(Event::DocumentStart));
+                }
+                (false, false) => break,
+            }
+
+            self.document += 1;
+        }
+    }
+
+    fn diff_document(&mut self) {
+        while !self.is_done() {
+            match (self.left.at_end(), self.right.at_end()) {
+                (true, true) => break,
+                (true, false) => {
+                    if let Some(event) = self.right.skip_node() {
+                        self.report(DifferenceKind::Added(event));
+                    }
+                }
+                (false, true) => {
+                    if let Some(event) = self.left.skip_node() {
+                        self.report(DifferenceKind::Removed(event));
+                    }
+                }
+                (false, false) => self.diff_node(),
+            }
+        }
+
+        if self.left.at(&Event::DocumentEnd) {
+            self.left.next();
+        }
+
+        if self.right.at(&Event::DocumentEnd) {
+            self.right.next();
+        }
+    }
+
+    fn diff_node(&mut self) {
+        let (Some(left), Some(right)) = (self.left.next(), self.right.next()) else {
+            return;
+        };
+
+        match (&left, &right) {
+            (Event::SequenceStart { .. }, Event::SequenceStart { .. }) => self.diff_sequence(),
+            (Event::MappingStart { .. }, Event::MappingStart { .. }) => self.diff_mapping(),
+            (
+                Event::Scalar {
+                    value: left_value,
+                    tag: left_tag,
+                    ..
+                },
+                Event::Scalar {
+                    value: right_value,
+                    tag: right_tag,
+                    ..
+                },
+            ) => {
+                if (left_value, left_tag) != (right_value, right_tag) {
+                    self.report(DifferenceKind::Changed { left, right });
+                }
+            }
+            (Event::Alias(_), Event::Alias(_)) => {
+                if left != right {
+                    self.report(DifferenceKind::Changed { left, right });
+                }
+            }
+            _ => {
+                if is_collection_start(&left) {
+                    self.left.skip_rest();
+                }
+
+                if is_collection_start(&right) {
+                    self.right.skip_rest();
+                }
+
+                self.report(DifferenceKind::Changed { left, right });
+            }
+        }
+    }
+
+    fn diff_sequence(&mut self) {
+        let mut index = 0;
+
+        loop {
+            match (self.left.at_end(), self.right.at_end()) {
+                (true, true) => break,
+                _ if self.is_done() => return,
+                (true, false) => {
+                    self.path.push(PathSegment::Index(index));
+                    if let Some(event) = self.right.skip_node() {
+                        self.report(DifferenceKind::Added(event));
+                    }
+                    self.path.pop();
+                }
+                (false, true) => {
+                    self.path.push(PathSegment::Index(index));
+                    if let Some(event) = self.left.skip_node() {
+                        self.report(DifferenceKind::Removed(event));
+                    }
+                    self.path.pop();
+                }
+                (false, false) => {
+                    self.path.push(PathSegment::Index(index));
+                    self.diff_node();
+                    self.path.pop();
+                }
+            }
+
+            index += 1;
+        }
+
+        self.left.next();
+        self.right.next();
+    }
+
+    fn diff_mapping(&mut self) {
+        loop {
+            match (self.left.at_end(), self.right.at_end()) {
+                (true, true) => break,
+                _ if self.is_done() => return,
+                (true, false) => {
+                    let segment = key_segment(self.right.peek());
+                    self.path.push(segment);
+                    self.right.skip_node();
+                    if let Some(event) = self.right.skip_node() {
+                        self.report(DifferenceKind::Added(event));
+                    }
+                    self.path.pop();
+                }
+                (false, true) => {
+                    let segment = key_segment(self.left.peek());
+                    self.path.push(segment);
+                    self.left.skip_node();
+                    if let Some(event) = self.left.skip_node() {
+                        self.report(DifferenceKind::Removed(event));
+                    }
+                    self.path.pop();
+                }
+                (false, false) => {
+                    let segment = key_segment(self.left.peek());
+                    self.path.push(segment);
+                    self.diff_node();
+                    self.diff_node();
+                    self.path.pop();
+                }
+            }
+        }
+
+        self.left.next();
+        self.right.next();
+    }
+}
+
+fn is_collection_start(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::SequenceStart { .. } | Event::MappingStart { .. }
+    )
+}
+
+fn key_segment(event: Option<&Event>) -> PathSegment {
+    match event {
+        Some(Event::Scalar { value, .. }) => PathSegment::Key(value.clone()),
+        _ => PathSegment::Key("?".into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{events::IntoEvents, Document, Mapping, Node, Sequence, Stream};
+
+    use super::*;
+
+    fn stream(nodes: Vec<Node>) -> Stream {
+        let mut stream = Stream::new();
+
+        for node in nodes {
+            stream.push_document(Document {
+                nodes: vec![node],
+                ..Default::default()
+            });
+        }
+
+        stream
+    }
+
+    fn manifest(replicas: i64, images: &[&str]) -> Node {
+        Node::Mapping(Mapping::from([
+            (
+                Node::String("kind".into()),
+                Node::String("Deployment".into()),
+            ),
+            (
+                Node::String("spec".into()),
+                Node::Mapping(Mapping::from([
+                    (Node::String("replicas".into()), Node::Integer(replicas)),
+                    (
+                        Node::String("images".into()),
+                        Node::Sequence(
+                            images
+                                .iter()
+                                .map(|image| Node::String(image.to_string()))
+                                .collect::<Sequence>(),
+                        ),
+                    ),
+                ])),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn differences() {
+        let a = stream(vec![manifest(1, &["a"]), manifest(2, &["b", "c"])]);
+        let b = stream(vec![
+            manifest(1, &["a"]),
+            manifest(3, &["b"]),
+            manifest(1, &[]),
+        ]);
+
+        let differences = diff_events(a.into_events(), b.into_events(), 10);
+        let rendered: Vec<_> = differences.iter().map(ToString::to_string).collect();
+
+        assert_eq!(rendered.len(), 3);
+        assert!(rendered[0].starts_with("document 1: spec.replicas changed"));
+        assert!(rendered[1].starts_with("document 1: spec.images[1] removed"));
+        assert_eq!(rendered[2], "document 2: . added DocumentStart");
+    }
+
+    #[test]
+    fn limit() {
+        let a = stream(vec![manifest(1, &["a", "b"])]);
+        let b = stream(vec![Node::Sequence(Sequence::new()), manifest(2, &[])]);
+
+        let differences = diff_events(a.into_events(), b.into_events(), 1);
+        assert_eq!(differences.len(), 1);
+        assert!(matches!(
+            &differences[0].kind,
+            DifferenceKind::Changed {
+                left: Event::MappingStart { .. },
+                right: Event::SequenceStart { .. },
+            }
+        ));
+
+        let a = stream(vec![manifest(1, &["a", "b"])]);
+        let b = stream(vec![manifest(1, &["a", "b"])]);
+        assert!(diff_events(a.into_events(), b.into_events(), 10).is_empty());
+    }
+
+    #[test]
+    fn path_patterns() {
+        let path = [
+            PathSegment::Key("spec".into()),
+            PathSegment::Key("containers".into()),
+            PathSegment::Index(0),
+            PathSegment::Key("env".into()),
+        ];
+
+        assert!(matches_path("spec.containers.0.env", &path));
+        assert!(matches_path("spec.*.*.env", &path));
+        assert!(matches_path("**.env", &path));
+        assert!(matches_path("spec.**", &path));
+        assert!(!matches_path("*.env", &path));
+        assert!(!matches_path("", &path));
+        assert!(matches_path("", &[]));
+
+        assert!(matches_segment("db_*_key", "db_private_key"));
+        assert!(matches_segment("*ä*", "längd"));
+        assert!(!matches_segment("*_key", "key"));
+    }
+
+    #[test]
+    fn paths() {
+        let path = [
+            PathSegment::Key("labels".into()),
+            PathSegment::Key("app.kubernetes.io/name".into()),
+            PathSegment::Index(1),
+            PathSegment::Key(String::new()),
+        ];
+
+        assert_eq!(
+            display_path(&path),
+            "labels[\"app.kubernetes.io/name\"][1][\"\"]"
+        );
+        assert_eq!(
+            format_path(&path[..3], |_| "*".into()),
+            "labels[\"app.kubernetes.io/name\"][*]"
+        );
+        assert_eq!(display_path(&[]), ".");
+    }
+}
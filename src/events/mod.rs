@@ -0,0 +1,212 @@
+use std::ops::ControlFlow;
+
+use crate::directive::Directive;
+
+mod diff;
+mod receiver;
+mod test_suite;
+mod typed;
+
+pub(crate) use receiver::push_all;
+
+pub use crate::cst::{CollectionStyle, ScalarStyle};
+pub use diff::*;
+pub use receiver::*;
+pub use test_suite::*;
+pub use typed::*;
+
+/// This trait is used to turn higher level representations of a YAML stream
+/// into an event stream. These event streams can be produced/consumed by
+/// high and low-level components.
+pub trait IntoEvents {
+    /// Turns the stream of documents into a list of ordered events.
+    ///
+    /// These events are used by the emitter to write the event tree as a
+    /// character stream in a human-friendly manner. This is the last step in
+    /// the "dump" sequence.
+    ///
+    /// #### Reference
+    ///
+    /// - <https://yaml.org/spec/1.2.2/#serializing-the-representation-graph>
+    /// - <https://yaml.org/spec/1.2.2/#presenting-the-serialization-tree>
+    fn into_events(self) -> Vec<Event>;
+
+    /// Pushes the events into the `receiver` until it breaks, see
+    /// [`EventReceiver`]. Returns the value the receiver broke with.
+    ///
+    /// The default implementation collects the events first, [`Stream`],
+    /// [`Document`] and [`Node`] produce them lazily.
+    ///
+    /// [`Stream`]: crate::Stream
+    /// [`Document`]: crate::Document
+    /// [`Node`]: crate::Node
+    fn push_events<R: EventReceiver + ?Sized>(self, receiver: &mut R) -> ControlFlow<R::Break>
+    where
+        Self: Sized,
+    {
+        push_all(self.into_events(), receiver)
+    }
+}
+
+pub trait FromEvents {
+    fn from_events(events: Vec<Event>) -> Self;
+}
+
+/// An event of the serialization of a YAML stream, modeled after the events
+/// of libyaml.
+///
+/// Nodes carry their properties, i.e. their tag and anchor, and optionally
+/// the style they should be presented in. Producers of events, like
+/// [`Node`](crate::Node), leave the style to the emitter by setting it to
+/// [`None`]. Use [`Event::scalar`], [`Event::sequence_start`] and
+/// [`Event::mapping_start`] to create events without properties.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    StreamStart,
+    StreamEnd,
+    DocumentStart,
+    DocumentEnd,
+
+    /// An alias of the node with the given anchor.
+    Alias(String),
+
+    Scalar {
+        value: String,
+
+        /// The resolved tag URI of the scalar, e.g. `tag:yaml.org,2002:str`,
+        /// or an application-specific tag, like `!Ref`. Scalars without a
+        /// tag are resolved by the consumer.
+        tag: Option<String>,
+
+        /// The anchor of the scalar, without the leading `&`.
+        anchor: Option<String>,
+
+        /// The preferred style of the scalar. Emitters fall back to another
+        /// style if the value can't be written in this style.
+        style: Option<ScalarStyle>,
+    },
+
+    SequenceStart {
+        /// An application-specific tag of the sequence, like `!Sub`.
+        tag: Option<String>,
+
+        /// The anchor of the sequence, without the leading `&`.
+        anchor: Option<String>,
+
+        /// The preferred style of the sequence. Block style can't be used
+        /// inside of flow collections.
+        style: Option<CollectionStyle>,
+    },
+    SequenceEnd,
+
+    MappingStart {
+        /// An application-specific tag of the mapping, like `!Sub`.
+        tag: Option<String>,
+
+        /// The anchor of the mapping, without the leading `&`.
+        anchor: Option<String>,
+
+        /// The preferred style of the mapping. Block style can't be used
+        /// inside of flow collections.
+        style: Option<CollectionStyle>,
+    },
+    MappingEnd,
+
+    /// A directive of the following document. Directives precede the
+    /// [`Event::DocumentStart`] event.
+    Directive(Directive),
+
+    /// One or more intentional blank lines preceding the next document or
+    /// mapping pair.
+    BlankLines(usize),
+
+    /// A comment on its own line preceding the next document, mapping pair
+    /// or sequence item. The text doesn't include the leading `#`. Multiple
+    /// lines are written as multiple comments.
+    Comment(String),
+
+    /// A comment at the end of the line of the preceding scalar or alias.
+    /// Directly following a [`Event::SequenceStart`] or
+    /// [`Event::MappingStart`], the comment is written on the line where
+    /// the collection starts, e.g. after the key of a nested mapping.
+    InlineComment(String),
+
+    /// Writes the following mapping key using the explicit key indicator,
+    /// i.e. as `? key` followed by `: value` on the next line. Directly
+    /// precedes the key node. Complex keys are always written this way.
+    ExplicitKey,
+}
+
+impl Event {
+    /// Returns a scalar event without anchor and style.
+    pub fn scalar(value: impl Into<String>, tag: Option<String>) -> Self {
+        Self::Scalar {
+            value: value.into(),
+            tag,
+            anchor: None,
+            style: None,
+        }
+    }
+
+    /// Returns the start event of an untagged sequence without anchor and
+    /// style.
+    pub fn sequence_start() -> Self {
+        Self::SequenceStart {
+            tag: None,
+            anchor: None,
+            style: None,
+        }
+    }
+
+    /// Returns the start event of an untagged mapping without anchor and
+    /// style.
+    pub fn mapping_start() -> Self {
+        Self::MappingStart {
+            tag: None,
+            anchor: None,
+            style: None,
+        }
+    }
+
+    /// Returns the anchor of a scalar or the start of a collection.
+    pub fn anchor(&self) -> Option<&str> {
+        match self {
+            Self::Scalar { anchor, .. }
+            | Self::SequenceStart { anchor, .. }
+            | Self::MappingStart { anchor, .. } => anchor.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the tag of a scalar or the start of a collection.
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            Self::Scalar { tag, .. }
+            | Self::SequenceStart { tag, .. }
+            | Self::MappingStart { tag, .. } => tag.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the event starts a node, i.e. it's a scalar, an
+    /// alias or the start of a collection.
+    pub fn starts_node(&self) -> bool {
+        matches!(
+            self,
+            Self::Scalar { .. }
+                | Self::Alias(_)
+                | Self::SequenceStart { .. }
+                | Self::MappingStart { .. }
+        )
+    }
+
+    /// Returns `true` if the event is the start of a collection.
+    pub fn starts_collection(&self) -> bool {
+        matches!(self, Self::SequenceStart { .. } | Self::MappingStart { .. })
+    }
+
+    /// Returns `true` if the event is the end of a collection.
+    pub fn ends_collection(&self) -> bool {
+        matches!(self, Self::SequenceEnd | Self::MappingEnd)
+    }
+}
@@ -0,0 +1,121 @@
+use std::{convert::Infallible, ops::ControlFlow};
+
+use crate::{events::Event, parser::Span};
+
+/// A consumer of events which are pushed into it one by one, e.g. by
+/// [`IntoEvents::push_events`](crate::events::IntoEvents::push_events).
+///
+/// Receivers process the events as they are produced, without collecting
+/// them first. Returning [`ControlFlow::Break`] stops the producer, which
+/// returns the value to its caller. Closures taking an event and its span
+/// are receivers as well.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// use yaml_ast::{events::{Event, IntoEvents}, yaml};
+///
+/// let mut scalars = 0;
+/// let result = yaml!({name: "web", ports: [80, 443]}).push_events(&mut |event, _| {
+///     if let Event::Scalar { value, .. } = event {
+///         scalars += 1;
+///         if value == "80" {
+///             return ControlFlow::Break(scalars);
+///         }
+///     }
+///     ControlFlow::Continue(())
+/// });
+///
+/// assert_eq!(result, ControlFlow::Break(4));
+/// ```
+pub trait EventReceiver {
+    /// The value returned when the receiver stops the producer, like an
+    /// error.
+    type Break;
+
+    /// Receives the next `event`. The `span` locates the event in the
+    /// source text, if the producer knows it, like a [`Cst`](crate::cst::Cst).
+    fn on_event(&mut self, event: Event, span: Option<Span>) -> ControlFlow<Self::Break>;
+}
+
+/// Collects all events.
+impl EventReceiver for Vec<Event> {
+    type Break = Infallible;
+
+    fn on_event(&mut self, event: Event, _span: Option<Span>) -> ControlFlow<Self::Break> {
+        self.push(event);
+        ControlFlow::Continue(())
+    }
+}
+
+impl<F, B> EventReceiver for F
+where
+    F: FnMut(Event, Option<Span>) -> ControlFlow<B>,
+{
+    type Break = B;
+
+    fn on_event(&mut self, event: Event, span: Option<Span>) -> ControlFlow<Self::Break> {
+        self(event, span)
+    }
+}
+
+/// Pushes all `events` without a span into the `receiver`.
+pub(crate) fn push_all<R: EventReceiver + ?Sized>(
+    events: impl IntoIterator<Item = Event>,
+    receiver: &mut R,
+) -> ControlFlow<R::Break> {
+    events
+        .into_iter()
+        .try_for_each(|event| receiver.on_event(event, None))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{cst::Cst, events::ScalarStyle};
+
+    #[test]
+    fn spans() {
+        let cst = Cst::parse("a: &x 'b'\nc: [*x]\n").unwrap();
+        let mut events = Vec::new();
+        let _ = cst.push_events(&mut |event, span: Option<Span>| {
+            events.push((event, span.map(|span| (span.start, span.end))));
+            ControlFlow::<Infallible>::Continue(())
+        });
+
+        assert_eq!(events.len(), 12);
+        assert_eq!(
+            events[4],
+            (
+                Event::Scalar {
+                    value: "b".into(),
+                    tag: None,
+                    anchor: Some("x".into()),
+                    style: Some(ScalarStyle::SingleQuoted),
+                },
+                Some((6, 9))
+            )
+        );
+        assert_eq!(events[7], (Event::Alias("x".into()), Some((14, 16))));
+
+        let mut collected = Vec::new();
+        let _ = cst.push_events(&mut collected);
+        assert_eq!(
+            collected,
+            events
+                .into_iter()
+                .map(|(event, _)| event)
+                .collect::<Vec<_>>()
+        );
+
+        let mut count = 0;
+        let stopped = cst.push_events(&mut |_, _| {
+            count += 1;
+            match count {
+                3 => ControlFlow::Break(count),
+                _ => ControlFlow::Continue(()),
+            }
+        });
+        assert_eq!(stopped, ControlFlow::Break(3));
+    }
+}
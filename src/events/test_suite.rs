@@ -0,0 +1,192 @@
+use crate::events::{CollectionStyle, Event, ScalarStyle};
+
+impl Event {
+    /// Returns the event in the notation of the yaml-test-suite, e.g.
+    /// `+DOC` or `=VAL <tag:yaml.org,2002:str> :foo`.
+    ///
+    /// Scalars without a style are written as plain scalars. Comments,
+    /// blank lines and directives have no notation of their own and return
+    /// [`None`].
+    ///
+    /// See <https://github.com/yaml/yaml-test-suite>
+    pub fn to_test_suite_string(&self) -> Option<String> {
+        let notation = match self {
+            Event::StreamStart => "+STR".into(),
+            Event::StreamEnd => "-STR".into(),
+            Event::DocumentStart => "+DOC".into(),
+            Event::DocumentEnd => "-DOC".into(),
+            Event::Alias(anchor) => format!("=ALI *{anchor}"),
+            Event::Scalar {
+                value,
+                tag,
+                anchor,
+                style,
+            } => {
+                let indicator = match style {
+                    None | Some(ScalarStyle::Plain) => ':',
+                    Some(ScalarStyle::SingleQuoted) => '\'',
+                    Some(ScalarStyle::DoubleQuoted) => '"',
+                    Some(ScalarStyle::Literal) => '|',
+                    Some(ScalarStyle::Folded) => '>',
+                };
+
+                test_suite_node(
+                    "=VAL",
+                    anchor.as_deref(),
+                    tag.as_deref(),
+                    Some((indicator, value)),
+                )
+            }
+            Event::SequenceStart { tag, anchor, style } => {
+                let indicator = collection_indicator("+SEQ", *style, "[]");
+                test_suite_node(&indicator, anchor.as_deref(), tag.as_deref(), None)
+            }
+            Event::SequenceEnd => "-SEQ".into(),
+            Event::MappingStart { tag, anchor, style } => {
+                let indicator = collection_indicator("+MAP", *style, "{}");
+                test_suite_node(&indicator, anchor.as_deref(), tag.as_deref(), None)
+            }
+            Event::MappingEnd => "-MAP".into(),
+            Event::Directive(_)
+            | Event::BlankLines(_)
+            | Event::Comment(_)
+            | Event::InlineComment(_)
+            | Event::ExplicitKey => return None,
+        };
+
+        Some(notation)
+    }
+}
+
+/// Formats the event stream in the notation of the yaml-test-suite, one
+/// event per line. See [`Event::to_test_suite_string`].
+pub fn to_test_suite_string(events: &[Event]) -> String {
+    events
+        .iter()
+        .filter_map(Event::to_test_suite_string)
+        .map(|line| line + "\n")
+        .collect()
+}
+
+/// Flow collections are marked by their indicators, e.g. `+SEQ []`.
+fn collection_indicator(
+    indicator: &str,
+    style: Option<CollectionStyle>,
+    flow_indicator: &str,
+) -> String {
+    match style {
+        Some(CollectionStyle::Flow) => format!("{indicator} {flow_indicator}"),
+        _ => indicator.into(),
+    }
+}
+
+/// Formats a node event starting with `indicator`, followed by the optional
+/// properties and the scalar `value` with its style indicator.
+pub(crate) fn test_suite_node(
+    indicator: &str,
+    anchor: Option<&str>,
+    tag: Option<&str>,
+    value: Option<(char, &str)>,
+) -> String {
+    let mut line = String::from(indicator);
+
+    if let Some(anchor) = anchor {
+        line.push_str(&format!(" &{anchor}"));
+    }
+
+    if let Some(tag) = tag {
+        line.push_str(&format!(" <{tag}>"));
+    }
+
+    if let Some((style, value)) = value {
+        line.push(' ');
+        line.push(style);
+        line.push_str(&escape(value));
+    }
+
+    line
+}
+
+/// Escapes backslashes and non-printable characters of scalar values the
+/// way the test suite does.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\0' => escaped.push_str("\\0"),
+            '\x08' => escaped.push_str("\\b"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{events::IntoEvents, tag::Tagged, yaml, Document, Node, Stream};
+
+    #[test]
+    fn notation() {
+        let node = yaml!({name: "a\tb", ports: [80]});
+        let tagged = Node::Tagged(Box::new(Tagged::new("!Sub", yaml!([]))));
+        let stream = Stream::from_iter([Document {
+            nodes: vec![Node::Sequence(vec![node, tagged])],
+            ..Default::default()
+        }]);
+
+        assert_eq!(
+            to_test_suite_string(&stream.into_events()),
+            "+STR
++DOC
++SEQ
++MAP
+=VAL <tag:yaml.org,2002:str> :name
+=VAL <tag:yaml.org,2002:str> :a\\tb
+=VAL <tag:yaml.org,2002:str> :ports
++SEQ
+=VAL <tag:yaml.org,2002:int> :80
+-SEQ
+-MAP
++SEQ <!Sub>
+-SEQ
+-SEQ
+-DOC
+-STR
+"
+        );
+
+        assert_eq!(
+            Event::Alias("a".into()).to_test_suite_string().unwrap(),
+            "=ALI *a"
+        );
+        assert_eq!(
+            Event::Scalar {
+                value: "b".into(),
+                tag: None,
+                anchor: Some("a".into()),
+                style: Some(ScalarStyle::SingleQuoted),
+            }
+            .to_test_suite_string()
+            .unwrap(),
+            "=VAL &a 'b"
+        );
+        assert_eq!(
+            Event::MappingStart {
+                tag: Some("!t".into()),
+                anchor: None,
+                style: Some(CollectionStyle::Flow),
+            }
+            .to_test_suite_string()
+            .unwrap(),
+            "+MAP {} <!t>"
+        );
+        assert_eq!(Event::Comment("a".into()).to_test_suite_string(), None);
+    }
+}
@@ -0,0 +1,396 @@
+//! A typed API to generate event streams.
+//!
+//! Pushing [`Event`]s into a [`Vec`] by hand makes it easy to produce invalid
+//! sequences, like a [`Event::MappingEnd`] without a matching
+//! [`Event::MappingStart`] or a mapping key without a value. The builders
+//! of this module only allow valid sequences: every node is written through
+//! a [`NodeEvents`], which must be consumed exactly once and returns a
+//! [`Written`] proof, which cannot be created in any other way.
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use crate::{
+    directive::Directive,
+    emitter::{BINARY_TAG, CORE_TAG_PREFIX},
+    events::{Event, IntoEvents},
+    float::Float,
+    tag::Tagged,
+    Node,
+};
+
+/// Proof that exactly one node was written. It can only be obtained by
+/// consuming a [`NodeEvents`].
+#[derive(Debug)]
+#[must_use]
+pub struct Written(());
+
+/// Builds the events of a stream, consisting of zero or more documents.
+///
+/// ```
+/// use yaml_ast::events::{IntoEvents, StreamEvents};
+///
+/// let events = StreamEvents::new()
+///     .document(|doc| {
+///         doc.root().mapping(|mapping| {
+///             mapping
+///                 .entry("name", |node| node.string("web"))
+///                 .entry("ports", |node| {
+///                     node.sequence(|ports| {
+///                         ports.item(|node| node.integer(80));
+///                     })
+///                 });
+///         })
+///     })
+///     .into_events();
+///
+/// assert_eq!(events.len(), 12);
+/// ```
+#[derive(Debug)]
+pub struct StreamEvents {
+    events: Vec<Event>,
+}
+
+impl Default for StreamEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamEvents {
+    pub fn new() -> Self {
+        Self {
+            events: vec![Event::StreamStart],
+        }
+    }
+
+    /// Appends a document, whose directives and root node are written by
+    /// `f`.
+    pub fn document<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(DocEvents<'_>) -> Written,
+    {
+        let _ = f(DocEvents {
+            events: &mut self.events,
+        });
+
+        self.events.push(Event::DocumentEnd);
+        self
+    }
+}
+
+impl IntoEvents for StreamEvents {
+    fn into_events(mut self) -> Vec<Event> {
+        self.events.push(Event::StreamEnd);
+        self.events
+    }
+}
+
+/// Builds the events of a single document. Directives and blank lines must
+/// be added before the root node.
+#[derive(Debug)]
+pub struct DocEvents<'a> {
+    events: &'a mut Vec<Event>,
+}
+
+impl<'a> DocEvents<'a> {
+    /// Adds a directive to the document.
+    pub fn directive(self, directive: Directive) -> Self {
+        self.events.push(Event::Directive(directive));
+        self
+    }
+
+    /// Adds `n` blank lines before the document.
+    pub fn blank_lines(self, n: usize) -> Self {
+        self.events.push(Event::BlankLines(n));
+        self
+    }
+
+    /// Adds a comment before the document.
+    pub fn comment(self, text: impl Into<String>) -> Self {
+        self.events.push(Event::Comment(text.into()));
+        self
+    }
+
+    /// Starts the document and returns the writer of its root node.
+    pub fn root(self) -> NodeEvents<'a> {
+        self.events.push(Event::DocumentStart);
+        NodeEvents::child(self.events)
+    }
+
+    /// Writes a document without a root node.
+    pub fn empty(self) -> Written {
+        self.events.push(Event::DocumentStart);
+        Written(())
+    }
+}
+
+/// Writes exactly one node: a scalar, an alias or a collection.
+#[derive(Debug)]
+pub struct NodeEvents<'a> {
+    events: &'a mut Vec<Event>,
+    tag: Option<String>,
+    anchor: Option<String>,
+}
+
+impl NodeEvents<'_> {
+    /// Sets an explicit tag of the node, e.g. `!Ref`. Tags of aliases are
+    /// ignored.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Sets the anchor of the node, which can be referred to by
+    /// [`NodeEvents::alias`]. Anchors of aliases are ignored.
+    pub fn anchor(mut self, anchor: impl Into<String>) -> Self {
+        self.anchor = Some(anchor.into());
+        self
+    }
+
+    /// Writes a scalar with the given `tag`. An explicit tag set by
+    /// [`NodeEvents::tag`] takes precedence.
+    pub fn scalar(self, value: impl Into<String>, tag: Option<String>) -> Written {
+        self.events.push(Event::Scalar {
+            value: value.into(),
+            tag: self.tag.or(tag),
+            anchor: self.anchor,
+            style: None,
+        });
+
+        Written(())
+    }
+
+    fn core_scalar(self, value: impl Into<String>, name: &str) -> Written {
+        self.scalar(value, Some(format!("{CORE_TAG_PREFIX}{name}")))
+    }
+
+    pub fn string(self, value: impl Into<String>) -> Written {
+        self.core_scalar(value, "str")
+    }
+
+    pub fn null(self) -> Written {
+        self.core_scalar("null", "null")
+    }
+
+    pub fn boolean(self, value: bool) -> Written {
+        self.core_scalar(value.to_string(), "bool")
+    }
+
+    pub fn integer(self, value: i64) -> Written {
+        self.core_scalar(value.to_string(), "int")
+    }
+
+    pub fn float(self, value: impl Into<Float>) -> Written {
+        self.core_scalar(value.into().to_string(), "float")
+    }
+
+    /// Writes binary data, see [`Node::Binary`].
+    pub fn binary(self, value: &[u8]) -> Written {
+        let tag = Some(BINARY_TAG.to_string());
+        self.scalar(BASE64.encode(value), tag)
+    }
+
+    /// Writes an alias of the `anchor`.
+    pub fn alias(self, anchor: impl Into<String>) -> Written {
+        self.events.push(Event::Alias(anchor.into()));
+        Written(())
+    }
+
+    /// Writes the events of a complete `node`.
+    pub fn node(self, node: Node) -> Written {
+        let node = match self.tag {
+            Some(tag) => Node::Tagged(Box::new(Tagged::new(tag, node))),
+            None => node,
+        };
+
+        let start = self.events.len();
+        self.events.extend(node.into_events());
+
+        if let Some(anchor) = self.anchor {
+            if let Event::Scalar {
+                anchor: node_anchor,
+                ..
+            }
+            | Event::SequenceStart {
+                anchor: node_anchor,
+                ..
+            }
+            | Event::MappingStart {
+                anchor: node_anchor,
+                ..
+            } = &mut self.events[start]
+            {
+                *node_anchor = Some(anchor);
+            }
+        }
+
+        Written(())
+    }
+
+    /// Writes a mapping whose pairs are added by `f`.
+    pub fn mapping<F>(self, f: F) -> Written
+    where
+        F: FnOnce(&mut MappingEvents<'_>),
+    {
+        self.events.push(Event::MappingStart {
+            tag: self.tag,
+            anchor: self.anchor,
+            style: None,
+        });
+        f(&mut MappingEvents {
+            events: self.events,
+        });
+        self.events.push(Event::MappingEnd);
+
+        Written(())
+    }
+
+    /// Writes a sequence whose items are added by `f`.
+    pub fn sequence<F>(self, f: F) -> Written
+    where
+        F: FnOnce(&mut SequenceEvents<'_>),
+    {
+        self.events.push(Event::SequenceStart {
+            tag: self.tag,
+            anchor: self.anchor,
+            style: None,
+        });
+        f(&mut SequenceEvents {
+            events: self.events,
+        });
+        self.events.push(Event::SequenceEnd);
+
+        Written(())
+    }
+
+    fn child(events: &mut Vec<Event>) -> NodeEvents<'_> {
+        NodeEvents {
+            events,
+            tag: None,
+            anchor: None,
+        }
+    }
+}
+
+/// Adds the pairs of a mapping. Each pair consists of exactly one key and
+/// one value.
+#[derive(Debug)]
+pub struct MappingEvents<'a> {
+    events: &'a mut Vec<Event>,
+}
+
+impl MappingEvents<'_> {
+    /// Adds a pair whose key and value are written by `key` and `value`.
+    pub fn pair<K, V>(&mut self, key: K, value: V) -> &mut Self
+    where
+        K: FnOnce(NodeEvents<'_>) -> Written,
+        V: FnOnce(NodeEvents<'_>) -> Written,
+    {
+        let _ = key(NodeEvents::child(self.events));
+        let _ = value(NodeEvents::child(self.events));
+        self
+    }
+
+    /// Adds a pair with the string `key`.
+    pub fn entry<V>(&mut self, key: impl Into<String>, value: V) -> &mut Self
+    where
+        V: FnOnce(NodeEvents<'_>) -> Written,
+    {
+        self.pair(|node| node.string(key), value)
+    }
+
+    /// Adds `n` blank lines before the next pair.
+    pub fn blank_lines(&mut self, n: usize) -> &mut Self {
+        self.events.push(Event::BlankLines(n));
+        self
+    }
+
+    /// Writes the key of the next pair using the explicit key indicator.
+    /// Call this after the comments of the pair.
+    pub fn explicit_key(&mut self) -> &mut Self {
+        self.events.push(Event::ExplicitKey);
+        self
+    }
+
+    /// Adds a comment on its own line before the next pair.
+    pub fn comment(&mut self, text: impl Into<String>) -> &mut Self {
+        self.events.push(Event::Comment(text.into()));
+        self
+    }
+}
+
+/// Adds the items of a sequence.
+#[derive(Debug)]
+pub struct SequenceEvents<'a> {
+    events: &'a mut Vec<Event>,
+}
+
+impl SequenceEvents<'_> {
+    /// Adds an item written by `f`.
+    pub fn item<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(NodeEvents<'_>) -> Written,
+    {
+        let _ = f(NodeEvents::child(self.events));
+        self
+    }
+
+    /// Adds a comment on its own line before the next item.
+    pub fn comment(&mut self, text: impl Into<String>) -> &mut Self {
+        self.events.push(Event::Comment(text.into()));
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Document, Mapping, Sequence, Stream};
+
+    use super::*;
+
+    #[test]
+    fn matches_node_events() {
+        let node = Node::Mapping(Mapping::from([
+            (Node::String("name".into()), Node::String("web".into())),
+            (
+                Node::String("ports".into()),
+                Node::Sequence(Sequence::from([Node::Integer(80), Node::Null])),
+            ),
+            (
+                Node::String("bucket".into()),
+                Node::Tagged(Box::new(Tagged::new(
+                    "!Sub",
+                    Node::Sequence(Sequence::from([Node::Boolean(true)])),
+                ))),
+            ),
+        ]));
+
+        let mut document = Document::new();
+        document.push_directive(Directive::yaml_1_2());
+        document.push_node(node);
+
+        let events = StreamEvents::new()
+            .document(|doc| {
+                doc.directive(Directive::yaml_1_2())
+                    .root()
+                    .mapping(|mapping| {
+                        mapping
+                            .entry("name", |node| node.string("web"))
+                            .entry("ports", |node| {
+                                node.sequence(|ports| {
+                                    ports.item(|node| node.integer(80)).item(|node| node.null());
+                                })
+                            })
+                            .entry("bucket", |node| {
+                                node.tag("!Sub").sequence(|items| {
+                                    items.item(|node| node.boolean(true));
+                                })
+                            });
+                    })
+            })
+            .document(|doc| doc.empty())
+            .into_events();
+
+        let expected = Stream::from_documents(vec![document, Document::new()]).into_events();
+        assert_eq!(events, expected);
+    }
+}
@@ -0,0 +1,171 @@
+//! Tabular export of sequences of mappings, e.g. for spreadsheets.
+//!
+//! Each item of the sequence becomes a row and each column selects a value
+//! of the item by a dot-separated path, see [`Node::get_path`]. Missing
+//! values and `null`s result in empty cells, nested collections are written
+//! in flow style.
+//!
+//! ```
+//! use yaml_ast::{export, yaml};
+//!
+//! let hosts = yaml!([
+//!     {name: "web-1", ip: "10.0.0.1", labels: {zone: "a"}},
+//!     {name: "web, 2", labels: {zone: "b"}}
+//! ]);
+//! let hosts = hosts.try_into_sequence().unwrap();
+//!
+//! assert_eq!(
+//!     export::to_csv(&hosts, &["name", "ip", "labels.zone"]).unwrap(),
+//!     "name,ip,labels.zone\r\nweb-1,10.0.0.1,a\r\n\"web, 2\",,b\r\n"
+//! );
+//! ```
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use snafu::Snafu;
+
+use crate::{cst::render_flow, Node, Sequence};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("expected a mapping at index {index}, found a {found}"))]
+    NotAMapping { index: usize, found: String },
+}
+
+/// Exports the `sequence` as comma-separated values with a header row as
+/// defined in RFC 4180. Cells containing commas, quotes or line breaks are
+/// quoted. If no `columns` are provided, the keys of all items are used in
+/// the order of their first occurrence.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc4180>
+pub fn to_csv(sequence: &Sequence, columns: &[&str]) -> Result<String, Error> {
+    to_table(sequence, columns, |row| {
+        let cells: Vec<_> = row.iter().map(|cell| csv_cell(cell)).collect();
+        cells.join(",") + "\r\n"
+    })
+}
+
+/// Exports the `sequence` as tab-separated values with a header row. Tabs,
+/// line breaks and backslashes in cells are escaped as `\t`, `\n`, `\r` and
+/// `\\`. See [`to_csv`] for the selection of the columns.
+pub fn to_tsv(sequence: &Sequence, columns: &[&str]) -> Result<String, Error> {
+    to_table(sequence, columns, |row| {
+        let cells: Vec<_> = row.iter().map(|cell| tsv_cell(cell)).collect();
+        cells.join("\t") + "\n"
+    })
+}
+
+fn to_table<'a>(
+    sequence: &'a Sequence,
+    columns: &[&str],
+    format_row: impl Fn(&[String]) -> String,
+) -> Result<String, Error> {
+    let mut keys: Vec<String> = columns.iter().map(ToString::to_string).collect();
+
+    for (index, item) in sequence.iter().enumerate() {
+        let Some(mapping) = item.untagged().as_mapping() else {
+            return NotAMappingSnafu {
+                index,
+                found: item.description(),
+            }
+            .fail();
+        };
+
+        if columns.is_empty() {
+            for pair in mapping.pairs() {
+                let key = cell(&pair.key);
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+    }
+
+    // Keys collected from the items are looked up as they are, as they may
+    // contain dots
+    let value = |item: &'a Node, key: &str| -> Option<&'a Node> {
+        match columns.is_empty() {
+            true => item
+                .untagged()
+                .as_mapping()?
+                .pairs()
+                .find(|pair| cell(&pair.key) == key)
+                .map(|pair| &pair.value),
+            false => item.get_path(key),
+        }
+    };
+
+    let mut table = format_row(&keys);
+
+    for item in sequence {
+        let row: Vec<_> = keys
+            .iter()
+            .map(|key| value(item, key).map(cell).unwrap_or_default())
+            .collect();
+
+        table.push_str(&format_row(&row));
+    }
+
+    Ok(table)
+}
+
+/// Returns the text of a cell containing the `node`.
+fn cell(node: &Node) -> String {
+    match node {
+        Node::String(s) => s.clone(),
+        Node::Null => String::new(),
+        Node::Boolean(b) => b.to_string(),
+        Node::Integer(i) => i.to_string(),
+        Node::BigInteger(i) => i.to_string(),
+        Node::FloatingPoint(f) => f.to_string(),
+        Node::Binary(b) => BASE64.encode(b),
+        Node::Timestamp(t) => t.to_string(),
+        Node::Tagged(tagged) => cell(&tagged.node),
+        Node::Mapping(_) | Node::Sequence(_) => render_flow(node),
+    }
+}
+
+fn csv_cell(cell: &str) -> String {
+    match cell.contains([',', '"', '\r', '\n']) {
+        true => format!("\"{}\"", cell.replace('"', "\"\"")),
+        false => cell.into(),
+    }
+}
+
+fn tsv_cell(cell: &str) -> String {
+    cell.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    #[test]
+    fn tables() {
+        let items = yaml!([
+            {name: "a", ports: [80, 443], note: "say \"hi\"\n"},
+            {name: "b", enabled: false, note: null, "app.kubernetes.io/name": "b"}
+        ])
+        .try_into_sequence()
+        .unwrap();
+
+        assert_eq!(
+            to_csv(&items, &[]).unwrap(),
+            "name,ports,note,enabled,app.kubernetes.io/name\r\na,\"[80, 443]\",\"say \"\"hi\"\"\n\",,\r\nb,,,false,b\r\n"
+        );
+        assert_eq!(
+            to_tsv(&items, &["name", "note"]).unwrap(),
+            "name\tnote\na\tsay \"hi\"\\n\nb\t\n"
+        );
+
+        assert_eq!(
+            to_csv(&vec![yaml!({a: 1}), yaml!([1])], &["a"]),
+            Err(Error::NotAMapping {
+                index: 1,
+                found: "sequence".into()
+            })
+        );
+    }
+}
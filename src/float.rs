@@ -0,0 +1,224 @@
+//! Floating point numbers.
+//!
+//! A [`Float`] stores the numeric value as an [`f64`]. Floats parsed from
+//! YAML additionally keep their original text, like `1.50` or `1e3`, which
+//! is emitted unchanged unless another [`FloatFormat`] is requested.
+//!
+//! ```
+//! use yaml_ast::float::{Float, FloatFormat};
+//!
+//! let float: Float = "1.50".parse().unwrap();
+//! assert_eq!(float.value(), 1.5);
+//! assert_eq!(float.to_string(), "1.50");
+//! assert_eq!(float.format(FloatFormat::Shortest), "1.5");
+//! assert_eq!(float.format(FloatFormat::Fixed(3)), "1.500");
+//!
+//! assert_eq!(Float::from(f64::NEG_INFINITY).to_string(), "-.inf");
+//! ```
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use snafu::{OptionExt, Snafu};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("invalid float {value:?}"))]
+    InvalidFloat { value: String },
+}
+
+/// The textual format of emitted floats. Special values are always written
+/// as `.nan`, `.inf` and `-.inf`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// Writes the original text of parsed floats and the shortest
+    /// representation of all other floats.
+    #[default]
+    Preserve,
+
+    /// Writes the shortest representation which parses back to the same
+    /// value, e.g. `1.5` or `1e-7`.
+    Shortest,
+
+    /// Writes the given number of fractional digits, e.g. `1.50`.
+    Fixed(usize),
+
+    /// Writes the value in scientific notation, e.g. `1.5e3`.
+    Scientific,
+}
+
+/// A floating point number with its optional original text.
+///
+/// Floats are compared by their numeric value: `.nan` is equal to itself
+/// and `-0.0` is equal to `0.0`. The text is ignored.
+#[derive(Clone, Debug)]
+pub struct Float {
+    value: f64,
+    text: Option<String>,
+}
+
+impl Float {
+    /// Creates a float without original text.
+    pub fn new(value: f64) -> Self {
+        Self { value, text: None }
+    }
+
+    /// Returns the numeric value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the original text, if the float was parsed.
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Removes the original text, which is then formatted as
+    /// [`FloatFormat::Shortest`].
+    pub fn without_text(mut self) -> Self {
+        self.text = None;
+        self
+    }
+
+    /// Returns the float in the given `format`.
+    pub fn format(&self, format: FloatFormat) -> String {
+        let value = self.value;
+
+        if value.is_nan() {
+            return ".nan".into();
+        }
+
+        if value.is_infinite() {
+            return if value > 0.0 { ".inf" } else { "-.inf" }.into();
+        }
+
+        match (format, &self.text) {
+            (FloatFormat::Preserve, Some(text)) => text.clone(),
+            (FloatFormat::Preserve | FloatFormat::Shortest, _) => format!("{value:?}"),
+            (FloatFormat::Fixed(precision), _) => format!("{value:.precision$}"),
+            (FloatFormat::Scientific, _) => format!("{value:e}"),
+        }
+    }
+
+    /// The canonical value used for comparisons, with all NaNs and both
+    /// zeros unified.
+    fn key(&self) -> f64 {
+        match self.value {
+            value if value.is_nan() => f64::NAN,
+            0.0 => 0.0,
+            value => value,
+        }
+    }
+}
+
+impl fmt::Display for Float {
+    /// Formats the float as [`FloatFormat::Preserve`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(FloatFormat::Preserve))
+    }
+}
+
+impl FromStr for Float {
+    type Err = Error;
+
+    /// Parses a float, including the special values `.inf`, `-.inf` and
+    /// `.nan` in all spellings allowed by the Core schema. The text is kept.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = parse_float(s).context(InvalidFloatSnafu { value: s })?;
+
+        Ok(Self {
+            value,
+            text: Some(s.into()),
+        })
+    }
+}
+
+impl From<f64> for Float {
+    fn from(value: f64) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<f32> for Float {
+    /// Keeps the shortest representation of the [`f32`] as text, because
+    /// widening it to an [`f64`] adds digits, e.g. `1.1` becomes
+    /// `1.100000023841858`.
+    fn from(value: f32) -> Self {
+        Self {
+            value: value.into(),
+            text: value.is_finite().then(|| format!("{value:?}")),
+        }
+    }
+}
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Float {}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    /// Floats are totally ordered with `.nan` sorted last.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().total_cmp(&other.key())
+    }
+}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().to_bits().hash(state);
+    }
+}
+
+/// Parses the textual representation of a YAML float, including the special
+/// values `.inf`, `-.inf` and `.nan` in all spellings allowed by the Core
+/// schema.
+///
+/// See <https://yaml.org/spec/1.2.2/#10214-floating-point>
+pub(crate) fn parse_float(text: &str) -> Option<f64> {
+    match text {
+        ".nan" | ".NaN" | ".NAN" => Some(f64::NAN),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => Some(f64::INFINITY),
+        "-.inf" | "-.Inf" | "-.INF" => Some(f64::NEG_INFINITY),
+        // Rust also accepts `inf` and `NaN`, which aren't YAML floats
+        text if text
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b)) =>
+        {
+            text.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format() {
+        let float: Float = "1.5e3".parse().unwrap();
+        assert_eq!(float.to_string(), "1.5e3");
+        assert_eq!(float.format(FloatFormat::Shortest), "1500.0");
+        assert_eq!(float.format(FloatFormat::Fixed(1)), "1500.0");
+        assert_eq!(float.format(FloatFormat::Scientific), "1.5e3");
+
+        let nan: Float = ".NaN".parse().unwrap();
+        assert_eq!(nan.to_string(), ".nan");
+        assert_eq!(nan, Float::new(f64::NAN));
+        assert_eq!(Float::from(1.1f32).to_string(), "1.1");
+        assert_eq!(Float::new(1e-7).to_string(), "1e-7");
+        assert!("1.5.1".parse::<Float>().is_err());
+    }
+}
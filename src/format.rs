@@ -0,0 +1,153 @@
+//! Reformatting of YAML text, like `yamlfmt`.
+//!
+//! [`format`] parses the input and emits it again using the given
+//! [`EmitterOptions`], which normalizes the indentation, the quoting of
+//! scalars and the spacing around indicators. Comments, blank lines, the
+//! order of keys and the styles of flow collections and block scalars are
+//! kept. The output is parsed again to guarantee that it has the same
+//! meaning as the input.
+//!
+//! ```
+//! use yaml_ast::{emitter::EmitterOptions, format};
+//!
+//! let input = "# Ports\nports:   [80,   443]\nname:    'web'   # inline\nrun: |\n  make\n";
+//! let options = EmitterOptions::builder().indent_size(4).build().unwrap();
+//!
+//! assert_eq!(
+//!     format::format(input, options).unwrap(),
+//!     "---\n# Ports\nports: [80, 443]\nname: web # inline\nrun: |\n    make\n...\n"
+//! );
+//! ```
+//!
+//! The line ending of the input isn't kept by default, use
+//! [`LineEnding::detect`](crate::emitter::LineEnding::detect) to preserve
+//! it.
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::{
+    cst::{self, Cst, ResolveOptions},
+    emitter::{self, Emitter, EmitterOptions},
+    events::IntoEvents,
+    Stream,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("failed to parse the input"))]
+    Parse { source: cst::Error },
+
+    #[snafu(display("failed to emit the formatted output"))]
+    Emit { source: emitter::Error },
+
+    #[snafu(display("failed to parse the formatted output"))]
+    Reparse { source: cst::Error },
+
+    #[snafu(display(
+        "the formatted output of document {document} has a different meaning than the input"
+    ))]
+    ChangedMeaning { document: usize },
+}
+
+/// Formats the YAML `input` using the `options`, resolving it with the
+/// default [`ResolveOptions`] which [keep the styles](ResolveOptions::keep_styles)
+/// of the input. See [`format_with`].
+pub fn format(input: &str, options: EmitterOptions) -> Result<String, Error> {
+    let resolve = ResolveOptions::builder().keep_styles(true).build();
+    format_with(input, &resolve, options)
+}
+
+/// Formats the YAML `input`, resolved using `resolve`, with the emitter
+/// `options` and returns the normalized text. Flow collections and block
+/// scalars are only kept if [`ResolveOptions::keep_styles`] is set.
+///
+/// Fails with [`Error::ChangedMeaning`] if the output doesn't resolve to
+/// the same nodes as the input, e.g. because the
+/// [`EmitterOptions::unrepresentable`] policy drops constructs.
+pub fn format_with(
+    input: &str,
+    resolve: &ResolveOptions,
+    options: EmitterOptions,
+) -> Result<String, Error> {
+    let stream = read(input, resolve).context(ParseSnafu)?;
+
+    let mut output = String::new();
+    Emitter::new(stream.clone().into_events(), options)
+        .emit(&mut output)
+        .context(EmitSnafu)?;
+
+    let formatted = read(&output, resolve).context(ReparseSnafu)?;
+    ensure!(
+        formatted.len() == stream.len(),
+        ChangedMeaningSnafu {
+            document: formatted.len().min(stream.len()),
+        }
+    );
+
+    for (document, (input, output)) in stream.iter().zip(formatted.iter()).enumerate() {
+        ensure!(
+            input.nodes == output.nodes,
+            ChangedMeaningSnafu { document }
+        );
+    }
+
+    Ok(output)
+}
+
+fn read(input: &str, resolve: &ResolveOptions) -> Result<Stream, cst::Error> {
+    Cst::parse(input)
+        .context(cst::ParseSnafu)?
+        .to_stream_with(resolve)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::emitter::{Handling, UnrepresentablePolicy};
+
+    #[test]
+    fn format() {
+        let input = "a:   1\n\n\n# b\nb: {c:   [x,y]}\n---\n- 'yes'\n";
+        let options = EmitterOptions::builder()
+            .max_blank_lines(Some(1))
+            .build()
+            .unwrap();
+        let output = super::format(input, options).unwrap();
+
+        assert_eq!(
+            output,
+            "---\na: 1\n\n# b\nb: {c: [x, y]}\n...\n---\n- yes\n...\n"
+        );
+        assert_eq!(
+            super::format(&output, EmitterOptions::default()).unwrap(),
+            output
+        );
+
+        let options = EmitterOptions::builder()
+            .unrepresentable(UnrepresentablePolicy {
+                tags: Handling::Drop,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        assert!(matches!(
+            super::format("a: 1\n---\nb: !Ref c\n", options),
+            Err(Error::ChangedMeaning { document: 1 })
+        ));
+    }
+
+    #[test]
+    fn styles() {
+        let input = "jobs:\n    test:\n        steps:\n            - run: |\n                  cargo build\n                  cargo test\n              with: {toolchain:   stable,  components: [clippy,rustfmt]}\n            - run: >\n                  echo\n                  done\n";
+
+        assert_eq!(
+            super::format(input, EmitterOptions::default()).unwrap(),
+            "---\njobs:\n  test:\n    steps:\n      - run: |\n          cargo build\n          cargo test\n        with: {toolchain: stable, components: [clippy, rustfmt]}\n      - run: >\n          echo done\n...\n"
+        );
+
+        // Without the styles of the input, the emitter chooses them
+        assert_eq!(
+            format_with(input, &ResolveOptions::default(), EmitterOptions::default()).unwrap(),
+            "---\njobs:\n  test:\n    steps:\n      - run: \"cargo build\\ncargo test\\n\"\n        with:\n          toolchain: stable\n          components:\n            - clippy\n            - rustfmt\n      - run: \"echo done\\n\"\n...\n"
+        );
+    }
+}
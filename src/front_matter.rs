@@ -0,0 +1,184 @@
+//! Front matter of Markdown and other text files, like used by static site
+//! generators.
+//!
+//! The front matter is a YAML document at the very start of the content,
+//! delimited by `---` lines. [`extract`] parses it into a [`Document`] and
+//! returns the remaining body, [`render`] emits a [`Document`] as front
+//! matter prepended to a body.
+//!
+//! ```
+//! use yaml_ast::{emitter::EmitterOptions, front_matter};
+//!
+//! let content = "---\ntitle: Hello\ntags: [yaml]\n---\n# Hello\n";
+//! let mut front_matter = front_matter::extract(content).unwrap().unwrap();
+//! assert_eq!(front_matter.body, "# Hello\n");
+//!
+//! front_matter.document.nodes[0]
+//!     .as_mapping_mut()
+//!     .unwrap()
+//!     .push_pair("draft".into(), true.into());
+//!
+//! let output = front_matter::render(
+//!     &front_matter.document,
+//!     front_matter.body,
+//!     EmitterOptions::default(),
+//! )
+//! .unwrap();
+//! assert_eq!(
+//!     output,
+//!     "---\ntitle: Hello\ntags:\n  - yaml\ndraft: true\n---\n# Hello\n"
+//! );
+//! ```
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use crate::{
+    cst,
+    emitter::{self, Emitter, EmitterOptions, LineEnding},
+    events::IntoEvents,
+    Document, Stream,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("the front matter isn't closed by a `---` or `...` line"))]
+    Unterminated,
+
+    #[snafu(display("failed to parse the front matter"))]
+    Parse { source: cst::Error },
+
+    #[snafu(display("failed to emit the front matter"))]
+    Emit { source: emitter::Error },
+}
+
+/// The front matter of some content, split off its body.
+#[derive(Clone, Debug)]
+pub struct FrontMatter<'a> {
+    pub document: Document,
+
+    /// The content after the closing delimiter.
+    pub body: &'a str,
+}
+
+/// Returns `true` if `line` is the delimiter `marker`, ignoring trailing
+/// whitespace and carriage returns.
+fn is_delimiter(line: &str, marker: &str) -> bool {
+    line.trim_end_matches([' ', '\t', '\r']) == marker
+}
+
+/// Extracts the front matter from the start of `content`. Returns `None` if
+/// the content doesn't start with a `---` line. The front matter ends at the
+/// next `---` or `...` line, an empty front matter results in an empty
+/// document.
+pub fn extract(content: &str) -> Result<Option<FrontMatter<'_>>, Error> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+
+    let Some((first, rest)) = content.split_once('\n') else {
+        return Ok(None);
+    };
+    if !is_delimiter(first, "---") {
+        return Ok(None);
+    }
+
+    let mut offset = 0;
+    let (end, body) = rest
+        .split_inclusive('\n')
+        .find_map(|line| {
+            let start = offset;
+            offset += line.len();
+
+            let line = line.strip_suffix('\n').unwrap_or(line);
+            (is_delimiter(line, "---") || is_delimiter(line, "..."))
+                .then(|| (start, &rest[offset..]))
+        })
+        .context(UnterminatedSnafu)?;
+
+    let document = rest[..end].parse().context(ParseSnafu)?;
+    Ok(Some(FrontMatter { document, body }))
+}
+
+/// Emits `document` with the `options` as front matter and appends the
+/// `body`. Directives of the document are dropped and comments before the
+/// document are moved after the opening delimiter, as nothing may precede
+/// it. A byte order mark is never written.
+pub fn render(document: &Document, body: &str, options: EmitterOptions) -> Result<String, Error> {
+    let line_break = match options.line_ending {
+        LineEnding::Lf => "\n",
+        LineEnding::CrLf => "\r\n",
+    };
+    let start = format!("---{line_break}");
+
+    let mut document = document.clone();
+    document.directives.clear();
+    document.blank_lines = 0;
+
+    let options = EmitterOptions {
+        byte_order_mark: false,
+        ..options
+    };
+
+    let mut output = String::new();
+    Emitter::new(
+        Stream::from_documents(vec![document]).into_events(),
+        options,
+    )
+    .emit(&mut output)
+    .context(EmitSnafu)?;
+
+    // Only comments precede the start marker, now that the directives are
+    // gone.
+    let (comments, yaml) = match output.find(&start) {
+        Some(index) => output.split_at(index),
+        None => ("", output.as_str()),
+    };
+    let yaml = yaml.strip_prefix(&start).unwrap_or(yaml);
+    let yaml = yaml
+        .strip_suffix(&format!("...{line_break}"))
+        .unwrap_or(yaml);
+
+    Ok(format!("{start}{comments}{yaml}{start}{body}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn extract() {
+        assert!(super::extract("# Title\n---\na: 1\n---\n")
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            super::extract("---\na: 1\n"),
+            Err(Error::Unterminated)
+        ));
+
+        let front_matter = super::extract("\u{feff}---\r\n---\r\nbody")
+            .unwrap()
+            .unwrap();
+        assert!(front_matter.document.nodes.is_empty());
+        assert_eq!(front_matter.body, "body");
+
+        let front_matter = super::extract("--- \na: [1\n...\n").unwrap_err();
+        assert!(matches!(front_matter, Error::Parse { .. }));
+    }
+
+    #[test]
+    fn render() {
+        let mut document: Document = "%YAML 1.2\n---\na: 1 # one\n".parse().unwrap();
+        document.comments.push("generated".into());
+
+        let options = EmitterOptions::builder()
+            .line_ending(LineEnding::CrLf)
+            .build()
+            .unwrap();
+        let output = super::render(&document, "body\r\n", options).unwrap();
+        assert_eq!(
+            output,
+            "---\r\n# generated\r\na: 1 # one\r\n---\r\nbody\r\n"
+        );
+
+        let front_matter = super::extract(&output).unwrap().unwrap();
+        assert_eq!(front_matter.document.nodes, document.nodes);
+        assert_eq!(front_matter.body, "body\r\n");
+    }
+}
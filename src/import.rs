@@ -0,0 +1,436 @@
+//! Import of tabular data, like CSV files, as sequences of mappings.
+//!
+//! This is the inverse of the [`export`](crate::export) module: the first
+//! row names the columns, each further row becomes a mapping. Columns are
+//! mapped to dot-separated paths, which create nested mappings, and cells
+//! are typed using the Core schema, e.g. `80` becomes an integer and `true`
+//! a boolean. Empty cells are omitted.
+//!
+//! ```
+//! use yaml_ast::{import::{self, ImportOptions}, yaml, Node};
+//!
+//! let csv = "name,zone,port\nweb-1,a,80\n\"web, 2\",b,\n";
+//! let options = ImportOptions::builder()
+//!     .column("name", "name")
+//!     .column("zone", "labels.zone")
+//!     .column("port", "port")
+//!     .build();
+//!
+//! assert_eq!(
+//!     Node::Sequence(import::from_csv(csv.as_bytes(), &options).unwrap()),
+//!     yaml!([
+//!         {name: "web-1", labels: {zone: "a"}, port: 80},
+//!         {name: "web, 2", labels: {zone: "b"}}
+//!     ])
+//! );
+//! ```
+use std::io::Read;
+
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::{
+    events::PathSegment, schema::resolve_core, Document, ItemComments, Mapping, Node, Sequence,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("failed to read the table"))]
+    Read { source: std::io::Error },
+
+    #[snafu(display("the quoted cell starting on line {line} is not terminated"))]
+    UnterminatedQuote { line: usize },
+
+    #[snafu(display("expected {expected} cells on line {line}, found {found}"))]
+    CellCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+
+    #[snafu(display("the table has no column {column:?}"))]
+    UnknownColumn { column: String },
+
+    #[snafu(display("the path {path:?} conflicts with another column"))]
+    PathConflict { path: String },
+}
+
+/// The format of the table read by [`read_document`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Comma-separated values as defined in RFC 4180. Lines may end with
+    /// `\r\n` or `\n`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc4180>
+    Csv,
+
+    /// Tab-separated values, where tabs, line breaks and backslashes in
+    /// cells are escaped as `\t`, `\n`, `\r` and `\\`.
+    Tsv,
+}
+
+/// These options control how the columns of a table are imported.
+///
+/// It provides a builder to selectively customize individual settings. If no
+/// customization is required, use [`ImportOptions::default()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// The columns to import with the dot-separated path of their value in
+    /// each mapping. If empty, all columns are imported using their header
+    /// as the key.
+    pub columns: Vec<(String, String)>,
+
+    /// The column whose cells are used as the leading comments of the
+    /// items, see [`read_document`]. It is not imported as a value.
+    pub comment_column: Option<String>,
+
+    /// Imports all cells as strings instead of typing them using the Core
+    /// schema.
+    pub strings_only: bool,
+}
+
+impl ImportOptions {
+    pub fn builder() -> ImportOptionsBuilder {
+        ImportOptionsBuilder::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportOptionsBuilder {
+    columns: Vec<(String, String)>,
+    comment_column: Option<String>,
+    strings_only: bool,
+}
+
+impl ImportOptionsBuilder {
+    /// Imports the column with the `header` at the `path`. See
+    /// [`ImportOptions::columns`].
+    pub fn column(mut self, header: impl Into<String>, path: impl Into<String>) -> Self {
+        self.columns.push((header.into(), path.into()));
+        self
+    }
+
+    /// See [`ImportOptions::comment_column`].
+    pub fn comment_column(mut self, header: impl Into<String>) -> Self {
+        self.comment_column = Some(header.into());
+        self
+    }
+
+    /// See [`ImportOptions::strings_only`].
+    pub fn strings_only(mut self, strings_only: bool) -> Self {
+        self.strings_only = strings_only;
+        self
+    }
+
+    pub fn build(self) -> ImportOptions {
+        ImportOptions {
+            columns: self.columns,
+            comment_column: self.comment_column,
+            strings_only: self.strings_only,
+        }
+    }
+}
+
+/// Reads comma-separated values into a sequence of mappings. See the
+/// [module](self) documentation for details.
+pub fn from_csv(reader: impl Read, options: &ImportOptions) -> Result<Sequence, Error> {
+    read_rows(reader, TableFormat::Csv, options).map(|(items, _)| items)
+}
+
+/// Reads tab-separated values into a sequence of mappings. See the
+/// [module](self) documentation for details.
+pub fn from_tsv(reader: impl Read, options: &ImportOptions) -> Result<Sequence, Error> {
+    read_rows(reader, TableFormat::Tsv, options).map(|(items, _)| items)
+}
+
+/// Reads the table into a document whose root is the sequence of mappings.
+/// The cells of the [`ImportOptions::comment_column`] become the leading
+/// comments of the items, see [`Document::item_comments`].
+pub fn read_document(
+    reader: impl Read,
+    format: TableFormat,
+    options: &ImportOptions,
+) -> Result<Document, Error> {
+    let (items, comments) = read_rows(reader, format, options)?;
+    let mut document = Document::new();
+
+    for (index, comment) in comments.into_iter().enumerate() {
+        if let Some(comment) = comment {
+            document.item_comments.insert(
+                vec![PathSegment::Index(index)],
+                ItemComments {
+                    comments: comment.lines().map(ToString::to_string).collect(),
+                    comment: None,
+                },
+            );
+        }
+    }
+
+    document.push_node(Node::Sequence(items));
+    Ok(document)
+}
+
+/// Returns the items and the comment of each item.
+fn read_rows(
+    mut reader: impl Read,
+    format: TableFormat,
+    options: &ImportOptions,
+) -> Result<(Sequence, Vec<Option<String>>), Error> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input).context(ReadSnafu)?;
+
+    let rows = match format {
+        TableFormat::Csv => csv_rows(&input)?,
+        TableFormat::Tsv => tsv_rows(&input),
+    };
+
+    let mut rows = rows.into_iter();
+    let Some((_, headers)) = rows.next() else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+
+    let position = |column: &str| {
+        headers
+            .iter()
+            .position(|header| header == column)
+            .context(UnknownColumnSnafu { column })
+    };
+
+    // The index of each imported column with the segments of its path
+    let columns: Vec<(usize, Vec<&str>)> = match options.columns.is_empty() {
+        true => headers
+            .iter()
+            .enumerate()
+            .filter(|(_, header)| Some(*header) != options.comment_column.as_ref())
+            .map(|(index, header)| (index, vec![header.as_str()]))
+            .collect(),
+        false => options
+            .columns
+            .iter()
+            .map(|(header, path)| Ok((position(header)?, path.split('.').collect())))
+            .collect::<Result<_, Error>>()?,
+    };
+
+    let comment_column = options
+        .comment_column
+        .as_deref()
+        .map(position)
+        .transpose()?;
+
+    let mut items = Vec::new();
+    let mut comments = Vec::new();
+
+    for (line, cells) in rows {
+        ensure!(
+            cells.len() == headers.len(),
+            CellCountSnafu {
+                line,
+                expected: headers.len(),
+                found: cells.len(),
+            }
+        );
+
+        let mut mapping = Mapping::new();
+
+        for (index, path) in &columns {
+            let cell = &cells[*index];
+            if cell.is_empty() {
+                continue;
+            }
+
+            let value = match options.strings_only {
+                true => Node::String(cell.clone()),
+                false => resolve_core(cell),
+            };
+
+            ensure!(
+                insert_path(&mut mapping, path, value),
+                PathConflictSnafu {
+                    path: path.join("."),
+                }
+            );
+        }
+
+        items.push(Node::Mapping(mapping));
+        comments.push(
+            comment_column
+                .map(|index| cells[index].clone())
+                .filter(|comment| !comment.is_empty()),
+        );
+    }
+
+    Ok((items, comments))
+}
+
+/// Inserts the `value` at the `path` of the `mapping`, creating nested
+/// mappings as needed. Returns `false` if the path is already taken.
+fn insert_path(mapping: &mut Mapping, path: &[&str], value: Node) -> bool {
+    let [key, rest @ ..] = path else {
+        return false;
+    };
+
+    let position = mapping.position(&Node::String((*key).into()));
+
+    match (position, rest.is_empty()) {
+        (None, true) => {
+            mapping.push_pair((*key).into(), value);
+            true
+        }
+        (None, false) => {
+            let mut nested = Mapping::new();
+            insert_path(&mut nested, rest, value);
+            mapping.push_pair((*key).into(), Node::Mapping(nested));
+            true
+        }
+        (Some(index), false) => match mapping.value_at_mut(index) {
+            Some(Node::Mapping(nested)) => insert_path(nested, rest, value),
+            _ => false,
+        },
+        (Some(_), true) => false,
+    }
+}
+
+/// Splits the CSV `input` into rows of cells, each with the line it starts
+/// on.
+fn csv_rows(input: &str) -> Result<Vec<(usize, Vec<String>)>, Error> {
+    let mut rows = Vec::new();
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start = line;
+        let mut cells = Vec::new();
+        let mut cell = String::new();
+
+        loop {
+            match chars.next() {
+                Some('"') if cell.is_empty() => loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            cell.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            cell.push(c);
+                        }
+                        None => return UnterminatedQuoteSnafu { line: start }.fail(),
+                    }
+                },
+                Some(',') => cells.push(std::mem::take(&mut cell)),
+                Some('\r') if chars.peek() == Some(&'\n') => {}
+                Some('\n') | None => {
+                    line += 1;
+                    cells.push(cell);
+                    break;
+                }
+                Some(c) => cell.push(c),
+            }
+        }
+
+        rows.push((start, cells));
+    }
+
+    Ok(rows)
+}
+
+/// Splits the TSV `input` into rows of unescaped cells, each with its line.
+fn tsv_rows(input: &str) -> Vec<(usize, Vec<String>)> {
+    let unescape = |cell: &str| {
+        let mut unescaped = String::with_capacity(cell.len());
+        let mut chars = cell.chars();
+
+        while let Some(c) = chars.next() {
+            match (c, chars.clone().next()) {
+                ('\\', Some(next @ ('t' | 'n' | 'r' | '\\'))) => {
+                    chars.next();
+                    unescaped.push(match next {
+                        't' => '\t',
+                        'n' => '\n',
+                        'r' => '\r',
+                        _ => '\\',
+                    });
+                }
+                (c, _) => unescaped.push(c),
+            }
+        }
+
+        unescaped
+    };
+
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.split('\t').map(unescape).collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{export, yaml};
+
+    #[test]
+    fn round_trip() {
+        let items = yaml!([
+            {name: "a", port: 80, note: "say \"hi\"\nbye", tls: true},
+            {name: "b", port: 8.5, tls: false}
+        ])
+        .try_into_sequence()
+        .unwrap();
+
+        let csv = export::to_csv(&items, &[]).unwrap();
+        assert_eq!(
+            from_csv(csv.as_bytes(), &ImportOptions::default()).unwrap(),
+            items
+        );
+
+        let tsv = export::to_tsv(&items, &[]).unwrap();
+        assert_eq!(
+            from_tsv(tsv.as_bytes(), &ImportOptions::default()).unwrap(),
+            items
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let csv = "host,note\nweb-1,Primary\nweb-2,\n";
+        let options = ImportOptions::builder()
+            .comment_column("note")
+            .strings_only(true)
+            .build();
+
+        let document = read_document(csv.as_bytes(), TableFormat::Csv, &options).unwrap();
+        assert_eq!(
+            document.to_string(),
+            "---\n# Primary\n- host: web-1\n- host: web-2\n...\n"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let options = ImportOptions::default();
+
+        assert!(matches!(
+            from_csv("a,b\n1\n".as_bytes(), &options),
+            Err(Error::CellCount {
+                line: 2,
+                expected: 2,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            from_csv("a\n\"1\n".as_bytes(), &options),
+            Err(Error::UnterminatedQuote { line: 2 })
+        ));
+
+        let options = ImportOptions::builder()
+            .column("a", "x")
+            .column("b", "x.y")
+            .build();
+        assert!(matches!(
+            from_csv("a,b\n1,2\n".as_bytes(), &options),
+            Err(Error::PathConflict { .. })
+        ));
+    }
+}
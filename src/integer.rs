@@ -0,0 +1,247 @@
+//! Arbitrary-precision integers.
+//!
+//! YAML integers are arbitrarily sized. Integers which fit into an [`i64`]
+//! are represented by [`Node::Integer`], all others by
+//! [`Node::BigInteger`]. Use [`Node::from`] with a [`BigInteger`] to pick the
+//! right variant automatically.
+//!
+//! ```
+//! use yaml_ast::{integer::BigInteger, Node};
+//!
+//! let big: BigInteger = "0xFFFFFFFFFFFFFFFFFF".parse().unwrap();
+//! assert_eq!(big.to_string(), "4722366482869645213695");
+//! assert_eq!(u128::try_from(&big), Ok(0xFFFFFFFFFFFFFFFFFF));
+//! assert!(i64::try_from(&big).is_err());
+//!
+//! let small: BigInteger = "-42".parse().unwrap();
+//! assert_eq!(Node::from(small), Node::Integer(-42));
+//! ```
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use snafu::{OptionExt, Snafu};
+
+use crate::Node;
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("invalid integer {value:?}"))]
+    InvalidInteger { value: String },
+
+    #[snafu(display("the integer {value} doesn't fit into {target}"))]
+    Overflow { value: String, target: String },
+}
+
+/// An integer of arbitrary size.
+///
+/// Values are stored as their decimal digits without leading zeros. Zero is
+/// never negative.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BigInteger {
+    negative: bool,
+    digits: String,
+}
+
+impl BigInteger {
+    /// Returns `true` if the integer is less than zero.
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Returns the decimal digits of the absolute value.
+    pub fn digits(&self) -> &str {
+        &self.digits
+    }
+
+    /// Creates an integer from the `digits` of the absolute value in the
+    /// given `radix`. Returns [`None`] if `digits` is empty or contains an
+    /// invalid digit.
+    fn from_radix(negative: bool, digits: &str, radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+
+        // Little-endian decimal digits of the absolute value
+        let mut decimal: Vec<u8> = vec![0];
+
+        for c in digits.chars() {
+            let mut carry = c.to_digit(radix)?;
+
+            for digit in &mut decimal {
+                let value = u32::from(*digit) * radix + carry;
+                *digit = (value % 10) as u8;
+                carry = value / 10;
+            }
+
+            while carry > 0 {
+                decimal.push((carry % 10) as u8);
+                carry /= 10;
+            }
+        }
+
+        while decimal.len() > 1 && decimal.last() == Some(&0) {
+            decimal.pop();
+        }
+
+        let digits: String = decimal.iter().rev().map(|d| char::from(b'0' + d)).collect();
+
+        Some(Self {
+            negative: negative && digits != "0",
+            digits,
+        })
+    }
+
+    /// Returns the value as an [`i128`] if it fits.
+    fn to_i128(&self) -> Option<i128> {
+        let value: i128 = self.digits.parse().ok()?;
+        Some(if self.negative { -value } else { value })
+    }
+}
+
+impl fmt::Display for BigInteger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            f.write_str("-")?;
+        }
+
+        f.write_str(&self.digits)
+    }
+}
+
+impl FromStr for BigInteger {
+    type Err = Error;
+
+    /// Parses decimal integers with an optional sign, as well as octal
+    /// (`0o`) and hexadecimal (`0x`) integers as defined by the Core schema.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = if let Some(octal) = s.strip_prefix("0o") {
+            Self::from_radix(false, octal, 8)
+        } else if let Some(hex) = s.strip_prefix("0x") {
+            Self::from_radix(false, hex, 16)
+        } else {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, s.strip_prefix('+').unwrap_or(s)),
+            };
+
+            digits
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+                .then(|| Self::from_radix(negative, digits, 10))
+                .flatten()
+        };
+
+        parsed.context(InvalidIntegerSnafu { value: s })
+    }
+}
+
+impl PartialOrd for BigInteger {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInteger {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let magnitude = self
+            .digits
+            .len()
+            .cmp(&other.digits.len())
+            .then_with(|| self.digits.cmp(&other.digits));
+
+        match (self.negative, other.negative) {
+            (false, false) => magnitude,
+            (true, true) => magnitude.reverse(),
+            (negative, _) => other.negative.cmp(&negative),
+        }
+    }
+}
+
+macro_rules! impl_big_integer_conversions {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for BigInteger {
+                fn from(value: $ty) -> Self {
+                    let digits = value.to_string();
+                    match digits.strip_prefix('-') {
+                        Some(digits) => Self { negative: true, digits: digits.into() },
+                        None => Self { negative: false, digits },
+                    }
+                }
+            }
+
+            impl TryFrom<&BigInteger> for $ty {
+                type Error = Error;
+
+                fn try_from(value: &BigInteger) -> Result<Self, Self::Error> {
+                    value.to_string().parse().ok().context(OverflowSnafu {
+                        value: value.to_string(),
+                        target: stringify!($ty),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_big_integer_conversions!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl From<BigInteger> for Node {
+    /// Returns a [`Node::Integer`] if the value fits into an [`i64`] and a
+    /// [`Node::BigInteger`] otherwise.
+    fn from(value: BigInteger) -> Self {
+        match i64::try_from(&value) {
+            Ok(i) => Node::Integer(i),
+            Err(_) => Node::BigInteger(value),
+        }
+    }
+}
+
+/// Compares an [`i64`] with a big integer.
+pub(crate) fn cmp_i64(value: i64, big: &BigInteger) -> Ordering {
+    match big.to_i128() {
+        Some(big) => i128::from(value).cmp(&big),
+        None if big.negative => Ordering::Greater,
+        None => Ordering::Less,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_convert() {
+        let big: BigInteger = "-170141183460469231731687303715884105729".parse().unwrap();
+        assert!(big.is_negative());
+        assert!(i128::try_from(&big).is_err());
+        assert_eq!(
+            BigInteger::from(i128::MIN),
+            "-170141183460469231731687303715884105728".parse().unwrap()
+        );
+
+        assert_eq!("0o777".parse::<BigInteger>().unwrap().to_string(), "511");
+        assert_eq!("-0".parse::<BigInteger>().unwrap().to_string(), "0");
+        assert_eq!("+007".parse::<BigInteger>().unwrap().to_string(), "7");
+        assert!("0x".parse::<BigInteger>().is_err());
+        assert!("1_000".parse::<BigInteger>().is_err());
+
+        let mut values: Vec<BigInteger> = ["10", "-2", "9", "-10", "0"]
+            .iter()
+            .map(|v| v.parse().unwrap())
+            .collect();
+        values.sort();
+        assert_eq!(
+            values.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["-10", "-2", "0", "9", "10"]
+        );
+
+        assert_eq!(
+            Node::from(BigInteger::from(u64::MAX)),
+            Node::BigInteger(BigInteger::from(u64::MAX))
+        );
+        assert_eq!(
+            cmp_i64(i64::MAX, &BigInteger::from(u64::MAX)),
+            Ordering::Less
+        );
+    }
+}
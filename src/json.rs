@@ -0,0 +1,150 @@
+//! Conversions between [`Node`]s and [`serde_json::Value`]s.
+//!
+//! This is only available when the `json` feature is enabled.
+//!
+//! JSON is a subset of YAML, which is why [`Node::from_json`] is lossless.
+//! The other direction is lossy and follows these rules:
+//!
+//! - Comments and blank lines are dropped, JSON has no equivalent.
+//! - Tags are dropped, the tagged node is converted as if it was untagged.
+//! - Non-string keys are converted into strings: scalars use their textual
+//!   value, e.g. `1` or `true`, and collections their flow style, e.g.
+//!   `[1, 2]`. If multiple keys result in the same string, the last pair
+//!   wins.
+//! - Binary data is converted into a base64-encoded string and timestamps
+//!   into an RFC 3339 string.
+//! - Floats which aren't finite and integers which don't fit into an
+//!   [`i64`] or [`u64`] result in an error.
+//!
+//! ```
+//! use serde_json::json;
+//! use yaml_ast::{yaml, Node};
+//!
+//! let node = yaml!({name: "web", ports: [80, 443], (1): null});
+//! assert_eq!(
+//!     node.to_json().unwrap(),
+//!     json!({"name": "web", "ports": [80, 443], "1": null})
+//! );
+//!
+//! assert_eq!(Node::from_json(json!([1.5, true])), yaml!([1.5, true]));
+//! ```
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{Map, Number, Value};
+use snafu::{OptionExt, Snafu};
+
+use crate::{cst::render_flow, Mapping, Node};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("the float {value} cannot be represented in JSON"))]
+    UnrepresentableFloat { value: String },
+
+    #[snafu(display("the integer {value} cannot be represented in JSON"))]
+    UnrepresentableInteger { value: String },
+}
+
+impl Node {
+    /// Converts a JSON `value` into a node. Numbers are converted into
+    /// integers where possible and into floats otherwise.
+    pub fn from_json(value: Value) -> Self {
+        match value {
+            Value::Null => Node::Null,
+            Value::Bool(b) => Node::Boolean(b),
+            Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) => Node::Integer(i),
+                (None, Some(u)) => Node::from(u),
+                _ => Node::from(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::String(s) => Node::String(s),
+            Value::Array(items) => Node::Sequence(items.into_iter().map(Node::from_json).collect()),
+            Value::Object(map) => Node::Mapping(
+                map.into_iter()
+                    .map(|(key, value)| (Node::String(key), Node::from_json(value)))
+                    .collect::<Mapping>(),
+            ),
+        }
+    }
+
+    /// Converts the node into a JSON value. See the [module](self)
+    /// documentation for the conversion rules.
+    pub fn to_json(&self) -> Result<Value, Error> {
+        let value = match self {
+            Node::Null => Value::Null,
+            Node::Boolean(b) => Value::Bool(*b),
+            Node::Integer(i) => Value::from(*i),
+            Node::BigInteger(i) => {
+                Value::from(u64::try_from(i).ok().context(UnrepresentableIntegerSnafu {
+                    value: i.to_string(),
+                })?)
+            }
+            Node::FloatingPoint(f) => Value::Number(Number::from_f64(f.value()).context(
+                UnrepresentableFloatSnafu {
+                    value: f.to_string(),
+                },
+            )?),
+            Node::String(s) => Value::String(s.clone()),
+            Node::Binary(b) => Value::String(BASE64.encode(b)),
+            Node::Timestamp(t) => Value::String(t.to_string()),
+            Node::Sequence(items) => {
+                Value::Array(items.iter().map(Node::to_json).collect::<Result<_, _>>()?)
+            }
+            Node::Mapping(mapping) => {
+                let mut map = Map::new();
+                for pair in mapping.pairs() {
+                    map.insert(json_key(&pair.key), pair.value.to_json()?);
+                }
+                Value::Object(map)
+            }
+            Node::Tagged(tagged) => tagged.node.to_json()?,
+        };
+
+        Ok(value)
+    }
+}
+
+/// Returns the string used as the JSON object key of `key`.
+fn json_key(key: &Node) -> String {
+    match key.untagged() {
+        Node::String(s) => s.clone(),
+        Node::Binary(b) => BASE64.encode(b),
+        key @ (Node::Mapping(_) | Node::Sequence(_)) => render_flow(key),
+        key => key.to_string().trim_end().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::{float::Float, tag::Tagged, yaml};
+
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let node = yaml!({
+            (1): Tagged::new("!Ref", "web".into()),
+            (yaml!([1, 2])): u64::MAX,
+            (true): Node::Binary(b"yaml".to_vec()),
+        });
+        assert_eq!(
+            node.to_json().unwrap(),
+            json!({"1": "web", "[1, 2]": u64::MAX, "true": "eWFtbA=="})
+        );
+
+        let json = json!({"a": [null, -1, 2.5, {"b": "c"}]});
+        assert_eq!(Node::from_json(json.clone()).to_json().unwrap(), json);
+
+        assert_eq!(
+            Node::from(f64::INFINITY).to_json(),
+            Err(Error::UnrepresentableFloat {
+                value: ".inf".into()
+            })
+        );
+        assert!(Node::from(i128::MIN).to_json().is_err());
+        assert_eq!(
+            Node::from_json(json!(1.0)),
+            Node::FloatingPoint(Float::new(1.0))
+        );
+    }
+}
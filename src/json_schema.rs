@@ -0,0 +1,262 @@
+//! Validation of [`Node`]s against a JSON Schema, e.g. the published schema
+//! of Helm values or a CI configuration.
+//!
+//! This is only available when the `json-schema` feature is enabled.
+//!
+//! A [`Validator`] compiles the schema once. The draft is detected from the
+//! `$schema` keyword and defaults to 2020-12. Nodes are converted into JSON
+//! as described in the [`json`](crate::json) module before they are
+//! validated, each [`Violation`] is located by the path of the offending
+//! node and, when validating a [`Cst`], by its span in the source text.
+//!
+//! ```
+//! use yaml_ast::{cst::Cst, json_schema::Validator, yaml};
+//!
+//! let schema = yaml!({
+//!     type: "object",
+//!     properties: {replicas: {type: "integer", minimum: 1}}
+//! });
+//! let validator = Validator::new(&schema).unwrap();
+//!
+//! let cst = Cst::parse("name: web\nreplicas: 0\n").unwrap();
+//! let violations = validator.validate_cst(&cst).unwrap();
+//!
+//! assert_eq!(violations.len(), 1);
+//! assert_eq!(violations[0].to_string(), "replicas: 0 is less than the minimum of 1");
+//! assert_eq!(violations[0].span.map(|span| span.start), Some(20));
+//! ```
+use std::fmt;
+
+use serde_json::Value;
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    cst::{self, Cst, CstNode},
+    events::{display_path, PathSegment},
+    json,
+    parser::Span,
+    Node,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("failed to convert the schema into JSON"))]
+    ConvertSchema { source: json::Error },
+
+    #[snafu(display("failed to compile the schema"))]
+    Compile {
+        #[snafu(source(from(jsonschema::ValidationError<'static>, Box::new)))]
+        source: Box<jsonschema::ValidationError<'static>>,
+    },
+
+    #[snafu(display("failed to convert document {document} into JSON"))]
+    ConvertInstance {
+        source: json::Error,
+        document: usize,
+    },
+
+    #[snafu(display("failed to resolve the CST"))]
+    Resolve { source: cst::Error },
+}
+
+/// A single finding of a [`Validator`], located by the path of the node
+/// within its document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The index of the document within the stream. Always zero for
+    /// [`Validator::validate`].
+    pub document: usize,
+    pub path: Vec<PathSegment>,
+
+    /// The span of the node in the source text. Only set by
+    /// [`Validator::validate_cst`], and only if the path to the node doesn't
+    /// pass through an alias.
+    pub span: Option<Span>,
+
+    /// The JSON pointer of the failed keyword within the schema, e.g.
+    /// `/properties/replicas/minimum`.
+    pub keyword: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", display_path(&self.path), self.message)
+    }
+}
+
+/// A compiled JSON Schema.
+#[derive(Debug)]
+pub struct Validator {
+    validator: jsonschema::Validator,
+}
+
+impl Validator {
+    /// Compiles the `schema`, which is written in YAML.
+    pub fn new(schema: &Node) -> Result<Self, Error> {
+        Self::from_json(&schema.to_json().context(ConvertSchemaSnafu)?)
+    }
+
+    /// Compiles the JSON `schema`.
+    pub fn from_json(schema: &Value) -> Result<Self, Error> {
+        let validator = jsonschema::validator_for(schema).context(CompileSnafu)?;
+        Ok(Self { validator })
+    }
+
+    /// Validates the `node` and returns all violations of the schema.
+    pub fn validate(&self, node: &Node) -> Result<Vec<Violation>, Error> {
+        self.validate_document(0, node, None)
+    }
+
+    /// Validates the root nodes of all documents of the `cst`, resolved with
+    /// the default [`ResolveOptions`](cst::ResolveOptions). Empty documents
+    /// are validated as `null`.
+    pub fn validate_cst(&self, cst: &Cst) -> Result<Vec<Violation>, Error> {
+        let stream = cst.to_stream().context(ResolveSnafu)?;
+        let mut violations = Vec::new();
+
+        let null = Node::Null;
+
+        for (index, (document, cst_document)) in stream.iter().zip(cst.documents()).enumerate() {
+            let root = document.nodes.first().unwrap_or(&null);
+            violations.extend(self.validate_document(index, root, cst_document.root())?);
+        }
+
+        Ok(violations)
+    }
+
+    fn validate_document(
+        &self,
+        document: usize,
+        node: &Node,
+        cst: Option<&CstNode>,
+    ) -> Result<Vec<Violation>, Error> {
+        let instance = node.to_json().context(ConvertInstanceSnafu { document })?;
+
+        let violations = self
+            .validator
+            .iter_errors(&instance)
+            .map(|error| {
+                let path = to_path(node, error.instance_path.as_str());
+                Violation {
+                    document,
+                    span: cst.and_then(|cst| locate(cst, &path)),
+                    path,
+                    keyword: error.schema_path.as_str().to_string(),
+                    message: error.to_string(),
+                }
+            })
+            .collect();
+
+        Ok(violations)
+    }
+}
+
+/// Converts the JSON `pointer` into the path of a node within `node`.
+/// Segments are indices if they address an item of a sequence and keys
+/// otherwise.
+fn to_path(node: &Node, pointer: &str) -> Vec<PathSegment> {
+    let mut node = Some(node);
+    let mut path = Vec::new();
+
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+
+        let (segment, next) = match (node.map(Node::untagged), segment.parse::<usize>()) {
+            (Some(Node::Sequence(items)), Ok(index)) => {
+                (PathSegment::Index(index), items.get(index))
+            }
+            (Some(Node::Mapping(mapping)), _) => {
+                let next = mapping.get(&segment);
+                (PathSegment::Key(segment), next)
+            }
+            _ => (PathSegment::Key(segment), None),
+        };
+
+        path.push(segment);
+        node = next;
+    }
+
+    path
+}
+
+/// Returns the span of the node at `path` within `node`, or [`None`] if the
+/// path can't be followed, e.g. because it passes an alias.
+fn locate(node: &CstNode, path: &[PathSegment]) -> Option<Span> {
+    path.iter()
+        .try_fold(node, |node, segment| match segment {
+            PathSegment::Key(key) => node.get(key),
+            PathSegment::Index(index) => node.item(*index),
+        })
+        .map(|node| node.span)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    #[test]
+    fn validate() {
+        let schema = yaml!({
+            type: "object",
+            required: ["name"],
+            properties: {
+                containers: {
+                    type: "array",
+                    items: {
+                        type: "object",
+                        properties: {"a/b": {type: "string"}, ports: {type: "array"}}
+                    }
+                }
+            }
+        });
+        let validator = Validator::new(&schema).unwrap();
+
+        let cst = Cst::parse(
+            "name: web\ncontainers:\n  - a/b: 1\n    ports: 80\n---\nbase: &base\n  ports: 80\ncontainers:\n  - *base\n",
+        )
+        .unwrap();
+        let violations = validator.validate_cst(&cst).unwrap();
+
+        let violations: Vec<_> = violations
+            .iter()
+            .map(|violation| {
+                let span = violation
+                    .span
+                    .map(|span| &cst.source()[span.start..span.end]);
+                (violation.document, violation.to_string(), span)
+            })
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                (
+                    0,
+                    "containers[0].a/b: 1 is not of type \"string\"".to_string(),
+                    Some("1")
+                ),
+                (
+                    0,
+                    "containers[0].ports: 80 is not of type \"array\"".to_string(),
+                    Some("80")
+                ),
+                (
+                    1,
+                    "containers[0].ports: 80 is not of type \"array\"".to_string(),
+                    None
+                ),
+                (
+                    1,
+                    ".: \"name\" is a required property".to_string(),
+                    Some("base: &base\n  ports: 80\ncontainers:\n  - *base")
+                ),
+            ]
+        );
+
+        assert!(matches!(
+            Validator::new(&yaml!({type: 1})),
+            Err(Error::Compile { .. })
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+//! Helpers for streams of Kubernetes manifests.
+//!
+//! Documents are identified by the `apiVersion` and `kind` of their root
+//! mapping as a [`GroupVersionKind`], and additionally by their
+//! `metadata.namespace` and `metadata.name` as a [`ResourceId`]. Streams can
+//! be grouped by kind and sorted into the order in which the resources
+//! should be applied to a cluster.
+//!
+//! ```
+//! use yaml_ast::Stream;
+//!
+//! let mut stream: Stream = "\
+//! apiVersion: apps/v1
+//! kind: Deployment
+//! metadata: {name: web, namespace: shop}
+//! ---
+//! apiVersion: v1
+//! kind: Namespace
+//! metadata: {name: shop}
+//! "
+//! .parse()
+//! .unwrap();
+//!
+//! stream.sort_for_apply();
+//!
+//! let ids: Vec<_> = stream
+//!     .iter()
+//!     .filter_map(|document| document.resource_id())
+//!     .map(|id| id.to_string())
+//!     .collect();
+//! assert_eq!(ids, ["v1 Namespace shop", "apps/v1 Deployment shop/web"]);
+//! ```
+//!
+//! This module requires the `k8s` feature.
+use std::fmt;
+
+use crate::{Document, Node, Stream};
+
+/// The kinds in the order in which they are applied, following the install
+/// order of Helm. Namespaces and cluster-wide definitions come first, so
+/// that the resources which depend on them can be created. Other kinds are
+/// applied afterwards.
+pub const APPLY_ORDER: &[&str] = &[
+    "Namespace",
+    "NetworkPolicy",
+    "ResourceQuota",
+    "LimitRange",
+    "PodSecurityPolicy",
+    "PodDisruptionBudget",
+    "ServiceAccount",
+    "Secret",
+    "SecretList",
+    "ConfigMap",
+    "StorageClass",
+    "PersistentVolume",
+    "PersistentVolumeClaim",
+    "CustomResourceDefinition",
+    "ClusterRole",
+    "ClusterRoleList",
+    "ClusterRoleBinding",
+    "ClusterRoleBindingList",
+    "Role",
+    "RoleList",
+    "RoleBinding",
+    "RoleBindingList",
+    "Service",
+    "DaemonSet",
+    "Pod",
+    "ReplicationController",
+    "ReplicaSet",
+    "Deployment",
+    "HorizontalPodAutoscaler",
+    "StatefulSet",
+    "Job",
+    "CronJob",
+    "IngressClass",
+    "Ingress",
+    "APIService",
+    "MutatingWebhookConfiguration",
+    "ValidatingWebhookConfiguration",
+];
+
+/// The type of a resource. The group of the core API, e.g. of `v1`
+/// `Service`s, is empty.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GroupVersionKind {
+    pub group: String,
+    pub version: String,
+    pub kind: String,
+}
+
+impl GroupVersionKind {
+    /// Returns the `apiVersion` of the resource, e.g. `apps/v1` or `v1`.
+    pub fn api_version(&self) -> String {
+        match self.group.is_empty() {
+            true => self.version.clone(),
+            false => format!("{}/{}", self.group, self.version),
+        }
+    }
+}
+
+impl fmt::Display for GroupVersionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.api_version(), self.kind)
+    }
+}
+
+/// Identifies a resource by its type, namespace and name. Cluster-scoped
+/// resources have no namespace, generated names (`generateName`) are not
+/// considered.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceId {
+    pub gvk: GroupVersionKind,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+}
+
+impl fmt::Display for ResourceId {
+    /// Formats the id like `apps/v1 Deployment shop/web`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.gvk)?;
+
+        match (&self.namespace, &self.name) {
+            (Some(namespace), Some(name)) => write!(f, " {namespace}/{name}"),
+            (Some(namespace), None) => write!(f, " {namespace}/"),
+            (None, Some(name)) => write!(f, " {name}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Returns the [`GroupVersionKind`] of a root node, which requires the
+/// `apiVersion` and `kind` strings.
+fn gvk(root: &Node) -> Option<GroupVersionKind> {
+    let api_version = root.get_path("apiVersion")?.as_str()?;
+    let kind = root.get_path("kind")?.as_str()?;
+
+    let (group, version) = match api_version.split_once('/') {
+        Some((group, version)) => (group, version),
+        None => ("", api_version),
+    };
+
+    Some(GroupVersionKind {
+        group: group.into(),
+        version: version.into(),
+        kind: kind.into(),
+    })
+}
+
+impl Document {
+    /// Returns the type of the resource described by the root node, or
+    /// [`None`] if it lacks the `apiVersion` or `kind`.
+    pub fn gvk(&self) -> Option<GroupVersionKind> {
+        self.nodes.first().and_then(gvk)
+    }
+
+    /// Returns the id of the resource described by the root node, or
+    /// [`None`] if it lacks the `apiVersion` or `kind`.
+    pub fn resource_id(&self) -> Option<ResourceId> {
+        let root = self.nodes.first()?;
+        let metadata = |path| {
+            root.get_path(path)
+                .and_then(Node::as_str)
+                .map(ToString::to_string)
+        };
+
+        Some(ResourceId {
+            gvk: gvk(root)?,
+            namespace: metadata("metadata.namespace"),
+            name: metadata("metadata.name"),
+        })
+    }
+}
+
+impl Stream {
+    /// Groups the documents by their [`GroupVersionKind`]. The groups are
+    /// ordered by their first document, the documents keep their order
+    /// within each group. Documents without a type are skipped.
+    pub fn group_by_gvk(&self) -> Vec<(GroupVersionKind, Vec<&Document>)> {
+        let mut groups: Vec<(GroupVersionKind, Vec<&Document>)> = Vec::new();
+
+        for document in self.iter() {
+            let Some(gvk) = document.gvk() else {
+                continue;
+            };
+
+            match groups.iter_mut().find(|(group, _)| *group == gvk) {
+                Some((_, documents)) => documents.push(document),
+                None => groups.push((gvk, vec![document])),
+            }
+        }
+
+        groups
+    }
+
+    /// Sorts the documents into the order in which they should be applied,
+    /// see [`APPLY_ORDER`]. Documents of other kinds follow, and documents
+    /// without a kind come last. The sort is stable.
+    pub fn sort_for_apply(&mut self) -> &mut Self {
+        self.sort_documents_by(|root| {
+            let kind = root.and_then(|root| root.get_path("kind")?.as_str());
+
+            match kind {
+                Some(kind) => APPLY_ORDER
+                    .iter()
+                    .position(|ordered| *ordered == kind)
+                    .unwrap_or(APPLY_ORDER.len()),
+                None => APPLY_ORDER.len() + 1,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn group_and_sort() {
+        let mut stream: Stream = "\
+apiVersion: apps/v1
+kind: Deployment
+metadata: {name: api}
+---
+apiVersion: example.com/v1
+kind: Widget
+metadata: {name: w}
+---
+# no kind
+a: 1
+---
+apiVersion: v1
+kind: ConfigMap
+metadata: {name: config}
+---
+apiVersion: apps/v1
+kind: Deployment
+metadata: {name: web}
+"
+        .parse()
+        .unwrap();
+
+        let groups: Vec<_> = stream
+            .group_by_gvk()
+            .into_iter()
+            .map(|(gvk, documents)| (gvk.to_string(), documents.len()))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("apps/v1 Deployment".to_string(), 2),
+                ("example.com/v1 Widget".to_string(), 1),
+                ("v1 ConfigMap".to_string(), 1),
+            ]
+        );
+
+        stream.sort_for_apply();
+        let ids: Vec<_> = stream
+            .iter()
+            .map(|document| document.resource_id().map(|id| id.to_string()))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                Some("v1 ConfigMap config".to_string()),
+                Some("apps/v1 Deployment api".to_string()),
+                Some("apps/v1 Deployment web".to_string()),
+                Some("example.com/v1 Widget w".to_string()),
+                None,
+            ]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Preservation of the lexical forms of null and boolean scalars.
+//!
+//! The Core schema accepts multiple spellings of null (`null`, `Null`, `NULL`
+//! and `~`) and booleans (e.g. `true`, `True` and `TRUE`). The AST only keeps
+//! the resolved value, which is why [`Document::lexical_forms`](crate::Document::lexical_forms) records the
+//! original spelling of parsed scalars by their path. Re-emitting the original
+//! spelling keeps diffs of round-tripped third-party files minimal.
+//!
+//! ```
+//! use yaml_ast::Stream;
+//!
+//! let stream: Stream = "enabled: True\nproxy: ~\nport: 80\n".parse().unwrap();
+//! assert_eq!(stream.to_string(), "---\nenabled: True\nproxy: ~\nport: 80\n...\n");
+//! ```
+use std::collections::HashMap;
+
+use crate::{
+    emitter::key_segment,
+    events::{Event, PathSegment},
+    schema::resolve_core,
+    Node,
+};
+
+/// Returns `true` if the plain scalar `value` is a non-default lexical form
+/// of null or a boolean, which is worth preserving.
+pub(crate) fn is_lexical_form(value: &str) -> bool {
+    matches!(
+        value,
+        "~" | "Null" | "NULL" | "True" | "TRUE" | "False" | "FALSE"
+    )
+}
+
+/// Returns the lexical form of each scalar of `node` in the order of the
+/// scalar events produced by [`IntoEvents`](crate::events::IntoEvents), or
+/// [`None`] if the scalar keeps its default form.
+pub(crate) fn scalar_forms(
+    node: &Node,
+    forms: &HashMap<Vec<PathSegment>, String>,
+) -> Vec<Option<String>> {
+    let mut scalars = Vec::new();
+
+    if !forms.is_empty() {
+        collect_forms(node, Some(forms), &mut Vec::new(), &mut scalars);
+    }
+
+    scalars
+}
+
+/// Replaces the values of the scalar events with the `forms` returned by
+/// [`scalar_forms`].
+pub(crate) fn apply_scalar_forms(
+    events: impl Iterator<Item = Event>,
+    forms: Vec<Option<String>>,
+) -> impl Iterator<Item = Event> {
+    let mut forms = forms.into_iter();
+
+    events.map(move |event| match event {
+        Event::Scalar {
+            value,
+            tag,
+            anchor,
+            style,
+        } => Event::Scalar {
+            value: forms.next().flatten().unwrap_or(value),
+            tag,
+            anchor,
+            style,
+        },
+        event => event,
+    })
+}
+
+fn collect_forms(
+    node: &Node,
+    forms: Option<&HashMap<Vec<PathSegment>, String>>,
+    path: &mut Vec<PathSegment>,
+    scalars: &mut Vec<Option<String>>,
+) {
+    match node {
+        Node::Mapping(mapping) => {
+            for pair in mapping.pairs() {
+                collect_forms(&pair.key, None, path, scalars);
+
+                path.push(key_segment(&pair.key));
+                collect_forms(&pair.value, forms, path, scalars);
+                path.pop();
+            }
+        }
+        Node::Sequence(items) => {
+            for (index, item) in items.iter().enumerate() {
+                path.push(PathSegment::Index(index));
+                collect_forms(item, forms, path, scalars);
+                path.pop();
+            }
+        }
+        // Tagged scalars are written with their tag, which doesn't match
+        // the form anymore
+        Node::Tagged(tagged) => collect_forms(&tagged.node, None, path, scalars),
+        scalar => scalars.push(
+            forms
+                .and_then(|forms| forms.get(path.as_slice()))
+                .filter(|form| resolve_core(form) == *scalar)
+                .cloned(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        emitter::{Emitter, EmitterOptions},
+        events::IntoEvents,
+        yaml, Stream,
+    };
+
+    fn emit(input: &str, options: EmitterOptions) -> String {
+        let stream: Stream = input.parse().unwrap();
+        let mut output = String::new();
+
+        Emitter::new(stream.into_events(), options)
+            .emit(&mut output)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn round_trip() {
+        let input = "a: TRUE\nb: [~, False, NULL]\n~: Null\n";
+
+        assert_eq!(
+            emit(input, EmitterOptions::default()),
+            "---\na: TRUE\nb:\n  - ~\n  - False\n  - NULL\nnull: Null\n...\n"
+        );
+        assert_eq!(
+            emit(
+                input,
+                EmitterOptions::builder()
+                    .normalize_scalars(true)
+                    .build()
+                    .unwrap()
+            ),
+            "---\na: true\nb:\n  - null\n  - false\n  - null\nnull: null\n...\n"
+        );
+    }
+
+    #[test]
+    fn edited() {
+        let mut stream: Stream = "a: True\nb: True\n".parse().unwrap();
+
+        // Forms which don't resolve to the new value are ignored
+        stream.iter_mut().next().unwrap().nodes[0] = yaml!({a: false, b: true});
+        assert_eq!(stream.to_string(), "---\na: false\nb: True\n...\n");
+    }
+}
@@ -1,13 +1,87 @@
-use crate::events::{Event, IntoEvents};
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+    mem,
+    ops::ControlFlow,
+};
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use crate::{
+    directive::Directive,
+    events::{Event, EventReceiver, IntoEvents, PathSegment},
+    float::Float,
+    integer::BigInteger,
+    tag::Tagged,
+    timestamp::Timestamp,
+};
+
+mod access;
+pub mod analysis;
+pub mod borrowed;
+mod builder;
+#[cfg(feature = "bump")]
+pub mod bump;
+mod cmp;
+pub mod collector;
+mod comments;
+pub mod compat;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod convert;
+pub mod cst;
+#[cfg(feature = "miette")]
+mod diagnostic;
+pub mod diff;
+pub mod directive;
+mod dot;
+mod dump;
+pub mod edit;
 pub mod emitter;
 pub mod events;
+pub mod export;
+pub mod float;
+pub mod format;
+pub mod front_matter;
+pub mod import;
+pub mod integer;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "json-schema")]
+pub mod json_schema;
+#[cfg(feature = "k8s")]
+pub mod k8s;
+mod lexical;
+pub mod limits;
+pub mod lint;
+mod macros;
+mod mapping;
+pub mod merge;
+pub mod metadata;
+pub mod overlay;
+pub mod parser;
+pub mod patch;
+pub mod provenance;
+pub mod redact;
+pub mod schema;
+pub mod structure;
+pub mod style;
+pub mod substitute;
+pub mod tag;
+mod text;
+pub mod timestamp;
+pub mod trailer;
+pub mod tree;
+pub mod validate;
 
-pub enum Error {}
+pub use access::Error;
+pub use builder::*;
+pub use mapping::*;
 
 /// A stream represents one or more [`Document`]s separated by `---`
 /// (triple dash) and `...` (triple dot).
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct Stream(Vec<Document>);
 
 impl Stream {
@@ -16,53 +90,446 @@ impl Stream {
         Self::default()
     }
 
+    /// Creates a new stream from a list of YAML [`Document`]s.
+    pub fn from_documents(documents: Vec<Document>) -> Self {
+        Self(documents)
+    }
+
     /// Appends one YAML [`Document`] at the end of the stream.
     pub fn push_document(&mut self, document: Document) -> &mut Self {
         self.0.push(document);
         self
     }
+
+    /// Returns the events of the stream like
+    /// [`IntoEvents::into_events`], but produces them lazily. Passed to the
+    /// [`Emitter`](emitter::Emitter), the stream is emitted without holding
+    /// all of its events in memory.
+    ///
+    /// ```
+    /// use yaml_ast::{emitter::{Emitter, EmitterOptions}, yaml, Document, Stream};
+    ///
+    /// let stream = Stream::from_documents(vec![Document {
+    ///     nodes: vec![yaml!({ports: [80, 443]})],
+    ///     ..Default::default()
+    /// }]);
+    ///
+    /// let mut output = String::new();
+    /// Emitter::new(stream.into_events_iter(), EmitterOptions::default())
+    ///     .emit(&mut output)
+    ///     .unwrap();
+    /// assert_eq!(output, "---\nports:\n  - 80\n  - 443\n...\n");
+    /// ```
+    pub fn into_events_iter(self) -> impl Iterator<Item = Event> {
+        std::iter::once(Event::StreamStart)
+            .chain(self.0.into_iter().flat_map(Document::into_events_iter))
+            .chain([Event::StreamEnd])
+    }
+
+    /// Returns the events of the stream like [`Stream::into_events_iter`],
+    /// but borrows the stream instead of consuming it.
+    pub fn events_iter(&self) -> impl Iterator<Item = Event> + '_ {
+        std::iter::once(Event::StreamStart)
+            .chain(self.0.iter().flat_map(Document::events_iter))
+            .chain([Event::StreamEnd])
+    }
+
+    /// Returns an iterator over the documents of the stream.
+    pub fn iter(&self) -> std::slice::Iter<'_, Document> {
+        self.0.iter()
+    }
+
+    /// Returns an iterator which allows modifying each document of the
+    /// stream.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Document> {
+        self.0.iter_mut()
+    }
+
+    /// Returns the number of documents in the stream.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the stream contains no documents.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns a reference to the document at `index`, or [`None`] if out of
+    /// bounds.
+    pub fn get(&self, index: usize) -> Option<&Document> {
+        self.0.get(index)
+    }
+
+    /// Returns a mutable reference to the document at `index`, or [`None`] if
+    /// out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Document> {
+        self.0.get_mut(index)
+    }
+
+    /// Inserts a document at `index`, shifting all documents after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, document: Document) -> &mut Self {
+        self.0.insert(index, document);
+        self
+    }
+
+    /// Removes and returns the document at `index`, or [`None`] if out of
+    /// bounds.
+    pub fn remove(&mut self, index: usize) -> Option<Document> {
+        (index < self.0.len()).then(|| self.0.remove(index))
+    }
+
+    /// Retains only the documents for which `f` returns `true`, preserving
+    /// their order.
+    pub fn retain<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnMut(&Document) -> bool,
+    {
+        self.0.retain(f);
+        self
+    }
+
+    /// Sorts the documents by the key which `f` extracts from the root node
+    /// of each document. Documents without a root node are passed [`None`].
+    /// The sort is stable, documents with equal keys keep their order.
+    pub fn sort_documents_by<K, F>(&mut self, mut f: F) -> &mut Self
+    where
+        K: Ord,
+        F: FnMut(Option<&Node>) -> K,
+    {
+        self.0
+            .sort_by_cached_key(|document| f(document.nodes.first()));
+        self
+    }
+
+    /// Sorts the documents by the values at `paths` in their root nodes,
+    /// e.g. `["kind", "metadata.name"]` for Kubernetes manifests. Later paths
+    /// are only compared if the values of all previous paths are equal.
+    /// Documents which lack a path sort before all others. See
+    /// [`Node::get_path`] for the path syntax.
+    pub fn sort_documents_by_paths(&mut self, paths: &[&str]) -> &mut Self {
+        fn values<'a>(document: &'a Document, paths: &[&str]) -> Vec<Option<&'a Node>> {
+            let root = document.nodes.first();
+            paths
+                .iter()
+                .map(|path| root.and_then(|root| root.get_path(path)))
+                .collect()
+        }
+
+        self.0
+            .sort_by(|a, b| values(a, paths).cmp(&values(b, paths)));
+        self
+    }
+
+    /// Removes documents whose key, which `key_fn` extracts from the root
+    /// node of each document, equals the key of another document. Documents
+    /// without a root node are passed [`None`]. The `policy` decides which
+    /// of the duplicates is kept, the order of the kept documents doesn't
+    /// change. With [`DuplicatePolicy::Error`], the stream is left unchanged
+    /// and the first duplicate is reported as [`Error::DuplicateDocument`].
+    pub fn dedup_documents_by<K, F>(
+        &mut self,
+        mut key_fn: F,
+        policy: DuplicatePolicy,
+    ) -> Result<&mut Self, Error>
+    where
+        K: Eq + Hash,
+        F: FnMut(Option<&Node>) -> K,
+    {
+        let keys: Vec<K> = self
+            .0
+            .iter()
+            .map(|document| key_fn(document.nodes.first()))
+            .collect();
+
+        let mut seen = HashMap::new();
+        let mut keep = vec![true; keys.len()];
+
+        for (index, key) in keys.iter().enumerate() {
+            match seen.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(index);
+                }
+                Entry::Occupied(mut entry) => match policy {
+                    DuplicatePolicy::KeepFirst => keep[index] = false,
+                    DuplicatePolicy::KeepLast => keep[entry.insert(index)] = false,
+                    DuplicatePolicy::Error => {
+                        return Err(Error::DuplicateDocument {
+                            first: *entry.get(),
+                            duplicate: index,
+                        })
+                    }
+                },
+            }
+        }
+
+        let mut keep = keep.into_iter();
+        self.0.retain(|_| keep.next().unwrap_or(true));
+        Ok(self)
+    }
+
+    /// Merges all documents into a single document with a mapping as its
+    /// root. Each root node becomes the value of the key which `key_fn`
+    /// derives from the index of the document and its root node. Documents
+    /// without a root node are merged as `null`. If multiple documents map
+    /// to the same key, the last one wins. The directives of all documents
+    /// are kept, without duplicates.
+    ///
+    /// This is the reverse of [`Document::split_at_keys`] for bundles which
+    /// need a single-document view.
+    pub fn flatten_into_mapping<F>(self, mut key_fn: F) -> Document
+    where
+        F: FnMut(usize, Option<&Node>) -> Node,
+    {
+        let mut directives = Vec::new();
+        let mut mapping = Mapping::new();
+        let mut lexical_forms = HashMap::new();
+        let mut item_comments = HashMap::new();
+        let mut provenance = HashMap::new();
+        let mut styles = HashMap::new();
+
+        for (index, document) in self.0.into_iter().enumerate() {
+            for directive in document.directives {
+                if !directives.contains(&directive) {
+                    directives.push(directive);
+                }
+            }
+
+            let root = document.nodes.into_iter().next();
+            let key = key_fn(index, root.as_ref());
+            let value = root.unwrap_or_default();
+
+            let segment = emitter::key_segment(&key);
+            lexical_forms.extend(
+                document
+                    .lexical_forms
+                    .into_iter()
+                    .map(|(path, form)| ([vec![segment.clone()], path].concat(), form)),
+            );
+            item_comments.extend(
+                document
+                    .item_comments
+                    .into_iter()
+                    .map(|(path, comments)| ([vec![segment.clone()], path].concat(), comments)),
+            );
+            provenance.extend(
+                document
+                    .provenance
+                    .into_iter()
+                    .map(|(path, origin)| ([vec![segment.clone()], path].concat(), origin)),
+            );
+            styles.extend(
+                document
+                    .styles
+                    .into_iter()
+                    .map(|(path, style)| ([vec![segment.clone()], path].concat(), style)),
+            );
+
+            match mapping.position(&key) {
+                Some(position) => {
+                    let Some(node) = mapping.value_at_mut(position) else {
+                        unreachable!()
+                    };
+                    *node = value;
+                }
+                None => {
+                    mapping.push_pair(key, value);
+                }
+            }
+        }
+
+        Document {
+            directives,
+            nodes: vec![Node::Mapping(mapping)],
+            lexical_forms,
+            item_comments,
+            provenance,
+            styles,
+            ..Default::default()
+        }
+    }
+
+    /// Consumes the stream and returns its documents.
+    pub fn into_documents(self) -> Vec<Document> {
+        self.0
+    }
+}
+
+impl FromIterator<Document> for Stream {
+    fn from_iter<T: IntoIterator<Item = Document>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Extend<Document> for Stream {
+    fn extend<T: IntoIterator<Item = Document>>(&mut self, iter: T) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Stream {
+    type Item = Document;
+    type IntoIter = std::vec::IntoIter<Document>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Stream {
+    type Item = &'a Document;
+    type IntoIter = std::slice::Iter<'a, Document>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
 }
 
 impl IntoEvents for Stream {
     fn into_events(self) -> Vec<Event> {
-        let mut events = Vec::new();
-        events.push(Event::StreamStart);
-
-        for doc in self.0 {
-            events.extend(doc.into_events())
-        }
+        self.into_events_iter().collect()
+    }
 
-        events.push(Event::StreamEnd);
-        events
+    fn push_events<R: EventReceiver + ?Sized>(self, receiver: &mut R) -> ControlFlow<R::Break> {
+        events::push_all(self.into_events_iter(), receiver)
     }
 }
 
+/// Decides which of multiple duplicate documents is kept, see
+/// [`Stream::dedup_documents_by`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Keeps the first document and removes all later duplicates.
+    #[default]
+    KeepFirst,
+
+    /// Keeps the last document and removes all earlier duplicates.
+    KeepLast,
+
+    /// Fails on the first duplicate.
+    Error,
+}
+
 /// A document is part (or chunk) of a larger [`Stream`].
 ///
 /// Each document can have zero or more directives attached to it. These
 /// directives influence the behavior of the YAML processor. The content of the
 /// document is stored in zero or more [`Node`]s.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct Document {
-    pub directives: Vec<String>,
+    pub directives: Vec<Directive>,
     pub nodes: Vec<Node>,
+
+    /// The number of blank lines emitted before the document, e.g. to
+    /// visually separate documents in a stream.
+    pub blank_lines: usize,
+
+    /// Comments emitted before the document, without the leading `#`.
+    pub comments: Vec<String>,
+
+    /// The original lexical forms of null and boolean scalars, like `True`
+    /// or `~`, by the path of the node. They are written instead of `null`,
+    /// `true` and `false` unless
+    /// [`EmitterOptions::normalize_scalars`](emitter::EmitterOptions::normalize_scalars)
+    /// is set. Forms which don't resolve to the node at their path (anymore)
+    /// are ignored.
+    pub lexical_forms: HashMap<Vec<PathSegment>, String>,
+
+    /// The comments of sequence items by the path of the item, e.g.
+    /// `[Key("args"), Index(0)]`. Sequences are plain lists of nodes, which
+    /// is why their comments are kept alongside the nodes, like the
+    /// [`lexical_forms`](Document::lexical_forms). Comments of paths which
+    /// don't exist (anymore) are ignored.
+    ///
+    /// The comments only follow their items through the [edits](edit) of
+    /// the document, like [`Document::insert`] and [`Document::delete`].
+    /// Inserting or removing items with the methods of [`Vec`] leaves the
+    /// comments at their previous index.
+    pub item_comments: HashMap<Vec<PathSegment>, ItemComments>,
+
+    /// User metadata about the origin of nodes, e.g. the file they were
+    /// read from, by the path of the node. Nodes without an entry inherit
+    /// the provenance of their closest ancestor, see
+    /// [`Document::provenance`]. Entries follow their nodes through edits
+    /// and merges, and are never emitted.
+    pub provenance: HashMap<Vec<PathSegment>, provenance::Provenance>,
+
+    /// The presentation styles of nodes by their path, e.g. to write a
+    /// single sequence in flow style. They take precedence over the
+    /// [`EmitterOptions`](emitter::EmitterOptions), see
+    /// [`Document::set_style`]. Entries follow their nodes through edits.
+    pub styles: HashMap<Vec<PathSegment>, style::Style>,
 }
 
 impl IntoEvents for Document {
     fn into_events(self) -> Vec<Event> {
+        self.into_events_iter().collect()
+    }
+
+    fn push_events<R: EventReceiver + ?Sized>(self, receiver: &mut R) -> ControlFlow<R::Break> {
+        events::push_all(self.into_events_iter(), receiver)
+    }
+}
+
+impl Document {
+    /// Returns the events of the document like
+    /// [`IntoEvents::into_events`], but produces them lazily. See
+    /// [`Stream::into_events_iter`].
+    pub fn into_events_iter(self) -> impl Iterator<Item = Event> {
+        let events = self.start_events();
+        let Document {
+            nodes,
+            lexical_forms,
+            item_comments,
+            styles,
+            ..
+        } = self;
+
+        let nodes = nodes.into_iter().flat_map(move |node| {
+            node_events(Cow::Owned(node), &lexical_forms, &item_comments, &styles)
+        });
+
+        events.into_iter().chain(nodes).chain([Event::DocumentEnd])
+    }
+
+    /// Returns the events of the document like
+    /// [`Document::into_events_iter`], but borrows the document instead of
+    /// consuming it.
+    pub fn events_iter(&self) -> impl Iterator<Item = Event> + '_ {
+        let nodes = self.nodes.iter().flat_map(|node| {
+            node_events(
+                Cow::Borrowed(node),
+                &self.lexical_forms,
+                &self.item_comments,
+                &self.styles,
+            )
+        });
+
+        self.start_events()
+            .into_iter()
+            .chain(nodes)
+            .chain([Event::DocumentEnd])
+    }
+
+    /// Returns the events up to and including the document start.
+    fn start_events(&self) -> Vec<Event> {
         let mut events = Vec::new();
-        events.push(Event::DocumentStart);
 
-        for node in self.nodes {
-            events.extend(node.into_events())
+        if self.blank_lines > 0 {
+            events.push(Event::BlankLines(self.blank_lines));
         }
 
-        events.push(Event::DocumentEnd);
+        events.extend(self.comments.iter().cloned().map(Event::Comment));
+        events.extend(self.directives.iter().cloned().map(Event::Directive));
+        events.push(Event::DocumentStart);
+
         events
     }
-}
 
-impl Document {
     pub fn new() -> Self {
         Self::default()
     }
@@ -76,7 +543,9 @@ impl Document {
         }
     }
 
-    pub fn push_directive(&mut self, directive: String) -> &mut Self {
+    /// Appends a directive, which is emitted before the document start
+    /// marker.
+    pub fn push_directive(&mut self, directive: Directive) -> &mut Self {
         self.directives.push(directive);
         self
     }
@@ -85,29 +554,136 @@ impl Document {
         self.nodes.push(node);
         self
     }
-}
 
-#[derive(Debug)]
-pub enum ScopedTag {
-    Global(Node),
+    /// Splits the top-level mapping into multiple documents: each pair whose
+    /// key is one of `keys` is moved into a document of its own, in the order
+    /// of the mapping. All remaining pairs stay in the first document, which
+    /// is omitted if none remain. Every document keeps the directives.
+    ///
+    /// Documents whose root node isn't a mapping are returned unchanged as
+    /// the only document of the stream.
+    pub fn split_at_keys(self, keys: &[&str]) -> Stream {
+        if !matches!(self.nodes.as_slice(), [Node::Mapping(_)]) {
+            return Stream::from_documents(vec![self]);
+        }
 
-    // TODO (Techassi): Let's see how we can deal with custom tags
-    Local(Node),
-}
+        let Document {
+            directives,
+            mut nodes,
+            blank_lines,
+            comments,
+            lexical_forms,
+            item_comments,
+            provenance,
+            styles,
+        } = self;
+
+        let Some(Ok(mapping)) = nodes.pop().map(Node::try_into_mapping) else {
+            unreachable!()
+        };
+
+        // Split pairs keep their path, which is why all documents share the
+        // lexical forms, item comments, provenance and styles
+        let document = |pairs: Mapping| Document {
+            directives: directives.clone(),
+            nodes: vec![Node::Mapping(pairs)],
+            lexical_forms: lexical_forms.clone(),
+            item_comments: item_comments.clone(),
+            provenance: provenance.clone(),
+            styles: styles.clone(),
+            ..Default::default()
+        };
 
-impl Default for ScopedTag {
-    fn default() -> Self {
-        Self::Global(Node::default())
+        let mut rest = Mapping::new();
+        let mut split = Vec::new();
+
+        for pair in mapping {
+            match &pair.key {
+                Node::String(key) if keys.contains(&key.as_str()) => {
+                    split.push(document(Mapping::from_iter([pair])));
+                }
+                _ => rest.push(pair),
+            }
+        }
+
+        let mut documents = Vec::with_capacity(split.len() + 1);
+        if !rest.is_empty() {
+            documents.push(document(rest));
+        }
+        documents.extend(split);
+
+        if let Some(first) = documents.first_mut() {
+            first.blank_lines = blank_lines;
+            first.comments = comments;
+        }
+
+        Stream::from_documents(documents)
     }
 }
 
-// TODO (Techassi): Ensure keys are unique in mappings
-/// Type alias for a [`Vec<(Node, Node)>`].
-pub type Mapping = Vec<(Node, Node)>;
+/// Returns the events of a root `node` of a document, presented with the
+/// lexical forms, item comments and styles of the document.
+fn node_events<'a>(
+    node: Cow<'a, Node>,
+    lexical_forms: &HashMap<Vec<PathSegment>, String>,
+    item_comments: &HashMap<Vec<PathSegment>, ItemComments>,
+    styles: &HashMap<Vec<PathSegment>, style::Style>,
+) -> impl Iterator<Item = Event> + 'a {
+    let forms = lexical::scalar_forms(&node, lexical_forms);
+    let comments = comments::item_comments(&node, item_comments);
+    let node_styles = style::node_styles(&node, styles);
+    let events = lexical::apply_scalar_forms(NodeEventIter::new(node), forms);
+    let events = style::apply_node_styles(events, node_styles);
+
+    comments::apply_item_comments(events, comments)
+}
 
 /// Type alias for a [`Vec<Node>`].
+///
+/// Sequences are edited with the methods of [`Vec`], e.g. `push`, `insert`,
+/// `remove`, `retain` and `sort_by`. Use [`Node::as_sequence_mut`] to edit
+/// the sequence of a node in place.
+///
+/// The [`item_comments`](Document::item_comments),
+/// [`lexical_forms`](Document::lexical_forms),
+/// [`provenance`](Document::provenance) and [`styles`](Document::styles) of
+/// a document are kept by the index of the items, which the methods of
+/// [`Vec`] don't update. Moving items of a parsed document with them
+/// attaches these to the wrong items, use [`Document::insert`] and
+/// [`Document::delete`] instead.
+///
+/// ```
+/// use yaml_ast::{yaml, Node};
+///
+/// let mut node = yaml!({args: ["--port=80", "-v"]});
+/// let args = node.get_path_mut("args").unwrap().as_sequence_mut().unwrap();
+///
+/// args.insert(0, "serve".into());
+/// args.retain(|arg| arg.as_str() != Some("-v"));
+/// args.push("--tls".into());
+///
+/// assert_eq!(node, yaml!({args: ["serve", "--port=80", "--tls"]}));
+/// ```
 pub type Sequence = Vec<Node>;
 
+/// The comments of a sequence item, see [`Document::item_comments`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ItemComments {
+    /// Comments on their own lines before the item, without the leading
+    /// `#`.
+    pub comments: Vec<String>,
+
+    /// A comment at the end of the line of the item, e.g. `- item # why`.
+    /// For collections, this is the line of the entry indicator `-`.
+    pub comment: Option<String>,
+}
+
+impl ItemComments {
+    pub fn is_empty(&self) -> bool {
+        self.comments.is_empty() && self.comment.is_none()
+    }
+}
+
 /// A YAML schema is a combination of a set of tags and a mechanism for
 /// resolving non-specific tags.
 ///
@@ -130,13 +706,18 @@ pub type Sequence = Vec<Node>;
 /// The YAML specification defines nodes and tags a two separate (but related)
 /// concepts. Because Rust allows us to combine enums with structured data,
 /// this crate decides to combine both these concepts into one.
-#[derive(Debug)]
+///
+/// Nodes are dropped without recursion, which is why they implement
+/// [`Drop`] and can't be destructured by value. Use the `try_into_*`
+/// accessors, like [`Node::try_into_mapping`], or [`std::mem::take`] on a
+/// mutable reference to move the content out of a node.
+#[derive(Clone, Debug, Default)]
 pub enum Node {
     /// Represents an associative container, where each key is unique in the
     /// association and mapped to exactly one value.
     ///
     /// See <https://yaml.org/spec/1.2.2/#10111-generic-mapping>
-    Mapping(Vec<(Node, Node)>),
+    Mapping(Mapping),
 
     /// Represents a collection indexed by sequential integers starting with
     /// zero.
@@ -153,6 +734,7 @@ pub enum Node {
     /// Represents the lack of a value.
     ///
     /// See <https://yaml.org/spec/1.2.2/#10211-null>
+    #[default]
     Null,
 
     /// Represents a true/false value.
@@ -165,54 +747,289 @@ pub enum Node {
     /// See <https://yaml.org/spec/1.2.2/#10213-integer>
     Integer(i64),
 
-    /// Represents an approximation to real numbers.
+    /// Represents integers which don't fit into an [`i64`]. See the
+    /// [`integer`] module.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#10213-integer>
+    BigInteger(BigInteger),
+
+    /// Represents an approximation to real numbers. See the [`float`]
+    /// module.
     ///
     /// See <https://yaml.org/spec/1.2.2/#10214-floating-point>
-    FloatingPoint(String),
-}
+    FloatingPoint(Float),
 
-impl Default for Node {
-    fn default() -> Self {
-        Self::Null
-    }
+    /// Represents a sequence of arbitrary bytes, which is emitted as
+    /// base64-encoded data tagged with `!!binary`.
+    ///
+    /// See <https://yaml.org/type/binary.html>
+    Binary(Vec<u8>),
+
+    /// Represents a point in time, optionally only a date. Timestamps are
+    /// emitted in the RFC 3339 format and tagged with `!!timestamp`.
+    ///
+    /// See <https://yaml.org/type/timestamp.html>
+    Timestamp(Timestamp),
+
+    /// Represents a node with an application-specific tag, like `!Ref`.
+    /// See the [`tag`] module for typed conversions.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#tags>
+    Tagged(Box<Tagged>),
 }
 
 impl IntoEvents for Node {
+    /// Returns the events of the node, see [`Node::into_events_iter`].
     fn into_events(self) -> Vec<Event> {
-        let mut events = Vec::new();
+        self.into_events_iter().collect()
+    }
 
-        match self {
-            Node::Mapping(mapping) => {
-                events.push(Event::MappingStart(0));
+    fn push_events<R: EventReceiver + ?Sized>(self, receiver: &mut R) -> ControlFlow<R::Break> {
+        events::push_all(self.into_events_iter(), receiver)
+    }
+}
 
-                for (k, v) in mapping {
-                    events.extend(k.into_events());
-                    events.extend(v.into_events());
-                }
+/// The lazily produced events of a [`Node`], see
+/// [`Node::into_events_iter`] and [`Node::events_iter`].
+///
+/// Nested collections are processed using a work stack on the heap instead
+/// of recursion, which is why the nesting depth is only limited by the
+/// available memory. Owned nodes are taken apart as their events are
+/// produced, the scalars of borrowed nodes are copied.
+#[derive(Debug)]
+pub struct NodeEventIter<'a> {
+    stack: Vec<Work<'a>>,
+
+    /// The inline comment following the last event.
+    comment: Option<String>,
+}
+
+impl<'a> NodeEventIter<'a> {
+    fn new(node: Cow<'a, Node>) -> Self {
+        Self {
+            stack: vec![Work::Node(node, None)],
+            comment: None,
+        }
+    }
+
+    /// Pushes the key and the value of the `pair`, preceded by the events
+    /// of its blank lines and comments.
+    fn push_pair(&mut self, pair: Cow<'a, MappingPair>) {
+        let explicit_key = pair.explicit_key;
+        let blank_lines = pair.blank_lines;
+        let (key, value, comments, comment) = match pair {
+            Cow::Borrowed(pair) => (
+                Cow::Borrowed(&pair.key),
+                Cow::Borrowed(&pair.value),
+                pair.comments.clone(),
+                pair.comment.clone(),
+            ),
+            Cow::Owned(pair) => (
+                Cow::Owned(pair.key),
+                Cow::Owned(pair.value),
+                pair.comments,
+                pair.comment,
+            ),
+        };
 
-                events.push(Event::MappingEnd);
+        self.stack.push(Work::Node(value, comment));
+        self.stack.push(Work::Node(key, None));
+        if explicit_key {
+            self.stack.push(Work::Event(Event::ExplicitKey));
+        }
+        self.stack.extend(
+            comments
+                .into_iter()
+                .rev()
+                .map(|comment| Work::Event(Event::Comment(comment))),
+        );
+
+        if blank_lines > 0 {
+            self.stack.push(Work::Event(Event::BlankLines(blank_lines)));
+        }
+    }
+}
+
+impl Iterator for NodeEventIter<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(comment) = self.comment.take() {
+            return Some(Event::InlineComment(comment));
+        }
+
+        let (mut node, comment) = loop {
+            match self.stack.pop()? {
+                Work::Node(node, comment) => break (node, comment),
+                Work::Pair(pair) => self.push_pair(pair),
+                Work::Event(event) => return Some(event),
             }
-            Node::Sequence(sequence) => {
-                events.push(Event::SequenceStart(0));
+        };
 
-                for item in sequence {
-                    events.extend(item.into_events());
+        // The tag is a property of the node event, the outermost tag of
+        // nested tagged nodes applies
+        let mut tag = None;
+        while let Some(tagged) = node.as_tagged() {
+            tag = tag.or_else(|| Some(tagged.tag.clone()));
+            node = match node {
+                Cow::Borrowed(node) => Cow::Borrowed(&node.as_tagged().unwrap().node),
+                Cow::Owned(mut node) => match &mut node {
+                    Node::Tagged(tagged) => Cow::Owned(mem::take(&mut tagged.node)),
+                    _ => unreachable!(),
+                },
+            };
+        }
+
+        let event = match node {
+            Cow::Borrowed(Node::Mapping(mapping)) => {
+                self.stack.push(Work::Event(Event::MappingEnd));
+                self.stack.extend(
+                    mapping
+                        .pairs()
+                        .rev()
+                        .map(|pair| Work::Pair(Cow::Borrowed(pair))),
+                );
+
+                Event::MappingStart {
+                    tag,
+                    anchor: None,
+                    style: None,
                 }
+            }
+            Cow::Borrowed(Node::Sequence(items)) => {
+                self.stack.push(Work::Event(Event::SequenceEnd));
+                self.stack.extend(
+                    items
+                        .iter()
+                        .rev()
+                        .map(|item| Work::Node(Cow::Borrowed(item), None)),
+                );
 
-                events.push(Event::SequenceEnd);
+                Event::SequenceStart {
+                    tag,
+                    anchor: None,
+                    style: None,
+                }
             }
-            Node::String(s) => events.push(Event::Scalar(s)),
-            Node::Null => events.push(Event::Scalar("null".into())),
-            Node::Boolean(b) => events.push(Event::Scalar(b.to_string())),
-            Node::Integer(i) => events.push(Event::Scalar(i.to_string())),
-            Node::FloatingPoint(_) => todo!(),
+            Cow::Borrowed(scalar) => {
+                let tag = tag.or_else(|| Some(scalar.uri()));
+                Event::scalar(scalar_value(scalar), tag)
+            }
+            Cow::Owned(mut node) => match &mut node {
+                Node::Mapping(mapping) => {
+                    self.stack.push(Work::Event(Event::MappingEnd));
+                    self.stack.extend(
+                        mem::take(mapping)
+                            .into_iter()
+                            .rev()
+                            .map(|pair| Work::Pair(Cow::Owned(pair))),
+                    );
+
+                    Event::MappingStart {
+                        tag,
+                        anchor: None,
+                        style: None,
+                    }
+                }
+                Node::Sequence(items) => {
+                    self.stack.push(Work::Event(Event::SequenceEnd));
+                    self.stack.extend(
+                        mem::take(items)
+                            .into_iter()
+                            .rev()
+                            .map(|item| Work::Node(Cow::Owned(item), None)),
+                    );
+
+                    Event::SequenceStart {
+                        tag,
+                        anchor: None,
+                        style: None,
+                    }
+                }
+                scalar => {
+                    let tag = tag.or_else(|| Some(scalar.uri()));
+                    let value = match scalar {
+                        Node::String(s) => mem::take(s),
+                        scalar => scalar_value(scalar),
+                    };
+
+                    Event::scalar(value, tag)
+                }
+            },
+        };
+
+        self.comment = comment;
+        Some(event)
+    }
+}
+
+/// The pending work of [`NodeEventIter`]: a node followed by its inline
+/// comment, a mapping pair or an event, like the end of a collection.
+#[derive(Debug)]
+enum Work<'a> {
+    Node(Cow<'a, Node>, Option<String>),
+    Pair(Cow<'a, MappingPair>),
+    Event(Event),
+}
+
+/// Returns the value of the scalar event of the untagged `scalar`.
+fn scalar_value(scalar: &Node) -> String {
+    match scalar {
+        Node::String(s) => s.clone(),
+        Node::Null => "null".into(),
+        Node::Boolean(b) => b.to_string(),
+        Node::Integer(i) => i.to_string(),
+        Node::BigInteger(i) => i.to_string(),
+        Node::FloatingPoint(f) => f.to_string(),
+        Node::Binary(b) => BASE64.encode(b),
+        Node::Timestamp(t) => t.to_string(),
+        Node::Mapping(_) | Node::Sequence(_) | Node::Tagged(_) => unreachable!(),
+    }
+}
+
+impl Drop for Node {
+    /// Drops nested collections using a stack on the heap instead of
+    /// recursion, like [`NodeEventIter`] visits them. Nodes are dropped
+    /// after their children were moved onto the stack.
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        move_children(self, &mut stack);
+
+        while let Some(mut node) = stack.pop() {
+            move_children(&mut node, &mut stack);
         }
+    }
+}
 
-        events
+/// Moves the children of `node` onto the `stack`.
+fn move_children(node: &mut Node, stack: &mut Vec<Node>) {
+    match node {
+        Node::Mapping(mapping) => stack.extend(
+            mem::take(mapping)
+                .into_iter()
+                .flat_map(|pair| [pair.key, pair.value]),
+        ),
+        Node::Sequence(items) => stack.append(items),
+        Node::Tagged(tagged) => stack.push(mem::take(&mut tagged.node)),
+        _ => {}
     }
 }
 
 impl Node {
+    /// Returns the events of the node like [`IntoEvents::into_events`], but
+    /// produces them lazily. See [`Stream::into_events_iter`].
+    pub fn into_events_iter(self) -> NodeEventIter<'static> {
+        NodeEventIter::new(Cow::Owned(self))
+    }
+
+    /// Returns the events of the node like [`Node::into_events_iter`], but
+    /// borrows the node instead of consuming it.
+    pub fn events_iter(&self) -> NodeEventIter<'_> {
+        NodeEventIter::new(Cow::Borrowed(self))
+    }
+
+    /// Returns the tag of the node. Tagged nodes return their explicit tag,
+    /// all other nodes the Core schema tag implied by their type.
     pub fn uri(&self) -> String {
         use Node::*;
 
@@ -222,12 +1039,17 @@ impl Node {
             String(_) => "tag:yaml.org,2002:str",
             Null => "tag:yaml.org,2002:null",
             Boolean(_) => "tag:yaml.org,2002:bool",
-            Integer(_) => "tag:yaml.org,2002:int",
+            Integer(_) | BigInteger(_) => "tag:yaml.org,2002:int",
             FloatingPoint(_) => "tag:yaml.org,2002:float",
+            Binary(_) => "tag:yaml.org,2002:binary",
+            Timestamp(_) => "tag:yaml.org,2002:timestamp",
+            Tagged(tagged) => &tagged.tag,
         }
         .into()
     }
 
+    /// Returns the kind of the node. Tags are looked through, a tagged
+    /// sequence is a [`Kind::Sequence`]. Nodes are never [`Kind::Alias`].
     pub fn kind(&self) -> Kind {
         use Node::*;
 
@@ -238,10 +1060,74 @@ impl Node {
             Null => Kind::Scalar,
             Boolean(_) => Kind::Scalar,
             Integer(_) => Kind::Scalar,
+            BigInteger(_) => Kind::Scalar,
             FloatingPoint(_) => Kind::Scalar,
+            Binary(_) => Kind::Scalar,
+            Timestamp(_) => Kind::Scalar,
+            Tagged(tagged) => tagged.node.kind(),
+        }
+    }
+
+    /// Returns the explicit tag of the node, or [`None`] if its tag is
+    /// implied by its type.
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            Node::Tagged(tagged) => Some(&tagged.tag),
+            _ => None,
         }
     }
 
+    /// Returns the tag of the node's content, ignoring explicit tags. For
+    /// `!Ref Bucket` this is the tag of strings, while [`Node::uri`]
+    /// returns `!Ref`.
+    pub fn resolved_tag(&self) -> String {
+        self.untagged().uri()
+    }
+
+    /// Returns the node at the dot-separated `path`, e.g. `metadata.name`
+    /// or `spec.containers.0.image`. Segments select the value of a string
+    /// key in mappings and the item at an index in sequences. Tags are
+    /// looked through. An empty path returns the node itself.
+    pub fn get_path(&self, path: &str) -> Option<&Node> {
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        path.split('.').try_fold(self, |node, segment| {
+            let mut node = node;
+            while let Node::Tagged(tagged) = node {
+                node = &tagged.node;
+            }
+
+            match node {
+                Node::Mapping(mapping) => mapping.get(segment),
+                Node::Sequence(items) => items.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns a mutable reference to the node at the dot-separated `path`,
+    /// see [`Node::get_path`].
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Node> {
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        path.split('.').try_fold(self, |node, segment| {
+            let mut node = node;
+            while let Node::Tagged(tagged) = node {
+                node = &mut tagged.node;
+            }
+
+            match node {
+                Node::Mapping(mapping) => mapping.get_mut(segment),
+                Node::Sequence(items) => items.get_mut(segment.parse::<usize>().ok()?),
+                _ => None,
+            }
+        })
+    }
+
     pub fn as_name(&self) -> Option<&String> {
         use Node::*;
 
@@ -252,11 +1138,26 @@ impl Node {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Kind {
     Sequence,
     Mapping,
     Scalar,
+
+    /// An alias of an anchored node. Only nodes of the [`cst`] are aliases,
+    /// the aliases of the AST are resolved.
+    Alias,
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Kind::Sequence => "sequence",
+            Kind::Mapping => "mapping",
+            Kind::Scalar => "scalar",
+            Kind::Alias => "alias",
+        })
+    }
 }
 
 #[cfg(test)]
@@ -301,4 +1202,256 @@ mod test {
         // println!("{events:?}");
         println!("{output}")
     }
+
+    #[test]
+    fn stream_helpers() {
+        let kinds = ["Service", "Deployment", "ConfigMap"];
+        let mut stream = Stream::from_documents(
+            kinds
+                .iter()
+                .map(|kind| {
+                    Document::from_mapping(Mapping::from([(
+                        Node::String("kind".into()),
+                        Node::String(kind.to_string()),
+                    )]))
+                })
+                .collect(),
+        );
+
+        assert_eq!(stream.len(), 3);
+        assert!(stream.get(3).is_none());
+        assert!(stream.remove(3).is_none());
+
+        stream.retain(|doc| {
+            doc.nodes[0]
+                != Node::Mapping(Mapping::from([(
+                    Node::String("kind".into()),
+                    Node::String("ConfigMap".into()),
+                )]))
+        });
+        assert_eq!(stream.len(), 2);
+
+        let service = stream.remove(0).unwrap();
+        stream.insert(1, service);
+
+        for doc in stream.iter_mut() {
+            doc.blank_lines = 1;
+        }
+
+        let kinds: Vec<_> = stream
+            .iter()
+            .map(|doc| match &doc.nodes[0] {
+                Node::Mapping(mapping) => mapping[0].value.clone(),
+                _ => Node::Null,
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                Node::String("Deployment".into()),
+                Node::String("Service".into())
+            ]
+        );
+        assert!(stream.iter().all(|doc| doc.blank_lines == 1));
+    }
+
+    #[test]
+    fn sort_documents() {
+        let manifest = |kind: &str, name: &str| {
+            Document::from_mapping(Mapping::from([
+                (Node::String("kind".into()), Node::String(kind.into())),
+                (
+                    Node::String("metadata".into()),
+                    Node::Mapping(Mapping::from([(
+                        Node::String("name".into()),
+                        Node::String(name.into()),
+                    )])),
+                ),
+            ]))
+        };
+
+        let mut stream = Stream::from_documents(vec![
+            manifest("Service", "web"),
+            manifest("Deployment", "web"),
+            manifest("Service", "api"),
+            Document::new(),
+        ]);
+
+        let names = |stream: &Stream| -> Vec<_> {
+            stream
+                .iter()
+                .map(|doc| {
+                    let root = doc.nodes.first();
+                    let path = |path| root.and_then(|root| root.get_path(path)).cloned();
+                    (path("kind"), path("metadata.name"))
+                })
+                .collect()
+        };
+
+        stream.sort_documents_by_paths(&["kind", "metadata.name"]);
+        assert_eq!(
+            names(&stream),
+            [
+                (None, None),
+                (
+                    Some(Node::String("Deployment".into())),
+                    Some(Node::String("web".into()))
+                ),
+                (
+                    Some(Node::String("Service".into())),
+                    Some(Node::String("api".into()))
+                ),
+                (
+                    Some(Node::String("Service".into())),
+                    Some(Node::String("web".into()))
+                ),
+            ]
+        );
+
+        stream.sort_documents_by(|root| {
+            root.and_then(|root| root.get_path("metadata.name"))
+                .cloned()
+        });
+        assert_eq!(
+            names(&stream)[1..],
+            [
+                (
+                    Some(Node::String("Service".into())),
+                    Some(Node::String("api".into()))
+                ),
+                (
+                    Some(Node::String("Deployment".into())),
+                    Some(Node::String("web".into()))
+                ),
+                (
+                    Some(Node::String("Service".into())),
+                    Some(Node::String("web".into()))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_at_keys() {
+        let mut document = Document::from_mapping(Mapping::from([
+            (Node::String("name".into()), Node::String("web".into())),
+            (Node::String("server".into()), Node::Integer(80)),
+            (Node::Integer(1), Node::Null),
+            (Node::String("database".into()), Node::Boolean(true)),
+        ]));
+        document.push_directive(Directive::yaml_1_2());
+
+        let stream = document.split_at_keys(&["database", "server", "missing"]);
+        let roots: Vec<_> = stream.iter().map(|doc| doc.nodes.clone()).collect();
+
+        assert_eq!(
+            roots,
+            [
+                vec![Node::Mapping(Mapping::from([
+                    (Node::String("name".into()), Node::String("web".into())),
+                    (Node::Integer(1), Node::Null),
+                ]))],
+                vec![Node::Mapping(Mapping::from([(
+                    Node::String("server".into()),
+                    Node::Integer(80)
+                )]))],
+                vec![Node::Mapping(Mapping::from([(
+                    Node::String("database".into()),
+                    Node::Boolean(true)
+                )]))],
+            ]
+        );
+        assert!(stream
+            .iter()
+            .all(|doc| doc.directives == [Directive::yaml_1_2()]));
+
+        let scalar = Document {
+            nodes: vec![Node::Null],
+            ..Default::default()
+        };
+        assert_eq!(scalar.split_at_keys(&["a"]).len(), 1);
+    }
+
+    #[test]
+    fn flatten_into_mapping() {
+        let manifest = |kind: &str| {
+            Document::from_mapping(Mapping::from([(
+                Node::String("kind".into()),
+                Node::String(kind.into()),
+            )]))
+        };
+
+        let stream = Stream::from_documents(vec![
+            manifest("Service"),
+            Document::new(),
+            manifest("Deployment"),
+        ]);
+
+        let document = stream.flatten_into_mapping(|index, root| {
+            root.and_then(|root| root.get_path("kind"))
+                .cloned()
+                .unwrap_or(Node::Integer(index as i64))
+        });
+
+        assert_eq!(
+            document.nodes,
+            [Node::Mapping(Mapping::from([
+                (
+                    Node::String("Service".into()),
+                    manifest("Service").nodes[0].clone()
+                ),
+                (Node::Integer(1), Node::Null),
+                (
+                    Node::String("Deployment".into()),
+                    manifest("Deployment").nodes[0].clone()
+                ),
+            ]))]
+        );
+    }
+
+    #[test]
+    fn dedup_documents_by() {
+        let manifest = |name: &str, replicas: i64| {
+            Document::from_mapping(Mapping::from([
+                ("name".into(), name.into()),
+                ("replicas".into(), replicas.into()),
+            ]))
+        };
+        let stream = Stream::from_documents(vec![
+            manifest("web", 1),
+            manifest("db", 1),
+            manifest("web", 2),
+        ]);
+        let name = |root: Option<&Node>| root.and_then(|root| root.get_path("name")).cloned();
+        let replicas = |stream: &Stream| {
+            stream
+                .iter()
+                .filter_map(|document| document.nodes[0].get_path("replicas")?.as_i64())
+                .collect::<Vec<_>>()
+        };
+
+        let mut first = stream.clone();
+        first
+            .dedup_documents_by(name, DuplicatePolicy::KeepFirst)
+            .unwrap();
+        assert_eq!(replicas(&first), [1, 1]);
+
+        let mut last = stream.clone();
+        last.dedup_documents_by(name, DuplicatePolicy::KeepLast)
+            .unwrap();
+        assert_eq!(replicas(&last), [1, 2]);
+
+        let mut error = stream.clone();
+        assert_eq!(
+            error
+                .dedup_documents_by(name, DuplicatePolicy::Error)
+                .map(|_| ()),
+            Err(Error::DuplicateDocument {
+                first: 0,
+                duplicate: 2
+            })
+        );
+        assert_eq!(error.len(), 3);
+    }
 }
@@ -0,0 +1,207 @@
+//! Limits which protect against pathological documents, e.g. when parsing
+//! YAML from untrusted sources.
+//!
+//! Deeply nested collections exhaust the stack of recursive algorithms and
+//! aliases can expand a small document into an enormous one, known as the
+//! "billion laughs" attack. The [`Limits`] are enforced by the
+//! [`parser`](crate::parser) and when resolving a [`Cst`](crate::cst::Cst)
+//! into an AST, which expands aliases.
+//!
+//! ```
+//! use yaml_ast::{cst::Cst, cst::Error, limits::Limit};
+//!
+//! let input = "a: &a [1, 1, 1, 1, 1, 1, 1, 1, 1, 1]
+//! b: &b [*a, *a, *a, *a, *a, *a, *a, *a, *a, *a]
+//! c: &c [*b, *b, *b, *b, *b, *b, *b, *b, *b, *b]
+//! d: &d [*c, *c, *c, *c, *c, *c, *c, *c, *c, *c]
+//! ";
+//!
+//! let cst = Cst::parse(input).unwrap();
+//! assert!(matches!(
+//!     cst.to_stream(),
+//!     Err(Error::LimitExceeded {
+//!         limit: Limit::AliasExpansion(100),
+//!         ..
+//!     })
+//! ));
+//! ```
+use std::fmt;
+
+/// The maximum nesting depth of [`Limits::none`]. Parsing and resolving
+/// collections of this depth fits into the stack of the main thread.
+pub const MAX_DEPTH: usize = 512;
+
+/// The limits enforced while parsing and resolving a character stream. The
+/// defaults allow all reasonable documents, use [`Limits::none`] to lift
+/// the limits for trusted input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum nesting depth of collections. The root collection has
+    /// a depth of 1. Defaults to 256. [`None`] disables the limit, which
+    /// lets deeply nested input overflow the stack, see [`Limits::none`].
+    pub max_depth: Option<usize>,
+
+    /// The maximum number of nodes of a stream, counting the nodes of
+    /// expanded aliases. Unlimited by default.
+    pub max_nodes: Option<usize>,
+
+    /// The maximum factor by which aliases may increase the number of nodes
+    /// of a stream. Resolving a stream of `n` nodes may result in at most
+    /// `n * max_alias_expansion` nodes. Defaults to 100.
+    pub max_alias_expansion: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(256),
+            max_nodes: None,
+            max_alias_expansion: Some(100),
+        }
+    }
+}
+
+impl Limits {
+    /// Disables the limits of the number of nodes and of the alias
+    /// expansion. The parser and the resolver are recursive, which is why
+    /// the nesting depth is still limited to [`MAX_DEPTH`]: deeper input
+    /// would overflow the stack and abort the process instead of failing
+    /// with an error.
+    pub fn none() -> Self {
+        Self {
+            max_depth: Some(MAX_DEPTH),
+            max_nodes: None,
+            max_alias_expansion: None,
+        }
+    }
+}
+
+/// A limit of the [`Limits`] which was exceeded, with its configured value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Depth(usize),
+    Nodes(usize),
+    AliasExpansion(usize),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Depth(depth) => write!(f, "maximum nesting depth of {depth}"),
+            Limit::Nodes(nodes) => write!(f, "maximum number of {nodes} nodes"),
+            Limit::AliasExpansion(factor) => {
+                write!(f, "maximum alias expansion factor of {factor}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+    use crate::{
+        cst::{Cst, Error, ResolveOptions},
+        events::PathSegment,
+        parser::{self, Location, ParseOptions},
+    };
+
+    #[test]
+    fn depth() {
+        let input = format!("{}{}", "[".repeat(300), "]".repeat(300));
+        assert!(matches!(
+            Cst::parse(input.as_str()),
+            Err(parser::Error::LimitExceeded {
+                limit: Limit::Depth(256),
+                position: Location {
+                    line: 1,
+                    column: 257
+                }
+            })
+        ));
+
+        let options = ParseOptions::builder().limits(Limits::none()).build();
+        assert!(Cst::parse_with(input, &options).is_ok());
+
+        // Trusted input is still limited to a depth which fits into the
+        // stack of the main thread
+        let parse = |depth: usize| {
+            let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+            thread::Builder::new()
+                .stack_size(8 << 20)
+                .spawn(move || {
+                    let options = ParseOptions::builder().limits(Limits::none()).build();
+                    let cst = Cst::parse_with(input, &options)?;
+
+                    let options = ResolveOptions::builder().limits(Limits::none()).build();
+                    Ok(cst.to_stream_with(&options).is_ok())
+                })
+                .unwrap()
+                .join()
+                .unwrap()
+        };
+
+        assert!(matches!(parse(MAX_DEPTH), Ok(true)));
+        assert!(matches!(
+            parse(MAX_DEPTH + 1),
+            Err(parser::Error::LimitExceeded {
+                limit: Limit::Depth(MAX_DEPTH),
+                ..
+            })
+        ));
+
+        // Aliases may nest anchored collections beyond the parsed depth
+        let cst = Cst::parse("a: &a [[1]]\nb: [[*a]]\n").unwrap();
+        let options = ResolveOptions::builder()
+            .limits(Limits {
+                max_depth: Some(4),
+                ..Default::default()
+            })
+            .build();
+        assert!(matches!(
+            cst.to_stream_with(&options),
+            Err(Error::LimitExceeded {
+                limit: Limit::Depth(4),
+                path
+            }) if path == [PathSegment::Key("b".into()), PathSegment::Index(0), PathSegment::Index(0)]
+        ));
+    }
+
+    #[test]
+    fn nodes() {
+        let input = "a: &a [1, 2, 3]\nb: [*a, *a]\n";
+        let limits = Limits {
+            max_nodes: Some(12),
+            ..Default::default()
+        };
+
+        let options = ParseOptions::builder().limits(limits).build();
+        assert!(Cst::parse_with(input, &options).is_ok());
+
+        let cst = Cst::parse(input).unwrap();
+        let options = ResolveOptions::builder().limits(limits).build();
+        assert!(matches!(
+            cst.to_stream_with(&options),
+            Err(Error::LimitExceeded {
+                limit: Limit::Nodes(12),
+                ..
+            })
+        ));
+
+        let options = ParseOptions::builder()
+            .limits(Limits {
+                max_nodes: Some(5),
+                ..Default::default()
+            })
+            .build();
+        assert!(matches!(
+            Cst::parse_with(input, &options),
+            Err(parser::Error::LimitExceeded {
+                limit: Limit::Nodes(5),
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,477 @@
+//! Linting of YAML text, like `yamllint`.
+//!
+//! A [`Linter`] runs a set of [`Rule`]s against the [`Cst`] of the input,
+//! which keeps all presentation details like whitespace and indentation.
+//! Each finding is reported as a [`Problem`] with the span and location of
+//! the offending text and the severity configured for its rule.
+//!
+//! ```
+//! use yaml_ast::lint::Linter;
+//!
+//! let problems = Linter::default()
+//!     .lint("enabled: yes\nname: web \nname: api\n")
+//!     .unwrap();
+//!
+//! let problems: Vec<_> = problems.iter().map(ToString::to_string).collect();
+//! assert_eq!(
+//!     problems,
+//!     [
+//!         "1:10: warning: truthy value \"yes\" is a string in YAML 1.2 (truthy)",
+//!         "2:10: warning: trailing spaces (trailing-spaces)",
+//!         "3:1: error: duplicate key \"name\" (duplicate-keys)",
+//!     ]
+//! );
+//! ```
+//!
+//! Custom rules implement [`Rule`] and are added with [`Linter::with_rule`].
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    cst::{Cst, CstKind, CstNode, ScalarStyle},
+    emitter::Severity,
+    events::CollectionStyle,
+    parser::{self, Location, Span},
+};
+
+/// A check of the source text or the structure of a [`Cst`].
+pub trait Rule: fmt::Debug {
+    /// The name of the rule in [`Problem`]s, like `trailing-spaces`.
+    fn name(&self) -> &str;
+
+    /// Returns the violations of the rule found in `cst`.
+    fn check(&self, cst: &Cst) -> Vec<Violation>;
+}
+
+/// A violation of a [`Rule`], located by the span of the offending text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Violation {
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+/// A [`Violation`] reported by a [`Linter`], with the rule it violates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub rule: String,
+    pub severity: Severity,
+    pub span: Span,
+
+    /// The location of the start of the span.
+    pub location: Location,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(
+            f,
+            "{}:{}: {severity}: {} ({})",
+            self.location.line, self.location.column, self.message, self.rule
+        )
+    }
+}
+
+/// Runs [`Rule`]s with their configured [`Severity`].
+///
+/// [`Linter::default`] runs all built-in rules, [`Linter::new`] starts
+/// without any rules.
+#[derive(Debug)]
+pub struct Linter {
+    rules: Vec<(Box<dyn Rule>, Severity)>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+            .with_rule(TrailingSpaces, Severity::Warning)
+            .with_rule(DuplicateKeys, Severity::Error)
+            .with_rule(Truthy, Severity::Warning)
+            .with_rule(LineLength::default(), Severity::Warning)
+            .with_rule(Indentation, Severity::Warning)
+            .with_rule(EmptyValues, Severity::Warning)
+    }
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Adds the `rule`, whose violations are reported with `severity`.
+    pub fn with_rule(mut self, rule: impl Rule + 'static, severity: Severity) -> Self {
+        self.rules.push((Box::new(rule), severity));
+        self
+    }
+
+    /// Parses `input` and lints its CST, see [`Linter::lint_cst`].
+    pub fn lint(&self, input: &str) -> Result<Vec<Problem>, parser::Error> {
+        Ok(self.lint_cst(&Cst::parse(input)?))
+    }
+
+    /// Runs all rules against `cst` and returns the problems found, ordered
+    /// by their position in the source.
+    pub fn lint_cst(&self, cst: &Cst) -> Vec<Problem> {
+        let mut problems: Vec<_> = self
+            .rules
+            .iter()
+            .flat_map(|(rule, severity)| {
+                rule.check(cst).into_iter().map(|violation| Problem {
+                    rule: rule.name().to_string(),
+                    severity: *severity,
+                    span: violation.span,
+                    location: Location::from_offset(cst.source(), violation.span.start),
+                    message: violation.message,
+                })
+            })
+            .collect();
+
+        problems.sort_by_key(|problem| problem.span);
+        problems
+    }
+}
+
+/// Reports spaces and tabs at the end of lines.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrailingSpaces;
+
+impl Rule for TrailingSpaces {
+    fn name(&self) -> &str {
+        "trailing-spaces"
+    }
+
+    fn check(&self, cst: &Cst) -> Vec<Violation> {
+        lines(cst.source())
+            .filter_map(|(start, line)| {
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                let trimmed = line.trim_end_matches([' ', '\t']);
+
+                (trimmed.len() < line.len()).then(|| {
+                    let span = Span {
+                        start: start + trimmed.len(),
+                        end: start + line.len(),
+                    };
+                    Violation::new(span, "trailing spaces")
+                })
+            })
+            .collect()
+    }
+}
+
+/// Reports lines which are longer than `max` characters.
+#[derive(Clone, Copy, Debug)]
+pub struct LineLength {
+    pub max: usize,
+}
+
+impl Default for LineLength {
+    fn default() -> Self {
+        Self { max: 80 }
+    }
+}
+
+impl Rule for LineLength {
+    fn name(&self) -> &str {
+        "line-length"
+    }
+
+    fn check(&self, cst: &Cst) -> Vec<Violation> {
+        lines(cst.source())
+            .filter_map(|(start, line)| {
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                let len = line.chars().count();
+                let (offset, _) = line.char_indices().nth(self.max)?;
+
+                let span = Span {
+                    start: start + offset,
+                    end: start + line.len(),
+                };
+                Some(Violation::new(
+                    span,
+                    format!("line too long ({len} > {} characters)", self.max),
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Reports keys which occur more than once in a mapping, compared by their
+/// scalar value.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DuplicateKeys;
+
+impl Rule for DuplicateKeys {
+    fn name(&self) -> &str {
+        "duplicate-keys"
+    }
+
+    fn check(&self, cst: &Cst) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        walk(cst, &mut |node| {
+            let CstKind::Mapping { pairs, .. } = &node.kind else {
+                return;
+            };
+
+            let mut keys = HashSet::new();
+            for pair in pairs {
+                if let CstKind::Scalar { value, .. } = &pair.key.kind {
+                    if !keys.insert(value) {
+                        violations.push(Violation::new(
+                            pair.key.span,
+                            format!("duplicate key {value:?}"),
+                        ));
+                    }
+                }
+            }
+        });
+
+        violations
+    }
+}
+
+/// Reports plain scalars which YAML 1.1 resolves to booleans, but YAML 1.2
+/// doesn't, like `yes`, `off` or `True`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Truthy;
+
+/// The booleans of YAML 1.1 which are no booleans in YAML 1.2.
+///
+/// See <https://yaml.org/type/bool.html>
+const TRUTHY: [&str; 18] = [
+    "y", "Y", "yes", "Yes", "YES", "n", "N", "no", "No", "NO", "on", "On", "ON", "off", "Off",
+    "OFF", "TRUE", "FALSE",
+];
+
+impl Rule for Truthy {
+    fn name(&self) -> &str {
+        "truthy"
+    }
+
+    fn check(&self, cst: &Cst) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        walk(cst, &mut |node| {
+            if let CstKind::Scalar {
+                style: ScalarStyle::Plain,
+                value,
+            } = &node.kind
+            {
+                if node.tag.is_none() && TRUTHY.contains(&value.as_str()) {
+                    violations.push(Violation::new(
+                        node.span,
+                        format!("truthy value {value:?} is a string in YAML 1.2"),
+                    ));
+                }
+            }
+        });
+
+        violations
+    }
+}
+
+/// Reports block collections whose indentation differs from the first
+/// indented collection of the stream. Sequences which aren't indented
+/// relative to their key, like `key:\n- item`, and collections starting on
+/// the line of their parent, like `- key: value`, are accepted.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Indentation;
+
+impl Rule for Indentation {
+    fn name(&self) -> &str {
+        "indentation"
+    }
+
+    fn check(&self, cst: &Cst) -> Vec<Violation> {
+        let source = cst.source();
+        let column = |node: &CstNode| Location::from_offset(source, node.span.start).column;
+        let starts_line = |node: &CstNode| {
+            let line_start = source[..node.span.start].rfind('\n').map_or(0, |i| i + 1);
+            source[line_start..node.span.start].trim().is_empty()
+        };
+
+        let mut expected = None;
+        let mut violations = Vec::new();
+
+        walk(cst, &mut |node| {
+            let children: Vec<&CstNode> = match &node.kind {
+                CstKind::Mapping {
+                    style: CollectionStyle::Block,
+                    pairs,
+                } => pairs.iter().map(|pair| &pair.value).collect(),
+                CstKind::Sequence {
+                    style: CollectionStyle::Block,
+                    items,
+                } => items.iter().map(|item| &item.node).collect(),
+                _ => return,
+            };
+
+            for child in children {
+                let is_block = matches!(
+                    &child.kind,
+                    CstKind::Mapping {
+                        style: CollectionStyle::Block,
+                        ..
+                    } | CstKind::Sequence {
+                        style: CollectionStyle::Block,
+                        ..
+                    }
+                );
+                if !is_block || !starts_line(child) {
+                    continue;
+                }
+
+                let indent = column(child).saturating_sub(column(node));
+                let is_indentless = indent == 0
+                    && matches!(node.kind, CstKind::Mapping { .. })
+                    && matches!(child.kind, CstKind::Sequence { .. });
+                if is_indentless {
+                    continue;
+                }
+
+                match expected {
+                    None => expected = Some(indent),
+                    Some(expected) if expected != indent => violations.push(Violation::new(
+                        child.span,
+                        format!("wrong indentation: expected {expected} but found {indent} spaces"),
+                    )),
+                    Some(_) => {}
+                }
+            }
+        });
+
+        violations
+    }
+}
+
+/// Reports mapping values which are omitted, like `key:`, instead of being
+/// written as an explicit `null` or `~`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmptyValues;
+
+impl Rule for EmptyValues {
+    fn name(&self) -> &str {
+        "empty-values"
+    }
+
+    fn check(&self, cst: &Cst) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        walk(cst, &mut |node| {
+            let CstKind::Mapping { pairs, .. } = &node.kind else {
+                return;
+            };
+
+            for pair in pairs.iter().filter(|pair| pair.value.is_empty()) {
+                let message = match &pair.key.kind {
+                    CstKind::Scalar { value, .. } => format!("empty value of key {value:?}"),
+                    _ => "empty value".into(),
+                };
+                violations.push(Violation::new(pair.key.span, message));
+            }
+        });
+
+        violations
+    }
+}
+
+/// Returns the lines of `source` with the byte offset of their start. The
+/// lines include a trailing carriage return, but no line feed.
+fn lines(source: &str) -> impl Iterator<Item = (usize, &str)> {
+    source.split('\n').scan(0, |start, line| {
+        let line_start = *start;
+        *start += line.len() + 1;
+        Some((line_start, line))
+    })
+}
+
+/// Calls `f` for every node of all documents of `cst`, including keys, in
+/// source order.
+fn walk<'a>(cst: &'a Cst, f: &mut impl FnMut(&'a CstNode)) {
+    fn visit<'a>(node: &'a CstNode, f: &mut impl FnMut(&'a CstNode)) {
+        f(node);
+
+        match &node.kind {
+            CstKind::Mapping { pairs, .. } => {
+                for pair in pairs {
+                    visit(&pair.key, f);
+                    visit(&pair.value, f);
+                }
+            }
+            CstKind::Sequence { items, .. } => {
+                for item in items {
+                    visit(&item.node, f);
+                }
+            }
+            CstKind::Scalar { .. } | CstKind::Alias(_) => {}
+        }
+    }
+
+    for document in cst.documents() {
+        if let Some(root) = document.root() {
+            visit(root, f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lint(rule: impl Rule + 'static, input: &str) -> Vec<String> {
+        Linter::new()
+            .with_rule(rule, Severity::Warning)
+            .lint(input)
+            .unwrap()
+            .into_iter()
+            .map(|problem| format!("{}: {}", problem.location, problem.message))
+            .collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn rules() {
+        assert_eq!(
+            lint(LineLength { max: 10 }, "short: 1\nlonger: 123456\n"),
+            ["line 2 column 11: line too long (14 > 10 characters)"]
+        );
+        assert_eq!(
+            lint(
+                Indentation,
+                "a:\n  b:\n    - c: 1\n      d:\n         e: 2\n  f:\n  - 3\n"
+            ),
+            ["line 5 column 10: wrong indentation: expected 2 but found 3 spaces"]
+        );
+        assert_eq!(
+            lint(EmptyValues, "a:\nb: ~\nc: {d: }\n"),
+            [
+                "line 1 column 1: empty value of key \"a\"",
+                "line 3 column 5: empty value of key \"d\"",
+            ]
+        );
+        assert_eq!(
+            lint(Truthy, "[on, \"off\", !!bool yes, true]"),
+            ["line 1 column 2: truthy value \"on\" is a string in YAML 1.2"]
+        );
+        assert_eq!(
+            lint(TrailingSpaces, "a: 1 \r\nb: 2\t\n"),
+            [
+                "line 1 column 5: trailing spaces",
+                "line 2 column 5: trailing spaces",
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! The [`yaml!`](crate::yaml) macro to construct [`Node`](crate::Node)s.
+
+/// Constructs a [`Node`](crate::Node) from a YAML-like flow syntax.
+///
+/// Mapping keys can be identifiers (which become strings), literals or
+/// parenthesized expressions. Values can be `null`, nested sequences and
+/// mappings, or any expression which converts into a node using [`From`].
+/// The outer braces of a mapping can be omitted.
+///
+/// ```
+/// use yaml_ast::{yaml, Mapping, Node};
+///
+/// let replicas = 3;
+/// let node = yaml!({
+///     name: "web",
+///     replicas: replicas,
+///     "app.kubernetes.io/part-of": null,
+///     ports: [80, 443, {port: 8080, internal: true}],
+///     (1.5): -1,
+/// });
+///
+/// assert_eq!(node.get_path("ports.2.port"), Some(&Node::Integer(8080)));
+/// assert_eq!(
+///     yaml! { key: [] },
+///     Node::from(Mapping::from([("key".into(), Node::Sequence(vec![]))]))
+/// );
+/// ```
+#[macro_export]
+macro_rules! yaml {
+    (null) => {
+        $crate::Node::Null
+    };
+    ([$($seq:tt)*]) => {
+        $crate::Node::Sequence($crate::yaml!(@seq [] $($seq)*))
+    };
+    ({$($map:tt)*}) => {
+        $crate::Node::Mapping($crate::yaml!(@map [] $($map)*))
+    };
+    ($key:tt : $($rest:tt)*) => {
+        $crate::Node::Mapping($crate::yaml!(@map [] $key : $($rest)*))
+    };
+    ($other:expr) => {
+        $crate::Node::from($other)
+    };
+
+    // Munches the items of a sequence, which are accumulated in brackets
+    (@seq [$($items:expr,)*]) => {
+        ::std::vec![$($items,)*]
+    };
+    (@seq [$($items:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::yaml!(@seq [$($items,)* $crate::Node::Null,] $($($rest)*)?)
+    };
+    (@seq [$($items:expr,)*] [$($seq:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::yaml!(@seq [$($items,)* $crate::yaml!([$($seq)*]),] $($($rest)*)?)
+    };
+    (@seq [$($items:expr,)*] {$($map:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::yaml!(@seq [$($items,)* $crate::yaml!({$($map)*}),] $($($rest)*)?)
+    };
+    (@seq [$($items:expr,)*] $value:expr $(, $($rest:tt)*)?) => {
+        $crate::yaml!(@seq [$($items,)* $crate::Node::from($value),] $($($rest)*)?)
+    };
+
+    // Munches the pairs of a mapping, which are accumulated as
+    // `(key) (value)` in brackets
+    (@map [$(($key:expr) ($value:expr))*]) => {{
+        #[allow(unused_mut)]
+        let mut mapping = $crate::Mapping::new();
+        $(mapping.push_pair($key, $value);)*
+        mapping
+    }};
+    (@map [$($pairs:tt)*] $key:tt : null $(, $($rest:tt)*)?) => {
+        $crate::yaml!(@map [$($pairs)* ($crate::yaml!(@key $key)) ($crate::Node::Null)] $($($rest)*)?)
+    };
+    (@map [$($pairs:tt)*] $key:tt : [$($seq:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::yaml!(@map [$($pairs)* ($crate::yaml!(@key $key)) ($crate::yaml!([$($seq)*]))] $($($rest)*)?)
+    };
+    (@map [$($pairs:tt)*] $key:tt : {$($map:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::yaml!(@map [$($pairs)* ($crate::yaml!(@key $key)) ($crate::yaml!({$($map)*}))] $($($rest)*)?)
+    };
+    (@map [$($pairs:tt)*] $key:tt : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::yaml!(@map [$($pairs)* ($crate::yaml!(@key $key)) ($crate::Node::from($value))] $($($rest)*)?)
+    };
+
+    (@key $key:ident) => {
+        $crate::Node::String(::std::stringify!($key).into())
+    };
+    (@key $key:expr) => {
+        $crate::Node::from($key)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Mapping, Node, Sequence};
+
+    #[test]
+    fn yaml() {
+        let image = String::from("nginx");
+        let node = yaml!({
+            spec: {
+                containers: [{name: "web", image: image.clone(), ports: [80, -443]}],
+                paused: false,
+                selector: null,
+            },
+            (1): [null, [], {}],
+        });
+
+        let expected = Node::Mapping(Mapping::from([
+            (
+                Node::String("spec".into()),
+                Node::Mapping(Mapping::from([
+                    (
+                        Node::String("containers".into()),
+                        Node::Sequence(Sequence::from([Node::Mapping(Mapping::from([
+                            (Node::String("name".into()), Node::String("web".into())),
+                            (Node::String("image".into()), Node::String(image)),
+                            (
+                                Node::String("ports".into()),
+                                Node::Sequence(Sequence::from([
+                                    Node::Integer(80),
+                                    Node::Integer(-443),
+                                ])),
+                            ),
+                        ]))])),
+                    ),
+                    (Node::String("paused".into()), Node::Boolean(false)),
+                    (Node::String("selector".into()), Node::Null),
+                ])),
+            ),
+            (
+                Node::Integer(1),
+                Node::Sequence(Sequence::from([
+                    Node::Null,
+                    Node::Sequence(Sequence::new()),
+                    Node::Mapping(Mapping::new()),
+                ])),
+            ),
+        ]));
+
+        assert_eq!(node, expected);
+        assert_eq!(yaml!(null), Node::Null);
+        assert_eq!(yaml!("a"), Node::String("a".into()));
+    }
+}
@@ -0,0 +1,499 @@
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+    slice,
+    sync::OnceLock,
+};
+
+use crate::{
+    events::{Event, IntoEvents},
+    Node,
+};
+
+// TODO (Techassi): Ensure keys are unique in mappings
+/// An ordered list of [`MappingPair`]s.
+///
+/// Mappings compare and hash by their keys and values only. Presentation
+/// details, like blank lines and comments, are ignored.
+///
+/// Lookups by key, like [`Mapping::get`], use a hash index of the scalar
+/// keys, which is built on the first lookup. Pairs appended with
+/// [`Mapping::push_pair`] or [`Mapping::push`] are added to the index, any
+/// other mutable access to the pairs discards it. Collection keys are kept
+/// in a list which is searched linearly.
+#[derive(Clone, Default)]
+pub struct Mapping {
+    pairs: Vec<MappingPair>,
+    index: OnceLock<KeyIndex>,
+}
+
+/// The positions of the first pair of each key of a [`Mapping`].
+#[derive(Clone, Debug, Default)]
+struct KeyIndex {
+    /// Untagged string keys, which can be looked up by `&str`.
+    strings: HashMap<String, usize>,
+
+    /// All other scalar keys.
+    scalars: HashMap<Node, usize>,
+
+    /// The positions of all pairs with collection keys.
+    complex: Vec<usize>,
+}
+
+impl KeyIndex {
+    fn new(pairs: &[MappingPair]) -> Self {
+        let mut index = Self::default();
+
+        for (position, pair) in pairs.iter().enumerate() {
+            index.insert(&pair.key, position);
+        }
+
+        index
+    }
+
+    fn insert(&mut self, key: &Node, position: usize) {
+        match key {
+            Node::String(key) => {
+                if let Entry::Vacant(entry) = self.strings.entry(key.clone()) {
+                    entry.insert(position);
+                }
+            }
+            key if key.untagged().is_mapping() || key.untagged().is_sequence() => {
+                self.complex.push(position)
+            }
+            key => {
+                if let Entry::Vacant(entry) = self.scalars.entry(key.clone()) {
+                    entry.insert(position);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Mapping").field(&self.pairs).finish()
+    }
+}
+
+impl Deref for Mapping {
+    type Target = Vec<MappingPair>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pairs
+    }
+}
+
+impl DerefMut for Mapping {
+    /// Discards the key index, as the pairs might be changed.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.index.take();
+        &mut self.pairs
+    }
+}
+
+impl<const N: usize> From<[(Node, Node); N]> for Mapping {
+    fn from(pairs: [(Node, Node); N]) -> Self {
+        pairs.into_iter().collect()
+    }
+}
+
+impl FromIterator<(Node, Node)> for Mapping {
+    fn from_iter<T: IntoIterator<Item = (Node, Node)>>(iter: T) -> Self {
+        iter.into_iter()
+            .map(|(key, value)| MappingPair::new(key, value))
+            .collect()
+    }
+}
+
+impl FromIterator<MappingPair> for Mapping {
+    fn from_iter<T: IntoIterator<Item = MappingPair>>(iter: T) -> Self {
+        Self {
+            pairs: iter.into_iter().collect(),
+            index: OnceLock::new(),
+        }
+    }
+}
+
+impl Extend<(Node, Node)> for Mapping {
+    fn extend<T: IntoIterator<Item = (Node, Node)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.push_pair(key, value);
+        }
+    }
+}
+
+impl Extend<MappingPair> for Mapping {
+    fn extend<T: IntoIterator<Item = MappingPair>>(&mut self, iter: T) {
+        self.index.take();
+        self.pairs.extend(iter);
+    }
+}
+
+impl IntoIterator for Mapping {
+    type Item = MappingPair;
+    type IntoIter = std::vec::IntoIter<MappingPair>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+impl PartialEq for Mapping {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Mapping {}
+
+impl PartialOrd for Mapping {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Mapping {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl Hash for Mapping {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+
+        for (key, value) in self.iter() {
+            key.hash(state);
+            value.hash(state);
+        }
+    }
+}
+
+impl IntoEvents for Mapping {
+    fn into_events(self) -> Vec<Event> {
+        Node::Mapping(self).into_events()
+    }
+}
+
+impl Mapping {
+    /// Creates a new (empty) mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a pair consisting of `key` and `value` at the end of the
+    /// mapping.
+    pub fn push_pair(&mut self, key: Node, value: Node) -> &mut Self {
+        if let Some(index) = self.index.get_mut() {
+            index.insert(&key, self.pairs.len());
+        }
+
+        self.pairs.push(MappingPair::new(key, value));
+        self
+    }
+
+    /// Appends the `pair` at the end of the mapping, keeping its comments
+    /// and the key index.
+    pub fn push(&mut self, pair: MappingPair) {
+        if let Some(index) = self.index.get_mut() {
+            index.insert(&pair.key, self.pairs.len());
+        }
+
+        self.pairs.push(pair);
+    }
+
+    /// Returns a mutable reference to the value of the pair at `position`,
+    /// see [`Mapping::position`]. Changing the value keeps the key index.
+    pub fn value_at_mut(&mut self, position: usize) -> Option<&mut Node> {
+        self.pairs.get_mut(position).map(|pair| &mut pair.value)
+    }
+
+    /// Returns the value of the first pair whose key is the string `key`.
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        let position = *self.index().strings.get(key)?;
+        Some(&self.pairs[position].value)
+    }
+
+    /// Returns a mutable reference to the value of the first pair whose key
+    /// is the string `key`. Changing the value keeps the key index.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Node> {
+        let position = *self.index().strings.get(key)?;
+        Some(&mut self.pairs[position].value)
+    }
+
+    /// Returns the value of the first pair whose key equals `key`, which
+    /// can be any node.
+    pub fn get_node(&self, key: &Node) -> Option<&Node> {
+        let position = self.position(key)?;
+        Some(&self.pairs[position].value)
+    }
+
+    /// Returns `true` if a pair's key equals `key`.
+    pub fn contains_key(&self, key: &Node) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Returns the position of the first pair whose key equals `key`.
+    pub fn position(&self, key: &Node) -> Option<usize> {
+        let index = self.index();
+
+        match key {
+            Node::String(key) => index.strings.get(key).copied(),
+            key if key.untagged().is_mapping() || key.untagged().is_sequence() => index
+                .complex
+                .iter()
+                .copied()
+                .find(|position| self.pairs[*position].key == *key),
+            key => index.scalars.get(key).copied(),
+        }
+    }
+
+    /// Returns an iterator over the pairs, including their comments and
+    /// blank lines.
+    pub fn pairs(&self) -> slice::Iter<'_, MappingPair> {
+        self.pairs.iter()
+    }
+
+    /// Returns an iterator over the mutable pairs. This discards the key
+    /// index, as the keys might be changed.
+    pub fn pairs_mut(&mut self) -> slice::IterMut<'_, MappingPair> {
+        self.deref_mut().iter_mut()
+    }
+
+    /// Returns an iterator over the keys and values of the pairs, in order.
+    /// Use [`Mapping::pairs`] to access the pairs including their comments
+    /// and blank lines.
+    pub fn iter(&self) -> impl Iterator<Item = (&Node, &Node)> {
+        self.pairs.iter().map(|pair| (&pair.key, &pair.value))
+    }
+
+    /// Returns an iterator over the keys and mutable values of the pairs.
+    /// Changing the values keeps the key index.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Node, &mut Node)> {
+        self.pairs
+            .iter_mut()
+            .map(|pair| (&pair.key, &mut pair.value))
+    }
+
+    /// Consumes the mapping and returns its keys and values, dropping the
+    /// comments and blank lines of the pairs.
+    pub fn into_entries(self) -> impl Iterator<Item = (Node, Node)> {
+        self.pairs.into_iter().map(|pair| (pair.key, pair.value))
+    }
+
+    /// Returns an iterator over the keys of the pairs.
+    pub fn keys(&self) -> impl Iterator<Item = &Node> {
+        self.pairs.iter().map(|pair| &pair.key)
+    }
+
+    /// Returns an iterator over the values of the pairs.
+    pub fn values(&self) -> impl Iterator<Item = &Node> {
+        self.pairs.iter().map(|pair| &pair.value)
+    }
+
+    /// Returns an iterator over the mutable values of the pairs. Changing
+    /// the values keeps the key index.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Node> {
+        self.pairs.iter_mut().map(|pair| &mut pair.value)
+    }
+
+    fn index(&self) -> &KeyIndex {
+        self.index.get_or_init(|| KeyIndex::new(&self.pairs))
+    }
+}
+
+/// A single key/value pair of a [`Mapping`].
+#[derive(Clone, Debug)]
+pub struct MappingPair {
+    pub key: Node,
+    pub value: Node,
+
+    /// The number of blank lines emitted before this pair. This is used to
+    /// visually group pairs, e.g. sections of a configuration file.
+    pub blank_lines: usize,
+
+    /// Comments emitted on their own lines before this pair, without the
+    /// leading `#`.
+    pub comments: Vec<String>,
+
+    /// A comment emitted at the end of the line of the pair, i.e. after a
+    /// scalar value or after the key of a collection value.
+    pub comment: Option<String>,
+
+    /// Writes the key using the explicit key indicator, i.e. as `? key`
+    /// followed by `: value` on the next line. Collection keys and very
+    /// long keys are always written this way.
+    pub explicit_key: bool,
+}
+
+impl MappingPair {
+    pub fn new(key: Node, value: Node) -> Self {
+        Self {
+            key,
+            value,
+            blank_lines: 0,
+            comments: Vec::new(),
+            comment: None,
+            explicit_key: false,
+        }
+    }
+
+    pub fn key(&self) -> &Node {
+        &self.key
+    }
+
+    pub fn value(&self) -> &Node {
+        &self.value
+    }
+
+    pub fn key_mut(&mut self) -> &mut Node {
+        &mut self.key
+    }
+
+    pub fn value_mut(&mut self) -> &mut Node {
+        &mut self.value
+    }
+
+    /// Consumes the pair and returns its key and value.
+    pub fn into_parts(self) -> (Node, Node) {
+        (self.key, self.value)
+    }
+
+    /// Sets the comments emitted on their own lines before this pair,
+    /// replacing all previous ones.
+    pub fn with_leading_comments<I, C>(mut self, comments: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<String>,
+    {
+        self.comments = comments.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the number of blank lines emitted before this pair.
+    pub fn with_blank_lines(mut self, blank_lines: usize) -> Self {
+        self.blank_lines = blank_lines;
+        self
+    }
+
+    /// Adds a comment emitted on its own line before this pair.
+    pub fn with_leading_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comments.push(comment.into());
+        self
+    }
+
+    /// Sets the comment emitted at the end of the line of this pair.
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Sets whether the key is written using the explicit key indicator.
+    pub fn with_explicit_key(mut self, explicit_key: bool) -> Self {
+        self.explicit_key = explicit_key;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{yaml, Mapping, MappingPair, Node};
+
+    #[test]
+    fn key_index() {
+        let mut mapping = Mapping::from([
+            ("a".into(), 1.into()),
+            (Node::Integer(2), 2.into()),
+            (yaml!([1, 2]), 3.into()),
+            ("a".into(), 4.into()),
+        ]);
+
+        assert_eq!(mapping.get("a"), Some(&Node::Integer(1)));
+        assert_eq!(mapping.get_node(&Node::Integer(2)), Some(&Node::Integer(2)));
+        assert_eq!(mapping.position(&yaml!([1, 2])), Some(2));
+        assert!(!mapping.contains_key(&"b".into()));
+
+        mapping.push_pair("b".into(), 5.into());
+        assert_eq!(mapping.get("b"), Some(&Node::Integer(5)));
+
+        *mapping.get_mut("b").unwrap() = 6.into();
+        assert_eq!(mapping[4].value, Node::Integer(6));
+
+        mapping.push(MappingPair::new("c".into(), 7.into()).with_comment("seven"));
+        *mapping.value_at_mut(5).unwrap() = 8.into();
+        assert_eq!(mapping.get("c"), Some(&Node::Integer(8)));
+        assert!(mapping.index.get().is_some());
+
+        // Mutable access to the pairs discards the index
+        mapping.remove(0);
+        assert_eq!(mapping.get("a"), Some(&Node::Integer(4)));
+        assert_eq!(mapping.position(&"b".into()), Some(3));
+        assert_eq!(mapping.position(&"c".into()), Some(4));
+    }
+
+    #[test]
+    fn extend() {
+        let mut mapping: Mapping = ["a", "b"]
+            .into_iter()
+            .map(|key| (key.into(), Node::Null))
+            .collect();
+        assert_eq!(mapping.get("b"), Some(&Node::Null));
+
+        mapping.extend((1..3).map(|i| (Node::String(format!("c{i}")), i.into())));
+        mapping.extend([MappingPair::new("d".into(), 3.into())]);
+
+        assert_eq!(mapping.get("c2"), Some(&Node::Integer(2)));
+        assert_eq!(mapping.get("d"), Some(&Node::Integer(3)));
+        assert_eq!(mapping.len(), 5);
+    }
+
+    #[test]
+    fn pair() {
+        let mut pair = MappingPair::new("a".into(), 1.into())
+            .with_leading_comments(["first", "second"])
+            .with_comment("inline");
+
+        *pair.value_mut() = 2.into();
+        assert_eq!(pair.key(), &Node::from("a"));
+        assert_eq!(pair.value(), &Node::Integer(2));
+        assert_eq!(pair.comments, ["first", "second"]);
+
+        *pair.key_mut() = "b".into();
+        assert_eq!(pair.into_parts(), ("b".into(), 2.into()));
+    }
+
+    #[test]
+    fn iter() {
+        let mut mapping = Mapping::from([("a".into(), 1.into()), ("b".into(), 2.into())]);
+
+        for value in mapping.values_mut() {
+            *value = (value.as_i64().unwrap() * 10).into();
+        }
+        for (key, value) in mapping.iter_mut() {
+            if key.as_str() == Some("b") {
+                *value = Node::Null;
+            }
+        }
+
+        assert_eq!(
+            mapping.keys().collect::<Vec<_>>(),
+            [&Node::from("a"), &Node::from("b")]
+        );
+        assert_eq!(
+            mapping.values().collect::<Vec<_>>(),
+            [&Node::Integer(10), &Node::Null]
+        );
+        assert_eq!(mapping.get("a"), Some(&Node::Integer(10)));
+        assert_eq!(
+            mapping.clone().into_entries().collect::<Vec<_>>(),
+            mapping
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,615 @@
+//! Merging of [`Node`]s, e.g. to layer environment-specific overrides on top
+//! of a base configuration.
+//!
+//! [`Node::merge`] merges mappings recursively and replaces all other nodes
+//! by default. The [`MergeStrategy`] selects between plain deep merging and
+//! JSON Merge Patch (RFC 7386) semantics, where `null` values remove keys,
+//! and configures how sequences are combined, either everywhere or only at
+//! specific paths, like the containers of a Kubernetes pod which are merged
+//! by their `name`.
+//!
+//! ```
+//! use yaml_ast::{merge::{MergeStrategy, SequenceMerge}, yaml};
+//!
+//! let mut values = yaml!({
+//!     image: {tag: "1.0", pullPolicy: "Always"},
+//!     env: [{name: "LOG", value: "info"}]
+//! });
+//! let overrides = yaml!({
+//!     image: {tag: "1.1", pullPolicy: null},
+//!     env: [{name: "LOG", value: "debug"}, {name: "PORT", value: "80"}]
+//! });
+//!
+//! let strategy = MergeStrategy::merge_patch().with_sequences(SequenceMerge::MergeByKey("name".into()));
+//! values.merge(&overrides, &strategy).unwrap();
+//!
+//! assert_eq!(values, yaml!({
+//!     image: {tag: "1.1"},
+//!     env: [{name: "LOG", value: "debug"}, {name: "PORT", value: "80"}]
+//! }));
+//! ```
+//!
+//! #### Reference
+//!
+//! - <https://www.rfc-editor.org/rfc/rfc7386>
+use snafu::{ensure, Snafu};
+
+use crate::{
+    emitter::key_segment,
+    events::{display_path, matches_path, PathSegment},
+    provenance::Provenance,
+    Document, Mapping, Node,
+};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    /// The `base` and `other` node kinds conflict. When merging documents,
+    /// the origins are the [`Provenance`] of the conflicting nodes.
+    #[snafu(display(
+        "cannot merge the {other}{} into the {base}{} at {path}",
+        origin(other_origin),
+        origin(base_origin)
+    ))]
+    Conflict {
+        path: String,
+        base: String,
+        other: String,
+        base_origin: Option<Box<Provenance>>,
+        other_origin: Option<Box<Provenance>>,
+    },
+}
+
+/// Returns the origin of a conflicting node for the error message, e.g.
+/// ` from values.yaml`.
+fn origin(provenance: &Option<Box<Provenance>>) -> String {
+    let Some(provenance) = provenance else {
+        return String::new();
+    };
+
+    if let Some(source) = provenance.source() {
+        return format!(" from {source}");
+    }
+
+    match provenance.entries.is_empty() {
+        true => String::new(),
+        false => {
+            let entries: Vec<_> = provenance
+                .entries
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+            format!(" ({})", entries.join(", "))
+        }
+    }
+}
+
+/// The way sequences are combined by [`Node::merge`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SequenceMerge {
+    /// Replaces the base sequence with the other sequence.
+    #[default]
+    Replace,
+
+    /// Appends the items of the other sequence to the base sequence.
+    Append,
+
+    /// Merges mapping items which have the same value for the given key,
+    /// e.g. containers with the same `name`. All other items are appended.
+    MergeByKey(String),
+}
+
+/// Configures how [`Node::merge`] combines two nodes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeStrategy {
+    /// Removes mapping pairs whose value in the other node is `null`,
+    /// instead of setting their value to `null`.
+    pub remove_nulls: bool,
+
+    /// The way sequences are combined.
+    pub sequences: SequenceMerge,
+
+    /// The way sequences at specific paths are combined, overriding
+    /// [`MergeStrategy::sequences`]. The paths are dot-separated patterns,
+    /// where a `*` within a segment matches any number of characters and a
+    /// `**` segment matches any number of segments, e.g.
+    /// `spec.template.spec.containers` or `**.env`. The first matching
+    /// pattern wins.
+    pub sequences_at: Vec<(String, SequenceMerge)>,
+
+    /// Fails with [`Error::Conflict`] if a mapping or sequence would be
+    /// replaced by a node of another kind, or the other way around.
+    /// Replacing `null`, or replacing with `null`, is always allowed.
+    pub strict: bool,
+}
+
+impl MergeStrategy {
+    /// Deep merging: mappings are merged recursively, all other nodes,
+    /// including sequences and `null`s, replace the base node.
+    pub fn deep() -> Self {
+        Self::default()
+    }
+
+    /// JSON Merge Patch semantics as defined in RFC 7386: like
+    /// [`MergeStrategy::deep`], but `null` values remove keys.
+    pub fn merge_patch() -> Self {
+        Self {
+            remove_nulls: true,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the way sequences are combined. See [`SequenceMerge`].
+    pub fn with_sequences(mut self, sequences: SequenceMerge) -> Self {
+        self.sequences = sequences;
+        self
+    }
+
+    /// Sets the way sequences at the paths matching `pattern` are combined.
+    /// See [`MergeStrategy::sequences_at`].
+    pub fn with_sequences_at(
+        mut self,
+        pattern: impl Into<String>,
+        sequences: SequenceMerge,
+    ) -> Self {
+        self.sequences_at.push((pattern.into(), sequences));
+        self
+    }
+
+    /// Returns the way the sequences at `path` are combined.
+    fn sequences(&self, path: &[PathSegment]) -> &SequenceMerge {
+        self.sequences_at
+            .iter()
+            .find(|(pattern, _)| matches_path(pattern, path))
+            .map_or(&self.sequences, |(_, sequences)| sequences)
+    }
+
+    /// Enables or disables failing on conflicting node kinds. See
+    /// [`MergeStrategy::strict`].
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
+impl Node {
+    /// Merges the `other` node into this node using the `strategy`. Tags
+    /// are looked through when merging collections, the base node keeps its
+    /// tag. Pairs which already exist keep their comments and blank lines.
+    pub fn merge(&mut self, other: &Node, strategy: &MergeStrategy) -> Result<(), Error> {
+        Merger::new(strategy).merge_node(self, other)
+    }
+}
+
+impl Document {
+    /// Merges the root node of the `other` document into the root node of
+    /// this document like [`Node::merge`]. A missing root node is replaced
+    /// by the other root node, the other document is ignored if it has no
+    /// root node.
+    ///
+    /// Nodes taken from the other document keep their
+    /// [`provenance`](Document::provenance), so that the origin of each
+    /// value of the merged document remains known, e.g. to report both
+    /// origins of an [`Error::Conflict`]. They also keep their
+    /// [`lexical_forms`](Document::lexical_forms),
+    /// [`item_comments`](Document::item_comments) and
+    /// [`styles`](Document::styles), while those of the replaced and removed
+    /// nodes are dropped. The document is left unchanged if the merge fails.
+    ///
+    /// ```
+    /// use yaml_ast::{merge::MergeStrategy, provenance::Provenance, Document};
+    ///
+    /// let mut values: Document = "image: {tag: '1.0', pullPolicy: Always}\n".parse().unwrap();
+    /// values.set_provenance("", Provenance::new().with_source("values.yaml")).unwrap();
+    ///
+    /// let mut overrides: Document = "image: {tag: '1.1'}\n".parse().unwrap();
+    /// overrides.set_provenance("", Provenance::new().with_source("prod.yaml")).unwrap();
+    ///
+    /// values.merge(&overrides, &MergeStrategy::deep()).unwrap();
+    ///
+    /// let source = |path| values.provenance(path).and_then(|provenance| provenance.source());
+    /// assert_eq!(source("image.tag"), Some("prod.yaml"));
+    /// assert_eq!(source("image.pullPolicy"), Some("values.yaml"));
+    /// ```
+    pub fn merge(&mut self, other: &Document, strategy: &MergeStrategy) -> Result<(), Error> {
+        let Some(other_root) = other.nodes.first() else {
+            return Ok(());
+        };
+
+        let Some(root) = self.nodes.first() else {
+            self.nodes.push(other_root.clone());
+            self.forget_replaced(&[]);
+            self.insert_tables(&[], other.tables_at(&[]));
+            return Ok(());
+        };
+
+        let mut root = root.clone();
+        let mut merger = Merger::new(strategy);
+        merger.documents = Some((self, other));
+        merger.merge_node(&mut root, other_root)?;
+
+        let Merger { taken, removed, .. } = merger;
+        self.nodes[0] = root;
+
+        for path in removed {
+            self.forget_removed(&path);
+        }
+
+        for (path, other_path, replaced) in taken {
+            let tables = other.tables_at(&other_path);
+            self.forget_replaced(&path);
+
+            match replaced {
+                true => self.insert_tables(&path, tables.without_item_comments()),
+                false => self.insert_tables(&path, tables),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Merges nodes while tracking the paths of the merged nodes and recording
+/// which nodes were taken from the other node.
+struct Merger<'a> {
+    strategy: &'a MergeStrategy,
+    path: Vec<PathSegment>,
+    other_path: Vec<PathSegment>,
+
+    /// The paths of the nodes taken from the other node, together with
+    /// their paths within the other node and whether they replaced a node
+    /// of the base node.
+    taken: Vec<(Vec<PathSegment>, Vec<PathSegment>, bool)>,
+
+    /// The paths of the mapping pairs removed from the base node.
+    removed: Vec<Vec<PathSegment>>,
+
+    /// The base and other document when merging documents, whose
+    /// provenance is reported for conflicts.
+    documents: Option<(&'a Document, &'a Document)>,
+}
+
+impl<'a> Merger<'a> {
+    fn new(strategy: &'a MergeStrategy) -> Self {
+        Self {
+            strategy,
+            path: Vec::new(),
+            other_path: Vec::new(),
+            taken: Vec::new(),
+            removed: Vec::new(),
+            documents: None,
+        }
+    }
+
+    fn merge_node(&mut self, base: &mut Node, other: &Node) -> Result<(), Error> {
+        match (untagged_mut(base), other.untagged()) {
+            (Node::Mapping(base), Node::Mapping(other)) => return self.merge_mappings(base, other),
+            (Node::Sequence(base), Node::Sequence(other)) => {
+                return self.merge_sequences(base, other)
+            }
+            _ => {}
+        }
+
+        let strategy = self.strategy;
+        ensure!(
+            !strategy.strict
+                || base.untagged().is_null()
+                || other.untagged().is_null()
+                || base.kind() == other.kind(),
+            ConflictSnafu {
+                path: display_path(&self.path),
+                base: base.description(),
+                other: other.description(),
+                base_origin: self.origin(false),
+                other_origin: self.origin(true),
+            }
+        );
+
+        *base = match strategy.remove_nulls {
+            true => without_nulls(other),
+            false => other.clone(),
+        };
+        self.take(None);
+
+        Ok(())
+    }
+
+    fn merge_mappings(&mut self, base: &mut Mapping, other: &Mapping) -> Result<(), Error> {
+        for pair in other.pairs() {
+            let position = base.position(&pair.key);
+            let remove = self.strategy.remove_nulls && pair.value.is_null();
+            let segment = key_segment(&pair.key);
+
+            match position {
+                // Removing a pair shifts the positions of the following
+                // pairs, which rebuilds the key index on the next lookup
+                Some(index) if remove => {
+                    base.remove(index);
+                    self.removed.push([&self.path[..], &[segment]].concat());
+                }
+                Some(index) => {
+                    let Some(value) = base.value_at_mut(index) else {
+                        unreachable!()
+                    };
+
+                    self.push(segment.clone(), segment);
+                    self.merge_node(value, &pair.value)?;
+                    self.pop();
+                }
+                None if remove => {}
+                None => {
+                    let mut pair = pair.clone();
+                    if self.strategy.remove_nulls {
+                        pair.value = without_nulls(&pair.value);
+                    }
+
+                    base.push(pair);
+                    self.take(Some((segment.clone(), segment)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn merge_sequences(&mut self, base: &mut Vec<Node>, other: &[Node]) -> Result<(), Error> {
+        match self.strategy.sequences(&self.path) {
+            SequenceMerge::Replace => {
+                *base = other.to_vec();
+                self.take(None);
+            }
+            SequenceMerge::Append => {
+                for index in 0..other.len() {
+                    let segment = PathSegment::Index(base.len() + index);
+                    self.take(Some((segment, PathSegment::Index(index))));
+                }
+
+                base.extend_from_slice(other);
+            }
+            SequenceMerge::MergeByKey(key) => {
+                let item_key = |item: &Node| -> Option<Node> {
+                    item.untagged().as_mapping()?.get(key).cloned()
+                };
+
+                for (other_index, item) in other.iter().enumerate() {
+                    let position = item_key(item).and_then(|value| {
+                        base.iter()
+                            .position(|base_item| item_key(base_item).as_ref() == Some(&value))
+                    });
+                    let other_segment = PathSegment::Index(other_index);
+
+                    match position {
+                        Some(index) => {
+                            self.push(PathSegment::Index(index), other_segment);
+                            self.merge_node(&mut base[index], item)?;
+                            self.pop();
+                        }
+                        None => {
+                            base.push(item.clone());
+                            self.take(Some((PathSegment::Index(base.len() - 1), other_segment)));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the provenance of the node at the current path of the base or
+    /// `other` document, if documents are merged.
+    fn origin(&self, other: bool) -> Option<Box<Provenance>> {
+        let (base_document, other_document) = self.documents?;
+        let provenance = match other {
+            true => other_document.provenance_at(&self.other_path),
+            false => base_document.provenance_at(&self.path),
+        };
+
+        provenance.cloned().map(Box::new)
+    }
+
+    fn push(&mut self, segment: PathSegment, other_segment: PathSegment) {
+        self.path.push(segment);
+        self.other_path.push(other_segment);
+    }
+
+    fn pop(&mut self) {
+        self.path.pop();
+        self.other_path.pop();
+    }
+
+    /// Records that the node at the current path was replaced by the other
+    /// node, or that the node at the `segments` below it was added from
+    /// the other node.
+    fn take(&mut self, segments: Option<(PathSegment, PathSegment)>) {
+        let (mut path, mut other_path) = (self.path.clone(), self.other_path.clone());
+        let replaced = segments.is_none();
+        if let Some((segment, other_segment)) = segments {
+            path.push(segment);
+            other_path.push(other_segment);
+        }
+
+        self.taken.push((path, other_path, replaced));
+    }
+}
+
+/// Returns a copy of `node` without mapping pairs whose value is `null`.
+/// Sequences are copied as they are.
+fn without_nulls(node: &Node) -> Node {
+    match node {
+        Node::Mapping(mapping) => Node::Mapping(
+            mapping
+                .pairs()
+                .filter(|pair| !pair.value.is_null())
+                .map(|pair| {
+                    let mut pair = pair.clone();
+                    pair.value = without_nulls(&pair.value);
+                    pair
+                })
+                .collect(),
+        ),
+        node => node.clone(),
+    }
+}
+
+fn untagged_mut(mut node: &mut Node) -> &mut Node {
+    while let Node::Tagged(tagged) = node {
+        node = &mut tagged.node;
+    }
+
+    node
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{events::CollectionStyle, style::Style, yaml};
+
+    #[test]
+    fn deep() {
+        let mut base = yaml!({a: {b: 1, c: [1]}, d: "x"});
+        let other = yaml!({a: {c: [2], e: null}, d: null});
+
+        base.merge(&other, &MergeStrategy::deep()).unwrap();
+        assert_eq!(base, yaml!({a: {b: 1, c: [2], e: null}, d: null}));
+
+        let mut appended = yaml!({a: [1]});
+        let strategy = MergeStrategy::deep().with_sequences(SequenceMerge::Append);
+
+        appended.merge(&yaml!({a: [2]}), &strategy).unwrap();
+        assert_eq!(appended, yaml!({a: [1, 2]}));
+    }
+
+    #[test]
+    fn merge_patch() {
+        // Examples of RFC 7386, appendix A
+        let cases = [
+            (yaml!({a: "b"}), yaml!({a: "c"}), yaml!({a: "c"})),
+            (yaml!({a: "b"}), yaml!({b: "c"}), yaml!({a: "b", b: "c"})),
+            (yaml!({a: "b"}), yaml!({a: null}), yaml!({})),
+            (yaml!({a: [{b: "c"}]}), yaml!({a: [1]}), yaml!({a: [1]})),
+            (yaml!(["a", "b"]), yaml!(["c", "d"]), yaml!(["c", "d"])),
+            (yaml!({a: "foo"}), yaml!("bar"), yaml!("bar")),
+            (yaml!({e: null}), yaml!({a: 1}), yaml!({e: null, a: 1})),
+            (yaml!([1, 2]), yaml!({a: "b", c: null}), yaml!({a: "b"})),
+            (
+                yaml!({}),
+                yaml!({a: {bb: {ccc: null}}}),
+                yaml!({a: {bb: {}}}),
+            ),
+        ];
+
+        for (mut base, patch, expected) in cases {
+            base.merge(&patch, &MergeStrategy::merge_patch()).unwrap();
+            assert_eq!(base, expected);
+        }
+    }
+
+    #[test]
+    fn sequences_at() {
+        let mut base = yaml!({
+            spec: {
+                containers: [
+                    {name: "web", image: "nginx:1.26", args: ["--debug"]},
+                    {name: "sidecar", image: "envoy"}
+                ]
+            }
+        });
+        let other = yaml!({
+            spec: {
+                containers: [
+                    {name: "web", image: "nginx:1.27", args: ["--quiet"]},
+                    {name: "init", image: "busybox"}
+                ]
+            }
+        });
+        let strategy = MergeStrategy::deep()
+            .with_sequences(SequenceMerge::Append)
+            .with_sequences_at("**.containers", SequenceMerge::MergeByKey("name".into()))
+            .with_sequences_at("**.args", SequenceMerge::Replace);
+
+        base.merge(&other, &strategy).unwrap();
+        assert_eq!(
+            base,
+            yaml!({
+                spec: {
+                    containers: [
+                        {name: "web", image: "nginx:1.27", args: ["--quiet"]},
+                        {name: "sidecar", image: "envoy"},
+                        {name: "init", image: "busybox"}
+                    ]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let mut base = yaml!({spec: {ports: [{name: "http", port: 80}]}});
+        let other = yaml!({spec: {ports: [{name: "http", port: {number: 8080}}]}});
+        let strategy = MergeStrategy::deep()
+            .with_sequences(SequenceMerge::MergeByKey("name".into()))
+            .with_strict(true);
+
+        assert_eq!(
+            base.merge(&other, &strategy),
+            Err(Error::Conflict {
+                path: "spec.ports[0].port".into(),
+                base: "integer".into(),
+                other: "mapping".into(),
+                base_origin: None,
+                other_origin: None,
+            })
+        );
+
+        let mut base: Document = "spec: {port: 80}\n".parse().unwrap();
+        base.set_provenance("", Provenance::new().with_source("values.yaml"))
+            .unwrap();
+        let mut other: Document = "spec: {port: [8080]}\n".parse().unwrap();
+        other
+            .set_provenance("spec", Provenance::new().with("overlay", "prod"))
+            .unwrap();
+
+        let error = base.merge(&other, &strategy).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "cannot merge the sequence (overlay=prod) into the integer from values.yaml at spec.port"
+        );
+    }
+
+    #[test]
+    fn presentation() {
+        let base = || -> Document {
+            "args:\n  - old # cx\nlevel: info\nremoved: ~\n"
+                .parse()
+                .unwrap()
+        };
+        let mut other: Document =
+            "args:\n  # oc\n  - new\n  - ~ # empty\nlevel: ~\nremoved: null\nextra: [1, 2]\n"
+                .parse()
+                .unwrap();
+        other
+            .set_style("extra", Style::new().with_collection(CollectionStyle::Flow))
+            .unwrap();
+
+        let mut replaced = base();
+        replaced
+            .merge(&other, &MergeStrategy::merge_patch())
+            .unwrap();
+        assert_eq!(
+            replaced.to_string(),
+            "---\nargs:\n  # oc\n  - new\n  - ~ # empty\nextra: [1, 2]\n...\n"
+        );
+        assert!(replaced
+            .lexical_forms
+            .keys()
+            .all(|path| path[0] != PathSegment::Key("removed".into())));
+
+        let mut appended = base();
+        let strategy = MergeStrategy::deep().with_sequences(SequenceMerge::Append);
+        appended.merge(&other, &strategy).unwrap();
+        assert_eq!(
+            appended.to_string(),
+            "---\nargs:\n  - old # cx\n  # oc\n  - new\n  - ~ # empty\nlevel: ~\nremoved: null\nextra: [1, 2]\n...\n"
+        );
+    }
+}
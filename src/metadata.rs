@@ -0,0 +1,302 @@
+//! A standardized metadata head describing how a stream was generated.
+//!
+//! [`Metadata`] records the generating tool, its version, the inputs with
+//! their hashes and the time of generation. It is written either as a block
+//! of `key: value` comments at the top of the stream or as a dedicated
+//! leading document, and can be read back from the output.
+//!
+//! ```
+//! use yaml_ast::{metadata::{Metadata, MetadataStyle}, timestamp::Timestamp, Stream};
+//!
+//! let metadata = Metadata::new("render")
+//!     .with_version("1.2.0")
+//!     .with_input("values.yaml", "sha256:9f86d0")
+//!     .with_timestamp(Timestamp::date(2024, 1, 1).with_time(12, 0, 0));
+//!
+//! let mut stream: Stream = "replicas: 3\n".parse().unwrap();
+//! metadata.apply(&mut stream, MetadataStyle::Comments);
+//!
+//! let output = stream.to_string();
+//! assert!(output.starts_with("# generator: render\n# version: 1.2.0\n# timestamp: 2024-01-01T12:00:00Z\n"));
+//! assert_eq!(Metadata::from_output(&output), Some(metadata));
+//! ```
+use std::{
+    env,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{timestamp::Timestamp, trailer::Signer, Document, Mapping, Node, Stream};
+
+/// The environment variable which overrides the current time for
+/// reproducible builds, see <https://reproducible-builds.org/specs/source-date-epoch/>.
+pub const SOURCE_DATE_EPOCH_VAR: &str = "SOURCE_DATE_EPOCH";
+
+/// The key of the root mapping of the leading metadata document.
+const DOCUMENT_KEY: &str = "metadata";
+
+/// How the [`Metadata`] is written into a stream.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MetadataStyle {
+    /// Writes the metadata as comments before the first document. Consumers
+    /// of the stream are not affected.
+    #[default]
+    Comments,
+
+    /// Writes the metadata as a dedicated leading document with a
+    /// `metadata` mapping.
+    Document,
+}
+
+/// An input of the generator, e.g. a values file, with its hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Input {
+    pub name: String,
+
+    /// The hash of the input, prefixed by the algorithm, e.g.
+    /// `sha256:9f86d0…`.
+    pub hash: String,
+}
+
+/// The metadata head of a generated stream.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// The name of the generating tool.
+    pub generator: String,
+    pub version: Option<String>,
+    pub inputs: Vec<Input>,
+
+    /// The time of generation. [`None`] omits the time, which keeps the
+    /// output reproducible.
+    pub timestamp: Option<Timestamp>,
+}
+
+impl Metadata {
+    pub fn new(generator: impl Into<String>) -> Self {
+        Self {
+            generator: generator.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Adds an input with its (algorithm-prefixed) `hash`.
+    pub fn with_input(mut self, name: impl Into<String>, hash: impl Into<String>) -> Self {
+        self.inputs.push(Input {
+            name: name.into(),
+            hash: hash.into(),
+        });
+        self
+    }
+
+    /// Adds an input, hashing its `content` using the `signer`.
+    pub fn with_hashed_input(
+        self,
+        name: impl Into<String>,
+        content: &str,
+        signer: &impl Signer,
+    ) -> Self {
+        let hash = format!("{}:{}", signer.algorithm(), signer.sign(content));
+        self.with_input(name, hash)
+    }
+
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Sets the timestamp to the current time, unless the
+    /// [`SOURCE_DATE_EPOCH_VAR`] environment variable overrides it for
+    /// reproducible builds.
+    pub fn with_current_time(self) -> Self {
+        let seconds = env::var(SOURCE_DATE_EPOCH_VAR)
+            .ok()
+            .and_then(|epoch| epoch.trim().parse().ok())
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs())
+            });
+
+        self.with_timestamp(Timestamp::from_unix_seconds(seconds))
+    }
+
+    /// Writes the metadata into the `stream` using the `style`. Comments
+    /// are placed before the comments of the first document, a metadata
+    /// document is inserted before all other documents.
+    pub fn apply(&self, stream: &mut Stream, style: MetadataStyle) {
+        match style {
+            MetadataStyle::Comments => {
+                if stream.is_empty() {
+                    stream.push_document(Document::new());
+                }
+
+                let document = stream.get_mut(0).expect("the stream has a document");
+                document.comments.splice(0..0, self.lines());
+            }
+            MetadataStyle::Document => {
+                let metadata = Mapping::from([(DOCUMENT_KEY.into(), self.to_node())]);
+                stream.insert(0, Document::from_mapping(metadata));
+            }
+        }
+    }
+
+    /// Reads the metadata from the first document of the `stream`, written
+    /// in either style.
+    pub fn from_stream(stream: &Stream) -> Option<Self> {
+        let document = stream.get(0)?;
+
+        if let Some(metadata) = Self::from_lines(document.comments.iter().map(String::as_str)) {
+            return Some(metadata);
+        }
+
+        match document.nodes.first()?.as_mapping()?.as_slice() {
+            [pair] if pair.key.as_str() == Some(DOCUMENT_KEY) => Self::from_node(&pair.value),
+            _ => None,
+        }
+    }
+
+    /// Reads the metadata from emitted `output`, written in either style.
+    pub fn from_output(output: &str) -> Option<Self> {
+        let comments = output
+            .lines()
+            .map_while(|line| line.strip_prefix('#'))
+            .map(str::trim);
+
+        Self::from_lines(comments).or_else(|| Self::from_stream(&output.parse().ok()?))
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("generator: {}", self.generator)];
+
+        if let Some(version) = &self.version {
+            lines.push(format!("version: {version}"));
+        }
+
+        if let Some(timestamp) = &self.timestamp {
+            lines.push(format!("timestamp: {timestamp}"));
+        }
+
+        for input in &self.inputs {
+            lines.push(format!("input: {} {}", input.name, input.hash));
+        }
+
+        lines
+    }
+
+    fn from_lines<'a>(lines: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let mut metadata = Self::default();
+
+        for line in lines {
+            let Some((key, value)) = line.trim().split_once(": ") else {
+                continue;
+            };
+
+            match key {
+                "generator" => metadata.generator = value.into(),
+                "version" => metadata.version = Some(value.into()),
+                "timestamp" => metadata.timestamp = Some(value.parse().ok()?),
+                "input" => {
+                    let (name, hash) = value.rsplit_once(' ')?;
+                    metadata = metadata.with_input(name, hash);
+                }
+                _ => {}
+            }
+        }
+
+        (!metadata.generator.is_empty()).then_some(metadata)
+    }
+
+    fn to_node(&self) -> Node {
+        let mut mapping = Mapping::from([("generator".into(), self.generator.as_str().into())]);
+
+        if let Some(version) = &self.version {
+            mapping.push_pair("version".into(), version.as_str().into());
+        }
+
+        if let Some(timestamp) = self.timestamp {
+            mapping.push_pair("timestamp".into(), timestamp.to_string().into());
+        }
+
+        if !self.inputs.is_empty() {
+            let inputs = self
+                .inputs
+                .iter()
+                .map(|input| {
+                    Node::from(Mapping::from([
+                        ("name".into(), input.name.as_str().into()),
+                        ("hash".into(), input.hash.as_str().into()),
+                    ]))
+                })
+                .collect();
+
+            mapping.push_pair("inputs".into(), Node::Sequence(inputs));
+        }
+
+        Node::Mapping(mapping)
+    }
+
+    fn from_node(node: &Node) -> Option<Self> {
+        let string = |node: &Node, key: &str| Some(node.get_path(key)?.as_str()?.to_string());
+        let mut metadata = Self::new(string(node, "generator")?);
+
+        metadata.version = string(node, "version");
+        metadata.timestamp = string(node, "timestamp").and_then(|t| t.parse().ok());
+
+        for input in node
+            .get_path("inputs")
+            .and_then(Node::as_sequence)
+            .into_iter()
+            .flatten()
+        {
+            metadata = metadata.with_input(string(input, "name")?, string(input, "hash")?);
+        }
+
+        Some(metadata)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    fn stream() -> Stream {
+        Stream::from_documents(vec![Document {
+            nodes: vec![yaml!({replicas: 3})],
+            comments: vec!["Deployment".into()],
+            ..Default::default()
+        }])
+    }
+
+    #[test]
+    fn styles() {
+        let metadata = Metadata::new("render")
+            .with_input("values.yaml", "sha256:9f86d0")
+            .with_input("values prod.yaml", "sha256:60303a");
+
+        let mut comments = stream();
+        metadata.apply(&mut comments, MetadataStyle::Comments);
+
+        let output = comments.to_string();
+        assert_eq!(
+            output,
+            "# generator: render\n# input: values.yaml sha256:9f86d0\n# input: values prod.yaml sha256:60303a\n# Deployment\n---\nreplicas: 3\n...\n"
+        );
+        assert_eq!(Metadata::from_stream(&comments), Some(metadata.clone()));
+        assert_eq!(Metadata::from_output(&output), Some(metadata.clone()));
+
+        let mut document = stream();
+        metadata.apply(&mut document, MetadataStyle::Document);
+
+        let output = document.to_string();
+        assert!(output.starts_with("---\nmetadata:\n  generator: render\n  inputs:\n"));
+        assert_eq!(Metadata::from_stream(&document), Some(metadata.clone()));
+        assert_eq!(Metadata::from_output(&output), Some(metadata));
+
+        assert_eq!(Metadata::from_stream(&stream()), None);
+    }
+}
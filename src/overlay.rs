@@ -0,0 +1,313 @@
+//! Overlays which patch selected documents of a [`Stream`], e.g. to derive
+//! environment-specific manifests from a common base, like Kustomize.
+//!
+//! An [`Overlay`] consists of a [`Selector`], which matches documents by the
+//! values at paths of their root node and an optional predicate, and a
+//! [`Patch`], which is either merged into the matched root nodes or applied
+//! as a JSON Patch. [`Stream::apply_overlays`] applies all overlays in order
+//! and reports the documents each overlay matched.
+//!
+//! ```
+//! use yaml_ast::{
+//!     merge::MergeStrategy,
+//!     overlay::{Overlay, Selector},
+//!     patch::Operation,
+//!     yaml, Stream,
+//! };
+//!
+//! let mut stream: Stream = "\
+//! kind: Deployment
+//! metadata: {name: web}
+//! spec: {replicas: 1}
+//! ---
+//! kind: Service
+//! metadata: {name: web}
+//! "
+//! .parse()
+//! .unwrap();
+//!
+//! let overlays = [
+//!     Overlay::merge(
+//!         "production",
+//!         Selector::new().with_value("kind", "Deployment"),
+//!         yaml!({spec: {replicas: 3}}),
+//!         MergeStrategy::deep(),
+//!     ),
+//!     Overlay::json(
+//!         "labels",
+//!         Selector::new(),
+//!         vec![Operation::add("/metadata/labels", yaml!({env: "prod"}))],
+//!     ),
+//! ];
+//!
+//! let applied = stream.apply_overlays(&overlays).unwrap();
+//! assert_eq!(applied[0].documents, [0]);
+//! assert_eq!(applied[1].documents, [0, 1]);
+//!
+//! let root = &stream.get(0).unwrap().nodes[0];
+//! assert_eq!(root.get_path("spec.replicas"), Some(&yaml!(3)));
+//! assert_eq!(root.get_path("metadata.labels.env"), Some(&yaml!("prod")));
+//! ```
+use std::{collections::HashMap, fmt};
+
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    merge::{self, MergeStrategy},
+    patch::{self, Operation},
+    provenance::Provenance,
+    Document, Node, Stream,
+};
+
+/// The key of the name of the overlay in the [`Provenance`] of merged
+/// nodes.
+pub const OVERLAY_KEY: &str = "overlay";
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("failed to merge overlay {overlay:?} into document {document}"))]
+    Merge {
+        source: merge::Error,
+        overlay: String,
+        document: usize,
+    },
+
+    #[snafu(display("failed to apply overlay {overlay:?} to document {document}"))]
+    Patch {
+        source: patch::Error,
+        overlay: String,
+        document: usize,
+    },
+}
+
+type Predicate = Box<dyn Fn(&Node) -> bool + Send + Sync>;
+
+/// Selects documents by their root node. A selector without any values or
+/// predicate selects all documents with a root node.
+#[derive(Default)]
+pub struct Selector {
+    values: Vec<(String, Node)>,
+    predicate: Option<Predicate>,
+}
+
+impl fmt::Debug for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Selector")
+            .field("values", &self.values)
+            .field("predicate", &self.predicate.is_some())
+            .finish()
+    }
+}
+
+impl Selector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only selects documents whose root node has the `value` at the
+    /// dot-separated `path`, see [`Node::get_path`].
+    pub fn with_value(mut self, path: impl Into<String>, value: impl Into<Node>) -> Self {
+        self.values.push((path.into(), value.into()));
+        self
+    }
+
+    /// Only selects documents whose root node satisfies the `predicate`.
+    pub fn with_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Node) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Some(Box::new(predicate));
+        self
+    }
+
+    /// Returns `true` if the selector selects the `root` node.
+    pub fn matches(&self, root: &Node) -> bool {
+        self.values
+            .iter()
+            .all(|(path, value)| root.get_path(path) == Some(value))
+            && self
+                .predicate
+                .as_ref()
+                .is_none_or(|predicate| predicate(root))
+    }
+}
+
+/// The change an [`Overlay`] makes to the selected documents.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Patch {
+    /// Merges the node into the root node, see [`Document::merge`]. The
+    /// merged nodes record the name of the overlay in their
+    /// [`Provenance`], see [`OVERLAY_KEY`].
+    Merge { node: Node, strategy: MergeStrategy },
+
+    /// Applies the operations to the document, see [`patch::apply`].
+    Json(Vec<Operation>),
+}
+
+/// A [`Patch`] applied to all documents selected by the [`Selector`].
+#[derive(Debug)]
+pub struct Overlay {
+    /// The name used in errors and reports, e.g. `production`.
+    pub name: String,
+    pub selector: Selector,
+    pub patch: Patch,
+}
+
+impl Overlay {
+    pub fn new(name: impl Into<String>, selector: Selector, patch: Patch) -> Self {
+        Self {
+            name: name.into(),
+            selector,
+            patch,
+        }
+    }
+
+    /// An overlay which merges `node` into the selected root nodes using
+    /// the `strategy`.
+    pub fn merge(
+        name: impl Into<String>,
+        selector: Selector,
+        node: impl Into<Node>,
+        strategy: MergeStrategy,
+    ) -> Self {
+        let node = node.into();
+        Self::new(name, selector, Patch::Merge { node, strategy })
+    }
+
+    /// An overlay which applies the JSON Patch `operations` to the selected
+    /// documents.
+    pub fn json(name: impl Into<String>, selector: Selector, operations: Vec<Operation>) -> Self {
+        Self::new(name, selector, Patch::Json(operations))
+    }
+}
+
+/// The documents an [`Overlay`] was applied to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Applied {
+    pub overlay: String,
+
+    /// The indices of the selected documents within the stream.
+    pub documents: Vec<usize>,
+}
+
+impl Stream {
+    /// Applies the `overlays` in order and returns the documents each
+    /// overlay was applied to, in the order of the overlays. Documents are
+    /// selected by their state after the previous overlays, documents
+    /// without a root node are never selected. The overlays are applied
+    /// atomically: if any of them fails, the stream is left unchanged.
+    pub fn apply_overlays(&mut self, overlays: &[Overlay]) -> Result<Vec<Applied>, Error> {
+        let mut stream = self.clone();
+        let mut applied = Vec::with_capacity(overlays.len());
+
+        for overlay in overlays {
+            let mut documents = Vec::new();
+
+            for (index, document) in stream.iter_mut().enumerate() {
+                let selected = document
+                    .nodes
+                    .first()
+                    .is_some_and(|root| overlay.selector.matches(root));
+                if !selected {
+                    continue;
+                }
+
+                match &overlay.patch {
+                    Patch::Merge { node, strategy } => {
+                        let patch = Document {
+                            nodes: vec![node.clone()],
+                            provenance: HashMap::from([(
+                                Vec::new(),
+                                Provenance::new().with(OVERLAY_KEY, &overlay.name),
+                            )]),
+                            ..Default::default()
+                        };
+
+                        document.merge(&patch, strategy).context(MergeSnafu {
+                            overlay: &overlay.name,
+                            document: index,
+                        })?
+                    }
+                    Patch::Json(operations) => {
+                        patch::apply(document, operations).context(PatchSnafu {
+                            overlay: &overlay.name,
+                            document: index,
+                        })?
+                    }
+                }
+
+                documents.push(index);
+            }
+
+            applied.push(Applied {
+                overlay: overlay.name.clone(),
+                documents,
+            });
+        }
+
+        *self = stream;
+        Ok(applied)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    #[test]
+    fn apply_overlays() {
+        let mut stream: Stream = "a: 1\n---\n---\nb: 2\n".parse().unwrap();
+        let overlays = [
+            Overlay::json(
+                "remove-a",
+                Selector::new().with_predicate(|root| root.get_path("a").is_some()),
+                vec![Operation::remove("/a")],
+            ),
+            Overlay::json("none", Selector::new().with_value("c", 3), Vec::new()),
+        ];
+
+        let applied = stream.apply_overlays(&overlays).unwrap();
+        assert_eq!(
+            applied,
+            [
+                Applied {
+                    overlay: "remove-a".into(),
+                    documents: vec![0],
+                },
+                Applied {
+                    overlay: "none".into(),
+                    documents: Vec::new(),
+                },
+            ]
+        );
+        assert_eq!(stream.get(0).unwrap().nodes[0], yaml!({}));
+
+        let overlays = [Overlay::merge(
+            "c",
+            Selector::new(),
+            yaml!({c: 1}),
+            MergeStrategy::deep(),
+        )];
+        stream.apply_overlays(&overlays).unwrap();
+        let provenance = stream.get(2).unwrap().provenance("c").unwrap();
+        assert_eq!(provenance.get(OVERLAY_KEY), Some("c"));
+        assert_eq!(stream.get(2).unwrap().provenance("b"), None);
+
+        let overlays = [
+            Overlay::merge("b", Selector::new(), yaml!({b: 3}), MergeStrategy::deep()),
+            Overlay::json("missing", Selector::new(), vec![Operation::remove("/d")]),
+        ];
+        assert_eq!(
+            stream.apply_overlays(&overlays),
+            Err(Error::Patch {
+                source: patch::Error::NotFound {
+                    pointer: "/d".into()
+                },
+                overlay: "missing".into(),
+                document: 0,
+            })
+        );
+        assert_eq!(stream.get(2).unwrap().nodes[0], yaml!({b: 2, c: 1}));
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use snafu::ensure;
+
+use crate::parser::{EncodingSnafu, Error};
+
+/// The character encodings of YAML character streams. Streams are encoded
+/// in UTF-8 unless they start with a byte order mark or the null bytes of
+/// the first character indicate another encoding.
+///
+/// See <https://yaml.org/spec/1.2.2/#52-character-encodings>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Utf32Le => "UTF-32LE",
+            Encoding::Utf32Be => "UTF-32BE",
+        })
+    }
+}
+
+impl Encoding {
+    /// Detects the encoding of the character stream `bytes` by its first
+    /// four bytes, as described by the YAML specification.
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [0, 0, 0xfe, 0xff, ..] | [0, 0, 0, _, ..] => Encoding::Utf32Be,
+            [0xff, 0xfe, 0, 0, ..] | [_, 0, 0, 0, ..] => Encoding::Utf32Le,
+            [0xfe, 0xff, ..] | [0, _, ..] => Encoding::Utf16Be,
+            [0xff, 0xfe, ..] | [_, 0, ..] => Encoding::Utf16Le,
+            _ => Encoding::Utf8,
+        }
+    }
+
+    /// Decodes the character stream `bytes`. A byte order mark is kept as
+    /// the first character, the parser skips it.
+    pub fn decode(self, bytes: &[u8]) -> Result<String, Error> {
+        let invalid = |offset| EncodingSnafu {
+            encoding: self,
+            offset,
+        };
+
+        match self {
+            Encoding::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(text) => Ok(text.into()),
+                Err(err) => invalid(err.valid_up_to()).fail(),
+            },
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let units = bytes.chunks_exact(2).map(|unit| match self {
+                    Encoding::Utf16Le => u16::from_le_bytes([unit[0], unit[1]]),
+                    _ => u16::from_be_bytes([unit[0], unit[1]]),
+                });
+
+                let mut text = String::with_capacity(bytes.len() / 2);
+                for c in char::decode_utf16(units) {
+                    match c {
+                        Ok(c) => text.push(c),
+                        Err(_) => {
+                            let offset: usize = text.chars().map(char::len_utf16).sum();
+                            return invalid(offset * 2).fail();
+                        }
+                    }
+                }
+
+                let rest = bytes.chunks_exact(2).remainder();
+                ensure!(rest.is_empty(), invalid(bytes.len() - rest.len()));
+                Ok(text)
+            }
+            Encoding::Utf32Le | Encoding::Utf32Be => {
+                let mut text = String::with_capacity(bytes.len() / 4);
+
+                for (index, unit) in bytes.chunks_exact(4).enumerate() {
+                    let unit = [unit[0], unit[1], unit[2], unit[3]];
+                    let code_point = match self {
+                        Encoding::Utf32Le => u32::from_le_bytes(unit),
+                        _ => u32::from_be_bytes(unit),
+                    };
+
+                    match char::from_u32(code_point) {
+                        Some(c) => text.push(c),
+                        None => return invalid(index * 4).fail(),
+                    }
+                }
+
+                let rest = bytes.chunks_exact(4).remainder();
+                ensure!(rest.is_empty(), invalid(bytes.len() - rest.len()));
+                Ok(text)
+            }
+        }
+    }
+}
+
+/// Decodes the character stream `bytes` in the encoding detected by
+/// [`Encoding::detect`].
+pub fn decode(bytes: &[u8]) -> Result<String, Error> {
+    Encoding::detect(bytes).decode(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn utf16(text: &str, little_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| match little_endian {
+                true => unit.to_le_bytes(),
+                false => unit.to_be_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn encodings() {
+        let text = "\u{feff}a: é\n";
+        let utf32: Vec<u8> = text
+            .chars()
+            .flat_map(|c| (c as u32).to_be_bytes())
+            .collect();
+
+        for (bytes, encoding) in [
+            (text.as_bytes().to_vec(), Encoding::Utf8),
+            (utf16(text, true), Encoding::Utf16Le),
+            (utf16(text, false), Encoding::Utf16Be),
+            (utf16(&text[3..], true), Encoding::Utf16Le),
+            (utf32, Encoding::Utf32Be),
+        ] {
+            assert_eq!(Encoding::detect(&bytes), encoding);
+            assert!(decode(&bytes).unwrap().ends_with("a: é\n"));
+        }
+
+        let mut bytes = utf16("a: b", true);
+        bytes.push(0);
+        assert!(matches!(
+            decode(&bytes),
+            Err(Error::Encoding {
+                encoding: Encoding::Utf16Le,
+                offset: 8
+            })
+        ));
+        assert!(matches!(
+            decode(b"a: \xff"),
+            Err(Error::Encoding {
+                encoding: Encoding::Utf8,
+                offset: 3
+            })
+        ));
+    }
+}
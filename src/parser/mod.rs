@@ -0,0 +1,1188 @@
+//! A parser which turns a YAML character stream into a lossless
+//! [`Cst`](crate::cst::Cst).
+//!
+//! The parser supports block and flow collections, all five scalar styles,
+//! node properties (tags and anchors), aliases, directives and multiple
+//! documents per stream. Every node records its [`Span`] in the source text.
+//! Character streams in UTF-16 or UTF-32 are decoded by [`decode`] first.
+//! Streams which arrive in chunks, e.g. over a socket, are parsed document
+//! by document by a [`StreamParser`]. Linters use [`parse_lenient`] to
+//! recover from syntax errors.
+use std::fmt;
+
+use snafu::Snafu;
+
+use crate::{
+    cst::{
+        CollectionStyle, Comment, CstDocument, CstItem, CstKind, CstNode, CstPair, ScalarStyle,
+        Trivia,
+    },
+    directive::Directive,
+    limits::{Limit, Limits},
+};
+
+mod encoding;
+mod recover;
+mod scalar;
+mod source;
+mod stream;
+
+pub use encoding::*;
+pub use recover::*;
+pub use source::*;
+pub use stream::*;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("{message} at {position}"))]
+    Syntax { message: String, position: Location },
+
+    #[snafu(display("exceeded the {limit} at {position}"))]
+    LimitExceeded { limit: Limit, position: Location },
+
+    #[snafu(display("invalid {encoding} at byte {offset}"))]
+    Encoding { encoding: Encoding, offset: usize },
+
+    #[snafu(display("failed to read the character stream"))]
+    Read { source: std::io::Error },
+}
+
+/// A byte range in the source text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+/// A human-readable position in the source text. Both the line and the
+/// column start at 1. The column is counted in characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} column {}", self.line, self.column)
+    }
+}
+
+impl Location {
+    /// Computes the location of the byte `offset` in `source`.
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// Computes the byte offset of the location in `source`, the inverse of
+    /// [`Location::from_offset`]. Locations past the end of a line or the
+    /// source are clamped to it.
+    pub fn offset(&self, source: &str) -> usize {
+        let line_start = match self.line {
+            0 | 1 => 0,
+            line => source
+                .match_indices('\n')
+                .nth(line - 2)
+                .map_or(source.len(), |(i, _)| i + 1),
+        };
+
+        source[line_start..]
+            .char_indices()
+            .take_while(|(_, c)| *c != '\n')
+            .nth(self.column.saturating_sub(1))
+            .map_or_else(
+                || {
+                    line_start
+                        + source[line_start..]
+                            .find('\n')
+                            .unwrap_or(source.len() - line_start)
+                },
+                |(i, _)| line_start + i,
+            )
+    }
+}
+
+/// These options control the strictness of the parser.
+///
+/// It provides a builder to selectively customize individual settings. If no
+/// customization is required, use [`ParseOptions::default()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Rejects tabs in the indentation of lines, which the YAML
+    /// specification forbids. By default, they are treated like spaces.
+    /// Tabs separating tokens on the same line are allowed either way.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#61-indentation-spaces>
+    pub forbid_tabs: bool,
+
+    /// The limits of the nesting depth and number of nodes. Aliases are
+    /// not expanded while parsing, see [`ResolveOptions`](crate::cst::ResolveOptions)
+    /// for the limits of their expansion.
+    pub limits: Limits,
+}
+
+impl ParseOptions {
+    pub fn builder() -> ParseOptionsBuilder {
+        ParseOptionsBuilder::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ParseOptionsBuilder {
+    forbid_tabs: bool,
+    limits: Limits,
+}
+
+impl ParseOptionsBuilder {
+    /// Enables or disables rejecting tabs in indentation. See
+    /// [`ParseOptions::forbid_tabs`].
+    pub fn forbid_tabs(mut self, forbid_tabs: bool) -> Self {
+        self.forbid_tabs = forbid_tabs;
+        self
+    }
+
+    /// Sets the limits of parsed documents. See [`ParseOptions::limits`].
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn build(self) -> ParseOptions {
+        ParseOptions {
+            forbid_tabs: self.forbid_tabs,
+            limits: self.limits,
+        }
+    }
+}
+
+/// Parses the character stream `source` into a list of CST documents.
+pub fn parse(source: &str) -> Result<Vec<CstDocument>, Error> {
+    parse_with(source, &ParseOptions::default())
+}
+
+/// Parses the character stream `source` into a list of CST documents
+/// according to `options`.
+pub fn parse_with(source: &str, options: &ParseOptions) -> Result<Vec<CstDocument>, Error> {
+    parse_counted(source, options, &mut 0)
+}
+
+/// Parses the character stream `source` like [`parse_with`], where `nodes`
+/// is the number of nodes of the preceding documents of the stream.
+fn parse_counted(
+    source: &str,
+    options: &ParseOptions,
+    nodes: &mut usize,
+) -> Result<Vec<CstDocument>, Error> {
+    let mut parser = Parser::new(source);
+    parser.forbid_tabs = options.forbid_tabs;
+    parser.limits = options.limits;
+    parser.nodes = *nodes;
+
+    let documents = parser.parse_stream();
+    *nodes = parser.nodes;
+
+    // The tab precedes any syntax error, which may be caused by it
+    match parser.indentation_tab {
+        Some(offset) => {
+            parser.pos = offset;
+            parser.error("tabs are not allowed in indentation")
+        }
+        None => documents,
+    }
+}
+
+/// The context in which a block node is parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BlockContext {
+    Root,
+    MappingValue,
+    SequenceItem,
+    ExplicitKey,
+}
+
+pub(crate) struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+
+    /// Comments and blank lines which were skipped but not yet attached to a
+    /// pair or item.
+    pending: Trivia,
+
+    /// All comments of the current document.
+    comments: Vec<Comment>,
+
+    /// See [`ParseOptions::forbid_tabs`].
+    forbid_tabs: bool,
+
+    /// The offset of the first tab in indentation, if tabs are forbidden.
+    indentation_tab: Option<usize>,
+
+    /// See [`ParseOptions::limits`].
+    limits: Limits,
+
+    /// The nesting depth of the collection being parsed.
+    depth: usize,
+
+    /// The number of nodes of all documents parsed so far.
+    nodes: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        let pos = if src.starts_with('\u{feff}') { 3 } else { 0 };
+
+        Self {
+            src,
+            pos,
+            pending: Trivia::default(),
+            comments: Vec::new(),
+            forbid_tabs: false,
+            indentation_tab: None,
+            limits: Limits::default(),
+            depth: 0,
+            nodes: 0,
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, Error> {
+        SyntaxSnafu {
+            message,
+            position: Location::from_offset(self.src, self.pos),
+        }
+        .fail()
+    }
+
+    fn limit_error<T>(&self, limit: Limit) -> Result<T, Error> {
+        LimitExceededSnafu {
+            limit,
+            position: Location::from_offset(self.src, self.pos),
+        }
+        .fail()
+    }
+
+    /// Enters a nested collection, enforcing [`Limits::max_depth`]. Must be
+    /// followed by [`Parser::leave`].
+    fn enter(&mut self) -> Result<(), Error> {
+        self.depth += 1;
+
+        match self.limits.max_depth {
+            Some(max_depth) if self.depth > max_depth => self.limit_error(Limit::Depth(max_depth)),
+            _ => Ok(()),
+        }
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.src.len()
+    }
+
+    fn is_eol(&self) -> bool {
+        matches!(self.peek(), None | Some('\n')) || self.rest().starts_with("\r\n")
+    }
+
+    /// Returns `true` if the character at `n` is whitespace, a line break or
+    /// the end of the input.
+    fn is_blank_at(&self, n: usize) -> bool {
+        matches!(self.peek_nth(n), None | Some(' ' | '\t' | '\n' | '\r'))
+    }
+
+    fn line_start(&self) -> usize {
+        self.src[..self.pos].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// The column of the current position in bytes.
+    fn column(&self) -> usize {
+        self.pos - self.line_start()
+    }
+
+    /// Returns `true` if only indentation precedes the current position on
+    /// the current line.
+    fn at_line_indent(&self) -> bool {
+        self.src[self.line_start()..self.pos]
+            .chars()
+            .all(|c| c == ' ' || c == '\u{feff}')
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(c @ (' ' | '\t')) = self.peek() {
+            if c == '\t'
+                && self.forbid_tabs
+                && self.indentation_tab.is_none()
+                && self.at_line_indent()
+            {
+                self.indentation_tab = Some(self.pos);
+            }
+
+            self.pos += 1;
+        }
+    }
+
+    /// Skips the leading whitespace of a continuation line of a multi-line
+    /// scalar, which may contain tabs even if they are forbidden in
+    /// indentation.
+    fn skip_line_prefix(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_newline(&mut self) -> bool {
+        if self.rest().starts_with("\r\n") {
+            self.pos += 2;
+            true
+        } else if self.peek() == Some('\n') {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reads a comment starting at the current `#` up to the end of the line.
+    fn read_comment(&mut self) -> Comment {
+        let start = self.pos;
+        while !self.is_eol() {
+            self.bump();
+        }
+
+        let text = self.src[start..self.pos].trim_end().to_string();
+        let comment = Comment {
+            span: Span::new(start, start + text.len()),
+            text,
+        };
+
+        self.comments.push(comment.clone());
+        comment
+    }
+
+    /// Returns `true` if a document marker (`---` or `...`) starts at the
+    /// current position.
+    fn at_document_marker(&self, marker: &str) -> bool {
+        self.column() == 0 && self.rest().starts_with(marker) && self.is_blank_at(3)
+    }
+
+    fn at_any_document_marker(&self) -> bool {
+        self.at_document_marker("---") || self.at_document_marker("...")
+    }
+
+    /// Skips whitespace, line breaks and comments until the next content.
+    /// Comments and blank lines are recorded as pending trivia.
+    fn skip_trivia(&mut self) {
+        // Blank lines are only counted if they follow a complete line.
+        let mut line_has_content = !self.at_line_indent();
+
+        loop {
+            self.skip_spaces();
+
+            match self.peek() {
+                Some('#') => {
+                    let comment = self.read_comment();
+                    if !line_has_content {
+                        self.pending.comments.push(comment);
+                    }
+                    line_has_content = true;
+                }
+                Some('\n' | '\r') if self.is_eol() => {
+                    self.skip_newline();
+                    if !line_has_content {
+                        self.pending.blank_lines += 1;
+                    }
+                    line_has_content = false;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn take_trivia(&mut self) -> Trivia {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Finishes the current line after an inline node. Returns the trailing
+    /// comment, if any.
+    fn finish_line(&mut self) -> Result<Option<Comment>, Error> {
+        self.skip_spaces();
+
+        let comment = if self.peek() == Some('#') {
+            Some(self.read_comment())
+        } else {
+            None
+        };
+
+        if !self.is_eol() {
+            return self.error("unexpected content after node");
+        }
+
+        self.skip_newline();
+        Ok(comment)
+    }
+
+    fn parse_stream(&mut self) -> Result<Vec<CstDocument>, Error> {
+        let mut documents = Vec::new();
+
+        loop {
+            self.skip_trivia();
+
+            if self.is_eof() {
+                break;
+            }
+
+            documents.push(self.parse_document()?);
+            self.pending = Trivia::default();
+        }
+
+        Ok(documents)
+    }
+
+    fn parse_document(&mut self) -> Result<CstDocument, Error> {
+        let start = self.pos;
+        let mut document = CstDocument::default();
+
+        while self.column() == 0 && self.peek() == Some('%') {
+            let line_start = self.pos;
+            while !self.is_eol() && !self.rest().starts_with(" #") {
+                self.bump();
+            }
+
+            match self.src[line_start..self.pos].parse::<Directive>() {
+                Ok(directive) => document.directives.push(directive),
+                Err(err) => {
+                    self.pos = line_start;
+                    return self.error(err.to_string());
+                }
+            }
+
+            self.finish_line()?;
+            self.skip_trivia();
+        }
+
+        if self.at_document_marker("---") {
+            self.pos += 3;
+            document.explicit_start = true;
+            document.blank_lines = std::mem::take(&mut self.pending.blank_lines);
+        } else if !document.directives.is_empty() {
+            return self.error("expected a document start marker after directives");
+        }
+
+        document.root = self.parse_document_root()?;
+
+        self.nodes += document.root.as_ref().map_or(0, CstNode::node_count);
+        if let Some(max_nodes) = self.limits.max_nodes.filter(|max| self.nodes > *max) {
+            return self.limit_error(Limit::Nodes(max_nodes));
+        }
+
+        self.skip_trivia();
+        if self.at_document_marker("...") {
+            self.pos += 3;
+            document.explicit_end = true;
+            self.finish_line()?;
+        } else if !self.is_eof() && !self.at_document_marker("---") {
+            return self.error("expected the end of the document");
+        }
+
+        document.span = Span::new(start, self.pos);
+        document.comments = std::mem::take(&mut self.comments);
+        Ok(document)
+    }
+
+    fn parse_document_root(&mut self) -> Result<Option<CstNode>, Error> {
+        self.skip_spaces();
+
+        if self.peek() == Some('#') || self.is_eol() {
+            self.skip_trivia();
+
+            if self.is_eof() || self.at_any_document_marker() {
+                return Ok(None);
+            }
+        }
+
+        let (node, _) = self.parse_block_node(-1, BlockContext::Root)?;
+        if !self.at_line_indent() {
+            self.finish_line()?;
+        }
+
+        Ok(Some(node))
+    }
+
+    /// Parses the node properties (anchor and tag) at the current position.
+    fn parse_properties(&mut self) -> Result<(Option<String>, Option<String>), Error> {
+        let mut anchor = None;
+        let mut tag = None;
+
+        loop {
+            match self.peek() {
+                Some('&') if anchor.is_none() => {
+                    self.bump();
+                    anchor = Some(self.read_name("anchor")?);
+                }
+                Some('!') if tag.is_none() => tag = Some(self.read_tag()?),
+                _ => break,
+            }
+
+            self.skip_spaces();
+        }
+
+        Ok((anchor, tag))
+    }
+
+    /// Reads an anchor or alias name.
+    fn read_name(&mut self, what: &str) -> Result<String, Error> {
+        let start = self.pos;
+        while !self.is_blank_at(0) && !matches!(self.peek(), Some(',' | '[' | ']' | '{' | '}')) {
+            self.bump();
+        }
+
+        if start == self.pos {
+            return self.error(format!("expected {what} name"));
+        }
+
+        Ok(self.src[start..self.pos].to_string())
+    }
+
+    /// Reads a tag including the leading `!`, e.g. `!!str` or `!<tag:x>`.
+    fn read_tag(&mut self) -> Result<String, Error> {
+        let start = self.pos;
+        self.bump();
+
+        if self.peek() == Some('<') {
+            while self.peek() != Some('>') {
+                if self.is_eol() {
+                    return self.error("unterminated verbatim tag");
+                }
+                self.bump();
+            }
+            self.bump();
+        } else {
+            while !self.is_blank_at(0) && !matches!(self.peek(), Some(',' | '[' | ']' | '{' | '}'))
+            {
+                self.bump();
+            }
+        }
+
+        Ok(self.src[start..self.pos].to_string())
+    }
+
+    fn empty_node(&self, anchor: Option<String>, tag: Option<String>, at: usize) -> CstNode {
+        CstNode {
+            span: Span::new(at, at),
+            anchor,
+            tag,
+            kind: CstKind::Scalar {
+                style: ScalarStyle::Plain,
+                value: String::new(),
+            },
+        }
+    }
+
+    /// Parses a node in block context. `indent` is the indentation of the
+    /// parent block collection (-1 for the document root). Returns the node
+    /// and the comment following the node properties on the same line, if
+    /// the node content starts on a following line.
+    fn parse_block_node(
+        &mut self,
+        indent: isize,
+        context: BlockContext,
+    ) -> Result<(CstNode, Option<Comment>), Error> {
+        self.skip_spaces();
+        let (anchor, tag) = self.parse_properties()?;
+        let properties_end = self.pos;
+
+        if self.peek() == Some('#') || self.is_eol() {
+            let comment = if self.peek() == Some('#') {
+                Some(self.read_comment())
+            } else {
+                None
+            };
+
+            self.skip_trivia();
+
+            let column = self.column() as isize;
+            let is_sequence = self.peek() == Some('-') && self.is_blank_at(1);
+
+            let node = if self.is_eof() || self.at_any_document_marker() {
+                self.empty_node(anchor, tag, properties_end)
+            } else if column > indent
+                || (column == indent && context == BlockContext::MappingValue && is_sequence)
+            {
+                let mut node = self.parse_block_content(indent, BlockContext::Root)?;
+                node.anchor = anchor;
+                node.tag = tag;
+                node
+            } else {
+                self.empty_node(anchor, tag, properties_end)
+            };
+
+            return Ok((node, comment));
+        }
+
+        if self.peek() == Some('-') && self.is_blank_at(1) && context == BlockContext::MappingValue
+        {
+            return self.error("block sequences are not allowed on the same line as a key");
+        }
+
+        let mut node = self.parse_block_content(indent, context)?;
+        match (&node.kind, anchor.is_some() || tag.is_some()) {
+            // Properties on the same line as an implicit key belong to the
+            // key, not to the mapping.
+            (CstKind::Mapping { style, pairs }, true)
+                if *style == CollectionStyle::Block
+                    && pairs[0].key.span.start == node.span.start =>
+            {
+                if let CstKind::Mapping { pairs, .. } = &mut node.kind {
+                    pairs[0].key.anchor = anchor;
+                    pairs[0].key.tag = tag;
+                }
+            }
+            _ => {
+                node.anchor = anchor;
+                node.tag = tag;
+            }
+        }
+
+        Ok((node, None))
+    }
+
+    /// Parses the content of a block node starting at the current position.
+    fn parse_block_content(
+        &mut self,
+        indent: isize,
+        context: BlockContext,
+    ) -> Result<CstNode, Error> {
+        let column = self.column();
+
+        match self.peek() {
+            Some('-') if self.is_blank_at(1) => self.parse_block_sequence(column),
+            Some('?') if self.is_blank_at(1) && context != BlockContext::MappingValue => {
+                self.parse_block_mapping(column, None)
+            }
+            Some('|' | '>') => self.parse_block_scalar(indent),
+            _ => {
+                let node = self.parse_inline_node(indent)?;
+
+                self.skip_spaces();
+                if self.peek() == Some(':') && self.is_blank_at(1) {
+                    if context == BlockContext::MappingValue {
+                        return self
+                            .error("implicit keys are not allowed on the same line as a key");
+                    }
+
+                    return self.parse_block_mapping(column, Some(node));
+                }
+
+                Ok(node)
+            }
+        }
+    }
+
+    /// Parses a single line node: a flow collection, a quoted or plain
+    /// scalar or an alias. Plain scalars may continue on following lines if
+    /// they are not used as an implicit key.
+    fn parse_inline_node(&mut self, indent: isize) -> Result<CstNode, Error> {
+        match self.peek() {
+            Some('[' | '{') => self.parse_flow_collection(),
+            Some('"') => self.parse_double_quoted(),
+            Some('\'') => self.parse_single_quoted(),
+            Some('*') => self.parse_alias(),
+            _ => self.parse_plain(false, indent),
+        }
+    }
+
+    fn parse_alias(&mut self) -> Result<CstNode, Error> {
+        let start = self.pos;
+        self.bump();
+        let name = self.read_name("alias")?;
+
+        Ok(CstNode {
+            span: Span::new(start, self.pos),
+            anchor: None,
+            tag: None,
+            kind: CstKind::Alias(name),
+        })
+    }
+
+    /// Parses the entries of a block sequence whose entry indicators are
+    /// located at `indent`.
+    fn parse_block_sequence(&mut self, indent: usize) -> Result<CstNode, Error> {
+        self.enter()?;
+        let start = self.pos;
+        let mut end = start;
+        let mut items = Vec::new();
+
+        loop {
+            let leading = self.take_trivia();
+            self.bump();
+
+            let (node, header_comment) =
+                self.parse_block_node(indent as isize, BlockContext::SequenceItem)?;
+            end = end.max(node.span.end).max(start + 1);
+
+            let trailing_comment = if self.at_line_indent() {
+                header_comment
+            } else {
+                self.finish_line()?
+            };
+
+            items.push(CstItem {
+                node,
+                leading,
+                trailing_comment,
+            });
+
+            self.skip_trivia();
+            if self.is_eof() || self.at_any_document_marker() || self.column() < indent {
+                break;
+            }
+
+            if self.column() > indent {
+                return self.error("expected a block sequence entry");
+            }
+
+            // A sequence nested in a mapping may share the indentation of the
+            // mapping keys.
+            if !(self.peek() == Some('-') && self.is_blank_at(1)) {
+                break;
+            }
+        }
+
+        self.leave();
+        Ok(CstNode {
+            span: Span::new(start, end),
+            anchor: None,
+            tag: None,
+            kind: CstKind::Sequence {
+                style: CollectionStyle::Block,
+                items,
+            },
+        })
+    }
+
+    /// Parses the pairs of a block mapping whose keys are located at
+    /// `indent`. The first (implicit) key may already be parsed.
+    fn parse_block_mapping(
+        &mut self,
+        indent: usize,
+        mut first_key: Option<CstNode>,
+    ) -> Result<CstNode, Error> {
+        self.enter()?;
+        let start = first_key.as_ref().map_or(self.pos, |key| key.span.start);
+        let mut end = start;
+        let mut pairs = Vec::new();
+
+        loop {
+            let leading = self.take_trivia();
+
+            let explicit_key =
+                first_key.is_none() && self.peek() == Some('?') && self.is_blank_at(1);
+            let (key, value, header_comment) = if let Some(key) = first_key.take() {
+                self.bump();
+                let (value, comment) =
+                    self.parse_block_node(indent as isize, BlockContext::MappingValue)?;
+                (key, value, comment)
+            } else if explicit_key {
+                self.parse_explicit_pair(indent)?
+            } else {
+                self.skip_spaces();
+                let (anchor, tag) = self.parse_properties()?;
+                let mut key = self.parse_inline_node(indent as isize)?;
+                key.anchor = anchor;
+                key.tag = tag;
+
+                self.skip_spaces();
+                if !(self.peek() == Some(':') && self.is_blank_at(1)) {
+                    return self.error("expected ':' after mapping key");
+                }
+
+                self.bump();
+                let (value, comment) =
+                    self.parse_block_node(indent as isize, BlockContext::MappingValue)?;
+                (key, value, comment)
+            };
+
+            end = end.max(value.span.end).max(key.span.end);
+
+            let trailing_comment = if self.at_line_indent() {
+                header_comment
+            } else {
+                self.finish_line()?
+            };
+
+            pairs.push(CstPair {
+                key,
+                value,
+                leading,
+                trailing_comment,
+                explicit_key,
+            });
+
+            self.skip_trivia();
+            if self.is_eof() || self.at_any_document_marker() || self.column() < indent {
+                break;
+            }
+
+            if self.column() > indent {
+                return self.error("unexpected indentation");
+            }
+        }
+
+        self.leave();
+        Ok(CstNode {
+            span: Span::new(start, end),
+            anchor: None,
+            tag: None,
+            kind: CstKind::Mapping {
+                style: CollectionStyle::Block,
+                pairs,
+            },
+        })
+    }
+
+    /// Parses a pair using the explicit key indicator `?`.
+    fn parse_explicit_pair(
+        &mut self,
+        indent: usize,
+    ) -> Result<(CstNode, CstNode, Option<Comment>), Error> {
+        self.bump();
+        let (key, key_comment) =
+            self.parse_block_node(indent as isize, BlockContext::ExplicitKey)?;
+
+        if !self.at_line_indent() {
+            self.finish_line()?;
+        }
+
+        self.skip_trivia();
+
+        if self.column() == indent && self.peek() == Some(':') && self.is_blank_at(1) {
+            // The trivia between the key and the value is not attached, the
+            // comments are still part of the document comments.
+            self.pending = Trivia::default();
+            self.bump();
+
+            let (value, comment) =
+                self.parse_block_node(indent as isize, BlockContext::MappingValue)?;
+            return Ok((key, value, comment.or(key_comment)));
+        }
+
+        let value = self.empty_node(None, None, key.span.end);
+        Ok((key, value, key_comment))
+    }
+
+    fn skip_flow_trivia(&mut self) {
+        loop {
+            self.skip_spaces();
+
+            match self.peek() {
+                Some('#') => {
+                    self.read_comment();
+                }
+                Some('\n' | '\r') if self.is_eol() => {
+                    self.skip_newline();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn parse_flow_collection(&mut self) -> Result<CstNode, Error> {
+        self.enter()?;
+        let start = self.pos;
+        let is_mapping = self.bump() == Some('{');
+        let close = if is_mapping { '}' } else { ']' };
+
+        let mut items = Vec::new();
+        let mut pairs = Vec::new();
+
+        loop {
+            self.skip_flow_trivia();
+
+            if self.peek() == Some(close) {
+                self.bump();
+                break;
+            }
+
+            if self.is_eof() {
+                return self.error(format!("unterminated flow collection, expected '{close}'"));
+            }
+
+            let explicit = self.peek() == Some('?') && self.is_blank_at(1);
+            if explicit {
+                self.bump();
+                self.skip_flow_trivia();
+            }
+
+            let entry = self.parse_flow_node()?;
+            self.skip_flow_trivia();
+
+            let is_pair = self.peek() == Some(':')
+                && (self.is_blank_at(1)
+                    || matches!(self.peek_nth(1), Some(',' | '[' | ']' | '{' | '}'))
+                    || matches!(entry.kind, CstKind::Scalar { style, .. } if style != ScalarStyle::Plain));
+
+            if is_mapping || is_pair || explicit {
+                let value = if is_pair {
+                    self.bump();
+                    self.skip_flow_trivia();
+
+                    if matches!(self.peek(), Some(',')) || self.peek() == Some(close) {
+                        self.empty_node(None, None, self.pos)
+                    } else {
+                        self.parse_flow_node()?
+                    }
+                } else {
+                    self.empty_node(None, None, entry.span.end)
+                };
+
+                let pair = CstPair {
+                    key: entry,
+                    value,
+                    leading: Trivia::default(),
+                    trailing_comment: None,
+                    explicit_key: explicit,
+                };
+
+                if is_mapping {
+                    pairs.push(pair);
+                } else {
+                    // A single pair inside a flow sequence is a mapping with
+                    // one entry.
+                    let span = Span::new(pair.key.span.start, pair.value.span.end);
+                    items.push(CstItem {
+                        node: CstNode {
+                            span,
+                            anchor: None,
+                            tag: None,
+                            kind: CstKind::Mapping {
+                                style: CollectionStyle::Flow,
+                                pairs: vec![pair],
+                            },
+                        },
+                        leading: Trivia::default(),
+                        trailing_comment: None,
+                    });
+                }
+            } else {
+                items.push(CstItem {
+                    node: entry,
+                    leading: Trivia::default(),
+                    trailing_comment: None,
+                });
+            }
+
+            self.skip_flow_trivia();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(c) if c == close => {}
+                _ => return self.error(format!("expected ',' or '{close}' in flow collection")),
+            }
+        }
+
+        let kind = if is_mapping {
+            CstKind::Mapping {
+                style: CollectionStyle::Flow,
+                pairs,
+            }
+        } else {
+            CstKind::Sequence {
+                style: CollectionStyle::Flow,
+                items,
+            }
+        };
+
+        self.leave();
+        Ok(CstNode {
+            span: Span::new(start, self.pos),
+            anchor: None,
+            tag: None,
+            kind,
+        })
+    }
+
+    fn parse_flow_node(&mut self) -> Result<CstNode, Error> {
+        let (anchor, tag) = self.parse_properties()?;
+        self.skip_flow_trivia();
+
+        let mut node = match self.peek() {
+            Some('[' | '{') => self.parse_flow_collection()?,
+            Some('"') => self.parse_double_quoted()?,
+            Some('\'') => self.parse_single_quoted()?,
+            Some('*') => self.parse_alias()?,
+            Some(',' | ']' | '}') => self.empty_node(None, None, self.pos),
+            _ => self.parse_plain(true, -1)?,
+        };
+
+        node.anchor = anchor;
+        node.tag = tag;
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn root(source: &str) -> CstNode {
+        parse(source).unwrap().remove(0).root.unwrap()
+    }
+
+    fn scalar(node: &CstNode) -> (&str, ScalarStyle) {
+        match &node.kind {
+            CstKind::Scalar { value, style } => (value, *style),
+            kind => panic!("expected scalar, got {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn nested_block_collections() {
+        let node = root("a:\n  b: 1\n  c:\n  - x\n  - - y\n    - z\n  - k: v\n    l: w\nd: ~\n");
+
+        let c = node.get("a").unwrap().get("c").unwrap();
+        assert_eq!(scalar(c.item(0).unwrap()).0, "x");
+        assert_eq!(scalar(c.item(1).unwrap().item(1).unwrap()).0, "z");
+        assert_eq!(scalar(c.item(2).unwrap().get("l").unwrap()).0, "w");
+        assert_eq!(scalar(node.get("d").unwrap()).0, "~");
+    }
+
+    #[test]
+    fn scalar_styles() {
+        let node = root(
+            "plain: multi\n  line\nsingle: 'it''s'\ndouble: \"a\\tb\\u00e9\"\nliteral: |-\n  a\n   b\nfolded: >\n  a\n  b\n\n  c\nempty:\n",
+        );
+
+        assert_eq!(
+            scalar(node.get("plain").unwrap()),
+            ("multi line", ScalarStyle::Plain)
+        );
+        assert_eq!(
+            scalar(node.get("single").unwrap()),
+            ("it's", ScalarStyle::SingleQuoted)
+        );
+        assert_eq!(
+            scalar(node.get("double").unwrap()),
+            ("a\tbé", ScalarStyle::DoubleQuoted)
+        );
+        assert_eq!(
+            scalar(node.get("literal").unwrap()),
+            ("a\n b", ScalarStyle::Literal)
+        );
+        assert_eq!(
+            scalar(node.get("folded").unwrap()),
+            ("a b\nc\n", ScalarStyle::Folded)
+        );
+        assert!(node.get("empty").unwrap().is_empty());
+    }
+
+    #[test]
+    fn kept_block_scalars_at_eof() {
+        let cases = [
+            ("a: |+\n  x\n", "x\n"),
+            ("a: |+\n  x", "x"),
+            ("a: |+\n  x\n\n", "x\n\n"),
+            ("a: |+\n  x\n  ", "x\n"),
+            ("a: >+\n  x\n  y\n", "x y\n"),
+            ("a: >+\n  x\n  y", "x y"),
+            ("a: >+\n  x\n\n", "x\n\n"),
+        ];
+
+        for (source, expected) in cases {
+            assert_eq!(
+                scalar(root(source).get("a").unwrap()).0,
+                expected,
+                "{source:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn flow_collections_and_properties() {
+        let node = root("seq: &s [a, 'b', {c: d}, e: f]\nmap: !!map {x: 1, y}\nref: *s\n");
+
+        let seq = node.get("seq").unwrap();
+        assert_eq!(seq.anchor.as_deref(), Some("s"));
+        assert_eq!(scalar(seq.item(2).unwrap().get("c").unwrap()).0, "d");
+        assert_eq!(scalar(seq.item(3).unwrap().get("e").unwrap()).0, "f");
+
+        let map = node.get("map").unwrap();
+        assert_eq!(map.tag.as_deref(), Some("!!map"));
+        assert!(map.get("y").unwrap().is_empty());
+        assert!(matches!(&node.get("ref").unwrap().kind, CstKind::Alias(name) if name == "s"));
+    }
+
+    #[test]
+    fn documents() {
+        let documents = parse("%YAML 1.2\n---\na: 1\n...\n--- text\n---\n").unwrap();
+
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[0].directives, vec![Directive::yaml_1_2()]);
+        assert!(documents[0].explicit_end);
+        assert_eq!(scalar(documents[1].root().unwrap()).0, "text");
+        assert!(documents[2].root().is_none());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse("a: b: c\n").is_err());
+        assert!(parse("a: [1, 2\n").is_err());
+        assert!(parse("a: 1\n  b: 2\n").is_err());
+        assert!(parse("%YAML 1.2\na: 1\n").is_err());
+        assert!(parse("%YAML 1.2.3\n---\na: 1\n").is_err());
+    }
+
+    #[test]
+    fn tabs() {
+        let options = ParseOptions::builder().forbid_tabs(true).build();
+
+        for source in ["a:\tb\n", "- a\n-\tb\n", "a: \"x\n\ty\"\n", "a: |\n  \tx\n"] {
+            assert!(parse_with(source, &options).is_ok(), "{source:?}");
+        }
+
+        for (source, line, column) in [("a:\n\tb: 1\n", 2, 1), ("a:\n \t b: 1\n", 2, 2)] {
+            assert!(parse(source).is_ok());
+
+            let Err(Error::Syntax { message, position }) = parse_with(source, &options) else {
+                panic!("expected an error for {source:?}");
+            };
+            assert_eq!(message, "tabs are not allowed in indentation");
+            assert_eq!(position, Location { line, column });
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    cst::CstDocument,
+    parser::{parse_with, stream::is_marker, Error, Location, ParseOptions, Span},
+};
+
+/// A syntax error which was skipped by [`parse_lenient`].
+#[derive(Debug)]
+pub struct RecoveredError {
+    pub error: Error,
+
+    /// The line which was skipped to recover from the error, without the
+    /// line break.
+    pub skipped: Span,
+}
+
+/// Parses the character stream `source` like [`parse_with`], but recovers
+/// from syntax errors instead of failing at the first one. Editors and
+/// linters use it to get a best-effort tree of broken files.
+///
+/// For each syntax error, one line is skipped and the stream is parsed
+/// again. The line is replaced by spaces, which keeps the spans of all other
+/// nodes valid. Errors like unclosed flow collections or quoted scalars are
+/// only detected after the line causing them, which is why the lines from
+/// the error back to the start of the document are tried, and the line
+/// whose removal gets the parser the furthest is skipped. The returned
+/// documents contain the nodes of all lines which were not skipped. Other
+/// errors, like exceeded limits, are returned as they are.
+///
+/// ```
+/// use yaml_ast::parser::{parse_lenient, ParseOptions};
+///
+/// let input = "a: 1\nb: [2\nc: 3\n";
+/// let (documents, errors) = parse_lenient(input, &ParseOptions::default()).unwrap();
+///
+/// let root = documents[0].root().unwrap();
+/// assert!(root.get("a").is_some() && root.get("c").is_some());
+/// assert_eq!(&input[errors[0].skipped.start..errors[0].skipped.end], "b: [2");
+/// ```
+pub fn parse_lenient(
+    source: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<CstDocument>, Vec<RecoveredError>), Error> {
+    let mut source = source.to_string();
+    let mut errors = Vec::new();
+
+    loop {
+        let error = match parse_with(&source, options) {
+            Ok(documents) => return Ok((documents, errors)),
+            Err(error) => error,
+        };
+
+        let position = match &error {
+            Error::Syntax { position, .. } => *position,
+            _ => return Err(error),
+        };
+
+        let Some(skipped) = skipped_line(&source, options, position) else {
+            return Err(error);
+        };
+
+        source = blank(&source, skipped);
+        errors.push(RecoveredError { error, skipped });
+    }
+}
+
+/// Returns the line to skip to recover from the syntax error at `position`,
+/// or [`None`] if there is no line with content before it.
+fn skipped_line(source: &str, options: &ParseOptions, position: Location) -> Option<Span> {
+    let mut start = 0;
+    let mut lines = Vec::new();
+
+    for text in source.split_inclusive('\n') {
+        let content = text.trim_end_matches(['\n', '\r']);
+        lines.push(Span::new(start, start + content.len()));
+        start += text.len();
+    }
+
+    lines.truncate(position.line);
+    let candidates: Vec<_> = lines
+        .into_iter()
+        .rev()
+        .filter(|span| !source[span.start..span.end].trim().is_empty())
+        .collect();
+
+    let error = (position.line, position.column);
+    let mut best: Option<(Span, (usize, usize))> = None;
+
+    for &span in &candidates {
+        let progress = match parse_with(&blank(source, span), options) {
+            Ok(_) => return Some(span),
+            Err(Error::Syntax { position, .. }) => Some((position.line, position.column)),
+            Err(_) => None,
+        };
+
+        if let Some(progress) = progress.filter(|progress| *progress > error) {
+            if best.is_none_or(|(_, best)| progress > best) {
+                best = Some((span, progress));
+            }
+        }
+
+        // Errors don't continue across documents
+        let line = &source[span.start..span.end];
+        if is_marker(line, "---") || is_marker(line, "...") {
+            break;
+        }
+    }
+
+    best.map(|(span, _)| span).or(candidates.first().copied())
+}
+
+/// Replaces the text of `span` in `source` by spaces, keeping the offsets of
+/// the following text.
+fn blank(source: &str, span: Span) -> String {
+    let spaces = " ".repeat(span.end - span.start);
+    let mut source = source.to_string();
+    source.replace_range(span.start..span.end, &spaces);
+
+    source
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cst::Cst;
+
+    #[test]
+    fn recovery() {
+        let input = "\
+a: 1
+b: {c: [2}
+d: 3
+---
+- ok
+- 'unclosed
+- ü: *
+";
+        let (documents, errors) = parse_lenient(input, &ParseOptions::default()).unwrap();
+        let skipped: Vec<_> = errors
+            .iter()
+            .map(|error| &input[error.skipped.start..error.skipped.end])
+            .collect();
+
+        assert_eq!(skipped, ["b: {c: [2}", "- ü: *", "- 'unclosed"]);
+        assert_eq!(documents.len(), 2);
+        assert!(documents[0].root().unwrap().get("d").is_some());
+        assert_eq!(
+            documents[1].root().unwrap().item(0).unwrap().span,
+            Span::new(27, 29)
+        );
+
+        let (cst, errors) = Cst::parse_lenient(input, &ParseOptions::default()).unwrap();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(cst.to_string(), input);
+    }
+}
@@ -0,0 +1,429 @@
+use crate::{
+    cst::{CstKind, CstNode, ScalarStyle},
+    parser::{Error, Parser, Span},
+};
+
+/// Controls how final line breaks and trailing empty lines of a block scalar
+/// are handled.
+///
+/// See <https://yaml.org/spec/1.2.2/#8112-block-chomping-indicator>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Chomping {
+    Strip,
+    Clip,
+    Keep,
+}
+
+fn scalar(start: usize, end: usize, style: ScalarStyle, value: String) -> CstNode {
+    CstNode {
+        span: Span::new(start, end),
+        anchor: None,
+        tag: None,
+        kind: CstKind::Scalar { style, value },
+    }
+}
+
+impl Parser<'_> {
+    /// Scans the rest of a plain scalar line starting at the current position
+    /// and returns its (trimmed) content.
+    fn scan_plain_line(&mut self, flow: bool) -> &str {
+        let start = self.pos;
+
+        loop {
+            match self.peek() {
+                None | Some('\n') => break,
+                Some('\r') if self.is_eol() => break,
+                Some(':')
+                    if self.is_blank_at(1)
+                        || (flow
+                            && matches!(self.peek_nth(1), Some(',' | '[' | ']' | '{' | '}'))) =>
+                {
+                    break
+                }
+                Some('#') if self.src[..self.pos].ends_with([' ', '\t']) => break,
+                Some(',' | '[' | ']' | '{' | '}') if flow => break,
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+
+        let line = self.src[start..self.pos].trim_end_matches([' ', '\t']);
+        self.pos = start + line.len();
+        line
+    }
+
+    /// Parses a plain scalar. Continuation lines must be indented more than
+    /// `indent` in block context.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#733-plain-style>
+    pub(super) fn parse_plain(&mut self, flow: bool, indent: isize) -> Result<CstNode, Error> {
+        let start = self.pos;
+        let first = self.peek();
+        let is_indicator = match first {
+            Some('-' | '?' | ':') => {
+                self.is_blank_at(1)
+                    || (flow && matches!(self.peek_nth(1), Some(',' | '[' | ']' | '{' | '}')))
+            }
+            Some(',' | '[' | ']' | '{' | '}' | '#' | '&' | '*' | '!' | '|' | '>' | '\'' | '"')
+            | Some('%' | '@' | '`') => true,
+            _ => false,
+        };
+
+        if is_indicator {
+            return self.error(format!(
+                "unexpected character {:?}",
+                first.unwrap_or_default()
+            ));
+        }
+
+        let mut value = self.scan_plain_line(flow).to_string();
+        let mut end = self.pos;
+
+        if value.is_empty() {
+            return self.error("expected a node");
+        }
+
+        loop {
+            self.skip_spaces();
+            if !self.is_eol() || self.is_eof() {
+                break;
+            }
+
+            let mut breaks = 0;
+            while self.is_eol() && !self.is_eof() {
+                self.skip_newline();
+                self.skip_line_prefix();
+                breaks += 1;
+            }
+
+            let blocked = self.is_eof()
+                || self.at_any_document_marker()
+                || self.peek() == Some('#')
+                || (!flow && self.column() as isize <= indent)
+                || (flow && matches!(self.peek(), Some(',' | ']' | '}' | ':')));
+
+            if blocked {
+                break;
+            }
+
+            let line = self.scan_plain_line(flow);
+            if line.is_empty() {
+                break;
+            }
+
+            if breaks == 1 {
+                value.push(' ');
+            } else {
+                value.push_str(&"\n".repeat(breaks - 1));
+            }
+
+            value.push_str(line);
+            end = self.pos;
+        }
+
+        self.pos = end;
+        Ok(scalar(start, end, ScalarStyle::Plain, value))
+    }
+
+    /// Folds the line break at the current position (and all following empty
+    /// lines) into a space or newlines, and skips the leading whitespace of
+    /// the continuation line.
+    fn fold_quoted_line_break(&mut self, value: &mut String, trailing_whitespace: usize) {
+        value.truncate(value.len() - trailing_whitespace);
+
+        let mut breaks = 0;
+        while self.is_eol() && !self.is_eof() {
+            self.skip_newline();
+            self.skip_line_prefix();
+            breaks += 1;
+        }
+
+        if breaks == 1 {
+            value.push(' ');
+        } else {
+            value.push_str(&"\n".repeat(breaks - 1));
+        }
+    }
+
+    /// See <https://yaml.org/spec/1.2.2/#731-double-quoted-style>
+    pub(super) fn parse_double_quoted(&mut self) -> Result<CstNode, Error> {
+        let start = self.pos;
+        self.bump();
+
+        let mut value = String::new();
+        let mut trailing_whitespace = 0;
+
+        loop {
+            match self.peek() {
+                None => return self.error("unterminated double-quoted scalar"),
+                Some('"') => {
+                    self.bump();
+                    break;
+                }
+                Some('\\') => {
+                    self.bump();
+                    trailing_whitespace = 0;
+
+                    if self.is_eol() {
+                        self.skip_newline();
+                        self.skip_line_prefix();
+                        continue;
+                    }
+
+                    let escaped = match self.bump() {
+                        Some('0') => '\0',
+                        Some('a') => '\x07',
+                        Some('b') => '\x08',
+                        Some('t' | '\t') => '\t',
+                        Some('n') => '\n',
+                        Some('v') => '\x0b',
+                        Some('f') => '\x0c',
+                        Some('r') => '\r',
+                        Some('e') => '\x1b',
+                        Some(' ') => ' ',
+                        Some('"') => '"',
+                        Some('/') => '/',
+                        Some('\\') => '\\',
+                        Some('N') => '\u{85}',
+                        Some('_') => '\u{a0}',
+                        Some('L') => '\u{2028}',
+                        Some('P') => '\u{2029}',
+                        Some('x') => self.read_escaped_code_point(2)?,
+                        Some('u') => self.read_escaped_code_point(4)?,
+                        Some('U') => self.read_escaped_code_point(8)?,
+                        _ => return self.error("invalid escape sequence"),
+                    };
+
+                    value.push(escaped);
+                }
+                Some('\n' | '\r') if self.is_eol() => {
+                    self.fold_quoted_line_break(&mut value, trailing_whitespace);
+                    trailing_whitespace = 0;
+                }
+                Some(c) => {
+                    self.bump();
+                    value.push(c);
+
+                    if c == ' ' || c == '\t' {
+                        trailing_whitespace += 1;
+                    } else {
+                        trailing_whitespace = 0;
+                    }
+                }
+            }
+        }
+
+        Ok(scalar(start, self.pos, ScalarStyle::DoubleQuoted, value))
+    }
+
+    fn read_escaped_code_point(&mut self, digits: usize) -> Result<char, Error> {
+        let hex = self.rest().get(..digits).unwrap_or_default();
+
+        match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+            Some(c) if hex.len() == digits => {
+                self.pos += digits;
+                Ok(c)
+            }
+            _ => self.error("invalid escaped code point"),
+        }
+    }
+
+    /// See <https://yaml.org/spec/1.2.2/#732-single-quoted-style>
+    pub(super) fn parse_single_quoted(&mut self) -> Result<CstNode, Error> {
+        let start = self.pos;
+        self.bump();
+
+        let mut value = String::new();
+        let mut trailing_whitespace = 0;
+
+        loop {
+            match self.peek() {
+                None => return self.error("unterminated single-quoted scalar"),
+                Some('\'') if self.peek_nth(1) == Some('\'') => {
+                    self.pos += 2;
+                    value.push('\'');
+                    trailing_whitespace = 0;
+                }
+                Some('\'') => {
+                    self.bump();
+                    break;
+                }
+                Some('\n' | '\r') if self.is_eol() => {
+                    self.fold_quoted_line_break(&mut value, trailing_whitespace);
+                    trailing_whitespace = 0;
+                }
+                Some(c) => {
+                    self.bump();
+                    value.push(c);
+
+                    if c == ' ' || c == '\t' {
+                        trailing_whitespace += 1;
+                    } else {
+                        trailing_whitespace = 0;
+                    }
+                }
+            }
+        }
+
+        Ok(scalar(start, self.pos, ScalarStyle::SingleQuoted, value))
+    }
+
+    /// Parses a literal (`|`) or folded (`>`) block scalar. The content must
+    /// be indented more than `indent`.
+    ///
+    /// See <https://yaml.org/spec/1.2.2/#81-block-scalar-styles>
+    pub(super) fn parse_block_scalar(&mut self, indent: isize) -> Result<CstNode, Error> {
+        let start = self.pos;
+        let literal = self.bump() == Some('|');
+
+        let mut chomping = Chomping::Clip;
+        let mut explicit_indent = None;
+
+        for _ in 0..2 {
+            match self.peek() {
+                Some('-') => chomping = Chomping::Strip,
+                Some('+') => chomping = Chomping::Keep,
+                Some(c @ '1'..='9') if explicit_indent.is_none() => {
+                    explicit_indent = c.to_digit(10).map(|d| indent.max(0) as usize + d as usize)
+                }
+                _ => break,
+            }
+
+            self.bump();
+        }
+
+        let mut end = self.pos;
+        self.skip_spaces();
+        if self.peek() == Some('#') {
+            self.read_comment();
+        }
+
+        if !self.is_eol() {
+            return self.error("unexpected content after block scalar header");
+        }
+
+        let mut content_indent = explicit_indent;
+        let mut lines: Vec<&str> = Vec::new();
+        let mut trailing_blank_lines = 0;
+        let mut final_break = false;
+        let mut after_content = None;
+
+        while self.skip_newline() {
+            let line_start = self.pos;
+            while !self.is_eol() {
+                self.bump();
+            }
+
+            let line = &self.src[line_start..self.pos];
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            let is_blank = line.trim().is_empty();
+
+            if is_blank {
+                // Empty lines end with a line break, the rest of the last
+                // line isn't one
+                if self.is_eof() {
+                    break;
+                }
+
+                let ci = content_indent.unwrap_or(usize::MAX);
+                lines.push(line.get(ci..).unwrap_or_default());
+                trailing_blank_lines += 1;
+                continue;
+            }
+
+            let is_marker = spaces == 0 && (line.starts_with("---") || line.starts_with("..."));
+            let ci = *content_indent.get_or_insert(spaces);
+
+            if spaces < ci || spaces as isize <= indent || is_marker {
+                self.pos = line_start;
+                break;
+            }
+
+            lines.push(&line[ci..]);
+            trailing_blank_lines = 0;
+            end = self.pos;
+            final_break = false;
+            after_content = Some(self.pos);
+        }
+
+        // Trailing blank lines are not part of the scalar (unless kept) and
+        // are handled as trivia.
+        lines.truncate(lines.len() - trailing_blank_lines);
+        if let Some(after_content) = after_content {
+            self.pos = after_content;
+            final_break = self.skip_newline();
+        } else {
+            self.pos = end;
+            self.skip_newline();
+        }
+
+        let mut value = if literal {
+            lines.join("\n")
+        } else {
+            fold_block_lines(&lines)
+        };
+
+        let has_content = after_content.is_some();
+        match chomping {
+            Chomping::Strip => {}
+            Chomping::Clip if has_content && final_break => value.push('\n'),
+            Chomping::Clip => {}
+            Chomping::Keep => {
+                if has_content && final_break {
+                    value.push('\n');
+                }
+
+                value.push_str(&"\n".repeat(trailing_blank_lines));
+            }
+        }
+
+        let style = if literal {
+            ScalarStyle::Literal
+        } else {
+            ScalarStyle::Folded
+        };
+
+        Ok(scalar(start, end, style, value))
+    }
+}
+
+/// Folds the content lines of a folded block scalar. Line breaks between two
+/// normal lines are folded into a space, empty lines are kept as line breaks
+/// and more-indented lines are not folded at all.
+///
+/// See <https://yaml.org/spec/1.2.2/#813-folded-style>
+fn fold_block_lines(lines: &[&str]) -> String {
+    let mut value = String::new();
+    let mut first = true;
+    let mut breaks = 0;
+    let mut previous_more_indented = false;
+
+    for line in lines {
+        if line.is_empty() {
+            breaks += 1;
+            continue;
+        }
+
+        let more_indented = line.starts_with([' ', '\t']);
+
+        if first {
+            value.push_str(&"\n".repeat(breaks));
+        } else if !more_indented && !previous_more_indented {
+            if breaks == 0 {
+                value.push(' ');
+            } else {
+                value.push_str(&"\n".repeat(breaks));
+            }
+        } else {
+            value.push_str(&"\n".repeat(breaks + 1));
+        }
+
+        value.push_str(line);
+        first = false;
+        breaks = 0;
+        previous_more_indented = more_indented;
+    }
+
+    value
+}
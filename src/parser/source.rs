@@ -0,0 +1,138 @@
+use std::fmt;
+
+use crate::parser::{Error, Location};
+
+/// A parse error together with the source text it occurred in, which allows
+/// to show the error in an excerpt of the source. See [`Error::with_source`].
+///
+/// ```
+/// use yaml_ast::parser::parse;
+///
+/// let source = "a: 1\nb: [2, 3\n";
+/// let error = parse(source).unwrap_err().with_source(source);
+///
+/// assert_eq!(
+///     error.render(),
+///     "error: expected ',' or ']' in flow collection at line 3 column 1\n  |\n3 | \n  | ^\n"
+/// );
+/// ```
+///
+/// With the `miette` feature enabled, this type implements
+/// `miette::Diagnostic`, which labels the position of the error in the
+/// source text.
+#[derive(Debug)]
+pub struct SourceError {
+    error: Error,
+    pub(crate) source_code: String,
+}
+
+impl Error {
+    /// Attaches the `source` text the error occurred in.
+    pub fn with_source(self, source: impl Into<String>) -> SourceError {
+        SourceError {
+            error: self,
+            source_code: source.into(),
+        }
+    }
+
+    /// Returns the position of the error in the source text, if it has one.
+    /// [`Error::Encoding`] errors occur before there is a source text.
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            Error::Syntax { position, .. } | Error::LimitExceeded { position, .. } => {
+                Some(*position)
+            }
+            Error::Encoding { .. } | Error::Read { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for SourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl SourceError {
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    pub fn into_error(self) -> Error {
+        self.error
+    }
+
+    pub fn source_code(&self) -> &str {
+        &self.source_code
+    }
+
+    /// Returns the byte offset of the error in the source text, see
+    /// [`Error::location`].
+    pub fn offset(&self) -> Option<usize> {
+        let location = self.error.location()?;
+        Some(location.offset(&self.source_code))
+    }
+
+    /// Renders the error followed by the line of the source text it
+    /// occurred in, with a caret pointing at the position of the error.
+    pub fn render(&self) -> String {
+        let mut output = format!("error: {}\n", self.error);
+
+        let Some(location) = self.error.location() else {
+            return output;
+        };
+
+        let line = self
+            .source_code
+            .lines()
+            .nth(location.line - 1)
+            .unwrap_or("");
+        let line = line.trim_end_matches('\r');
+        let number = location.line.to_string();
+        let gutter = " ".repeat(number.len());
+
+        // Tabs are kept to align the caret with the same indentation
+        let indent: String = line
+            .chars()
+            .take(location.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        output.push_str(&format!(
+            "{gutter} |\n{number} | {line}\n{gutter} | {indent}^\n"
+        ));
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn render() {
+        let source = "a: 1\nb: [\t'1' 2]\n";
+        let error = parse(source).unwrap_err().with_source(source);
+
+        assert_eq!(error.offset(), Some(14));
+        assert_eq!(
+            error.render(),
+            "error: expected ',' or ']' in flow collection at line 2 column 10\n  |\n2 | b: [\t'1' 2]\n  |     \t    ^\n"
+        );
+
+        let location = Location { line: 2, column: 5 };
+        assert_eq!(location.offset("ab\nü\n"), 5);
+        assert_eq!(
+            Location::from_offset("ab\nüx\n", 5),
+            Location { line: 2, column: 2 }
+        );
+        assert_eq!(Location { line: 2, column: 2 }.offset("ab\nüx\n"), 5);
+    }
+}
@@ -0,0 +1,391 @@
+use std::{collections::VecDeque, io::Read};
+
+use snafu::ResultExt;
+
+use crate::{
+    cst::{Comment, CstDocument, CstKind, CstNode, Trivia},
+    parser::{parse_counted, Encoding, Error, Location, ParseOptions, ReadSnafu, Span},
+};
+
+/// The number of bytes read at once by [`StreamParser::from_reader`].
+const READ_SIZE: usize = 8 * 1024;
+
+/// A parser which is fed the character stream in chunks, e.g. as it arrives
+/// over a socket, and returns each document as soon as it is complete.
+///
+/// A document is complete once the line with the start marker (`---`) of
+/// the next document or its own end marker (`...`) was fed. Only the text
+/// of the incomplete document is kept in memory. Spans and positions of
+/// errors are relative to the start of the whole stream.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// use yaml_ast::{events::Event, parser::{ParseOptions, StreamParser}};
+///
+/// let mut parser = StreamParser::new(ParseOptions::default());
+/// assert!(parser.feed(b"name: a\n--").unwrap().is_empty());
+///
+/// let documents = parser.feed(b"-\nname: b\n").unwrap();
+/// assert_eq!(documents.len(), 1);
+///
+/// let mut scalars = Vec::new();
+/// let _ = documents[0].push_events(&mut |event, _| {
+///     if let Event::Scalar { value, .. } = event {
+///         scalars.push(value);
+///     }
+///     ControlFlow::<()>::Continue(())
+/// });
+/// assert_eq!(scalars, ["name", "a"]);
+///
+/// let documents = parser.finish().unwrap();
+/// // The value `b` starts after `name: a\n---\nname: `
+/// assert_eq!(documents[0].root().unwrap().get("name").unwrap().span.start, 18);
+/// ```
+#[derive(Debug)]
+pub struct StreamParser {
+    options: ParseOptions,
+
+    /// The encoding of the stream, once enough bytes were fed to detect it.
+    encoding: Option<Encoding>,
+
+    /// Bytes which were not decoded yet, e.g. an incomplete character.
+    /// Streams which are not encoded in UTF-8 are decoded when finished.
+    bytes: Vec<u8>,
+
+    /// The decoded text of the incomplete document.
+    text: String,
+
+    /// The length of the text in `text` whose lines were checked for
+    /// document markers.
+    scanned: usize,
+
+    /// Indicates that the content of a document was found in `text`.
+    started: bool,
+
+    /// The end of the last complete document in `text`.
+    boundary: usize,
+
+    /// The byte offset and number of lines of the text preceding `text`.
+    offset: usize,
+    lines: usize,
+
+    /// The number of nodes of all documents parsed so far.
+    nodes: usize,
+}
+
+impl StreamParser {
+    pub fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            encoding: None,
+            bytes: Vec::new(),
+            text: String::new(),
+            scanned: 0,
+            started: false,
+            boundary: 0,
+            offset: 0,
+            lines: 0,
+            nodes: 0,
+        }
+    }
+
+    /// Returns an iterator over the documents of the character stream read
+    /// from `reader`. The stream is read in chunks whenever the next
+    /// document is requested. The iterator ends after the first error.
+    pub fn from_reader<R: Read>(reader: R, options: ParseOptions) -> ReaderDocuments<R> {
+        ReaderDocuments {
+            reader,
+            parser: Some(Self::new(options)),
+            documents: VecDeque::new(),
+        }
+    }
+
+    /// Feeds the next chunk of the character stream and returns the
+    /// documents which were completed by it.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Vec<CstDocument>, Error> {
+        self.bytes.extend_from_slice(bytes);
+
+        let encoding = match self.encoding {
+            Some(encoding) => encoding,
+            None if self.bytes.len() >= 4 => *self.encoding.insert(Encoding::detect(&self.bytes)),
+            None => return Ok(Vec::new()),
+        };
+
+        if encoding != Encoding::Utf8 {
+            return Ok(Vec::new());
+        }
+
+        match std::str::from_utf8(&self.bytes) {
+            Ok(text) => {
+                self.text.push_str(text);
+                self.bytes.clear();
+            }
+            // An incomplete character at the end is completed by the next
+            // chunk
+            Err(err) if err.error_len().is_none() => {
+                let len = err.valid_up_to();
+                let text = std::str::from_utf8(&self.bytes[..len]).expect("valid UTF-8");
+                self.text.push_str(text);
+                self.bytes.drain(..len);
+            }
+            Err(err) => {
+                return Err(Error::Encoding {
+                    encoding,
+                    offset: self.offset + self.text.len() + err.valid_up_to(),
+                })
+            }
+        }
+
+        self.scan();
+        self.parse(self.boundary)
+    }
+
+    /// Ends the character stream and returns the remaining documents.
+    pub fn finish(mut self) -> Result<Vec<CstDocument>, Error> {
+        if !self.bytes.is_empty() {
+            let encoding = self
+                .encoding
+                .unwrap_or_else(|| Encoding::detect(&self.bytes));
+            let decoded = self.offset + self.text.len();
+            let text = encoding.decode(&self.bytes).map_err(|err| match err {
+                Error::Encoding { encoding, offset } => Error::Encoding {
+                    encoding,
+                    offset: decoded + offset,
+                },
+                err => err,
+            })?;
+
+            self.text.push_str(&text);
+        }
+
+        self.parse(self.text.len())
+    }
+
+    /// Finds the end of the last complete document in the lines of `text`
+    /// which were not checked yet.
+    fn scan(&mut self) {
+        while let Some(len) = self.text[self.scanned..].find('\n') {
+            let start = self.scanned;
+            let end = start + len + 1;
+            let line = self.text[start..end].trim_start_matches('\u{feff}');
+            self.scanned = end;
+
+            if is_marker(line, "---") {
+                if self.started {
+                    self.boundary = start;
+                }
+                self.started = true;
+            } else if is_marker(line, "...") {
+                self.boundary = end;
+                self.started = false;
+            } else {
+                let content = line.trim_start();
+                let is_trivia = content.is_empty() || content.starts_with('#');
+                let is_directive = !self.started && line.starts_with('%');
+                self.started |= !is_trivia && !is_directive;
+            }
+        }
+    }
+
+    /// Parses the text up to `end` and removes it from `text`.
+    fn parse(&mut self, end: usize) -> Result<Vec<CstDocument>, Error> {
+        if end == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut documents = parse_counted(&self.text[..end], &self.options, &mut self.nodes)
+            .map_err(|err| self.shift_error(err))?;
+
+        for document in &mut documents {
+            shift_document(document, self.offset);
+        }
+
+        self.offset += end;
+        self.lines += self.text[..end].matches('\n').count();
+        self.text.drain(..end);
+        self.scanned -= end.min(self.scanned);
+        self.boundary = 0;
+
+        Ok(documents)
+    }
+
+    /// Makes the position of an error in `text` relative to the stream.
+    fn shift_error(&self, err: Error) -> Error {
+        let shift = |position: Location| Location {
+            line: position.line + self.lines,
+            column: position.column,
+        };
+
+        match err {
+            Error::Syntax { message, position } => Error::Syntax {
+                message,
+                position: shift(position),
+            },
+            Error::LimitExceeded { limit, position } => Error::LimitExceeded {
+                limit,
+                position: shift(position),
+            },
+            err => err,
+        }
+    }
+}
+
+/// The documents of a character stream read by
+/// [`StreamParser::from_reader`].
+#[derive(Debug)]
+pub struct ReaderDocuments<R> {
+    reader: R,
+    parser: Option<StreamParser>,
+    documents: VecDeque<CstDocument>,
+}
+
+impl<R: Read> Iterator for ReaderDocuments<R> {
+    type Item = Result<CstDocument, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buffer = [0; READ_SIZE];
+
+        loop {
+            if let Some(document) = self.documents.pop_front() {
+                return Some(Ok(document));
+            }
+
+            let parser = self.parser.as_mut()?;
+            let documents = match self.reader.read(&mut buffer) {
+                Ok(0) => self.parser.take().map(StreamParser::finish)?,
+                Ok(len) => parser.feed(&buffer[..len]),
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => Err(err).context(ReadSnafu),
+            };
+
+            match documents {
+                Ok(documents) => self.documents.extend(documents),
+                Err(err) => {
+                    self.parser = None;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+/// Returns whether the `line` is the document `marker`, optionally followed
+/// by content separated by white space.
+pub(super) fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n']))
+}
+
+fn shift_document(document: &mut CstDocument, offset: usize) {
+    shift_span(&mut document.span, offset);
+    document
+        .comments
+        .iter_mut()
+        .for_each(|comment| shift_comment(comment, offset));
+
+    if let Some(root) = &mut document.root {
+        shift_node(root, offset);
+    }
+}
+
+fn shift_node(node: &mut CstNode, offset: usize) {
+    shift_span(&mut node.span, offset);
+
+    match &mut node.kind {
+        CstKind::Scalar { .. } | CstKind::Alias(_) => {}
+        CstKind::Sequence { items, .. } => {
+            for item in items {
+                shift_node(&mut item.node, offset);
+                shift_trivia(&mut item.leading, &mut item.trailing_comment, offset);
+            }
+        }
+        CstKind::Mapping { pairs, .. } => {
+            for pair in pairs {
+                shift_node(&mut pair.key, offset);
+                shift_node(&mut pair.value, offset);
+                shift_trivia(&mut pair.leading, &mut pair.trailing_comment, offset);
+            }
+        }
+    }
+}
+
+fn shift_trivia(leading: &mut Trivia, trailing: &mut Option<Comment>, offset: usize) {
+    leading
+        .comments
+        .iter_mut()
+        .chain(trailing)
+        .for_each(|comment| shift_comment(comment, offset));
+}
+
+fn shift_comment(comment: &mut Comment, offset: usize) {
+    shift_span(&mut comment.span, offset);
+}
+
+fn shift_span(span: &mut Span, offset: usize) {
+    *span = Span::new(span.start + offset, span.end + offset);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn chunks() {
+        let input = "\
+%YAML 1.2
+---
+# Hosts
+hosts: [a, b]   # inline
+script: |
+  --- not a marker
+  ...
+...
+# second
+--- &x
+- ü
+- *x
+---
+";
+        let expected = format!("{:?}", parse(input).unwrap());
+
+        for size in [1, 2, 7, input.len()] {
+            let mut parser = StreamParser::new(ParseOptions::default());
+            let mut documents = Vec::new();
+
+            for chunk in input.as_bytes().chunks(size) {
+                documents.extend(parser.feed(chunk).unwrap());
+            }
+            documents.extend(parser.finish().unwrap());
+
+            assert_eq!(format!("{documents:?}"), expected);
+        }
+
+        let documents = StreamParser::from_reader(input.as_bytes(), ParseOptions::default());
+        let documents: Result<Vec<_>, _> = documents.collect();
+        assert_eq!(format!("{:?}", documents.unwrap()), expected);
+    }
+
+    #[test]
+    fn errors() {
+        let input = "a: 1\n---\nb: [\n";
+        let Err(Error::Syntax { position, .. }) = parse(input) else {
+            unreachable!()
+        };
+
+        let mut parser = StreamParser::new(ParseOptions::default());
+        assert_eq!(parser.feed(input.as_bytes()).unwrap().len(), 1);
+        assert!(matches!(
+            parser.finish(),
+            Err(Error::Syntax { position: actual, .. }) if actual == position
+        ));
+
+        let mut parser = StreamParser::new(ParseOptions::default());
+        parser.feed(b"a: 1\n---\nb: ").unwrap();
+        assert!(matches!(
+            parser.feed(&[0xff]),
+            Err(Error::Encoding { offset: 12, .. })
+        ));
+    }
+}
@@ -0,0 +1,529 @@
+//! Applying JSON Patches (RFC 6902) to [`Document`]s.
+//!
+//! A patch is a list of [`Operation`]s, each targeting a node by its JSON
+//! Pointer (RFC 6901), e.g. `/spec/containers/0/image`. Tokens select the
+//! value of a string key in mappings and the item at an index in sequences,
+//! where `-` refers to the end of a sequence. Tags are looked through.
+//!
+//! Nodes are modified in place: replacing a value keeps the blank lines and
+//! comments of its mapping pair, and all untouched nodes keep their
+//! presentation details. Like the [edits](crate::edit) of the document, the
+//! operations move the [`lexical_forms`](Document::lexical_forms),
+//! [`item_comments`](Document::item_comments),
+//! [`provenance`](Document::provenance) and [`styles`](Document::styles) of
+//! added, removed and moved nodes and of the sequence items after them.
+//!
+//! ```
+//! use yaml_ast::{patch::{self, Operation}, Document};
+//!
+//! let mut document: Document = "# Replicas\nreplicas: 1\nports: [80]\n".parse().unwrap();
+//!
+//! patch::apply(&mut document, &[
+//!     Operation::replace("/replicas", 3),
+//!     Operation::add("/ports/-", 443),
+//! ])
+//! .unwrap();
+//!
+//! assert_eq!(
+//!     document.to_string(),
+//!     "---\n# Replicas\nreplicas: 3\nports:\n  - 80\n  - 443\n...\n"
+//! );
+//! ```
+//!
+//! #### Reference
+//!
+//! - <https://www.rfc-editor.org/rfc/rfc6902>
+//! - <https://www.rfc-editor.org/rfc/rfc6901>
+use snafu::{ensure, OptionExt, Snafu};
+
+use crate::{edit::Tables, events::PathSegment, Document, Node};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("the JSON pointer {pointer:?} doesn't start with a slash"))]
+    InvalidPointer { pointer: String },
+
+    #[snafu(display("the JSON pointer {pointer:?} doesn't refer to an existing node"))]
+    NotFound { pointer: String },
+
+    #[snafu(display("the JSON pointer {pointer:?} doesn't refer to a valid sequence index"))]
+    InvalidIndex { pointer: String },
+
+    #[snafu(display("the node at {pointer:?} cannot be removed"))]
+    RemoveRoot { pointer: String },
+
+    #[snafu(display("the node at {from:?} cannot be moved into its own child {path:?}"))]
+    MoveIntoChild { from: String, path: String },
+
+    #[snafu(display("the node at {pointer:?} doesn't have the expected value"))]
+    TestFailed { pointer: String },
+
+    #[snafu(display("the document has no root node"))]
+    MissingRoot,
+
+    #[snafu(display("the patch is invalid: {reason}"))]
+    InvalidPatch { reason: String },
+}
+
+/// A single operation of a JSON Patch. Paths are JSON Pointers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    /// Adds the value at the path. Existing mapping values are replaced,
+    /// sequence items are inserted before the item at the index.
+    Add { path: String, value: Node },
+
+    /// Removes the node at the path.
+    Remove { path: String },
+
+    /// Replaces the node at the path, which must exist.
+    Replace { path: String, value: Node },
+
+    /// Removes the node at `from` and adds it at the path.
+    Move { from: String, path: String },
+
+    /// Adds a copy of the node at `from` at the path.
+    Copy { from: String, path: String },
+
+    /// Checks that the node at the path is equal to the value.
+    Test { path: String, value: Node },
+}
+
+impl Operation {
+    pub fn add(path: impl Into<String>, value: impl Into<Node>) -> Self {
+        Self::Add {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn remove(path: impl Into<String>) -> Self {
+        Self::Remove { path: path.into() }
+    }
+
+    pub fn replace(path: impl Into<String>, value: impl Into<Node>) -> Self {
+        Self::Replace {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn move_from(from: impl Into<String>, path: impl Into<String>) -> Self {
+        Self::Move {
+            from: from.into(),
+            path: path.into(),
+        }
+    }
+
+    pub fn copy_from(from: impl Into<String>, path: impl Into<String>) -> Self {
+        Self::Copy {
+            from: from.into(),
+            path: path.into(),
+        }
+    }
+
+    pub fn test(path: impl Into<String>, value: impl Into<Node>) -> Self {
+        Self::Test {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Reads the operations of a patch document, which is a sequence of
+    /// mappings with the `op`, `path`, `from` and `value` keys.
+    pub fn from_patch(patch: &Node) -> Result<Vec<Self>, Error> {
+        let invalid = |reason: &str| InvalidPatchSnafu { reason }.build();
+        let operations = patch
+            .as_sequence()
+            .ok_or_else(|| invalid("expected a sequence of operations"))?;
+
+        operations
+            .iter()
+            .map(|operation| {
+                let string = |key: &str| {
+                    operation
+                        .get_path(key)
+                        .and_then(Node::as_str)
+                        .map(String::from)
+                        .ok_or_else(|| invalid(&format!("missing string {key:?}")))
+                };
+                let value = || {
+                    operation
+                        .get_path("value")
+                        .cloned()
+                        .ok_or_else(|| invalid("missing \"value\""))
+                };
+
+                Ok(match string("op")?.as_str() {
+                    "add" => Self::Add {
+                        path: string("path")?,
+                        value: value()?,
+                    },
+                    "remove" => Self::Remove {
+                        path: string("path")?,
+                    },
+                    "replace" => Self::Replace {
+                        path: string("path")?,
+                        value: value()?,
+                    },
+                    "move" => Self::Move {
+                        from: string("from")?,
+                        path: string("path")?,
+                    },
+                    "copy" => Self::Copy {
+                        from: string("from")?,
+                        path: string("path")?,
+                    },
+                    "test" => Self::Test {
+                        path: string("path")?,
+                        value: value()?,
+                    },
+                    op => return Err(invalid(&format!("unknown operation {op:?}"))),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Applies the `operations` in order to the root node of the `document`.
+/// Patches are atomic: if any operation fails, the document is left
+/// unchanged.
+pub fn apply(document: &mut Document, operations: &[Operation]) -> Result<(), Error> {
+    ensure!(!document.nodes.is_empty(), MissingRootSnafu);
+    let mut patched = document.clone();
+
+    for operation in operations {
+        apply_operation(&mut patched, operation)?;
+    }
+
+    *document = patched;
+    Ok(())
+}
+
+fn apply_operation(document: &mut Document, operation: &Operation) -> Result<(), Error> {
+    match operation {
+        Operation::Add { path, value } => {
+            let added = add(root(document), path, value.clone())?;
+            insert_tables(document, added, Tables::default());
+        }
+        Operation::Remove { path } => {
+            let (_, removed) = remove(root(document), path)?;
+            document.forget_removed(&removed);
+        }
+        Operation::Replace { path, value } => {
+            let (node, replaced) = get_mut(root(document), path)?;
+            *node = value.clone();
+            document.forget_replaced(&replaced);
+        }
+        Operation::Move { from, path } => {
+            if from == path {
+                return get_mut(root(document), from).map(|_| ());
+            }
+
+            ensure!(
+                !path.starts_with(&format!("{from}/")),
+                MoveIntoChildSnafu { from, path }
+            );
+
+            let (_, from_path) = get_mut(root(document), from)?;
+            let tables = document.tables_at(&from_path);
+
+            let (value, removed) = remove(root(document), from)?;
+            document.forget_removed(&removed);
+
+            let added = add(root(document), path, value)?;
+            insert_tables(document, added, tables);
+        }
+        Operation::Copy { from, path } => {
+            let (value, from_path) = get_mut(root(document), from)?;
+            let value = value.clone();
+            let tables = document.tables_at(&from_path);
+
+            let added = add(root(document), path, value)?;
+            insert_tables(document, added, tables);
+        }
+        Operation::Test { path, value } => {
+            let (node, _) = get_mut(root(document), path)?;
+            ensure!(*node == *value, TestFailedSnafu { pointer: path });
+        }
+    }
+
+    Ok(())
+}
+
+fn root(document: &mut Document) -> &mut Node {
+    &mut document.nodes[0]
+}
+
+/// Inserts the `tables` of the node added at the path returned by [`add`].
+/// Items inserted into a sequence move the entries of the following items,
+/// replaced nodes lose their entries.
+fn insert_tables(
+    document: &mut Document,
+    (path, inserted): (Vec<PathSegment>, bool),
+    tables: Tables,
+) {
+    match inserted {
+        true => document.shift_inserted(&path),
+        false => document.forget_replaced(&path),
+    }
+
+    document.insert_tables(&path, tables);
+}
+
+/// Splits the JSON `pointer` into its unescaped reference tokens.
+fn tokens(pointer: &str) -> Result<Vec<String>, Error> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rest = pointer
+        .strip_prefix('/')
+        .context(InvalidPointerSnafu { pointer })?;
+
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// Returns the node selected by `token` in the (untagged) `node` and its
+/// path segment.
+fn child_mut<'a>(node: &'a mut Node, token: &str) -> Option<(&'a mut Node, PathSegment)> {
+    match untagged_mut(node) {
+        Node::Mapping(mapping) => {
+            let position = mapping.position(&Node::String(token.into()))?;
+            let value = mapping.value_at_mut(position)?;
+            Some((value, PathSegment::Key(token.into())))
+        }
+        Node::Sequence(items) => {
+            let index = index(token)?;
+            Some((items.get_mut(index)?, PathSegment::Index(index)))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the node at the `tokens` of `pointer` and its path.
+fn descend<'a>(
+    root: &'a mut Node,
+    tokens: &[String],
+    pointer: &str,
+) -> Result<(&'a mut Node, Vec<PathSegment>), Error> {
+    let mut node = root;
+    let mut path = Vec::new();
+
+    for token in tokens {
+        let (child, segment) = child_mut(node, token).context(NotFoundSnafu { pointer })?;
+        node = child;
+        path.push(segment);
+    }
+
+    Ok((node, path))
+}
+
+fn get_mut<'a>(
+    root: &'a mut Node,
+    pointer: &str,
+) -> Result<(&'a mut Node, Vec<PathSegment>), Error> {
+    descend(root, &tokens(pointer)?, pointer)
+}
+
+/// Adds the `value` at `pointer`. Returns the path of the added node and
+/// whether it was inserted into a sequence.
+fn add(root: &mut Node, pointer: &str, value: Node) -> Result<(Vec<PathSegment>, bool), Error> {
+    let mut tokens = tokens(pointer)?;
+    let Some(token) = tokens.pop() else {
+        *root = value;
+        return Ok((Vec::new(), false));
+    };
+
+    let (parent, mut path) = descend(root, &tokens, pointer)?;
+    match untagged_mut(parent) {
+        Node::Mapping(mapping) => {
+            let key = Node::String(token.clone());
+            match mapping.position(&key) {
+                Some(position) => {
+                    let Some(node) = mapping.value_at_mut(position) else {
+                        unreachable!()
+                    };
+                    *node = value;
+                }
+                None => {
+                    mapping.push_pair(key, value);
+                }
+            }
+
+            path.push(PathSegment::Key(token));
+            Ok((path, false))
+        }
+        Node::Sequence(items) => {
+            let index = match token.as_str() {
+                "-" => items.len(),
+                token => index(token)
+                    .filter(|index| *index <= items.len())
+                    .context(InvalidIndexSnafu { pointer })?,
+            };
+
+            items.insert(index, value);
+            path.push(PathSegment::Index(index));
+            Ok((path, true))
+        }
+        _ => NotFoundSnafu { pointer }.fail(),
+    }
+}
+
+/// Removes the node at `pointer` and returns it together with its path.
+fn remove(root: &mut Node, pointer: &str) -> Result<(Node, Vec<PathSegment>), Error> {
+    let mut tokens = tokens(pointer)?;
+    let token = tokens.pop().context(RemoveRootSnafu { pointer })?;
+
+    let (parent, mut path) = descend(root, &tokens, pointer)?;
+    match untagged_mut(parent) {
+        Node::Mapping(mapping) => {
+            let position = mapping
+                .position(&Node::String(token.clone()))
+                .context(NotFoundSnafu { pointer })?;
+
+            path.push(PathSegment::Key(token));
+            Ok((mapping.remove(position).value, path))
+        }
+        Node::Sequence(items) => {
+            let index = index(&token)
+                .filter(|index| *index < items.len())
+                .context(InvalidIndexSnafu { pointer })?;
+
+            path.push(PathSegment::Index(index));
+            Ok((items.remove(index), path))
+        }
+        _ => NotFoundSnafu { pointer }.fail(),
+    }
+}
+
+/// Parses a sequence index, which must not have leading zeros.
+fn index(token: &str) -> Option<usize> {
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+
+    token.parse().ok()
+}
+
+fn untagged_mut(mut node: &mut Node) -> &mut Node {
+    while let Node::Tagged(tagged) = node {
+        node = &mut tagged.node;
+    }
+
+    node
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    fn document(node: Node) -> Document {
+        Document {
+            nodes: vec![node],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn operations() {
+        let mut doc = document(yaml!({a: {"b/c": 1, "d~e": [1, 2]}, f: "x"}));
+
+        apply(
+            &mut doc,
+            &[
+                Operation::test("/a/b~1c", 1),
+                Operation::add("/a/d~0e/0", 0),
+                Operation::remove("/a/d~0e/2"),
+                Operation::move_from("/f", "/a/f"),
+                Operation::copy_from("/a/f", "/g"),
+                Operation::replace("/g", "y"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc.nodes[0],
+            yaml!({a: {"b/c": 1, "d~e": [0, 1], f: "x"}, g: "y"})
+        );
+    }
+
+    #[test]
+    fn atomic() {
+        let node = yaml!({a: [1]});
+        let mut doc = document(node.clone());
+
+        let result = apply(
+            &mut doc,
+            &[Operation::remove("/a/0"), Operation::test("/a", yaml!([1]))],
+        );
+
+        assert_eq!(
+            result,
+            Err(Error::TestFailed {
+                pointer: "/a".into()
+            })
+        );
+        assert_eq!(doc.nodes[0], node);
+
+        let result = apply(&mut doc, &[Operation::move_from("/a", "/a/0")]);
+        assert!(matches!(result, Err(Error::MoveIntoChild { .. })));
+
+        let result = apply(&mut doc, &[Operation::add("/a/01", 2)]);
+        assert!(matches!(result, Err(Error::InvalidIndex { .. })));
+    }
+
+    #[test]
+    fn presentation() {
+        let mut doc: Document = "\
+args:
+  - a # ia
+  - b # ib
+flags:
+  - x
+  - ~
+"
+        .parse()
+        .unwrap();
+
+        apply(
+            &mut doc,
+            &[
+                Operation::add("/args/0", "z"),
+                Operation::remove("/flags/0"),
+                Operation::move_from("/args/1", "/args/-"),
+                Operation::copy_from("/args/1", "/flags/-"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc.to_string(),
+            "---\nargs:\n  - z\n  - b # ib\n  - a # ia\nflags:\n  - ~\n  - b # ib\n...\n"
+        );
+
+        apply(&mut doc, &[Operation::replace("/flags", yaml!([1]))]).unwrap();
+        assert!(doc.lexical_forms.is_empty());
+        assert_eq!(doc.item_comments.len(), 2);
+    }
+
+    #[test]
+    fn from_patch() {
+        let patch = yaml!([
+            {op: "replace", path: "/a", value: 2},
+            {op: "remove", path: "/b"}
+        ]);
+
+        let operations = Operation::from_patch(&patch).unwrap();
+        assert_eq!(
+            operations,
+            vec![Operation::replace("/a", 2), Operation::remove("/b")]
+        );
+
+        let patch = yaml!([{op: "frobnicate", path: "/a"}]);
+        assert!(Operation::from_patch(&patch).is_err());
+    }
+}
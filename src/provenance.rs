@@ -0,0 +1,207 @@
+//! User metadata about the origin of nodes, e.g. to report which file a
+//! value came from when merging layered configurations.
+//!
+//! A [`Provenance`] is a set of arbitrary key-value pairs, like the source
+//! file, the generating tool or the overlay which set a value. Documents
+//! keep them by the path of the node in [`Document::provenance`], next to
+//! the nodes rather than inside of them, like the
+//! [`lexical_forms`](Document::lexical_forms). Nodes inherit the provenance
+//! of their closest ancestor, so annotating the root node is enough to
+//! record the file a document was read from. The entries follow their
+//! nodes through the [edits](crate::edit) of the document and through
+//! [`Document::merge`].
+//!
+//! ```
+//! use yaml_ast::{provenance::Provenance, Document};
+//!
+//! let mut document: Document = "image: nginx\nports: [80]\n".parse().unwrap();
+//! document
+//!     .set_provenance("", Provenance::new().with_source("values.yaml"))
+//!     .unwrap();
+//! document
+//!     .set_provenance("ports.0", Provenance::new().with("generated-by", "render"))
+//!     .unwrap();
+//!
+//! let image = document.provenance("image").unwrap();
+//! assert_eq!(image.source(), Some("values.yaml"));
+//!
+//! let port = document.provenance("ports.0").unwrap();
+//! assert_eq!(port.get("generated-by"), Some("render"));
+//! assert_eq!(port.source(), None);
+//! ```
+use std::collections::BTreeMap;
+
+use snafu::{OptionExt, Snafu};
+
+use crate::{emitter::key_segment, events::PathSegment, Document, Node};
+
+/// The key of the source of a node, see [`Provenance::source`].
+pub const SOURCE_KEY: &str = "source";
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("no node is located at {path:?}"))]
+    NotFound { path: String },
+}
+
+/// Metadata about the origin of a node, as key-value pairs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Provenance {
+    pub entries: BTreeMap<String, String>,
+}
+
+impl Provenance {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `value` of the `key`, e.g. `generated-by`.
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.entries.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the source of the node, e.g. the file it was read from.
+    pub fn with_source(self, source: impl Into<String>) -> Self {
+        self.with(SOURCE_KEY, source)
+    }
+
+    /// Returns the value of the `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    /// Returns the source of the node, e.g. the file it was read from.
+    pub fn source(&self) -> Option<&str> {
+        self.get(SOURCE_KEY)
+    }
+}
+
+impl Document {
+    /// Returns the provenance of the node at the dot-separated `path`, see
+    /// [`Node::get_path`]. Nodes without provenance of their own inherit
+    /// the provenance of their closest ancestor. Returns [`None`] if no node
+    /// is located at `path`.
+    pub fn provenance(&self, path: &str) -> Option<&Provenance> {
+        let root = self.nodes.first()?;
+        self.provenance_at(&segments(root, path)?)
+    }
+
+    /// Returns the provenance of the node at the `path`, see
+    /// [`Document::provenance`].
+    pub fn provenance_at(&self, path: &[PathSegment]) -> Option<&Provenance> {
+        let mut node = self.nodes.first()?;
+        let mut provenance = self.provenance.get(&[][..]);
+
+        for (depth, segment) in path.iter().enumerate() {
+            node = child(node, segment)?;
+            provenance = self.provenance.get(&path[..=depth]).or(provenance);
+        }
+
+        provenance
+    }
+
+    /// Sets the provenance of the node at the dot-separated `path` and
+    /// returns its previous provenance, if any. The provenance applies to
+    /// all nodes within the node which don't have provenance of their own.
+    pub fn set_provenance(
+        &mut self,
+        path: &str,
+        provenance: Provenance,
+    ) -> Result<Option<Provenance>, Error> {
+        let segments = self
+            .nodes
+            .first()
+            .and_then(|root| segments(root, path))
+            .context(NotFoundSnafu { path })?;
+
+        Ok(self.provenance.insert(segments, provenance))
+    }
+}
+
+/// Returns the path of the node at the dot-separated `path` within `root`.
+pub(crate) fn segments(root: &Node, path: &str) -> Option<Vec<PathSegment>> {
+    if path.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut node = root;
+    let mut segments = Vec::new();
+
+    for segment in path.split('.') {
+        let segment = match node.untagged() {
+            Node::Sequence(_) => PathSegment::Index(segment.parse().ok()?),
+            _ => PathSegment::Key(segment.into()),
+        };
+
+        node = child(node, &segment)?;
+        segments.push(segment);
+    }
+
+    Some(segments)
+}
+
+/// Returns the child of `node` at the `segment`, looking through tags.
+fn child<'a>(node: &'a Node, segment: &PathSegment) -> Option<&'a Node> {
+    match (node.untagged(), segment) {
+        (Node::Mapping(mapping), segment) => mapping
+            .pairs()
+            .find(|pair| key_segment(&pair.key) == *segment)
+            .map(|pair| &pair.value),
+        (Node::Sequence(items), PathSegment::Index(index)) => items.get(*index),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merge::{MergeStrategy, SequenceMerge};
+
+    fn document(source: &str, text: &str) -> Document {
+        let mut document: Document = text.parse().unwrap();
+        document
+            .set_provenance("", Provenance::new().with_source(source))
+            .unwrap();
+        document
+    }
+
+    fn source<'a>(document: &'a Document, path: &str) -> Option<&'a str> {
+        document.provenance(path).and_then(Provenance::source)
+    }
+
+    #[test]
+    fn merge_and_edit() {
+        let mut base = document("base.yaml", "a: {b: 1, c: 2}\nargs: [x]\n");
+        let mut overrides = document("prod.yaml", "a: {c: 3, d: {e: 4}}\nargs: [y]\n");
+        overrides
+            .set_provenance("a.d.e", Provenance::new().with_source("secret.yaml"))
+            .unwrap();
+
+        let strategy = MergeStrategy::deep().with_sequences(SequenceMerge::Append);
+        base.merge(&overrides, &strategy).unwrap();
+
+        assert_eq!(source(&base, "a.b"), Some("base.yaml"));
+        assert_eq!(source(&base, "a.c"), Some("prod.yaml"));
+        assert_eq!(source(&base, "a.d"), Some("prod.yaml"));
+        assert_eq!(source(&base, "a.d.e"), Some("secret.yaml"));
+        assert_eq!(source(&base, "args.0"), Some("base.yaml"));
+        assert_eq!(source(&base, "args.1"), Some("prod.yaml"));
+        assert_eq!(source(&base, "a.x"), None);
+
+        base.delete("args.0").unwrap();
+        base.rename_key("a", "z").unwrap();
+        assert_eq!(source(&base, "args.0"), Some("prod.yaml"));
+        assert_eq!(source(&base, "z.d.e"), Some("secret.yaml"));
+
+        base.set("z.d", 5).unwrap();
+        assert_eq!(source(&base, "z.d"), Some("base.yaml"));
+
+        assert_eq!(
+            base.set_provenance("missing", Provenance::new()),
+            Err(Error::NotFound {
+                path: "missing".into()
+            })
+        );
+    }
+}
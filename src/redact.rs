@@ -0,0 +1,180 @@
+//! Redaction of secrets, e.g. to log or diff a configuration without leaking
+//! credentials.
+//!
+//! A [`Redaction`] selects nodes by dot-separated path patterns like
+//! [`Node::get_path`]. A `*` within a segment matches any number of
+//! characters, e.g. `*_token`, and a `**` segment matches any number of
+//! segments, e.g. `**.password` matches `password` keys at every depth.
+//! Sequence items are matched by their index.
+//!
+//! ```
+//! use yaml_ast::{redact::Redaction, yaml};
+//!
+//! let mut node = yaml!({
+//!     kind: "Secret",
+//!     data: {username: "admin", password: "hunter2"},
+//!     db: {host: "db", password: "s3cret"}
+//! });
+//!
+//! let redaction = Redaction::new().with_path("data.*").with_path("**.password");
+//! let redacted = node.redact(&redaction);
+//!
+//! assert_eq!(node, yaml!({
+//!     kind: "Secret",
+//!     data: {username: "<redacted>", password: "<redacted>"},
+//!     db: {host: "db", password: "<redacted>"}
+//! }));
+//! assert_eq!(redacted[2].path(), "db.password");
+//! assert_eq!(redacted[2].original, yaml!("s3cret"));
+//! ```
+use crate::{
+    emitter::key_segment,
+    events::{display_path, matches_path, PathSegment},
+    Node,
+};
+
+/// The placeholder written instead of redacted nodes by default.
+pub const DEFAULT_PLACEHOLDER: &str = "<redacted>";
+
+/// Configures which nodes [`Node::redact`] replaces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Redaction {
+    /// The dot-separated path patterns of the redacted nodes.
+    pub paths: Vec<String>,
+
+    /// The string which replaces redacted nodes.
+    pub placeholder: String,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            placeholder: DEFAULT_PLACEHOLDER.into(),
+        }
+    }
+}
+
+impl Redaction {
+    /// A redaction without any paths, using the [`DEFAULT_PLACEHOLDER`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the path `pattern` of redacted nodes.
+    pub fn with_path(mut self, pattern: impl Into<String>) -> Self {
+        self.paths.push(pattern.into());
+        self
+    }
+
+    /// Sets the string which replaces redacted nodes.
+    pub fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    /// Returns `true` if any of the path patterns matches `path`.
+    fn matches(&self, path: &[PathSegment]) -> bool {
+        self.paths.iter().any(|pattern| matches_path(pattern, path))
+    }
+}
+
+/// A node replaced by [`Node::redact`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redacted {
+    pub segments: Vec<PathSegment>,
+
+    /// The node before it was redacted.
+    pub original: Node,
+}
+
+impl Redacted {
+    /// Returns the path of the node, e.g. `spec.containers[0].env`.
+    pub fn path(&self) -> String {
+        display_path(&self.segments)
+    }
+}
+
+impl Node {
+    /// Replaces all nodes matched by the `redaction`, including tagged ones,
+    /// with its placeholder. Returns the original nodes in document order,
+    /// so that they can be recorded out of band. Nodes nested in redacted
+    /// nodes aren't reported separately.
+    pub fn redact(&mut self, redaction: &Redaction) -> Vec<Redacted> {
+        let mut redacted = Vec::new();
+        redact_node(self, redaction, &mut Vec::new(), &mut redacted);
+        redacted
+    }
+}
+
+fn redact_node(
+    node: &mut Node,
+    redaction: &Redaction,
+    segments: &mut Vec<PathSegment>,
+    redacted: &mut Vec<Redacted>,
+) {
+    if redaction.matches(segments) {
+        let placeholder = Node::String(redaction.placeholder.clone());
+        redacted.push(Redacted {
+            segments: segments.clone(),
+            original: std::mem::replace(node, placeholder),
+        });
+        return;
+    }
+
+    match node {
+        Node::Mapping(mapping) => {
+            for pair in mapping.pairs_mut() {
+                segments.push(key_segment(&pair.key));
+                redact_node(&mut pair.value, redaction, segments, redacted);
+                segments.pop();
+            }
+        }
+        Node::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                segments.push(PathSegment::Index(index));
+                redact_node(item, redaction, segments, redacted);
+                segments.pop();
+            }
+        }
+        Node::Tagged(tagged) => redact_node(&mut tagged.node, redaction, segments, redacted),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    #[test]
+    fn redact() {
+        let mut node = yaml!({
+            api_token: "abc",
+            users: [{name: "a", password: "1"}, {name: "b", password: "2"}],
+            nested: {deeper: {refresh_token: "def"}}
+        });
+
+        let redaction = Redaction::new()
+            .with_path("**.*_token")
+            .with_path("users.1")
+            .with_placeholder("***");
+        let redacted = node.redact(&redaction);
+
+        assert_eq!(
+            node,
+            yaml!({
+                api_token: "***",
+                users: [{name: "a", password: "1"}, "***"],
+                nested: {deeper: {refresh_token: "***"}}
+            })
+        );
+
+        let paths: Vec<_> = redacted.iter().map(Redacted::path).collect();
+        assert_eq!(
+            paths,
+            ["api_token", "users[1]", "nested.deeper.refresh_token"]
+        );
+        assert_eq!(redacted[1].original, yaml!({name: "b", password: "2"}));
+    }
+}
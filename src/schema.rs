@@ -0,0 +1,487 @@
+//! Resolution of plain (untagged, unquoted) scalars into typed [`Node`]s.
+//!
+//! A [`Schema`] controls how scalars with the non-specific tag `?` are typed.
+//! The three schemas defined by the YAML specification are provided as
+//! [`FailsafeSchema`], [`JsonSchema`] and [`CoreSchema`]. Applications can
+//! resolve their own (local) tags by wrapping a schema in a [`CustomSchema`].
+//!
+//! See <https://yaml.org/spec/1.2.2/#chapter-10-recommended-schemas>
+use std::{collections::HashMap, fmt};
+
+use snafu::Snafu;
+
+use crate::{integer::BigInteger, Node};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("the scalar {value:?} is not a valid {tag}"))]
+    InvalidScalar { value: String, tag: String },
+}
+
+/// A schema controls how scalars are resolved into typed [`Node`]s.
+pub trait Schema: fmt::Debug {
+    /// Resolves the plain (untagged, unquoted) scalar `value`.
+    fn resolve_plain(&self, value: &str) -> Node;
+
+    /// Resolves the scalar `value` with the explicit application-specific
+    /// `tag`, as written in the source, e.g. `!port`. Returns [`None`] if the
+    /// schema doesn't know the tag, in which case the scalar is resolved as
+    /// if it was untagged.
+    fn resolve_tagged(&self, tag: &str, value: &str) -> Option<Result<Node, Error>> {
+        let _ = (tag, value);
+        None
+    }
+
+    /// Returns `true` if the plain scalar `value` resolves to a node other
+    /// than a string, i.e. it must be quoted to stay a string.
+    fn is_ambiguous(&self, value: &str) -> bool {
+        !matches!(self.resolve_plain(value), Node::String(_))
+    }
+}
+
+/// The failsafe schema resolves all scalars as strings.
+///
+/// See <https://yaml.org/spec/1.2.2/#101-failsafe-schema>
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FailsafeSchema;
+
+impl Schema for FailsafeSchema {
+    fn resolve_plain(&self, value: &str) -> Node {
+        Node::String(value.into())
+    }
+}
+
+/// The JSON schema only resolves scalars which are valid JSON literals, like
+/// `null`, `true` or `-1.5e3`. The specification considers all other plain
+/// scalars an error, this implementation resolves them as strings.
+///
+/// See <https://yaml.org/spec/1.2.2/#102-json-schema>
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonSchema;
+
+impl Schema for JsonSchema {
+    fn resolve_plain(&self, value: &str) -> Node {
+        match value {
+            "null" => return Node::Null,
+            "true" => return Node::Boolean(true),
+            "false" => return Node::Boolean(false),
+            _ => {}
+        }
+
+        let unsigned = value.strip_prefix('-').unwrap_or(value);
+        let int_len = unsigned
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(unsigned.len());
+        let (int, rest) = unsigned.split_at(int_len);
+
+        if int.is_empty() || (int.len() > 1 && int.starts_with('0')) {
+            return Node::String(value.into());
+        }
+
+        if rest.is_empty() {
+            return value
+                .parse::<BigInteger>()
+                .map_or_else(|_| Node::String(value.into()), Node::from);
+        }
+
+        let (fraction, exponent) = match rest.find(['e', 'E']) {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            None => (rest, None),
+        };
+
+        let fraction_valid = fraction.is_empty()
+            || fraction
+                .strip_prefix('.')
+                .is_some_and(|digits| digits.chars().all(|c| c.is_ascii_digit()));
+        let exponent_valid =
+            exponent.is_none_or(|exp| is_digits(exp.strip_prefix(['-', '+']).unwrap_or(exp), 10));
+
+        if fraction_valid && exponent_valid {
+            value
+                .parse()
+                .map_or_else(|_| Node::String(value.into()), Node::FloatingPoint)
+        } else {
+            Node::String(value.into())
+        }
+    }
+}
+
+/// The Core schema, which is the recommended default schema of YAML 1.2. See
+/// [`resolve_core`].
+///
+/// See <https://yaml.org/spec/1.2.2/#103-core-schema>
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CoreSchema;
+
+impl Schema for CoreSchema {
+    fn resolve_plain(&self, value: &str) -> Node {
+        resolve_core(value)
+    }
+
+    fn is_ambiguous(&self, value: &str) -> bool {
+        is_ambiguous_core(value)
+    }
+}
+
+type TagResolver = Box<dyn Fn(&str) -> Option<Node> + Send + Sync>;
+
+/// Extends a base schema with resolvers for application-specific tags.
+///
+/// ```
+/// use yaml_ast::{schema::{CoreSchema, CustomSchema, Schema}, Node};
+///
+/// let schema = CustomSchema::new(CoreSchema)
+///     .with_tag("!port", |value| value.parse().ok().map(Node::Integer));
+///
+/// assert_eq!(schema.resolve_tagged("!port", "80"), Some(Ok(Node::Integer(80))));
+/// assert!(matches!(schema.resolve_tagged("!port", "http"), Some(Err(_))));
+/// ```
+pub struct CustomSchema<S> {
+    base: S,
+    resolvers: HashMap<String, TagResolver>,
+}
+
+impl<S> fmt::Debug for CustomSchema<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomSchema")
+            .field("base", &self.base)
+            .field("tags", &self.resolvers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<S> CustomSchema<S>
+where
+    S: Schema,
+{
+    /// Creates a new custom schema which resolves plain scalars and unknown
+    /// tags using the `base` schema.
+    pub fn new(base: S) -> Self {
+        Self {
+            base,
+            resolvers: HashMap::new(),
+        }
+    }
+
+    /// Registers a `resolver` for scalars tagged with `tag`. The resolver
+    /// returns [`None`] if the value is invalid for the tag.
+    pub fn with_tag<F>(mut self, tag: impl Into<String>, resolver: F) -> Self
+    where
+        F: Fn(&str) -> Option<Node> + Send + Sync + 'static,
+    {
+        self.resolvers.insert(tag.into(), Box::new(resolver));
+        self
+    }
+}
+
+impl<S> Schema for CustomSchema<S>
+where
+    S: Schema,
+{
+    fn resolve_plain(&self, value: &str) -> Node {
+        self.base.resolve_plain(value)
+    }
+
+    fn resolve_tagged(&self, tag: &str, value: &str) -> Option<Result<Node, Error>> {
+        match self.resolvers.get(tag) {
+            Some(resolver) => {
+                Some(resolver(value).ok_or_else(|| InvalidScalarSnafu { value, tag }.build()))
+            }
+            None => self.base.resolve_tagged(tag, value),
+        }
+    }
+
+    fn is_ambiguous(&self, value: &str) -> bool {
+        self.base.is_ambiguous(value)
+    }
+}
+
+/// Resolves the plain scalar `value` using the tag resolution rules of the
+/// Core schema. Values which don't match any of the null, boolean, integer or
+/// float patterns are resolved as strings.
+///
+/// See <https://yaml.org/spec/1.2.2/#1032-tag-resolution>
+pub fn resolve_core(value: &str) -> Node {
+    match value {
+        "" | "~" | "null" | "Null" | "NULL" => return Node::Null,
+        "true" | "True" | "TRUE" => return Node::Boolean(true),
+        "false" | "False" | "FALSE" => return Node::Boolean(false),
+        _ => {}
+    }
+
+    if is_core_int(value) {
+        return match parse_core_int(value) {
+            Some(i) => Node::Integer(i),
+            None => value
+                .parse::<BigInteger>()
+                .map_or_else(|_| Node::String(value.into()), Node::BigInteger),
+        };
+    }
+
+    if is_core_float(value) {
+        return value
+            .parse()
+            .map_or_else(|_| Node::String(value.into()), Node::FloatingPoint);
+    }
+
+    Node::String(value.into())
+}
+
+/// Returns `true` if the plain scalar `value` resolves to a tag other than
+/// `!!str` under the Core schema, i.e. it must be quoted to stay a string.
+pub fn is_ambiguous_core(value: &str) -> bool {
+    !matches!(resolve_core(value), Node::String(_)) || is_core_int(value)
+}
+
+/// Returns `true` if a YAML 1.1 processor resolves the plain scalar `value` to
+/// a tag other than `!!str`, e.g. the booleans `yes`, `no`, `on` and `off`,
+/// octals like `0777`, sexagesimal numbers like `1:30` and timestamps.
+///
+/// See <https://yaml.org/type/>
+pub fn is_ambiguous_yaml_1_1(value: &str) -> bool {
+    match value {
+        "" | "~" | "null" | "Null" | "NULL" => return true,
+        "y" | "Y" | "yes" | "Yes" | "YES" | "n" | "N" | "no" | "No" | "NO" => return true,
+        "true" | "True" | "TRUE" | "false" | "False" | "FALSE" => return true,
+        "on" | "On" | "ON" | "off" | "Off" | "OFF" => return true,
+        "<<" | "=" => return true,
+        ".nan" | ".NaN" | ".NAN" => return true,
+        _ => {}
+    }
+
+    let unsigned = value.strip_prefix(['-', '+']).unwrap_or(value);
+    if matches!(unsigned, ".inf" | ".Inf" | ".INF") {
+        return true;
+    }
+
+    is_yaml_1_1_int(unsigned) || is_yaml_1_1_float(unsigned) || is_yaml_1_1_timestamp(value)
+}
+
+/// Matches the binary, octal, decimal, hexadecimal and sexagesimal integers
+/// of YAML 1.1, which may contain `_` as a digit separator.
+fn is_yaml_1_1_int(value: &str) -> bool {
+    let digits = |s: &str, radix| {
+        s.chars().any(|c| c.is_digit(radix)) && s.chars().all(|c| c.is_digit(radix) || c == '_')
+    };
+
+    if let Some(binary) = value.strip_prefix("0b") {
+        return digits(binary, 2);
+    }
+
+    if let Some(hex) = value.strip_prefix("0x") {
+        return digits(hex, 16);
+    }
+
+    match value.split_once(':') {
+        Some((base, rest)) => digits(base, 10) && is_sexagesimal(rest),
+        None => digits(value, 10),
+    }
+}
+
+/// Matches YAML 1.1 floats, including sexagesimal floats like `1:30.5` and
+/// floats without fractional digits like `1.`.
+fn is_yaml_1_1_float(value: &str) -> bool {
+    let (mantissa, exponent) = match value.find(['e', 'E']) {
+        Some(index) => (&value[..index], Some(&value[index + 1..])),
+        None => (value, None),
+    };
+
+    let exponent_valid =
+        exponent.is_none_or(|exp| is_digits(exp.strip_prefix(['-', '+']).unwrap_or(exp), 10));
+
+    let Some((int, frac)) = mantissa.split_once('.') else {
+        return false;
+    };
+
+    let int_valid = match int.split_once(':') {
+        Some((base, rest)) => is_digits(&base.replace('_', ""), 10) && is_sexagesimal(rest),
+        None => int.chars().all(|c| c.is_ascii_digit() || c == '_'),
+    };
+
+    exponent_valid
+        && int_valid
+        && frac.chars().all(|c| c.is_ascii_digit() || c == '_')
+        && (int.chars().any(|c| c.is_ascii_digit()) || frac.chars().any(|c| c.is_ascii_digit()))
+}
+
+/// Matches the `:`-separated parts of a sexagesimal number, e.g. `30:15` in
+/// `1:30:15`.
+fn is_sexagesimal(value: &str) -> bool {
+    value.split(':').all(|part| {
+        is_digits(part, 10) && (part.len() == 1 || (part.len() == 2 && part.as_bytes()[0] <= b'5'))
+    })
+}
+
+/// Matches YAML 1.1 timestamps, which start with a `YYYY-MM-DD` date.
+fn is_yaml_1_1_timestamp(value: &str) -> bool {
+    let mut parts = value.splitn(3, '-');
+    let (Some(year), Some(month), Some(rest)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+
+    let day_len = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let time = &rest[day_len..];
+
+    year.len() == 4
+        && is_digits(year, 10)
+        && (1..=2).contains(&month.len())
+        && is_digits(month, 10)
+        && (1..=2).contains(&day_len)
+        && (time.is_empty() || time.starts_with(['T', 't', ' ', '\t']))
+}
+
+fn parse_core_int(value: &str) -> Option<i64> {
+    if let Some(octal) = value.strip_prefix("0o") {
+        return is_digits(octal, 8)
+            .then(|| i64::from_str_radix(octal, 8).ok())
+            .flatten();
+    }
+
+    if let Some(hex) = value.strip_prefix("0x") {
+        return is_digits(hex, 16)
+            .then(|| i64::from_str_radix(hex, 16).ok())
+            .flatten();
+    }
+
+    let digits = value.strip_prefix(['-', '+']).unwrap_or(value);
+    is_digits(digits, 10).then(|| value.parse().ok()).flatten()
+}
+
+/// Returns `true` if `value` matches the Core schema integer pattern, even if
+/// it doesn't fit into an [`i64`].
+fn is_core_int(value: &str) -> bool {
+    if let Some(octal) = value.strip_prefix("0o") {
+        return is_digits(octal, 8);
+    }
+
+    if let Some(hex) = value.strip_prefix("0x") {
+        return is_digits(hex, 16);
+    }
+
+    is_digits(value.strip_prefix(['-', '+']).unwrap_or(value), 10)
+}
+
+fn is_core_float(value: &str) -> bool {
+    match value {
+        ".nan" | ".NaN" | ".NAN" => return true,
+        _ => {}
+    }
+
+    let value = value.strip_prefix(['-', '+']).unwrap_or(value);
+    if matches!(value, ".inf" | ".Inf" | ".INF") {
+        return true;
+    }
+
+    let (mantissa, exponent) = match value.find(['e', 'E']) {
+        Some(index) => (&value[..index], Some(&value[index + 1..])),
+        None => (value, None),
+    };
+
+    let mantissa_valid = match mantissa.split_once('.') {
+        Some((int, frac)) => {
+            (int.is_empty() || is_digits(int, 10))
+                && (frac.is_empty() || is_digits(frac, 10))
+                && !(int.is_empty() && frac.is_empty())
+        }
+        None => is_digits(mantissa, 10),
+    };
+
+    let exponent_valid = match exponent {
+        Some(exp) => is_digits(exp.strip_prefix(['-', '+']).unwrap_or(exp), 10),
+        None => true,
+    };
+
+    mantissa_valid && exponent_valid
+}
+
+fn is_digits(value: &str, radix: u32) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_digit(radix))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn core_resolution() {
+        assert_eq!(resolve_core("~"), Node::Null);
+        assert_eq!(resolve_core("True"), Node::Boolean(true));
+        assert_eq!(resolve_core("0x1F"), Node::Integer(31));
+        assert_eq!(resolve_core("0o17"), Node::Integer(15));
+        assert_eq!(resolve_core("-12"), Node::Integer(-12));
+        assert_eq!(
+            resolve_core("1e3"),
+            Node::FloatingPoint("1e3".parse().unwrap())
+        );
+        assert_eq!(
+            resolve_core("-.INF"),
+            Node::FloatingPoint("-.INF".parse().unwrap())
+        );
+        assert_eq!(resolve_core("yes"), Node::String("yes".into()));
+        assert_eq!(resolve_core("1.2.3"), Node::String("1.2.3".into()));
+        assert_eq!(
+            resolve_core("99999999999999999999"),
+            Node::BigInteger("99999999999999999999".parse().unwrap())
+        );
+        assert_eq!(resolve_core("0x7FFFFFFFFFFFFFFF"), Node::Integer(i64::MAX));
+        assert!(is_ambiguous_core("99999999999999999999"));
+        assert!(!is_ambiguous_core("."));
+    }
+
+    #[test]
+    fn schemas() {
+        assert_eq!(
+            FailsafeSchema.resolve_plain("true"),
+            Node::String("true".into())
+        );
+
+        assert_eq!(JsonSchema.resolve_plain("null"), Node::Null);
+        assert_eq!(JsonSchema.resolve_plain("-12"), Node::Integer(-12));
+        assert_eq!(
+            JsonSchema.resolve_plain("1.5e-3"),
+            Node::FloatingPoint("1.5e-3".parse().unwrap())
+        );
+        for value in ["True", "~", "0x1F", "012", ".5", "+1"] {
+            assert_eq!(JsonSchema.resolve_plain(value), Node::String(value.into()));
+        }
+
+        assert_eq!(CoreSchema.resolve_plain("0x1F"), Node::Integer(31));
+        assert!(CoreSchema.is_ambiguous("~"));
+        assert!(!JsonSchema.is_ambiguous("~"));
+    }
+
+    #[test]
+    fn yaml_1_1_resolution() {
+        for value in [
+            "yes",
+            "No",
+            "on",
+            "OFF",
+            "y",
+            "0777",
+            "0b1010",
+            "1_000",
+            "1:30",
+            "-1:30:00",
+            "1:30.5",
+            "1.",
+            "2001-12-14",
+            "2001-12-14t21:59:43.10-05:00",
+            "<<",
+        ] {
+            assert!(is_ambiguous_yaml_1_1(value), "{value} should be ambiguous");
+        }
+
+        for value in ["yesterday", "1:60", "1.2.3", "12:ab", "2001-12", "v1", "."] {
+            assert!(
+                !is_ambiguous_yaml_1_1(value),
+                "{value} should not be ambiguous"
+            );
+        }
+    }
+}
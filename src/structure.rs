@@ -0,0 +1,617 @@
+//! Declarative schemas describing the expected structure of nodes.
+//!
+//! A [`Schema`] is built in Rust and validates nodes without a JSON Schema
+//! engine. [`Schema::validate`] reports every [`Violation`] with the path of
+//! the offending node. Tags are looked through.
+//!
+//! ```
+//! use yaml_ast::{structure::Schema, yaml};
+//!
+//! let schema: Schema = Schema::mapping()
+//!     .key("name", Schema::string().min_length(1))
+//!     .key("replicas", Schema::integer().min(1))
+//!     .optional_key(
+//!         "ports",
+//!         Schema::sequence(Schema::integer().min(1).max(65535)),
+//!     )
+//!     .optional_key("labels", Schema::mapping().values(Schema::string()))
+//!     .deny_unknown_keys()
+//!     .into();
+//!
+//! let node = yaml!({replicas: 0, ports: [80, "http"], image: "nginx"});
+//! let violations: Vec<_> = schema
+//!     .validate(&node)
+//!     .iter()
+//!     .map(ToString::to_string)
+//!     .collect();
+//!
+//! assert_eq!(
+//!     violations,
+//!     [
+//!         ".: missing key \"name\"",
+//!         "replicas: 0 is less than the minimum of 1",
+//!         "ports[1]: expected integer, found string",
+//!         "image: unknown key",
+//!     ]
+//! );
+//! ```
+use std::fmt;
+
+use crate::{
+    emitter::key_segment,
+    events::{display_path, PathSegment},
+    integer::BigInteger,
+    Node,
+};
+
+/// The expected structure of a node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schema {
+    /// Accepts every node.
+    Any,
+    Null,
+    Boolean,
+    String(StringSchema),
+    Integer(IntegerSchema),
+    Number(NumberSchema),
+    Sequence(SequenceSchema),
+    Mapping(MappingSchema),
+
+    /// Accepts `null` in addition to the nodes accepted by the schema.
+    Nullable(Box<Schema>),
+
+    /// Accepts the nodes accepted by any of the schemas.
+    OneOf(Vec<Schema>),
+}
+
+impl Schema {
+    pub fn any() -> Self {
+        Self::Any
+    }
+
+    pub fn null() -> Self {
+        Self::Null
+    }
+
+    pub fn boolean() -> Self {
+        Self::Boolean
+    }
+
+    pub fn string() -> StringSchema {
+        StringSchema::default()
+    }
+
+    pub fn integer() -> IntegerSchema {
+        IntegerSchema::default()
+    }
+
+    /// A schema for integers and floats.
+    pub fn number() -> NumberSchema {
+        NumberSchema::default()
+    }
+
+    /// A schema for sequences whose items are accepted by `items`.
+    pub fn sequence(items: impl Into<Schema>) -> SequenceSchema {
+        SequenceSchema {
+            items: Box::new(items.into()),
+            min_items: None,
+            max_items: None,
+        }
+    }
+
+    pub fn mapping() -> MappingSchema {
+        MappingSchema::default()
+    }
+
+    /// A schema which also accepts `null`, e.g. for values which may be
+    /// omitted like `key:`.
+    pub fn nullable(schema: impl Into<Schema>) -> Self {
+        Self::Nullable(Box::new(schema.into()))
+    }
+
+    pub fn one_of<I, S>(schemas: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Schema>,
+    {
+        Self::OneOf(schemas.into_iter().map(Into::into).collect())
+    }
+
+    /// Validates the `node` and returns all violations of the schema, in
+    /// the order of the nodes. An empty list means the node is valid.
+    pub fn validate(&self, node: &Node) -> Vec<Violation> {
+        let mut validator = Validator {
+            path: Vec::new(),
+            violations: Vec::new(),
+        };
+
+        validator.validate(self, node);
+        validator.violations
+    }
+
+    /// Returns a short description of the accepted nodes used in violations,
+    /// e.g. `integer` or `string or null`.
+    fn description(&self) -> String {
+        match self {
+            Schema::Any => "any node".into(),
+            Schema::Null => "null".into(),
+            Schema::Boolean => "boolean".into(),
+            Schema::String(_) => "string".into(),
+            Schema::Integer(_) => "integer".into(),
+            Schema::Number(_) => "number".into(),
+            Schema::Sequence(_) => "sequence".into(),
+            Schema::Mapping(_) => "mapping".into(),
+            Schema::Nullable(schema) => format!("{} or null", schema.description()),
+            Schema::OneOf(schemas) => schemas
+                .iter()
+                .map(Schema::description)
+                .collect::<Vec<_>>()
+                .join(" or "),
+        }
+    }
+}
+
+/// The schema of strings, see [`Schema::string`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StringSchema {
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    allowed: Vec<String>,
+}
+
+impl StringSchema {
+    /// Sets the minimum number of characters.
+    pub fn min_length(mut self, min_length: usize) -> Self {
+        self.min_length = Some(min_length);
+        self
+    }
+
+    /// Sets the maximum number of characters.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Only accepts the given values, e.g. `["Always", "Never"]`.
+    pub fn allowed<I, S>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed = values.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// The schema of integers, see [`Schema::integer`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IntegerSchema {
+    min: Option<i64>,
+    max: Option<i64>,
+}
+
+impl IntegerSchema {
+    pub fn min(mut self, min: i64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn max(mut self, max: i64) -> Self {
+        self.max = Some(max);
+        self
+    }
+}
+
+/// The schema of integers and floats, see [`Schema::number`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NumberSchema {
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl NumberSchema {
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+}
+
+/// The schema of sequences, see [`Schema::sequence`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SequenceSchema {
+    items: Box<Schema>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+}
+
+impl SequenceSchema {
+    pub fn min_items(mut self, min_items: usize) -> Self {
+        self.min_items = Some(min_items);
+        self
+    }
+
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+}
+
+/// The schema of mappings, see [`Schema::mapping`]. Keys which aren't
+/// declared are accepted with any value by default.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MappingSchema {
+    keys: Vec<KeySchema>,
+    unknown_keys: UnknownKeys,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct KeySchema {
+    name: String,
+    schema: Schema,
+    required: bool,
+}
+
+/// The handling of keys which aren't declared in a [`MappingSchema`].
+#[derive(Clone, Debug, Default, PartialEq)]
+enum UnknownKeys {
+    #[default]
+    Allow,
+    Deny,
+    Validate(Box<Schema>),
+}
+
+impl MappingSchema {
+    /// Declares the required key `name`, whose value is accepted by
+    /// `schema`.
+    pub fn key(mut self, name: impl Into<String>, schema: impl Into<Schema>) -> Self {
+        self.keys.push(KeySchema {
+            name: name.into(),
+            schema: schema.into(),
+            required: true,
+        });
+        self
+    }
+
+    /// Declares the optional key `name`, whose value is accepted by `schema`
+    /// if present.
+    pub fn optional_key(mut self, name: impl Into<String>, schema: impl Into<Schema>) -> Self {
+        self.keys.push(KeySchema {
+            name: name.into(),
+            schema: schema.into(),
+            required: false,
+        });
+        self
+    }
+
+    /// Reports keys which aren't declared.
+    pub fn deny_unknown_keys(mut self) -> Self {
+        self.unknown_keys = UnknownKeys::Deny;
+        self
+    }
+
+    /// Validates the values of keys which aren't declared using `schema`,
+    /// e.g. for labels or environment variables.
+    pub fn values(mut self, schema: impl Into<Schema>) -> Self {
+        self.unknown_keys = UnknownKeys::Validate(Box::new(schema.into()));
+        self
+    }
+}
+
+macro_rules! impl_into_schema {
+    ($($builder:ident => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$builder> for Schema {
+                fn from(schema: $builder) -> Self {
+                    Schema::$variant(schema)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_schema!(
+    StringSchema => String,
+    IntegerSchema => Integer,
+    NumberSchema => Number,
+    SequenceSchema => Sequence,
+    MappingSchema => Mapping,
+);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViolationKind {
+    /// The node is of another type than expected.
+    UnexpectedType { expected: String, found: String },
+
+    /// A number is less than the minimum.
+    BelowMinimum { minimum: String, value: String },
+
+    /// A number is greater than the maximum.
+    AboveMaximum { maximum: String, value: String },
+
+    /// A string has fewer characters or a sequence has fewer items than
+    /// required.
+    TooShort { min: usize, length: usize },
+
+    /// A string has more characters or a sequence has more items than
+    /// allowed.
+    TooLong { max: usize, length: usize },
+
+    /// A string isn't one of the allowed values.
+    NotAllowed { value: String, allowed: Vec<String> },
+
+    /// A required key is missing. The violation is located at the mapping.
+    MissingKey { key: String },
+
+    /// A key isn't declared, see [`MappingSchema::deny_unknown_keys`].
+    UnknownKey,
+}
+
+/// A single finding of [`Schema::validate`], located by the path of the
+/// node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub path: Vec<PathSegment>,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", display_path(&self.path))?;
+
+        match &self.kind {
+            ViolationKind::UnexpectedType { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            ViolationKind::BelowMinimum { minimum, value } => {
+                write!(f, "{value} is less than the minimum of {minimum}")
+            }
+            ViolationKind::AboveMaximum { maximum, value } => {
+                write!(f, "{value} is greater than the maximum of {maximum}")
+            }
+            ViolationKind::TooShort { min, length } => {
+                write!(f, "length {length} is less than the minimum of {min}")
+            }
+            ViolationKind::TooLong { max, length } => {
+                write!(f, "length {length} is greater than the maximum of {max}")
+            }
+            ViolationKind::NotAllowed { value, allowed } => {
+                write!(f, "{value:?} is not one of {allowed:?}")
+            }
+            ViolationKind::MissingKey { key } => write!(f, "missing key {key:?}"),
+            ViolationKind::UnknownKey => write!(f, "unknown key"),
+        }
+    }
+}
+
+struct Validator {
+    path: Vec<PathSegment>,
+    violations: Vec<Violation>,
+}
+
+impl Validator {
+    fn report(&mut self, kind: ViolationKind) {
+        self.violations.push(Violation {
+            path: self.path.clone(),
+            kind,
+        });
+    }
+
+    fn unexpected_type(&mut self, schema: &Schema, node: &Node) {
+        self.report(ViolationKind::UnexpectedType {
+            expected: schema.description(),
+            found: node.description(),
+        });
+    }
+
+    fn validate(&mut self, schema: &Schema, node: &Node) {
+        let node = node.untagged();
+
+        match (schema, node) {
+            (Schema::Any, _)
+            | (Schema::Null | Schema::Nullable(_), Node::Null)
+            | (Schema::Boolean, Node::Boolean(_)) => {}
+            (Schema::Nullable(schema), node) => self.validate(schema, node),
+            (Schema::OneOf(schemas), node) => {
+                let matches = schemas
+                    .iter()
+                    .any(|schema| schema.validate(node).is_empty());
+                if !matches {
+                    self.unexpected_type(&Schema::OneOf(schemas.clone()), node);
+                }
+            }
+            (Schema::String(schema), Node::String(value)) => self.validate_string(schema, value),
+            (Schema::Integer(schema), Node::Integer(i)) => {
+                self.validate_integer(schema, BigInteger::from(*i))
+            }
+            (Schema::Integer(schema), Node::BigInteger(i)) => {
+                self.validate_integer(schema, i.clone())
+            }
+            (Schema::Number(schema), Node::Integer(i)) => {
+                self.validate_number(schema, *i as f64, i.to_string())
+            }
+            (Schema::Number(schema), Node::BigInteger(i)) => {
+                let value = i.to_string().parse().unwrap_or(f64::NAN);
+                self.validate_number(schema, value, i.to_string())
+            }
+            (Schema::Number(schema), Node::FloatingPoint(float)) => {
+                self.validate_number(schema, float.value(), float.to_string())
+            }
+            (Schema::Sequence(schema), Node::Sequence(items)) => {
+                self.validate_sequence(schema, items)
+            }
+            (Schema::Mapping(schema), Node::Mapping(_)) => self.validate_mapping(schema, node),
+            (schema, node) => self.unexpected_type(schema, node),
+        }
+    }
+
+    fn validate_string(&mut self, schema: &StringSchema, value: &str) {
+        let length = value.chars().count();
+
+        if let Some(min) = schema.min_length.filter(|min| length < *min) {
+            self.report(ViolationKind::TooShort { min, length });
+        }
+
+        if let Some(max) = schema.max_length.filter(|max| length > *max) {
+            self.report(ViolationKind::TooLong { max, length });
+        }
+
+        if !schema.allowed.is_empty() && !schema.allowed.iter().any(|allowed| allowed == value) {
+            self.report(ViolationKind::NotAllowed {
+                value: value.into(),
+                allowed: schema.allowed.clone(),
+            });
+        }
+    }
+
+    fn validate_integer(&mut self, schema: &IntegerSchema, value: BigInteger) {
+        if let Some(min) = schema.min.filter(|min| value < BigInteger::from(*min)) {
+            self.report(ViolationKind::BelowMinimum {
+                minimum: min.to_string(),
+                value: value.to_string(),
+            });
+        }
+
+        if let Some(max) = schema.max.filter(|max| value > BigInteger::from(*max)) {
+            self.report(ViolationKind::AboveMaximum {
+                maximum: max.to_string(),
+                value: value.to_string(),
+            });
+        }
+    }
+
+    fn validate_number(&mut self, schema: &NumberSchema, value: f64, display: String) {
+        if let Some(min) = schema.min.filter(|min| value.is_nan() || value < *min) {
+            self.report(ViolationKind::BelowMinimum {
+                minimum: min.to_string(),
+                value: display.clone(),
+            });
+        }
+
+        if let Some(max) = schema.max.filter(|max| value.is_nan() || value > *max) {
+            self.report(ViolationKind::AboveMaximum {
+                maximum: max.to_string(),
+                value: display,
+            });
+        }
+    }
+
+    fn validate_sequence(&mut self, schema: &SequenceSchema, items: &[Node]) {
+        let length = items.len();
+
+        if let Some(min) = schema.min_items.filter(|min| length < *min) {
+            self.report(ViolationKind::TooShort { min, length });
+        }
+
+        if let Some(max) = schema.max_items.filter(|max| length > *max) {
+            self.report(ViolationKind::TooLong { max, length });
+        }
+
+        for (index, item) in items.iter().enumerate() {
+            self.path.push(PathSegment::Index(index));
+            self.validate(&schema.items, item);
+            self.path.pop();
+        }
+    }
+
+    fn validate_mapping(&mut self, schema: &MappingSchema, node: &Node) {
+        let Some(mapping) = node.as_mapping() else {
+            return;
+        };
+
+        for key in schema.keys.iter().filter(|key| key.required) {
+            if mapping.get(&key.name).is_none() {
+                self.report(ViolationKind::MissingKey {
+                    key: key.name.clone(),
+                });
+            }
+        }
+
+        for pair in mapping.pairs() {
+            let declared = pair
+                .key
+                .untagged()
+                .as_str()
+                .and_then(|name| schema.keys.iter().find(|key| key.name == name));
+
+            self.path.push(key_segment(&pair.key));
+            match (declared, &schema.unknown_keys) {
+                (Some(key), _) => self.validate(&key.schema, &pair.value),
+                (None, UnknownKeys::Allow) => {}
+                (None, UnknownKeys::Deny) => self.report(ViolationKind::UnknownKey),
+                (None, UnknownKeys::Validate(values)) => self.validate(values, &pair.value),
+            }
+            self.path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    fn validate(schema: impl Into<Schema>, node: Node) -> Vec<String> {
+        schema
+            .into()
+            .validate(&node)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn validate_nodes() {
+        let policy = Schema::string().allowed(["Always", "Never"]);
+        assert_eq!(
+            validate(
+                Schema::sequence(policy).max_items(1),
+                yaml!(["Always", "Sometimes"])
+            ),
+            [
+                ".: length 2 is greater than the maximum of 1",
+                "[1]: \"Sometimes\" is not one of [\"Always\", \"Never\"]",
+            ]
+        );
+
+        let schema = Schema::mapping()
+            .key(
+                "cpu",
+                Schema::one_of([
+                    Schema::from(Schema::number().min(0.0)),
+                    Schema::string().into(),
+                ]),
+            )
+            .key("memory", Schema::nullable(Schema::string().min_length(2)));
+        assert_eq!(
+            validate(schema.clone(), yaml!({cpu: "500m", memory: null})),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            validate(schema, yaml!({cpu: -0.5, memory: "1"})),
+            [
+                "cpu: expected number or string, found float",
+                "memory: length 1 is less than the minimum of 2",
+            ]
+        );
+
+        assert_eq!(
+            validate(
+                Schema::integer().max(10),
+                Node::BigInteger("-99999999999999999999".parse().unwrap())
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            validate(
+                Schema::integer().min(0).max(10),
+                Node::BigInteger("99999999999999999999".parse().unwrap())
+            ),
+            [".: 99999999999999999999 is greater than the maximum of 10"]
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Per-node presentation styles which take precedence over the
+//! [`EmitterOptions`](crate::emitter::EmitterOptions), e.g. to write a
+//! single list in flow style.
+//!
+//! Documents keep the [`Style`] of a node by its path in
+//! [`Document::styles`], next to the nodes like the
+//! [`lexical_forms`](Document::lexical_forms). When the document is turned
+//! into events, the styles become the preferred styles of the events, see
+//! [`Event`]. The emitter falls back to another style if a node can't be
+//! written in its preferred style, e.g. a multiline string in a flow
+//! collection.
+//!
+//! ```
+//! use yaml_ast::{events::CollectionStyle, style::Style, Document};
+//!
+//! let mut document: Document = "name: web\nports:\n  - 80\n  - 443\n".parse().unwrap();
+//! document
+//!     .set_style("ports", Style::new().with_collection(CollectionStyle::Flow))
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     document.to_string(),
+//!     "---\nname: web\nports: [80, 443]\n...\n"
+//! );
+//! ```
+use std::collections::HashMap;
+
+use snafu::{OptionExt, Snafu};
+
+use crate::{
+    emitter::{key_segment, needs_quotes, QuoteStyle},
+    events::{CollectionStyle, Event, PathSegment, ScalarStyle},
+    provenance::segments,
+    Document, Node,
+};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("no node is located at {path:?}"))]
+    NotFound { path: String },
+}
+
+/// The presentation style of a node. Unset styles are left to the emitter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    /// The style of a sequence or mapping. Collections within a flow
+    /// collection are always written in flow style. The block style keeps
+    /// small collections from being written in flow style, see
+    /// [`EmitterOptions::flow_threshold`](crate::emitter::EmitterOptions::flow_threshold).
+    pub collection: Option<CollectionStyle>,
+
+    /// The style of a scalar, e.g. a literal block scalar. Only strings
+    /// can be quoted or written as block scalars.
+    pub scalar: Option<ScalarStyle>,
+
+    /// The quotes of strings which must be quoted, like
+    /// [`EmitterOptions::quote_style`](crate::emitter::EmitterOptions::quote_style).
+    /// Unlike the other styles, it applies to all strings within the node
+    /// which don't have quotes of their own.
+    pub quotes: Option<QuoteStyle>,
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the style of a sequence or mapping. See [`Style::collection`].
+    pub fn with_collection(mut self, collection: CollectionStyle) -> Self {
+        self.collection = Some(collection);
+        self
+    }
+
+    /// Sets the style of a scalar. See [`Style::scalar`].
+    pub fn with_scalar(mut self, scalar: ScalarStyle) -> Self {
+        self.scalar = Some(scalar);
+        self
+    }
+
+    /// Sets the quotes of strings which must be quoted. See
+    /// [`Style::quotes`].
+    pub fn with_quotes(mut self, quotes: QuoteStyle) -> Self {
+        self.quotes = Some(quotes);
+        self
+    }
+}
+
+impl Document {
+    /// Returns the style of the node at the dot-separated `path`, see
+    /// [`Node::get_path`]. Returns [`None`] if the node has no style of its
+    /// own.
+    pub fn style(&self, path: &str) -> Option<&Style> {
+        let root = self.nodes.first()?;
+        self.styles.get(&segments(root, path)?)
+    }
+
+    /// Sets the style of the node at the dot-separated `path` and returns
+    /// its previous style, if any.
+    pub fn set_style(&mut self, path: &str, style: Style) -> Result<Option<Style>, Error> {
+        let segments = self
+            .nodes
+            .first()
+            .and_then(|root| segments(root, path))
+            .context(NotFoundSnafu { path })?;
+
+        Ok(self.styles.insert(segments, style))
+    }
+}
+
+/// The preferred style of the event starting a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum EventStyle {
+    Collection(CollectionStyle),
+    Scalar(ScalarStyle),
+}
+
+/// Returns the preferred style of each node of `node` in the order of the
+/// events starting them produced by [`IntoEvents`](crate::events::IntoEvents),
+/// or [`None`] if the emitter chooses the style.
+pub(crate) fn node_styles(
+    node: &Node,
+    styles: &HashMap<Vec<PathSegment>, Style>,
+) -> Vec<Option<EventStyle>> {
+    let mut nodes = Vec::new();
+
+    if !styles.is_empty() {
+        let mut collector = Collector {
+            styles,
+            path: Vec::new(),
+            nodes: &mut nodes,
+        };
+        collector.collect(node, true, None, false);
+    }
+
+    nodes
+}
+
+/// Sets the `styles` returned by [`node_styles`] as the preferred styles of
+/// the events starting the nodes.
+pub(crate) fn apply_node_styles(
+    events: impl Iterator<Item = Event>,
+    styles: Vec<Option<EventStyle>>,
+) -> impl Iterator<Item = Event> {
+    let mut styles = styles.into_iter();
+
+    events.map(move |event| {
+        if !event.starts_node() {
+            return event;
+        }
+
+        match (event, styles.next().flatten()) {
+            (
+                Event::Scalar {
+                    value, tag, anchor, ..
+                },
+                Some(EventStyle::Scalar(style)),
+            ) => Event::Scalar {
+                value,
+                tag,
+                anchor,
+                style: Some(style),
+            },
+            (Event::SequenceStart { tag, anchor, .. }, Some(EventStyle::Collection(style))) => {
+                Event::SequenceStart {
+                    tag,
+                    anchor,
+                    style: Some(style),
+                }
+            }
+            (Event::MappingStart { tag, anchor, .. }, Some(EventStyle::Collection(style))) => {
+                Event::MappingStart {
+                    tag,
+                    anchor,
+                    style: Some(style),
+                }
+            }
+            (event, _) => event,
+        }
+    })
+}
+
+struct Collector<'a> {
+    styles: &'a HashMap<Vec<PathSegment>, Style>,
+    path: Vec<PathSegment>,
+    nodes: &'a mut Vec<Option<EventStyle>>,
+}
+
+impl Collector<'_> {
+    /// Collects the styles of `node`, where mapping keys have no `path`.
+    /// Strings inherit the `quotes` of their ancestors and must be quoted
+    /// differently within `flow` collections.
+    fn collect(&mut self, node: &Node, has_path: bool, quotes: Option<QuoteStyle>, flow: bool) {
+        let style = match has_path {
+            true => self.styles.get(&self.path).copied().unwrap_or_default(),
+            false => Style::default(),
+        };
+        let quotes = style.quotes.or(quotes);
+
+        match node {
+            Node::Mapping(mapping) => {
+                self.nodes
+                    .push(style.collection.map(EventStyle::Collection));
+                let flow = flow || style.collection == Some(CollectionStyle::Flow);
+
+                for pair in mapping.pairs() {
+                    self.collect(&pair.key, false, quotes, flow);
+
+                    self.path.push(key_segment(&pair.key));
+                    self.collect(&pair.value, true, quotes, flow);
+                    self.path.pop();
+                }
+            }
+            Node::Sequence(items) => {
+                self.nodes
+                    .push(style.collection.map(EventStyle::Collection));
+                let flow = flow || style.collection == Some(CollectionStyle::Flow);
+
+                for (index, item) in items.iter().enumerate() {
+                    self.path.push(PathSegment::Index(index));
+                    self.collect(item, true, quotes, flow);
+                    self.path.pop();
+                }
+            }
+            // The tag is a property of the event starting the tagged node,
+            // which has the same path
+            Node::Tagged(tagged) => self.collect(&tagged.node, has_path, quotes, flow),
+            Node::String(value) => {
+                let quoted =
+                    quotes
+                        .filter(|_| needs_quotes(value, flow))
+                        .map(|quotes| match quotes {
+                            QuoteStyle::Single => ScalarStyle::SingleQuoted,
+                            QuoteStyle::Double => ScalarStyle::DoubleQuoted,
+                        });
+                self.nodes
+                    .push(style.scalar.or(quoted).map(EventStyle::Scalar));
+            }
+            _ => self.nodes.push(style.scalar.map(EventStyle::Scalar)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        emitter::{Emitter, EmitterOptions},
+        events::IntoEvents,
+    };
+
+    fn emit(document: Document, options: EmitterOptions) -> String {
+        let mut output = String::new();
+        Emitter::new(document.into_events(), options)
+            .emit(&mut output)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn styles() {
+        let mut document: Document =
+            "script: |\n  make\nargs: [a, 'b, c', '1']\nenv: {a: 'on'}\nname: web\n"
+                .parse()
+                .unwrap();
+        document
+            .set_style(
+                "script",
+                Style::new().with_scalar(ScalarStyle::DoubleQuoted),
+            )
+            .unwrap();
+        document
+            .set_style(
+                "args",
+                Style::new()
+                    .with_collection(CollectionStyle::Flow)
+                    .with_quotes(QuoteStyle::Single),
+            )
+            .unwrap();
+        document
+            .set_style(
+                "args.0",
+                Style::new().with_scalar(ScalarStyle::DoubleQuoted),
+            )
+            .unwrap();
+        document
+            .set_style("name", Style::new().with_scalar(ScalarStyle::Literal))
+            .unwrap();
+
+        assert_eq!(document.style("env"), None);
+        assert_eq!(
+            document.set_style("missing", Style::new()),
+            Err(Error::NotFound {
+                path: "missing".into()
+            })
+        );
+
+        // The quotes of the options apply to all other strings
+        assert_eq!(
+            emit(document, EmitterOptions::k8s()),
+            "---\nscript: \"make\\n\"\nargs: [\"a\", 'b, c', '1']\nenv:\n  a: 'on'\nname: |-\n  web\n"
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Substitution of `${VAR}` placeholders in string scalars, e.g. to inject
+//! environment-specific values into a configuration.
+//!
+//! A [`Substitution`] looks up variables in its own map first and then,
+//! if enabled, in the environment of the process. `${VAR:-default}` uses
+//! the default if the variable is unset or empty. Placeholders without a
+//! closing `}` are kept as is.
+//!
+//! ```
+//! use yaml_ast::{substitute::Substitution, yaml};
+//!
+//! let mut node = yaml!({
+//!     image: "nginx:${TAG}",
+//!     replicas: 3,
+//!     env: [{name: "LOG", value: "${LOG_LEVEL:-info}"}]
+//! });
+//!
+//! let substitution = Substitution::new().with_variable("TAG", "1.27");
+//! node.substitute(&substitution).unwrap();
+//!
+//! assert_eq!(node, yaml!({
+//!     image: "nginx:1.27",
+//!     replicas: 3,
+//!     env: [{name: "LOG", value: "info"}]
+//! }));
+//! ```
+//!
+//! The AST doesn't record how scalars were quoted, which is why
+//! [`Substitution::skip_quoted`] only applies to [`Cst::substitute`].
+use std::{collections::HashMap, env};
+
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::{
+    cst::{self, Cst, CstKind, CstNode, ScalarStyle},
+    emitter::key_segment,
+    events::{display_path, PathSegment},
+    parser::Span,
+    Node,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("the variable {name:?} used at {path} is not set"))]
+    MissingVariable { name: String, path: String },
+
+    #[snafu(display("failed to replace the scalar at {path}"))]
+    Replace { source: cst::Error, path: String },
+}
+
+/// Configures how [`Node::substitute`] and [`Cst::substitute`] replace
+/// placeholders.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Substitution {
+    /// The variables by their name, which take precedence over the
+    /// environment.
+    pub variables: HashMap<String, String>,
+
+    /// Looks up variables which aren't in [`Substitution::variables`] in the
+    /// environment of the process.
+    pub environment: bool,
+
+    /// Fails with [`Error::MissingVariable`] if a placeholder without a
+    /// default refers to an unset variable, instead of replacing it with an
+    /// empty string.
+    pub strict: bool,
+
+    /// Keeps single and double quoted scalars as literals.
+    pub skip_quoted: bool,
+}
+
+impl Substitution {
+    /// A substitution without any variables.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A substitution which looks up variables in the environment.
+    pub fn from_env() -> Self {
+        Self {
+            environment: true,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the variable `name` to `value`.
+    pub fn with_variable(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
+
+    /// Enables or disables failing on unset variables. See
+    /// [`Substitution::strict`].
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Enables or disables keeping quoted scalars. See
+    /// [`Substitution::skip_quoted`].
+    pub fn with_skip_quoted(mut self, skip_quoted: bool) -> Self {
+        self.skip_quoted = skip_quoted;
+        self
+    }
+
+    fn lookup(&self, name: &str) -> Option<String> {
+        self.variables
+            .get(name)
+            .cloned()
+            .or_else(|| self.environment.then(|| env::var(name).ok()).flatten())
+    }
+
+    /// Returns `value` with all placeholders replaced, or [`None`] if it
+    /// contains no placeholders.
+    fn apply(&self, value: &str, path: &[PathSegment]) -> Result<Option<String>, Error> {
+        if !value.contains("${") {
+            return Ok(None);
+        }
+
+        let mut output = String::with_capacity(value.len());
+        let mut rest = value;
+
+        while let Some(start) = rest.find("${") {
+            let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+                break;
+            };
+
+            output.push_str(&rest[..start]);
+
+            let placeholder = &rest[start + 2..end];
+            let (name, default) = match placeholder.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (placeholder, None),
+            };
+
+            let value = self
+                .lookup(name)
+                .filter(|value| default.is_none() || !value.is_empty());
+            match (value, default) {
+                (Some(value), _) => output.push_str(&value),
+                (None, Some(default)) => output.push_str(default),
+                (None, None) => ensure!(
+                    !self.strict,
+                    MissingVariableSnafu {
+                        name,
+                        path: display_path(path),
+                    }
+                ),
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        output.push_str(rest);
+        Ok(Some(output))
+    }
+}
+
+impl Node {
+    /// Replaces the placeholders in all string scalars, including tagged
+    /// ones, using the `substitution`. Mapping keys are kept as is.
+    pub fn substitute(&mut self, substitution: &Substitution) -> Result<(), Error> {
+        substitute_node(self, substitution, &mut Vec::new())
+    }
+}
+
+fn substitute_node(
+    node: &mut Node,
+    substitution: &Substitution,
+    path: &mut Vec<PathSegment>,
+) -> Result<(), Error> {
+    match node {
+        Node::String(value) => {
+            if let Some(substituted) = substitution.apply(value, path)? {
+                *value = substituted;
+            }
+        }
+        Node::Mapping(mapping) => {
+            for pair in mapping.pairs_mut() {
+                path.push(key_segment(&pair.key));
+                substitute_node(&mut pair.value, substitution, path)?;
+                path.pop();
+            }
+        }
+        Node::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                path.push(PathSegment::Index(index));
+                substitute_node(item, substitution, path)?;
+                path.pop();
+            }
+        }
+        Node::Tagged(tagged) => substitute_node(&mut tagged.node, substitution, path)?,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+impl Cst {
+    /// Replaces the placeholders in all scalar values of the source text
+    /// using the `substitution`, like [`Node::substitute`]. Only the text of
+    /// the substituted scalars changes and they keep their quoting style
+    /// where possible, see [`Cst::replace`].
+    pub fn substitute(&mut self, substitution: &Substitution) -> Result<(), Error> {
+        let mut replacements = Vec::new();
+
+        for document in self.documents() {
+            if let Some(root) = document.root() {
+                collect_replacements(root, substitution, &mut Vec::new(), &mut replacements)?;
+            }
+        }
+
+        // Replacing from the end keeps the spans of the preceding scalars
+        // valid.
+        for (span, value, path) in replacements.into_iter().rev() {
+            self.replace(span, &Node::String(value))
+                .context(ReplaceSnafu {
+                    path: display_path(&path),
+                })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects the span, substituted value and path of all scalar values in
+/// `node` which contain placeholders, in source order.
+fn collect_replacements(
+    node: &CstNode,
+    substitution: &Substitution,
+    path: &mut Vec<PathSegment>,
+    replacements: &mut Vec<(Span, String, Vec<PathSegment>)>,
+) -> Result<(), Error> {
+    match &node.kind {
+        CstKind::Scalar { style, value } => {
+            let is_quoted = matches!(style, ScalarStyle::SingleQuoted | ScalarStyle::DoubleQuoted);
+            if substitution.skip_quoted && is_quoted {
+                return Ok(());
+            }
+
+            if let Some(substituted) = substitution.apply(value, path)? {
+                replacements.push((node.span, substituted, path.clone()));
+            }
+        }
+        CstKind::Mapping { pairs, .. } => {
+            for pair in pairs {
+                let key = match &pair.key.kind {
+                    CstKind::Scalar { value, .. } => value.clone(),
+                    _ => "?".into(),
+                };
+
+                path.push(PathSegment::Key(key));
+                collect_replacements(&pair.value, substitution, path, replacements)?;
+                path.pop();
+            }
+        }
+        CstKind::Sequence { items, .. } => {
+            for (index, item) in items.iter().enumerate() {
+                path.push(PathSegment::Index(index));
+                collect_replacements(&item.node, substitution, path, replacements)?;
+                path.pop();
+            }
+        }
+        CstKind::Alias(_) => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::yaml;
+
+    #[test]
+    fn substitute_node() {
+        let substitution = Substitution::new()
+            .with_variable("HOST", "db")
+            .with_variable("EMPTY", "");
+
+        let mut node = yaml!([
+            "${HOST}:${PORT:-5432}",
+            "${EMPTY:-default} ${EMPTY}",
+            "${UNSET}",
+            "${HOST",
+            {"${HOST}": "$HOST"}
+        ]);
+        node.substitute(&substitution).unwrap();
+        assert_eq!(
+            node,
+            yaml!(["db:5432", "default ", "", "${HOST", {"${HOST}": "$HOST"}])
+        );
+
+        let mut node = yaml!({spec: {hosts: ["${HOST}", "${UNSET}"]}});
+        let error = node
+            .substitute(&substitution.with_strict(true))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the variable \"UNSET\" used at spec.hosts[1] is not set"
+        );
+    }
+
+    #[test]
+    fn substitute_cst() {
+        let substitution = Substitution::new()
+            .with_variable("TAG", "1.27")
+            .with_variable("PORT", "80")
+            .with_skip_quoted(true);
+
+        let mut cst = Cst::parse(
+            "# Web\nimage: nginx:${TAG} # tag\nport: ${PORT}\nliteral: '${TAG}'\nargs:\n  - --port=${PORT}\n",
+        )
+        .unwrap();
+        cst.substitute(&substitution).unwrap();
+
+        assert_eq!(
+            cst.source(),
+            "# Web\nimage: nginx:1.27 # tag\nport: \"80\"\nliteral: '${TAG}'\nargs:\n  - --port=80\n"
+        );
+    }
+}
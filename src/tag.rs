@@ -0,0 +1,157 @@
+//! Application-specific tags and typed conversions of tagged nodes.
+//!
+//! Nodes with an application-specific tag, like `!Ref` or `!Sub` in
+//! CloudFormation templates, are represented by [`Node::Tagged`]. They
+//! round-trip through parsing and emitting unchanged. A [`TagRegistry`]
+//! additionally converts tagged nodes into user types and back.
+//!
+//! ```
+//! use yaml_ast::{tag::{TagRegistry, Tagged}, Node};
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Ref(String);
+//!
+//! let mut registry = TagRegistry::new();
+//! registry.register(
+//!     "!Ref",
+//!     |node| match node {
+//!         Node::String(name) => Ok(Ref(name.clone())),
+//!         _ => Err("expected a string".into()),
+//!     },
+//!     |value: &Ref| Node::String(value.0.clone()),
+//! );
+//!
+//! let node = registry.represent("!Ref", &Ref("Bucket".into())).unwrap();
+//! assert_eq!(node, Node::Tagged(Box::new(Tagged::new("!Ref", Node::String("Bucket".into())))));
+//! assert_eq!(registry.construct::<Ref>(&node).unwrap(), Ref("Bucket".into()));
+//! ```
+use std::{any::Any, collections::HashMap, fmt};
+
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use crate::Node;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("the node is not tagged"))]
+    NotTagged,
+
+    #[snafu(display("the tag {tag} is not registered"))]
+    UnknownTag { tag: String },
+
+    #[snafu(display("the tag {tag} is registered for a different type"))]
+    TypeMismatch { tag: String },
+
+    #[snafu(display("failed to construct a value from the node tagged {tag}"))]
+    Construct { tag: String, source: BoxError },
+}
+
+/// A node with an explicit, application-specific tag, e.g. `!Ref Bucket`.
+#[derive(Clone, Debug)]
+pub struct Tagged {
+    /// The tag, either a local tag like `!Ref` or a global tag URI like
+    /// `tag:example.com,2000:app/point`.
+    pub tag: String,
+    pub node: Node,
+}
+
+impl Tagged {
+    pub fn new(tag: impl Into<String>, node: Node) -> Self {
+        Self {
+            tag: tag.into(),
+            node,
+        }
+    }
+}
+
+type ConstructFn<T> = dyn Fn(&Node) -> Result<T, BoxError> + Send + Sync;
+type RepresentFn<T> = dyn Fn(&T) -> Node + Send + Sync;
+
+struct Handler<T> {
+    construct: Box<ConstructFn<T>>,
+    represent: Box<RepresentFn<T>>,
+}
+
+/// A registry of application-specific tags along with callbacks which
+/// construct typed values from tagged nodes and represent typed values as
+/// tagged nodes.
+#[derive(Default)]
+pub struct TagRegistry {
+    handlers: HashMap<String, Box<dyn Any + Send + Sync>>,
+}
+
+impl fmt::Debug for TagRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TagRegistry")
+            .field("tags", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl TagRegistry {
+    /// Creates a new (empty) registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the `tag` for values of type `T`. The `construct` callback
+    /// receives the untagged node, the `represent` callback returns it.
+    /// Registering a tag again replaces the previous callbacks.
+    pub fn register<T, C, R>(
+        &mut self,
+        tag: impl Into<String>,
+        construct: C,
+        represent: R,
+    ) -> &mut Self
+    where
+        T: 'static,
+        C: Fn(&Node) -> Result<T, BoxError> + Send + Sync + 'static,
+        R: Fn(&T) -> Node + Send + Sync + 'static,
+    {
+        let handler = Handler {
+            construct: Box::new(construct),
+            represent: Box::new(represent),
+        };
+
+        self.handlers.insert(tag.into(), Box::new(handler));
+        self
+    }
+
+    /// Returns `true` if the `tag` is registered.
+    pub fn contains(&self, tag: &str) -> bool {
+        self.handlers.contains_key(tag)
+    }
+
+    /// Returns an iterator over all registered tags.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    fn handler<T: 'static>(&self, tag: &str) -> Result<&Handler<T>, Error> {
+        self.handlers
+            .get(tag)
+            .context(UnknownTagSnafu { tag })?
+            .downcast_ref()
+            .context(TypeMismatchSnafu { tag })
+    }
+
+    /// Constructs a value of type `T` from the tagged `node`.
+    pub fn construct<T: 'static>(&self, node: &Node) -> Result<T, Error> {
+        let Node::Tagged(tagged) = node else {
+            return NotTaggedSnafu.fail();
+        };
+
+        let handler = self.handler::<T>(&tagged.tag)?;
+        (handler.construct)(&tagged.node).context(ConstructSnafu { tag: &tagged.tag })
+    }
+
+    /// Represents the `value` as a node tagged with `tag`.
+    pub fn represent<T: 'static>(&self, tag: &str, value: &T) -> Result<Node, Error> {
+        let handler = self.handler::<T>(tag)?;
+        let node = (handler.represent)(value);
+
+        Ok(Node::Tagged(Box::new(Tagged::new(tag, node))))
+    }
+}
@@ -0,0 +1,129 @@
+//! Conversions of [`Stream`]s, [`Document`]s and [`Node`]s from and to YAML
+//! text.
+//!
+//! [`Display`](fmt::Display) emits using the default [`EmitterOptions`] and
+//! [`FromStr`] parses using the default [`ResolveOptions`]. Use the
+//! [`Emitter`] and the [`Cst`] directly to customize either.
+//!
+//! ```
+//! use yaml_ast::{Document, Node, Stream};
+//!
+//! let document: Document = "a: 1".parse().unwrap();
+//! assert_eq!(document.to_string(), "---\na: 1\n...\n");
+//!
+//! let node: Node = "[1, two]".parse().unwrap();
+//! assert_eq!(node.to_string(), "- 1\n- two\n");
+//!
+//! let stream: Stream = "---\na: 1\n---\nb: 2\n".parse().unwrap();
+//! assert_eq!(stream.len(), 2);
+//! ```
+use std::{fmt, str::FromStr};
+
+use snafu::ResultExt;
+
+use crate::{
+    cst::{self, Cst, ParseSnafu, ResolveOptions},
+    emitter::{Emitter, EmitterOptions},
+    events::Event,
+    Document, Node, Stream,
+};
+
+/// Emits the `events` of a stream using the default options.
+fn emit(events: impl IntoIterator<Item = Event>) -> Result<String, fmt::Error> {
+    let mut output = String::new();
+    Emitter::new(events, EmitterOptions::default())
+        .emit(&mut output)
+        .map_err(|_| fmt::Error)?;
+
+    Ok(output)
+}
+
+impl fmt::Display for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&emit(self.events_iter())?)
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let events = std::iter::once(Event::StreamStart)
+            .chain(self.events_iter())
+            .chain([Event::StreamEnd]);
+
+        f.write_str(&emit(events)?)
+    }
+}
+
+impl fmt::Display for Node {
+    /// Formats the node without the document markers.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let events = [Event::StreamStart, Event::DocumentStart]
+            .into_iter()
+            .chain(self.events_iter())
+            .chain([Event::DocumentEnd, Event::StreamEnd]);
+
+        let output = emit(events)?;
+        let output = output.strip_prefix("---\n").unwrap_or(&output);
+        f.write_str(output.strip_suffix("...\n").unwrap_or(output))
+    }
+}
+
+impl FromStr for Stream {
+    type Err = cst::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Cst::parse(s)
+            .context(ParseSnafu)?
+            .to_stream_with(&ResolveOptions::default())
+    }
+}
+
+impl FromStr for Document {
+    type Err = cst::Error;
+
+    /// Parses a stream of at most one document. An empty stream results in
+    /// an empty document.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut documents = s.parse::<Stream>()?.into_documents();
+
+        match documents.len() {
+            0 => Ok(Document::new()),
+            1 => Ok(documents.remove(0)),
+            count => cst::MultipleDocumentsSnafu { count }.fail(),
+        }
+    }
+}
+
+impl FromStr for Node {
+    type Err = cst::Error;
+
+    /// Parses the root node of a single document. An empty document results
+    /// in [`Node::Null`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let document = s.parse::<Document>()?;
+        Ok(document.nodes.into_iter().next().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Mapping, Sequence};
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let node = Node::Mapping(Mapping::from([(
+            "ports".into(),
+            Node::Sequence(Sequence::from([80.into(), "http".into()])),
+        )]));
+
+        assert_eq!(node.to_string(), "ports:\n  - 80\n  - http\n");
+        assert_eq!(node.to_string().parse::<Node>().unwrap(), node);
+        assert_eq!("".parse::<Node>().unwrap(), Node::Null);
+        assert!(matches!(
+            "---\na\n---\nb\n".parse::<Document>(),
+            Err(cst::Error::MultipleDocuments { count: 2 })
+        ));
+    }
+}
@@ -0,0 +1,308 @@
+//! Timestamps as defined by the `!!timestamp` tag.
+//!
+//! The tag isn't part of the YAML 1.2 Core schema, which is why plain scalars
+//! like `2001-12-14` are resolved as strings. Timestamps are parsed from
+//! scalars explicitly tagged with `!!timestamp` and are emitted in the
+//! RFC 3339 format.
+//!
+//! See <https://yaml.org/type/timestamp.html>
+use std::{fmt, str::FromStr};
+
+use snafu::{OptionExt, Snafu};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("invalid timestamp {value:?}"))]
+    InvalidTimestamp { value: String },
+}
+
+/// A calendar date with an optional time of day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+
+    /// The time of day. Timestamps without a time only represent a date,
+    /// e.g. `2002-12-14`.
+    pub time: Option<Time>,
+}
+
+/// The time of day of a [`Timestamp`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+
+    /// The offset from UTC in minutes. Timestamps without an explicit time
+    /// zone are in UTC.
+    pub offset: i16,
+}
+
+impl Timestamp {
+    /// Creates a timestamp which only represents a date.
+    pub fn date(year: u16, month: u8, day: u8) -> Self {
+        Self {
+            year,
+            month,
+            day,
+            time: None,
+        }
+    }
+
+    /// Sets the time of day in UTC.
+    pub fn with_time(mut self, hour: u8, minute: u8, second: u8) -> Self {
+        self.time = Some(Time {
+            hour,
+            minute,
+            second,
+            nanosecond: 0,
+            offset: 0,
+        });
+        self
+    }
+
+    /// Creates a UTC timestamp from the number of seconds since the Unix
+    /// epoch, e.g. the value of `SOURCE_DATE_EPOCH`.
+    pub fn from_unix_seconds(seconds: u64) -> Self {
+        // See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+        let days = seconds / 86400 + 719468;
+        let era = days / 146097;
+        let day_of_era = days % 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let (year, month) = match month {
+            0..=9 => (year_of_era + era * 400, month + 3),
+            _ => (year_of_era + era * 400 + 1, month - 9),
+        };
+
+        let time = seconds % 86400;
+        Self::date(year as u16, month as u8, day as u8).with_time(
+            (time / 3600) as u8,
+            (time / 60 % 60) as u8,
+            (time % 60) as u8,
+        )
+    }
+}
+
+impl fmt::Display for Timestamp {
+    /// Formats the timestamp in the RFC 3339 format, e.g.
+    /// `2001-12-14T21:59:43.1-05:00`. Dates are formatted as `2001-12-14`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)?;
+
+        let Some(time) = self.time else {
+            return Ok(());
+        };
+
+        write!(f, "T{:02}:{:02}:{:02}", time.hour, time.minute, time.second)?;
+
+        if time.nanosecond > 0 {
+            let fraction = format!("{:09}", time.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+
+        match time.offset {
+            0 => write!(f, "Z"),
+            offset => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.unsigned_abs();
+                write!(f, "{sign}{:02}:{:02}", offset / 60, offset % 60)
+            }
+        }
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = Error;
+
+    /// Parses all formats of the `!!timestamp` tag, e.g. `2001-12-14`,
+    /// `2001-12-14t21:59:43.10-05:00` or `2001-12-14 21:59:43.10 -5`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s).context(InvalidTimestampSnafu { value: s })
+    }
+}
+
+/// A cursor over the characters of a timestamp.
+struct Cursor<'a>(&'a str);
+
+impl Cursor<'_> {
+    fn eat(&mut self, c: char) -> Option<()> {
+        self.0 = self.0.strip_prefix(c)?;
+        Some(())
+    }
+
+    fn eat_whitespace(&mut self) -> bool {
+        let rest = self.0.trim_start_matches([' ', '\t']);
+        let found = rest.len() < self.0.len();
+        self.0 = rest;
+        found
+    }
+
+    /// Consumes between `min` and `max` ASCII digits.
+    fn digits(&mut self, min: usize, max: usize) -> Option<&str> {
+        let len = self
+            .0
+            .bytes()
+            .take(max)
+            .take_while(u8::is_ascii_digit)
+            .count();
+
+        if len < min {
+            return None;
+        }
+
+        let (digits, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(digits)
+    }
+
+    fn number<T: FromStr>(&mut self, min: usize, max: usize) -> Option<T> {
+        self.digits(min, max)?.parse().ok()
+    }
+}
+
+fn parse(s: &str) -> Option<Timestamp> {
+    let mut cursor = Cursor(s);
+
+    let year = cursor.number(4, 4)?;
+    cursor.eat('-')?;
+    let month = cursor.number(1, 2)?;
+    cursor.eat('-')?;
+    let day = cursor.number(1, 2)?;
+
+    let mut timestamp = Timestamp::date(year, month, day);
+    let is_leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if is_leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+
+    if day == 0 || day > days {
+        return None;
+    }
+
+    if cursor.0.is_empty() {
+        return Some(timestamp);
+    }
+
+    if cursor.eat('T').or_else(|| cursor.eat('t')).is_none() && !cursor.eat_whitespace() {
+        return None;
+    }
+
+    let hour = cursor.number(1, 2)?;
+    cursor.eat(':')?;
+    let minute = cursor.number(2, 2)?;
+    cursor.eat(':')?;
+    let second = cursor.number(2, 2)?;
+
+    let nanosecond = match cursor.eat('.') {
+        Some(()) => {
+            let digits = cursor.digits(1, usize::MAX)?;
+            let digits = &digits[..digits.len().min(9)];
+            digits.parse::<u32>().ok()? * 10u32.pow(9 - digits.len() as u32)
+        }
+        None => 0,
+    };
+
+    cursor.eat_whitespace();
+    let offset = match cursor.0.chars().next() {
+        None => 0,
+        Some('Z' | 'z') => {
+            cursor.0 = &cursor.0[1..];
+            0
+        }
+        Some(sign @ ('+' | '-')) => {
+            cursor.0 = &cursor.0[1..];
+            let hours: i16 = cursor.number(1, 2)?;
+            let minutes: i16 = match cursor.eat(':') {
+                Some(()) => cursor.number(2, 2)?,
+                None => 0,
+            };
+
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+
+            let offset = hours * 60 + minutes;
+            if sign == '-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        Some(_) => return None,
+    };
+
+    if !cursor.0.is_empty() || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    timestamp.time = Some(Time {
+        hour,
+        minute,
+        second,
+        nanosecond,
+        offset,
+    });
+
+    Some(timestamp)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let timestamps = [
+            ("2002-12-14", "2002-12-14"),
+            (
+                "2001-12-14t21:59:43.10-05:00",
+                "2001-12-14T21:59:43.1-05:00",
+            ),
+            ("2001-12-14 21:59:43.10 -5", "2001-12-14T21:59:43.1-05:00"),
+            ("2001-12-15 2:59:43.10", "2001-12-15T02:59:43.1Z"),
+            ("2001-12-15T02:59:43.1Z", "2001-12-15T02:59:43.1Z"),
+            ("2024-02-29T00:00:00+05:30", "2024-02-29T00:00:00+05:30"),
+        ];
+
+        for (input, output) in timestamps {
+            let timestamp: Timestamp = input.parse().unwrap();
+            assert_eq!(timestamp.to_string(), output);
+            assert_eq!(output.parse(), Ok(timestamp));
+        }
+
+        for invalid in [
+            "2002-13-01",
+            "2023-02-29",
+            "2002-12-14T",
+            "2002-12-14T25:00:00",
+            "2002-12-14 12:00:00 +5:3",
+            "02-12-14",
+            "2002-12-14x",
+        ] {
+            assert!(invalid.parse::<Timestamp>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn from_unix_seconds() {
+        assert_eq!(
+            Timestamp::from_unix_seconds(0).to_string(),
+            "1970-01-01T00:00:00Z"
+        );
+        assert_eq!(
+            Timestamp::from_unix_seconds(1709164800 + 3723).to_string(),
+            "2024-02-29T01:02:03Z"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Checksum trailers to detect manual edits of generated files.
+//!
+//! A [`Signer`] computes a checksum or signature of the body of each emitted
+//! document. [`sign`] appends it as a trailer comment, like
+//! `# sha256: 9f86d0…`, right before the document end marker. [`verify`]
+//! recomputes the checksums and reports documents which were changed after
+//! they were signed.
+//!
+//! ```
+//! use yaml_ast::trailer::{sign, verify, Error, Signer};
+//!
+//! /// A (weak) checksum which counts the bytes of the body.
+//! struct Length;
+//!
+//! impl Signer for Length {
+//!     fn algorithm(&self) -> &str {
+//!         "length"
+//!     }
+//!
+//!     fn sign(&self, body: &str) -> String {
+//!         body.len().to_string()
+//!     }
+//! }
+//!
+//! let output = sign("---\nreplicas: 3\n...\n", &Length);
+//! assert_eq!(output, "---\nreplicas: 3\n# length: 12\n...\n");
+//! assert!(verify(&output, &Length).is_ok());
+//!
+//! let edited = output.replace("3", "30");
+//! assert!(matches!(verify(&edited, &Length), Err(Error::Mismatch { document: 0, .. })));
+//! ```
+use snafu::{ensure, OptionExt, Snafu};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    #[snafu(display("the output has no documents with a start marker to verify"))]
+    NoDocuments,
+
+    #[snafu(display("document {document} has no {algorithm} trailer"))]
+    MissingTrailer { document: usize, algorithm: String },
+
+    #[snafu(display(
+        "document {document} was modified, expected {algorithm} {expected}, found {found}"
+    ))]
+    Mismatch {
+        document: usize,
+        algorithm: String,
+        expected: String,
+        found: String,
+    },
+}
+
+/// Computes the checksum or signature of the body of a document.
+pub trait Signer {
+    /// The name of the algorithm, which is used as the label of the trailer
+    /// comment, e.g. `sha256`.
+    fn algorithm(&self) -> &str;
+
+    /// Returns the checksum or signature of the document `body`. The result
+    /// must not contain line breaks.
+    fn sign(&self, body: &str) -> String;
+}
+
+/// A [`Signer`] which computes the hex-encoded SHA-256 digest of the body.
+///
+/// This is only available when the `sha256` feature is enabled.
+#[cfg(feature = "sha256")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha256;
+
+#[cfg(feature = "sha256")]
+impl Signer for Sha256 {
+    fn algorithm(&self) -> &str {
+        "sha256"
+    }
+
+    fn sign(&self, body: &str) -> String {
+        use sha2::Digest;
+
+        sha2::Sha256::digest(body.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+/// A document of the output, split into its body and the value of the
+/// trailer (if any).
+struct Part<'a> {
+    body: String,
+    trailer: Option<&'a str>,
+}
+
+/// Splits `output` into its documents. The body of a document contains all
+/// lines between the start marker (`---`) and the end marker (`...`) or the
+/// next start marker, excluding the trailer comment of `algorithm`.
+fn documents<'a>(output: &'a str, algorithm: &str) -> Vec<Part<'a>> {
+    let prefix = format!("# {algorithm}: ");
+    let mut parts: Vec<Part<'a>> = Vec::new();
+    let mut open = false;
+
+    for line in output.lines() {
+        if line == "---" || line.starts_with("--- ") {
+            parts.push(Part {
+                body: String::new(),
+                trailer: None,
+            });
+            open = true;
+
+            // Content after the start marker, like `--- !!map`, belongs to
+            // the body as well.
+            if let Some(part) = parts.last_mut().filter(|_| line != "---") {
+                part.body.push_str(line);
+                part.body.push('\n');
+            }
+        } else if line == "..." {
+            open = false;
+        } else if let Some(part) = parts.last_mut().filter(|_| open) {
+            match line.strip_prefix(&prefix) {
+                Some(trailer) => part.trailer = Some(trailer),
+                None => {
+                    part.body.push_str(line);
+                    part.body.push('\n');
+                }
+            }
+        }
+    }
+
+    parts
+}
+
+/// Appends a trailer comment with the checksum of the body to each document
+/// of the emitted `output`. Existing trailers of the same algorithm are
+/// replaced. Only documents with a start marker (`---`) are signed.
+///
+/// The line breaks and the byte order mark of the output are kept, the
+/// trailers end with the first line break of the output. Bodies are signed
+/// with line feeds, which is why the checksums don't depend on either.
+pub fn sign(output: &str, signer: &impl Signer) -> String {
+    let algorithm = signer.algorithm();
+    let prefix = format!("# {algorithm}: ");
+    let (bom, output) = match output.strip_prefix('\u{feff}') {
+        Some(output) => ("\u{feff}", output),
+        None => ("", output),
+    };
+    let line_break = match output.find('\n') {
+        Some(end) if output[..end].ends_with('\r') => "\r\n",
+        _ => "\n",
+    };
+
+    let mut parts = documents(output, algorithm).into_iter();
+    let mut signed = String::with_capacity(output.len());
+    let mut pending: Option<String> = None;
+
+    signed.push_str(bom);
+    let to_trailer = |part: Part<'_>| format!("{prefix}{}{line_break}", signer.sign(&part.body));
+
+    for raw in output.split_inclusive('\n') {
+        let line = raw.strip_suffix('\n').unwrap_or(raw);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        if line == "---" || line.starts_with("--- ") {
+            if let Some(trailer) = pending.take() {
+                signed.push_str(&trailer);
+            }
+            pending = parts.next().map(to_trailer);
+        } else if line == "..." {
+            if let Some(trailer) = pending.take() {
+                signed.push_str(&trailer);
+            }
+        } else if line.starts_with(&prefix) && pending.is_some() {
+            continue;
+        }
+
+        signed.push_str(raw);
+    }
+
+    if let Some(trailer) = pending {
+        if !signed.ends_with('\n') {
+            signed.push_str(line_break);
+        }
+        signed.push_str(&trailer);
+    }
+
+    signed
+}
+
+/// Verifies the trailers of all documents in `output`, which was previously
+/// signed by [`sign`]. Returns an error for the first document which has no
+/// trailer or whose body doesn't match its trailer, and if the output has
+/// no documents with a start marker, which [`sign`] would have signed.
+pub fn verify(output: &str, signer: &impl Signer) -> Result<(), Error> {
+    let algorithm = signer.algorithm();
+    let output = output.strip_prefix('\u{feff}').unwrap_or(output);
+    let parts = documents(output, algorithm);
+    ensure!(!parts.is_empty(), NoDocumentsSnafu);
+
+    for (document, part) in parts.into_iter().enumerate() {
+        let found = part
+            .trailer
+            .context(MissingTrailerSnafu {
+                document,
+                algorithm,
+            })?
+            .trim();
+        let expected = signer.sign(&part.body);
+
+        ensure!(
+            found == expected,
+            MismatchSnafu {
+                document,
+                algorithm,
+                expected,
+                found,
+            }
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Length;
+
+    impl Signer for Length {
+        fn algorithm(&self) -> &str {
+            "length"
+        }
+
+        fn sign(&self, body: &str) -> String {
+            body.len().to_string()
+        }
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let output = "---\na: 1\n...\n---\nb: 22\n";
+        let signed = sign(output, &Length);
+
+        assert_eq!(
+            signed,
+            "---\na: 1\n# length: 5\n...\n---\nb: 22\n# length: 6\n"
+        );
+        assert_eq!(verify(&signed, &Length), Ok(()));
+
+        // Signing again replaces the existing trailers
+        assert_eq!(sign(&signed, &Length), signed);
+
+        assert_eq!(
+            verify(output, &Length),
+            Err(Error::MissingTrailer {
+                document: 0,
+                algorithm: "length".into()
+            })
+        );
+        assert!(matches!(
+            verify(&signed.replace("22", "2"), &Length),
+            Err(Error::Mismatch { document: 1, .. })
+        ));
+
+        // Output without document markers is never signed
+        assert_eq!(sign("a: 1\n", &Length), "a: 1\n");
+        assert_eq!(verify("a: 1\n", &Length), Err(Error::NoDocuments));
+        assert_eq!(verify("", &Length), Err(Error::NoDocuments));
+    }
+
+    #[test]
+    fn line_breaks() {
+        let output = "\u{feff}---\r\na: 1\r\n...\r\n---\r\nb: 22";
+        let signed = sign(output, &Length);
+
+        assert_eq!(
+            signed,
+            "\u{feff}---\r\na: 1\r\n# length: 5\r\n...\r\n---\r\nb: 22\r\n# length: 6\r\n"
+        );
+        assert_eq!(verify(&signed, &Length), Ok(()));
+        assert_eq!(sign(&signed, &Length), signed);
+
+        // The checksums don't depend on the line breaks
+        assert_eq!(
+            signed.trim_start_matches('\u{feff}').replace("\r\n", "\n"),
+            sign("---\na: 1\n...\n---\nb: 22\n", &Length)
+        );
+    }
+
+    #[cfg(feature = "sha256")]
+    #[test]
+    fn sha256() {
+        let signed = sign("---\n...\n", &Sha256);
+        assert_eq!(
+            signed,
+            "---\n# sha256: e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n...\n"
+        );
+        assert!(verify(&signed, &Sha256).is_ok());
+    }
+}
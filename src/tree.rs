@@ -0,0 +1,589 @@
+//! Index-based node trees.
+//!
+//! A [`Tree`] stores all nodes in a single vector and refers to them by
+//! [`NodeId`]s. Collections only store the ids of their children, which
+//! keeps the nodes of a large document close together in memory and lets
+//! transformations visit all nodes in a single linear pass, without walking
+//! the tree. See the `bump` module (behind the `bump` feature) for trees
+//! allocated in an arena.
+//!
+//! Nodes can be shared by referring to the same id from multiple
+//! collections, which turns the tree into a representation graph. Shared
+//! collections are stored only once and produce an anchor and aliases when
+//! turned into events, see [`EmitterOptions::anchor_threshold`] to repeat
+//! small nodes instead. Collections which (indirectly) contain themselves
+//! form a cycle, which can't be turned into nodes or events:
+//! [`Tree::try_node`] and [`Tree::try_into_events`] return an
+//! [`Error::Cycle`] for them.
+//!
+//! [`EmitterOptions::anchor_threshold`]: crate::emitter::EmitterOptions::anchor_threshold
+//!
+//! ```
+//! use yaml_ast::{tree::{Tree, TreeNode}, yaml, Node};
+//!
+//! let mut tree = Tree::from(yaml!({image: "nginx", sidecars: [{image: "envoy"}]}));
+//!
+//! // Pin all images in one pass over the nodes
+//! let ids: Vec<_> = tree.ids().collect();
+//! for id in ids {
+//!     if let TreeNode::Mapping(pairs) = tree.get(id) {
+//!         let values: Vec<_> = pairs
+//!             .iter()
+//!             .filter(|(key, _)| matches!(tree.get(*key), TreeNode::Scalar(Node::String(k)) if k == "image"))
+//!             .map(|(_, value)| *value)
+//!             .collect();
+//!
+//!         for value in values {
+//!             if let TreeNode::Scalar(Node::String(image)) = tree.get_mut(value) {
+//!                 image.push_str(":1.0");
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! assert_eq!(
+//!     tree.to_node(),
+//!     Some(yaml!({image: "nginx:1.0", sidecars: [{image: "envoy:1.0"}]}))
+//! );
+//! ```
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+};
+
+use snafu::Snafu;
+
+use crate::{
+    emitter::key_segment,
+    events::{display_path, Event, IntoEvents, PathSegment},
+    tag::Tagged,
+    Mapping, Node,
+};
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum Error {
+    /// The node at `path` refers to one of its ancestors. The `cycle`
+    /// lists the ids of the nodes from that ancestor down to the node.
+    #[snafu(display(
+        "the node at {} refers back to an ancestor, forming the cycle {cycle:?}",
+        display_path(path)
+    ))]
+    Cycle {
+        path: Vec<PathSegment>,
+        cycle: Vec<NodeId>,
+    },
+}
+
+/// The index of a node in a [`Tree`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// A node of a [`Tree`]. Collections and tagged nodes refer to their
+/// children by id, all other nodes are stored as scalar [`Node`]s.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TreeNode {
+    Mapping(Vec<(NodeId, NodeId)>),
+    Sequence(Vec<NodeId>),
+    Tagged(String, NodeId),
+
+    /// A scalar node, i.e. any node except mappings, sequences and tagged
+    /// nodes.
+    Scalar(Node),
+}
+
+/// A node tree stored in a single vector, see the [module](self)
+/// documentation for details.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tree {
+    nodes: Vec<TreeNode>,
+    root: Option<NodeId>,
+}
+
+impl From<Node> for Tree {
+    fn from(node: Node) -> Self {
+        let mut tree = Self::new();
+        let root = tree.insert(node);
+        tree.root = Some(root);
+        tree
+    }
+}
+
+impl Tree {
+    /// Creates a new (empty) tree without a root.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn root(&self) -> Option<NodeId> {
+        self.root
+    }
+
+    pub fn set_root(&mut self, root: NodeId) {
+        self.root = Some(root);
+    }
+
+    /// Returns the number of nodes, including nodes which are no longer
+    /// reachable from the root.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the ids of all nodes in the order they were added.
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.nodes.len()).map(NodeId)
+    }
+
+    /// Returns the node with the `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `id` belongs to another tree with more nodes.
+    pub fn get(&self, id: NodeId) -> &TreeNode {
+        &self.nodes[id.0]
+    }
+
+    /// Returns the node with the `id` mutably. See [`Tree::get`].
+    pub fn get_mut(&mut self, id: NodeId) -> &mut TreeNode {
+        &mut self.nodes[id.0]
+    }
+
+    /// Returns all nodes mutably in the order they were added, e.g. to
+    /// transform all scalars.
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = &mut TreeNode> {
+        self.nodes.iter_mut()
+    }
+
+    /// Adds a single node, whose children must already be part of the tree.
+    pub fn push(&mut self, node: TreeNode) -> NodeId {
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Adds the `node` with all its children and returns the id of the
+    /// `node`. Children are added before their parent.
+    pub fn insert(&mut self, mut node: Node) -> NodeId {
+        let node = match &mut node {
+            Node::Mapping(mapping) => TreeNode::Mapping(
+                mem::take(mapping)
+                    .into_iter()
+                    .map(|pair| (self.insert(pair.key), self.insert(pair.value)))
+                    .collect(),
+            ),
+            Node::Sequence(sequence) => TreeNode::Sequence(
+                mem::take(sequence)
+                    .into_iter()
+                    .map(|item| self.insert(item))
+                    .collect(),
+            ),
+            Node::Tagged(tagged) => {
+                let node = mem::take(&mut tagged.node);
+                TreeNode::Tagged(mem::take(&mut tagged.tag), self.insert(node))
+            }
+            _ => TreeNode::Scalar(node),
+        };
+
+        self.push(node)
+    }
+
+    /// Converts the root into a [`Node`], or returns [`None`] if the tree
+    /// has no root.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree contains a cycle, see [`Tree::try_to_node`].
+    pub fn to_node(&self) -> Option<Node> {
+        self.root.map(|root| self.node(root))
+    }
+
+    /// Converts the root into a [`Node`] like [`Tree::to_node`], but fails
+    /// if the tree contains a cycle.
+    pub fn try_to_node(&self) -> Result<Option<Node>, Error> {
+        self.root.map(|root| self.try_node(root)).transpose()
+    }
+
+    /// Converts the subtree starting at `id` into a [`Node`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subtree contains a cycle, see [`Tree::try_node`].
+    pub fn node(&self, id: NodeId) -> Node {
+        match self.try_node(id) {
+            Ok(node) => node,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    /// Converts the subtree starting at `id` into a [`Node`] like
+    /// [`Tree::node`], but fails if the subtree contains a cycle.
+    pub fn try_node(&self, id: NodeId) -> Result<Node, Error> {
+        self.check_cycles(id)?;
+        Ok(self.acyclic_node(id))
+    }
+
+    fn acyclic_node(&self, id: NodeId) -> Node {
+        match self.get(id) {
+            TreeNode::Mapping(pairs) => Node::Mapping(
+                pairs
+                    .iter()
+                    .map(|(key, value)| (self.acyclic_node(*key), self.acyclic_node(*value)))
+                    .collect::<Mapping>(),
+            ),
+            TreeNode::Sequence(items) => {
+                Node::Sequence(items.iter().map(|item| self.acyclic_node(*item)).collect())
+            }
+            TreeNode::Tagged(tag, node) => {
+                Node::Tagged(Box::new(Tagged::new(tag.clone(), self.acyclic_node(*node))))
+            }
+            TreeNode::Scalar(scalar) => scalar.clone(),
+        }
+    }
+
+    /// Fails if a node of the subtree starting at `id` refers to one of its
+    /// ancestors. Shared nodes are visited once.
+    fn check_cycles(&self, id: NodeId) -> Result<(), Error> {
+        enum Visit {
+            Enter(NodeId, Option<PathSegment>),
+            Exit,
+        }
+
+        // The nodes from `id` to the current node, with their segments
+        let mut open: Vec<(NodeId, Option<PathSegment>)> = Vec::new();
+        let mut on_path = HashSet::new();
+        let mut done = HashSet::new();
+        let mut stack = vec![Visit::Enter(id, None)];
+
+        while let Some(visit) = stack.pop() {
+            let (id, segment) = match visit {
+                Visit::Enter(id, segment) => (id, segment),
+                Visit::Exit => {
+                    if let Some((id, _)) = open.pop() {
+                        on_path.remove(&id);
+                        done.insert(id);
+                    }
+                    continue;
+                }
+            };
+
+            if on_path.contains(&id) {
+                let start = open.iter().position(|(open, _)| *open == id).unwrap_or(0);
+                let path: Vec<_> = open
+                    .iter()
+                    .filter_map(|(_, segment)| segment.clone())
+                    .chain(segment)
+                    .collect();
+                let cycle: Vec<_> = open[start..].iter().map(|(id, _)| *id).collect();
+                return CycleSnafu { path, cycle }.fail();
+            }
+
+            if done.contains(&id) {
+                continue;
+            }
+
+            open.push((id, segment));
+            on_path.insert(id);
+            stack.push(Visit::Exit);
+
+            match self.get(id) {
+                TreeNode::Mapping(pairs) => {
+                    for (key, value) in pairs.iter().rev() {
+                        let segment = match self.get(*key) {
+                            TreeNode::Scalar(key) => key_segment(key),
+                            _ => PathSegment::Key("?".into()),
+                        };
+                        stack.push(Visit::Enter(*value, Some(segment.clone())));
+                        stack.push(Visit::Enter(*key, Some(segment)));
+                    }
+                }
+                TreeNode::Sequence(items) => {
+                    for (index, item) in items.iter().enumerate().rev() {
+                        stack.push(Visit::Enter(*item, Some(PathSegment::Index(index))));
+                    }
+                }
+                TreeNode::Tagged(_, node) => stack.push(Visit::Enter(*node, None)),
+                TreeNode::Scalar(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Tree {
+    /// Returns whether the node is referred to by more than one collection
+    /// or tagged node reachable from the root.
+    pub fn is_shared(&self, id: NodeId) -> bool {
+        self.references().get(id.0).is_some_and(|count| *count > 1)
+    }
+
+    fn is_scalar(&self, id: NodeId) -> bool {
+        match self.get(id) {
+            TreeNode::Scalar(_) => true,
+            TreeNode::Tagged(_, node) => self.is_scalar(*node),
+            _ => false,
+        }
+    }
+
+    /// Counts the references to each node reachable from the root, visiting
+    /// each node once.
+    fn references(&self) -> Vec<usize> {
+        let mut references = vec![0; self.nodes.len()];
+        let mut stack: Vec<_> = self.root.into_iter().collect();
+
+        while let Some(id) = stack.pop() {
+            let children: Vec<_> = match self.get(id) {
+                TreeNode::Mapping(pairs) => pairs.iter().flat_map(|(k, v)| [*k, *v]).collect(),
+                TreeNode::Sequence(items) => items.clone(),
+                TreeNode::Tagged(_, node) => vec![*node],
+                TreeNode::Scalar(_) => Vec::new(),
+            };
+
+            for child in children {
+                references[child.0] += 1;
+                if references[child.0] == 1 {
+                    stack.push(child);
+                }
+            }
+        }
+
+        references
+    }
+}
+
+impl IntoEvents for &Tree {
+    /// Returns the events of the root, or no events if the tree has no
+    /// root. Shared collections, including tagged ones, are anchored where
+    /// they occur first and aliased afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree contains a cycle, see [`Tree::try_into_events`].
+    fn into_events(self) -> Vec<Event> {
+        match self.try_into_events() {
+            Ok(events) => events,
+            Err(error) => panic!("{error}"),
+        }
+    }
+}
+
+impl Tree {
+    /// Returns the events of the root like [`IntoEvents::into_events`], but
+    /// fails if the tree contains a cycle.
+    pub fn try_into_events(&self) -> Result<Vec<Event>, Error> {
+        if let Some(root) = self.root {
+            self.check_cycles(root)?;
+        }
+
+        let mut events = Vec::new();
+        let references = self.references();
+        let mut anchors: HashMap<NodeId, usize> = HashMap::new();
+
+        // Returns the anchor of a shared node, or the alias event if it was
+        // visited before
+        let mut anchor = |id: NodeId| -> Result<Option<String>, Event> {
+            if references[id.0] < 2 {
+                return Ok(None);
+            }
+
+            match anchors.get(&id) {
+                Some(anchor) => Err(Event::Alias(anchor.to_string())),
+                None => {
+                    let anchor = anchors.len() + 1;
+                    anchors.insert(id, anchor);
+                    Ok(Some(anchor.to_string()))
+                }
+            }
+        };
+
+        // The nodes to visit, or the end events of open collections
+        let mut stack: Vec<Result<NodeId, Event>> = self.root.into_iter().map(Ok).collect();
+
+        while let Some(next) = stack.pop() {
+            let id = match next {
+                Ok(id) => id,
+                Err(event) => {
+                    events.push(event);
+                    continue;
+                }
+            };
+
+            // Scalars are repeated instead
+            let anchor = match self.is_scalar(id) {
+                true => None,
+                false => match anchor(id) {
+                    Ok(anchor) => anchor,
+                    Err(alias) => {
+                        events.push(alias);
+                        continue;
+                    }
+                },
+            };
+
+            // The outermost tag of nested tagged nodes applies
+            let mut node = self.get(id);
+            let mut tag = None;
+            while let TreeNode::Tagged(outer, inner) = node {
+                tag = tag.or_else(|| Some(outer.clone()));
+                node = self.get(*inner);
+            }
+
+            match node {
+                TreeNode::Mapping(pairs) => {
+                    events.push(Event::MappingStart {
+                        tag,
+                        anchor,
+                        style: None,
+                    });
+                    stack.push(Err(Event::MappingEnd));
+                    for (key, value) in pairs.iter().rev() {
+                        stack.push(Ok(*value));
+                        stack.push(Ok(*key));
+                    }
+                }
+                TreeNode::Sequence(items) => {
+                    events.push(Event::SequenceStart {
+                        tag,
+                        anchor,
+                        style: None,
+                    });
+                    stack.push(Err(Event::SequenceEnd));
+                    stack.extend(items.iter().rev().map(|item| Ok(*item)));
+                }
+                TreeNode::Scalar(scalar) => events.push(Event::scalar(
+                    scalar_value(scalar),
+                    tag.or_else(|| Some(scalar.uri())),
+                )),
+                TreeNode::Tagged(..) => unreachable!(),
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+fn scalar_value(scalar: &Node) -> String {
+    match scalar.clone().into_events().pop() {
+        Some(Event::Scalar { value, .. }) => value,
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        emitter::{Emitter, EmitterOptions},
+        yaml,
+    };
+
+    #[test]
+    fn round_trip() {
+        let mut node = yaml!({name: "web", ports: [80, null], limits: {}});
+        if let Node::Mapping(mapping) = &mut node {
+            mapping.push_pair(
+                "ref".into(),
+                Node::Tagged(Box::new(Tagged::new("!Ref", "web".into()))),
+            );
+            mapping.push_pair(
+                "list".into(),
+                Node::Tagged(Box::new(Tagged::new("!List", yaml!([1])))),
+            );
+        }
+
+        let mut tree = Tree::from(node.clone());
+        assert_eq!(tree.to_node(), Some(node.clone()));
+        assert_eq!((&tree).into_events(), node.into_events());
+
+        for node in tree.nodes_mut() {
+            if let TreeNode::Scalar(Node::Integer(i)) = node {
+                *i += 1;
+            }
+        }
+
+        let ports = tree.push(TreeNode::Sequence(Vec::new()));
+        tree.set_root(ports);
+        assert_eq!(tree.to_node(), Some(yaml!([])));
+        assert_eq!(tree.len(), 17);
+    }
+
+    #[test]
+    fn shared() {
+        let mut tree = Tree::new();
+        let limits = tree.insert(yaml!({cpu: 1}));
+        let tagged = tree.insert(yaml!(["a"]));
+        let tagged = tree.push(TreeNode::Tagged("!Args".into(), tagged));
+
+        let mut containers = Vec::new();
+        for name in ["web", "worker"] {
+            let pairs = vec![
+                (tree.insert("name".into()), tree.insert(name.into())),
+                (tree.insert("limits".into()), limits),
+                (tree.insert("args".into()), tagged),
+            ];
+            containers.push(tree.push(TreeNode::Mapping(pairs)));
+        }
+        let root = tree.push(TreeNode::Sequence(containers));
+        tree.set_root(root);
+
+        assert!(tree.is_shared(limits));
+        assert!(!tree.is_shared(root));
+
+        let mut events = vec![Event::StreamStart, Event::DocumentStart];
+        events.extend((&tree).into_events());
+        events.extend([Event::DocumentEnd, Event::StreamEnd]);
+
+        let mut output = String::new();
+        Emitter::new(events, EmitterOptions::default())
+            .emit(&mut output)
+            .unwrap();
+        assert_eq!(
+            output,
+            "---\n- name: web\n  limits: &1\n    cpu: 1\n  args: &2 !Args\n    - a\n- name: worker\n  limits: *1\n  args: *2\n...\n"
+        );
+
+        // Shared nodes are copied into nodes
+        let items = tree.to_node().unwrap().try_into_sequence().unwrap();
+        assert_eq!(items[0].get_path("limits"), items[1].get_path("limits"));
+    }
+
+    #[test]
+    fn cycles() {
+        let mut tree = Tree::new();
+        let root = tree.push(TreeNode::Sequence(Vec::new()));
+        *tree.get_mut(root) = TreeNode::Sequence(vec![root]);
+        tree.set_root(root);
+
+        let error = Error::Cycle {
+            path: vec![PathSegment::Index(0)],
+            cycle: vec![root],
+        };
+        assert_eq!(tree.try_into_events(), Err(error));
+
+        let mut tree = Tree::new();
+        let key = tree.insert("spec".into());
+        let outer = tree.push(TreeNode::Mapping(Vec::new()));
+        let inner = tree.push(TreeNode::Tagged("!Ref".into(), outer));
+        *tree.get_mut(outer) = TreeNode::Mapping(vec![(key, inner)]);
+        tree.set_root(outer);
+
+        let error = Error::Cycle {
+            path: vec![PathSegment::Key("spec".into())],
+            cycle: vec![outer, inner],
+        };
+        assert_eq!(tree.try_node(outer), Err(error));
+        assert_eq!(
+            tree.try_to_node().unwrap_err().to_string(),
+            "the node at spec refers back to an ancestor, forming the cycle [NodeId(1), NodeId(2)]"
+        );
+
+        // Shared nodes aren't cycles
+        let mut tree = Tree::new();
+        let shared = tree.insert(yaml!([1]));
+        let root = tree.push(TreeNode::Sequence(vec![shared, shared]));
+        tree.set_root(root);
+        assert_eq!(tree.try_to_node(), Ok(Some(yaml!([[1], [1]]))));
+    }
+}
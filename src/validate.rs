@@ -0,0 +1,304 @@
+//! Structural validation of documents before they are emitted.
+//!
+//! [`Document::validate`] walks the nodes of a document and reports the
+//! problems which would result in invalid or unexpected output, each located
+//! by the path of the node it was found at.
+//!
+//! ```
+//! use yaml_ast::{Document, Mapping};
+//!
+//! let document = Document::from_mapping(Mapping::from([
+//!     ("name".into(), "web".into()),
+//!     ("name".into(), "api".into()),
+//! ]));
+//!
+//! let problems = document.validate();
+//! assert_eq!(problems.len(), 1);
+//! assert_eq!(problems[0].to_string(), "name: duplicate key \"name\"");
+//! ```
+//!
+//! The nodes of a document never refer to missing anchors and never form
+//! cycles: aliases are resolved while parsing, which fails with
+//! [`cst::Error::UnknownAlias`](crate::cst::Error::UnknownAlias) for aliases
+//! of unknown anchors and with
+//! [`cst::Error::AliasCycle`](crate::cst::Error::AliasCycle) for aliases
+//! nested in their anchored node.
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    emitter::key_segment,
+    events::{display_path, PathSegment},
+    Document, Kind, Node,
+};
+
+/// The options of [`Document::validate_with`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidateOptions {
+    /// Reports the constructs which have no JSON equivalent: non-string
+    /// keys, tags, multiline strings and floats which aren't finite. This is
+    /// the subset enforced by
+    /// [`EmitterOptions::json_compatible`](crate::emitter::EmitterOptions::json_compatible).
+    pub json: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProblemKind {
+    /// The document contains more than one root node. All but the first node
+    /// will be emitted as invalid YAML.
+    MultipleRootNodes { count: usize },
+
+    /// A key occurs more than once in a mapping.
+    DuplicateKey { key: Node },
+
+    /// A mapping key contains comments. Complex keys are written in flow
+    /// style, which drops them.
+    CommentInKey,
+
+    /// The [`Document::item_comments`] of a path which isn't a sequence item
+    /// of the document. They are ignored when emitting.
+    OrphanedItemComments,
+
+    /// A key isn't a string, which JSON doesn't support. Only reported if
+    /// [`ValidateOptions::json`] is set.
+    NonStringKey { kind: Kind },
+
+    /// A node has an explicit tag or a type without JSON equivalent, like
+    /// binary data. Only reported if [`ValidateOptions::json`] is set.
+    Tag { tag: String },
+
+    /// A string spans multiple lines. Only reported if
+    /// [`ValidateOptions::json`] is set.
+    MultilineString,
+
+    /// A float is not a number or infinite. Only reported if
+    /// [`ValidateOptions::json`] is set.
+    NonFiniteFloat,
+}
+
+/// A single finding of [`Document::validate`], located by the path of the
+/// node within the document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub path: Vec<PathSegment>,
+    pub kind: ProblemKind,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", display_path(&self.path))?;
+
+        match &self.kind {
+            ProblemKind::MultipleRootNodes { count } => {
+                write!(f, "document has {count} root nodes")
+            }
+            ProblemKind::DuplicateKey { key } => match key {
+                Node::String(key) => write!(f, "duplicate key {key:?}"),
+                key => write!(f, "duplicate key {}", display_path(&[key_segment(key)])),
+            },
+            ProblemKind::CommentInKey => write!(f, "comment inside of a key"),
+            ProblemKind::OrphanedItemComments => {
+                write!(f, "item comments of a path which isn't a sequence item")
+            }
+            ProblemKind::NonStringKey { kind } => write!(f, "{kind} key is not a JSON string"),
+            ProblemKind::Tag { tag } => write!(f, "tag {tag} has no JSON equivalent"),
+            ProblemKind::MultilineString => write!(f, "multiline string"),
+            ProblemKind::NonFiniteFloat => write!(f, "float is not finite"),
+        }
+    }
+}
+
+impl Document {
+    /// Validates the structure of the document with the default options, see
+    /// [`Document::validate_with`].
+    pub fn validate(&self) -> Vec<Problem> {
+        self.validate_with(&ValidateOptions::default())
+    }
+
+    /// Walks the nodes of the document and returns the structural problems
+    /// found, in the order of the nodes. An empty list means the document
+    /// can be emitted as is.
+    pub fn validate_with(&self, options: &ValidateOptions) -> Vec<Problem> {
+        let mut validator = Validator {
+            options,
+            path: Vec::new(),
+            items: HashSet::new(),
+            problems: Vec::new(),
+        };
+
+        if self.nodes.len() > 1 {
+            validator.report(ProblemKind::MultipleRootNodes {
+                count: self.nodes.len(),
+            });
+        }
+
+        for node in &self.nodes {
+            validator.validate_node(node, false);
+        }
+
+        let mut orphaned: Vec<_> = self
+            .item_comments
+            .iter()
+            .filter(|(path, comments)| {
+                !comments.is_empty() && !validator.items.contains(path.as_slice())
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+        orphaned.sort_by_cached_key(|path| display_path(path));
+
+        let Validator { mut problems, .. } = validator;
+        problems.extend(orphaned.into_iter().map(|path| Problem {
+            path,
+            kind: ProblemKind::OrphanedItemComments,
+        }));
+        problems
+    }
+}
+
+struct Validator<'a> {
+    options: &'a ValidateOptions,
+    path: Vec<PathSegment>,
+
+    /// The paths of the sequence items outside of keys, which can have
+    /// item comments.
+    items: HashSet<Vec<PathSegment>>,
+    problems: Vec<Problem>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, kind: ProblemKind) {
+        self.problems.push(Problem {
+            path: self.path.clone(),
+            kind,
+        });
+    }
+
+    fn validate_node(&mut self, node: &Node, in_key: bool) {
+        match node {
+            Node::Mapping(mapping) => {
+                let mut keys = HashSet::new();
+
+                for pair in mapping.pairs() {
+                    self.path.push(key_segment(&pair.key));
+
+                    if !keys.insert(&pair.key) {
+                        self.report(ProblemKind::DuplicateKey {
+                            key: pair.key.clone(),
+                        });
+                    }
+
+                    if in_key && (!pair.comments.is_empty() || pair.comment.is_some()) {
+                        self.report(ProblemKind::CommentInKey);
+                    }
+
+                    if self.options.json && !matches!(pair.key.untagged(), Node::String(_)) {
+                        self.report(ProblemKind::NonStringKey {
+                            kind: pair.key.kind(),
+                        });
+                    }
+
+                    self.validate_node(&pair.key, true);
+                    self.validate_node(&pair.value, in_key);
+                    self.path.pop();
+                }
+            }
+            Node::Sequence(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.path.push(PathSegment::Index(index));
+
+                    if !in_key {
+                        self.items.insert(self.path.clone());
+                    }
+
+                    self.validate_node(item, in_key);
+                    self.path.pop();
+                }
+            }
+            Node::Tagged(tagged) => {
+                if self.options.json {
+                    self.report(ProblemKind::Tag {
+                        tag: tagged.tag.clone(),
+                    });
+                }
+
+                self.validate_node(&tagged.node, in_key)
+            }
+            Node::Binary(_) | Node::Timestamp(_) if self.options.json => {
+                self.report(ProblemKind::Tag { tag: node.uri() })
+            }
+            Node::String(value) if self.options.json && value.contains('\n') => {
+                self.report(ProblemKind::MultilineString)
+            }
+            Node::FloatingPoint(float) if self.options.json && !float.value().is_finite() => {
+                self.report(ProblemKind::NonFiniteFloat)
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{yaml, ItemComments, Mapping, MappingPair};
+
+    #[test]
+    fn validate() {
+        let key = Node::Mapping(Mapping::from_iter([
+            MappingPair::new("a".into(), 1.into()).with_comment("dropped")
+        ]));
+        let mut document = Document {
+            nodes: vec![Node::Mapping(Mapping::from([
+                ("ports".into(), yaml!([80, 443, "a\nb"])),
+                (key, Node::Null),
+                (Node::Integer(1), Node::Null),
+                (Node::Integer(1), Node::Null),
+            ]))],
+            ..Default::default()
+        };
+
+        for index in [1, 3] {
+            document.item_comments.insert(
+                vec![PathSegment::Key("ports".into()), PathSegment::Index(index)],
+                ItemComments {
+                    comments: vec!["port".into()],
+                    comment: None,
+                },
+            );
+        }
+
+        let problems: Vec<_> = document
+            .validate()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                "?.a: comment inside of a key",
+                "1: duplicate key 1",
+                "ports[3]: item comments of a path which isn't a sequence item",
+            ]
+        );
+
+        let problems = document.validate_with(&ValidateOptions { json: true });
+        let kinds: Vec<_> = problems
+            .iter()
+            .filter_map(|problem| match problem.kind {
+                ProblemKind::NonStringKey { kind } => Some((display_path(&problem.path), kind)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("?".to_string(), Kind::Mapping),
+                ("1".to_string(), Kind::Scalar),
+                ("1".to_string(), Kind::Scalar),
+            ]
+        );
+        assert!(problems.contains(&Problem {
+            path: vec![PathSegment::Key("ports".into()), PathSegment::Index(2)],
+            kind: ProblemKind::MultilineString,
+        }));
+    }
+}